@@ -268,7 +268,12 @@ pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
         .path()
         .app_data_dir()
         .expect("Failed to get app data dir");
-    std::fs::create_dir_all(&app_dir).expect("Failed to create app data dir");
+    init_database_at(&app_dir)
+}
+
+/// Initialize the agents database in `app_dir`, for use without a running app
+pub fn init_database_at(app_dir: &std::path::Path) -> SqliteResult<Connection> {
+    std::fs::create_dir_all(app_dir).expect("Failed to create app data dir");
 
     let db_path = app_dir.join("agents.db");
     let conn = Connection::open(db_path)?;
@@ -2048,7 +2053,8 @@ struct GitHubApiResponse {
 pub async fn fetch_github_agents() -> Result<Vec<GitHubAgentFile>, String> {
     info!("Fetching agents from GitHub repository...");
 
-    let client = reqwest::Client::new();
+    let client = crate::http::build_client(crate::http::DEFAULT_TIMEOUT, &crate::http::proxy_settings())
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
     let url = "https://api.github.com/repos/getAsterisk/opcode/contents/cc_agents";
 
     let response = client
@@ -2094,7 +2100,8 @@ pub async fn fetch_github_agents() -> Result<Vec<GitHubAgentFile>, String> {
 pub async fn fetch_github_agent_content(download_url: String) -> Result<AgentExport, String> {
     info!("Fetching agent content from: {}", download_url);
 
-    let client = reqwest::Client::new();
+    let client = crate::http::build_client(crate::http::DEFAULT_TIMEOUT, &crate::http::proxy_settings())
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
     let response = client
         .get(&download_url)
         .header("Accept", "application/json")
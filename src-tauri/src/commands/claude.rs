@@ -10,6 +10,10 @@ use tauri::{AppHandle, Emitter, Manager};
 use tokio::process::{Child, Command};
 use tokio::sync::Mutex;
 
+use crate::session::{SessionEvent, SessionEventEmitter};
+use crate::skills::tool_stream::{blocked_by_hook, pre_tool_bridge_settings, run_tool_hooks, ToolStreamTracker};
+use crate::skills::types::HookTrigger;
+
 /// Global state to track current Claude process
 pub struct ClaudeProcessState {
     pub current_process: Arc<Mutex<Option<Child>>>,
@@ -290,7 +294,7 @@ fn create_command_with_env(program: &str) -> Command {
 }
 
 /// Creates a system binary command with the given arguments
-fn create_system_command(claude_path: &str, args: Vec<String>, project_path: &str) -> Command {
+pub(crate) fn create_system_command(claude_path: &str, args: Vec<String>, project_path: &str) -> Command {
     let mut cmd = create_command_with_env(claude_path);
 
     // Add all arguments
@@ -944,7 +948,7 @@ pub async fn execute_claude_code(
     ];
 
     let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    spawn_claude_process(app, cmd, prompt, model, project_path, None).await
 }
 
 /// Continue an existing Claude Code conversation with streaming output
@@ -976,7 +980,7 @@ pub async fn continue_claude_code(
     ];
 
     let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    spawn_claude_process(app, cmd, prompt, model, project_path, None).await
 }
 
 /// Resume an existing Claude Code session by ID with streaming output
@@ -1011,7 +1015,7 @@ pub async fn resume_claude_code(
     ];
 
     let cmd = create_system_command(&claude_path, args, &project_path);
-    spawn_claude_process(app, cmd, prompt, model, project_path).await
+    spawn_claude_process(app, cmd, prompt, model, project_path, Some(session_id)).await
 }
 
 /// Cancel the currently running Claude Code execution
@@ -1171,16 +1175,35 @@ pub async fn get_claude_session_output(
 }
 
 /// Helper function to spawn Claude process and handle streaming
+///
+/// Runs the SessionStart hooks first and the SessionEnd hooks once the process exits.
+/// `session_id` is the session being resumed, if any.
 async fn spawn_claude_process(
     app: AppHandle,
     mut cmd: Command,
     prompt: String,
     model: String,
     project_path: String,
+    session_id: Option<String>,
 ) -> Result<(), String> {
     use std::sync::Mutex;
     use tokio::io::{AsyncBufReadExt, BufReader};
 
+    crate::commands::skills::run_session_hooks(&app, HookTrigger::SessionStart, &project_path, session_id.clone())
+        .await;
+
+    // Blocking PreTool hooks must run before Claude's tools do, so Claude asks Opcode through its own hook
+    let executor = app
+        .try_state::<crate::commands::skills::SkillExecutorState>()
+        .map(|executor| executor.0.clone());
+    let pre_tool_bridged = match (&executor, std::env::current_exe()) {
+        (Some(executor), Ok(opcode_exe)) if executor.has_blocking_hooks(HookTrigger::PreTool) => {
+            cmd.arg("--settings").arg(pre_tool_bridge_settings(&opcode_exe));
+            true
+        }
+        _ => false,
+    };
+
     // Spawn the process
     let mut child = cmd
         .spawn()
@@ -1223,6 +1246,15 @@ async fn spawn_claude_process(
     let project_path_clone = project_path.clone();
     let prompt_clone = prompt.clone();
     let model_clone = model.clone();
+
+    // PreTool/PostTool hooks run one at a time, in stream order, without holding up the output
+    let tool_calls = executor.map(|executor| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(run_tool_hooks(executor, project_path.clone(), pre_tool_bridged, rx));
+        tx
+    });
+    let mut tool_tracker = ToolStreamTracker::new();
+
     let stdout_task = tokio::spawn(async move {
         let mut lines = stdout_reader.lines();
         while let Ok(Some(line)) = lines.next_line().await {
@@ -1230,6 +1262,23 @@ async fn spawn_claude_process(
 
             // Parse the line to check for init message with session ID
             if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
+                if let Some(ref tool_calls) = tool_calls {
+                    for call in tool_tracker.process_message(&msg) {
+                        if let (Some((hook_name, reason)), Some(session_id)) =
+                            (blocked_by_hook(&call), call.session_id.clone())
+                        {
+                            let event = SessionEvent::ToolBlocked {
+                                session_id,
+                                tool_name: call.tool_name.clone(),
+                                hook_name,
+                                reason,
+                            };
+                            let _ = SessionEventEmitter::new(app_handle.clone()).emit_to_session(&event);
+                        }
+                        let _ = tool_calls.send(call);
+                    }
+                }
+
                 if msg["type"] == "system" && msg["subtype"] == "init" {
                     if let Some(claude_session_id) = msg["session_id"].as_str() {
                         let mut session_id_guard = session_id_holder_clone.lock().unwrap();
@@ -1334,6 +1383,16 @@ async fn spawn_claude_process(
 
         // Clear the process from state
         *current_process = None;
+        drop(current_process);
+
+        let ended_session_id = session_id_holder_clone3.lock().unwrap().clone().or(session_id);
+        crate::commands::skills::run_session_hooks(
+            &app_handle_wait,
+            HookTrigger::SessionEnd,
+            &project_path,
+            ended_session_id,
+        )
+        .await;
     });
 
     Ok(())
@@ -1542,6 +1601,18 @@ pub async fn create_checkpoint(
     project_path: String,
     message_index: Option<usize>,
     description: Option<String>,
+) -> Result<crate::checkpoint::CheckpointResult, String> {
+    create_session_checkpoint(&app, session_id, project_id, project_path, message_index, description).await
+}
+
+/// Checkpoint a session from its JSONL history, up to `message_index` if given
+pub(crate) async fn create_session_checkpoint(
+    state: &crate::checkpoint::state::CheckpointState,
+    session_id: String,
+    project_id: String,
+    project_path: String,
+    message_index: Option<usize>,
+    description: Option<String>,
 ) -> Result<crate::checkpoint::CheckpointResult, String> {
     log::info!(
         "Creating checkpoint for session: {} in project: {}",
@@ -1549,7 +1620,7 @@ pub async fn create_checkpoint(
         project_id
     );
 
-    let manager = app
+    let manager = state
         .get_or_create_manager(
             session_id.clone(),
             project_id.clone(),
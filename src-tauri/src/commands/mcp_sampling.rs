@@ -0,0 +1,354 @@
+//! MCP Sampling Bridge
+//!
+//! Answers sampling/createMessage requests from remote MCP servers by running
+//! the prompt through the local Claude binary. Each request is shown to the
+//! user for approval first unless the server is set to auto-approve.
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use log::{info, warn};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
+
+use crate::commands::agents::AgentDb;
+use crate::commands::claude::create_system_command;
+use crate::commands::remote_mcp::{init_remote_mcp_table, McpRootsState};
+use crate::mcp::error::{McpError, McpResult};
+use crate::mcp::transport::SamplingHandler;
+use crate::mcp::types::{CreateMessageParams, CreateMessageResult, ModelPreferences, SamplingContent, SamplingMessage};
+
+/// Event asking the user to approve a sampling request
+pub const SAMPLING_REQUEST_EVENT: &str = "mcp-sampling-request";
+
+/// Largest completion the Claude CLI will produce
+const MAX_SAMPLING_TOKENS: u32 = 32_000;
+
+/// Local model aliases, checked against the server's model hints
+const MODEL_ALIASES: &[&str] = &["opus", "sonnet", "haiku"];
+
+/// Alias used when the server expresses no usable preference
+const DEFAULT_MODEL_ALIAS: &str = "sonnet";
+
+/// Sampling requests waiting for the user, keyed by request ID
+#[derive(Default)]
+pub struct McpSamplingState(pub Arc<DashMap<String, oneshot::Sender<bool>>>);
+
+/// Sampling request shown to the user for approval
+#[derive(Debug, Clone, Serialize)]
+pub struct McpSamplingRequest {
+    pub request_id: String,
+    pub server_id: String,
+    pub messages: Vec<SamplingMessage>,
+    pub system_prompt: Option<String>,
+    pub max_tokens: u32,
+    /// Local model alias the prompt will run on
+    pub model: String,
+}
+
+/// Removes a pending approval when the request finishes or is dropped by the timeout
+struct PendingApproval {
+    pending: Arc<DashMap<String, oneshot::Sender<bool>>>,
+    request_id: String,
+}
+
+impl Drop for PendingApproval {
+    fn drop(&mut self) {
+        self.pending.remove(&self.request_id);
+    }
+}
+
+/// Runs one server's sampling requests through the local Claude binary
+pub struct AppSamplingHandler {
+    app: AppHandle,
+    server_id: String,
+}
+
+impl AppSamplingHandler {
+    pub fn new(app: AppHandle, server_id: &str) -> Self {
+        Self {
+            app,
+            server_id: server_id.to_string(),
+        }
+    }
+
+    fn auto_approved(&self) -> McpResult<bool> {
+        let db = self.app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| McpError::Internal(e.to_string()))?;
+        let _ = init_remote_mcp_table(&conn);
+        let enabled: Option<Option<bool>> = conn
+            .query_row(
+                "SELECT sampling_auto_approve FROM remote_mcp_servers WHERE id = ?1",
+                params![self.server_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| McpError::Internal(e.to_string()))?;
+        Ok(enabled.flatten().unwrap_or(false))
+    }
+
+    /// Ask the user to approve the request, failing with Cancelled if they decline
+    async fn request_approval(&self, params: &CreateMessageParams, model: &str) -> McpResult<()> {
+        let pending = self.app.state::<McpSamplingState>().0.clone();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel();
+        pending.insert(request_id.clone(), tx);
+        let _guard = PendingApproval {
+            pending,
+            request_id: request_id.clone(),
+        };
+
+        let request = McpSamplingRequest {
+            request_id,
+            server_id: self.server_id.clone(),
+            messages: params.messages.clone(),
+            system_prompt: params.system_prompt.clone(),
+            max_tokens: params.max_tokens,
+            model: model.to_string(),
+        };
+        self.app
+            .emit(SAMPLING_REQUEST_EVENT, request)
+            .map_err(|e| McpError::Internal(e.to_string()))?;
+
+        match rx.await {
+            Ok(true) => Ok(()),
+            _ => Err(McpError::Cancelled),
+        }
+    }
+
+    /// Run the prompt through `claude -p` and return the completion text
+    async fn run_claude(&self, prompt: String, params: &CreateMessageParams, model: &str) -> McpResult<String> {
+        let claude_path = crate::claude_binary::find_claude_binary(&self.app).map_err(McpError::Internal)?;
+        let working_dir = self
+            .app
+            .state::<McpRootsState>()
+            .0
+            .read()
+            .clone()
+            .or_else(|| dirs::home_dir().map(|home| home.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| ".".to_string());
+
+        let mut args = vec![
+            "-p".to_string(),
+            prompt,
+            "--model".to_string(),
+            model.to_string(),
+            "--output-format".to_string(),
+            "json".to_string(),
+            "--max-turns".to_string(),
+            "1".to_string(),
+        ];
+        if let Some(ref system_prompt) = params.system_prompt {
+            args.push("--system-prompt".to_string());
+            args.push(system_prompt.clone());
+        }
+
+        let mut cmd = create_system_command(&claude_path, args, &working_dir);
+        let max_tokens = params.max_tokens.clamp(1, MAX_SAMPLING_TOKENS);
+        // Killed if the sampling timeout drops this future
+        cmd.env("CLAUDE_CODE_MAX_OUTPUT_TOKENS", max_tokens.to_string())
+            .kill_on_drop(true);
+
+        let output = cmd
+            .output()
+            .await
+            .map_err(|e| McpError::Internal(format!("Failed to run Claude: {}", e)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(McpError::Internal(format!(
+                "Claude exited with {}: {}",
+                output.status,
+                stderr.trim()
+            )));
+        }
+        parse_claude_result(&output.stdout)
+    }
+}
+
+#[async_trait]
+impl SamplingHandler for AppSamplingHandler {
+    async fn create_message(&self, params: CreateMessageParams) -> McpResult<CreateMessageResult> {
+        let prompt = sampling_prompt(&params.messages).map_err(McpError::InvalidConfig)?;
+        let model = model_alias(params.model_preferences.as_ref());
+
+        if self.auto_approved()? {
+            info!("Auto-approved sampling request from MCP server {}", self.server_id);
+        } else if let Err(e) = self.request_approval(&params, model).await {
+            info!("Sampling request from MCP server {} was declined", self.server_id);
+            return Err(e);
+        }
+
+        let text = self.run_claude(prompt, &params, model).await.map_err(|e| {
+            warn!("Sampling request from MCP server {} failed: {}", self.server_id, e);
+            e
+        })?;
+        Ok(CreateMessageResult {
+            role: "assistant".to_string(),
+            content: SamplingContent::Text { text },
+            model: model.to_string(),
+            stop_reason: Some("endTurn".to_string()),
+        })
+    }
+}
+
+/// Pick the local model alias for a request
+///
+/// The first hint naming a known alias wins; otherwise a strong intelligence
+/// preference picks opus and a strong speed or cost preference picks haiku.
+fn model_alias(preferences: Option<&ModelPreferences>) -> &'static str {
+    let preferences = match preferences {
+        Some(preferences) => preferences,
+        None => return DEFAULT_MODEL_ALIAS,
+    };
+
+    for hint in preferences.hints.iter().filter_map(|hint| hint.name.as_deref()) {
+        let hint = hint.to_lowercase();
+        if let Some(alias) = MODEL_ALIASES.iter().find(|alias| hint.contains(**alias)) {
+            return *alias;
+        }
+    }
+
+    let intelligence = preferences.intelligence_priority.unwrap_or(0.0);
+    let speed = preferences
+        .speed_priority
+        .unwrap_or(0.0)
+        .max(preferences.cost_priority.unwrap_or(0.0));
+    if intelligence >= 0.8 && intelligence > speed {
+        "opus"
+    } else if speed >= 0.8 && speed > intelligence {
+        "haiku"
+    } else {
+        DEFAULT_MODEL_ALIAS
+    }
+}
+
+/// Flatten the conversation into a single prompt; only text content can be sampled
+fn sampling_prompt(messages: &[SamplingMessage]) -> Result<String, String> {
+    let mut turns = Vec::with_capacity(messages.len());
+    for message in messages {
+        let text = match message.content {
+            SamplingContent::Text { ref text } => text.as_str(),
+            _ => return Err("Only text content can be sampled".to_string()),
+        };
+        let speaker = match message.role.as_str() {
+            "assistant" => "Assistant",
+            _ => "User",
+        };
+        turns.push((speaker, text));
+    }
+
+    match turns.as_slice() {
+        [] => Err("Sampling request has no messages".to_string()),
+        [(_, text)] => Ok(text.to_string()),
+        _ => Ok(turns
+            .iter()
+            .map(|(speaker, text)| format!("{}: {}", speaker, text))
+            .collect::<Vec<_>>()
+            .join("\n\n")),
+    }
+}
+
+/// Extract the completion from `claude --output-format json`
+fn parse_claude_result(stdout: &[u8]) -> McpResult<String> {
+    let output: serde_json::Value = serde_json::from_slice(stdout)
+        .map_err(|e| McpError::InvalidResponse(format!("Unexpected output from Claude: {}", e)))?;
+    let result = output
+        .get("result")
+        .and_then(|result| result.as_str())
+        .ok_or_else(|| McpError::InvalidResponse("Claude returned no result".to_string()))?;
+
+    if output.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false) {
+        return Err(McpError::Internal(result.to_string()));
+    }
+    Ok(result.to_string())
+}
+
+/// Approve or decline a pending sampling request
+#[tauri::command]
+pub async fn respond_to_mcp_sampling(
+    state: State<'_, McpSamplingState>,
+    request_id: String,
+    approved: bool,
+) -> Result<(), String> {
+    match state.0.remove(&request_id) {
+        Some((_, tx)) => {
+            let _ = tx.send(approved);
+            Ok(())
+        }
+        None => Err(format!("Sampling request not found or already answered: {}", request_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::types::ModelHint;
+
+    fn text_message(role: &str, text: &str) -> SamplingMessage {
+        SamplingMessage {
+            role: role.to_string(),
+            content: SamplingContent::Text { text: text.to_string() },
+        }
+    }
+
+    #[test]
+    fn test_model_alias() {
+        assert_eq!(model_alias(None), "sonnet");
+
+        let hinted = ModelPreferences {
+            hints: vec![
+                ModelHint { name: Some("gpt-4o".to_string()) },
+                ModelHint { name: Some("claude-3-5-Haiku".to_string()) },
+            ],
+            intelligence_priority: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(model_alias(Some(&hinted)), "haiku");
+
+        let smart = ModelPreferences {
+            intelligence_priority: Some(0.9),
+            speed_priority: Some(0.2),
+            ..Default::default()
+        };
+        assert_eq!(model_alias(Some(&smart)), "opus");
+
+        let cheap = ModelPreferences {
+            cost_priority: Some(0.9),
+            intelligence_priority: Some(0.5),
+            ..Default::default()
+        };
+        assert_eq!(model_alias(Some(&cheap)), "haiku");
+    }
+
+    #[test]
+    fn test_sampling_prompt() {
+        assert_eq!(sampling_prompt(&[text_message("user", "hi")]).unwrap(), "hi");
+        assert_eq!(
+            sampling_prompt(&[text_message("user", "hi"), text_message("assistant", "hello"), text_message("user", "?")])
+                .unwrap(),
+            "User: hi\n\nAssistant: hello\n\nUser: ?"
+        );
+        assert!(sampling_prompt(&[]).is_err());
+
+        let image = SamplingMessage {
+            role: "user".to_string(),
+            content: SamplingContent::Image {
+                data: String::new(),
+                mime_type: "image/png".to_string(),
+            },
+        };
+        assert!(sampling_prompt(&[image]).is_err());
+    }
+
+    #[test]
+    fn test_parse_claude_result() {
+        let output = br#"{"type":"result","subtype":"success","is_error":false,"result":"42"}"#;
+        assert_eq!(parse_claude_result(output).unwrap(), "42");
+
+        let failed = br#"{"type":"result","is_error":true,"result":"Credit balance too low"}"#;
+        assert!(parse_claude_result(failed).is_err());
+        assert!(parse_claude_result(b"not json").is_err());
+    }
+}
@@ -1,8 +1,10 @@
 pub mod agents;
 pub mod claude;
 pub mod mcp;
+pub mod mcp_sampling;  // Opcode 2.0: MCP sampling through the local Claude binary
 pub mod proxy;
 pub mod remote_mcp;  // Opcode 2.0: Remote MCP servers with Streamable HTTP
+pub mod sessions;    // Opcode 2.0: Session budgets
 pub mod skills;      // Opcode 2.0: Unified skills system
 pub mod tasks;       // Opcode 2.0: Parallel tasks and background jobs
 pub mod slash_commands;
@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use crate::commands::agents::AgentDb;
+use crate::commands::remote_mcp::{
+    restart_health_monitoring, McpConnectionPoolState, McpHealthMonitorState,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProxySettings {
@@ -13,6 +16,19 @@ pub struct ProxySettings {
     pub enabled: bool,
 }
 
+impl ProxySettings {
+    /// Hosts that bypass the proxy: localhost plus the user's list
+    pub fn no_proxy_value(&self) -> String {
+        let mut no_proxy_list = vec!["localhost", "127.0.0.1", "::1", "0.0.0.0"];
+        if let Some(user_no_proxy) = &self.no_proxy {
+            if !user_no_proxy.is_empty() {
+                no_proxy_list.push(user_no_proxy.as_str());
+            }
+        }
+        no_proxy_list.join(",")
+    }
+}
+
 impl Default for ProxySettings {
     fn default() -> Self {
         Self {
@@ -62,9 +78,13 @@ pub async fn get_proxy_settings(db: State<'_, AgentDb>) -> Result<ProxySettings,
 }
 
 /// Save proxy settings to the database
+///
+/// Pooled MCP connections are dropped so they reconnect through the new proxy.
 #[tauri::command]
 pub async fn save_proxy_settings(
     db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    health: State<'_, McpHealthMonitorState>,
     settings: ProxySettings,
 ) -> Result<(), String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
@@ -92,15 +112,21 @@ pub async fn save_proxy_settings(
         .map_err(|e| format!("Failed to save {}: {}", key, e))?;
     }
 
+    drop(conn); // Release lock
+
     // Apply the proxy settings immediately to the current process
     apply_proxy_settings(&settings);
 
+    pool.0.disconnect_all().await;
+    restart_health_monitoring(&db, &health.0);
+
     Ok(())
 }
 
-/// Apply proxy settings as environment variables
+/// Apply proxy settings to new HTTP clients and as environment variables for child processes
 pub fn apply_proxy_settings(settings: &ProxySettings) {
     log::info!("Applying proxy settings: enabled={}", settings.enabled);
+    crate::http::set_proxy_settings(settings);
 
     if !settings.enabled {
         // Clear proxy environment variables if disabled
@@ -118,13 +144,7 @@ pub fn apply_proxy_settings(settings: &ProxySettings) {
     }
 
     // Ensure NO_PROXY includes localhost by default
-    let mut no_proxy_list = vec!["localhost", "127.0.0.1", "::1", "0.0.0.0"];
-    if let Some(user_no_proxy) = &settings.no_proxy {
-        if !user_no_proxy.is_empty() {
-            no_proxy_list.push(user_no_proxy.as_str());
-        }
-    }
-    let no_proxy_value = no_proxy_list.join(",");
+    let no_proxy_value = settings.no_proxy_value();
 
     // Set proxy environment variables (uppercase is standard)
     if let Some(http_proxy) = &settings.http_proxy {
@@ -1,20 +1,160 @@
 //! Remote MCP Server Commands
 //!
-//! Tauri commands for managing remote MCP servers with Streamable HTTP transport.
-//! Supports Bearer token and API key authentication.
+//! Tauri commands for managing remote MCP servers over Streamable HTTP or WebSocket transport.
+//! Supports Bearer token and API key authentication; credentials live in the OS keychain.
 
-use log::info;
-use rusqlite::params;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use dashmap::mapref::entry::Entry;
+use dashmap::{DashMap, DashSet};
+use futures::StreamExt;
+use log::{debug, info, warn};
+use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tauri::State;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
 
 use crate::commands::agents::AgentDb;
-use crate::mcp::auth::{create_auth_from_config, McpAuth};
-use crate::mcp::health::{HealthStatus, ServerHealth};
-use crate::mcp::streamable_http::StreamableHttpTransport;
-use crate::mcp::transport::McpTransport;
-use crate::mcp::types::{McpAuthConfig, Tool};
+use crate::commands::mcp::{mcp_read_project_config, mcp_save_project_config, MCPServerConfig};
+use crate::commands::mcp_sampling::AppSamplingHandler;
+use crate::mcp::auth::{
+    create_auth_from_config, create_oauth_from_config, McpAuth, McpOAuthAuth, OAuthRefreshCallback,
+};
+use crate::mcp::error::{McpError, McpResult};
+use crate::mcp::health::{
+    DegradedThresholds, HealthEvent, HealthSample, HealthStatus, McpHealthMonitor, MonitoredServer, ServerHealth,
+    ServerMetrics, TransportConnector, HEALTH_HISTORY_CAPACITY,
+};
+use crate::mcp::pool::{McpConnectionPool, DEFAULT_KEEPALIVE_SECS};
+use crate::mcp::schema::{find_tool, validate_arguments, ArgumentError};
+use crate::mcp::secrets::{
+    decrypt_secret, delete_from_keychain, encrypt_secret, is_encrypted, keychain_ref, load_from_keychain,
+    store_in_keychain,
+};
+use crate::mcp::trace::{McpTrace, TraceEntry};
+use crate::mcp::transport::{
+    parse_extra_headers, McpTransport, RetryPolicy, TlsOptions, TransportConfig, TransportEvent, TransportFactory,
+};
+use crate::mcp::types::{
+    EmbeddedResource, GetPromptResult, McpAuthConfig, Prompt, ReadResourceResult, Resource,
+    ResourceContents, Root, ServerCapabilities, ServerInfo, Tool, ToolCallEvent, ToolCallResult,
+    ToolResultContent,
+};
+
+/// Request timeout for pooled connections (long enough for tool calls)
+const POOLED_TIMEOUT_MS: u64 = 60000;
+
+/// Longest time budget a tool call can be given
+const MAX_TOOL_TIMEOUT_MS: u64 = 3_600_000;
+
+/// Longest Retry-After a rate-limited tool call waits out before retrying once
+const DEFAULT_RATE_LIMIT_WAIT_MS: u64 = 10_000;
+
+/// Connection tests run at once by `test_remote_mcp_group`
+const GROUP_TEST_CONCURRENCY: usize = 4;
+
+/// Longest keepalive interval; 0 disables keepalive
+const MAX_KEEPALIVE_SECS: u64 = 3600;
+
+/// Shortest keepalive interval, so idle servers aren't flooded with pings
+const MIN_KEEPALIVE_SECS: u64 = 5;
+
+/// Health samples kept in the database per server
+const HEALTH_SAMPLE_RETENTION: i64 = 1000;
+
+/// Error prefix when a server name is already taken, so the frontend can flag the name field
+const DUPLICATE_NAME_ERROR: &str = "DUPLICATE_NAME";
+
+/// Version of the document written by `export_remote_mcp_servers`
+const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Stands in for secrets left out of an export
+const SECRET_PLACEHOLDER: &str = "<redacted>";
+
+/// Timeout for the reachability probe when adding a server
+const ENDPOINT_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Log lines kept in memory per server
+const MCP_LOG_CAPACITY: usize = 500;
+
+/// Levels accepted by logging/setLevel, least to most severe
+const MCP_LOG_LEVELS: [&str; 8] = ["debug", "info", "notice", "warning", "error", "critical", "alert", "emergency"];
+
+/// Connection pool state for remote MCP servers
+pub struct McpConnectionPoolState(pub Arc<McpConnectionPool>);
+
+impl Default for McpConnectionPoolState {
+    fn default() -> Self {
+        Self(Arc::new(McpConnectionPool::default()))
+    }
+}
+
+/// Health monitor state for remote MCP servers
+pub struct McpHealthMonitorState(pub Arc<McpHealthMonitor>);
+
+impl McpHealthMonitorState {
+    /// Create a monitor that checks servers with MCP pings over pooled connections
+    ///
+    /// Authenticated servers are connected on demand; others are pinged when
+    /// already pooled and otherwise probed over HTTP.
+    pub fn with_pool(pool: Arc<McpConnectionPool>, app: &AppHandle) -> Self {
+        let app = app.clone();
+        let connector: TransportConnector =
+            Arc::new(move |id: &str| create_server_transport(&app, id, POOLED_TIMEOUT_MS));
+        Self(Arc::new(
+            McpHealthMonitor::new()
+                .with_connection_pool(pool)
+                .with_transport_connector(connector),
+        ))
+    }
+}
+
+impl Default for McpHealthMonitorState {
+    fn default() -> Self {
+        Self(Arc::new(McpHealthMonitor::default()))
+    }
+}
+
+/// Servers whose resource updates are being forwarded to the frontend
+#[derive(Default)]
+pub struct McpResourceForwardersState(pub Arc<DashSet<String>>);
+
+/// In-flight tool calls, keyed by call ID, with a sender that cancels each one
+#[derive(Default)]
+pub struct McpToolCallsState(pub Arc<DashMap<String, oneshot::Sender<()>>>);
+
+/// Tool definitions from the last tools/list, keyed by server ID
+#[derive(Default)]
+pub struct McpToolCacheState(pub Arc<DashMap<String, Vec<Tool>>>);
+
+/// Recent log messages from each server, oldest first
+#[derive(Default)]
+pub struct McpServerLogsState(pub Arc<DashMap<String, VecDeque<McpServerLogEntry>>>);
+
+/// Request/response traces, keyed by server ID
+#[derive(Default)]
+pub struct McpTraceState(pub Arc<DashMap<String, Arc<McpTrace>>>);
+
+impl McpTraceState {
+    /// The server's trace, created disabled on first use
+    fn for_server(&self, server_id: &str) -> Arc<McpTrace> {
+        self.0.entry(server_id.to_string()).or_default().clone()
+    }
+}
+
+/// Active project directory, offered to every server as its first root
+#[derive(Default)]
+pub struct McpRootsState(pub Arc<parking_lot::RwLock<Option<String>>>);
+
+/// Log message sent by a remote MCP server (notifications/message)
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerLogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub logger: Option<String>,
+    pub message: String,
+}
 
 /// Remote MCP server for frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +169,33 @@ pub struct RemoteMcpServerInfo {
     pub health_interval: u64,
     pub last_health_check: Option<String>,
     pub latency_ms: Option<u64>,
+    /// MCP protocol version negotiated on the last successful connection
+    pub protocol_version: Option<String>,
+    /// Capabilities advertised on the last successful connection
+    pub capabilities: Option<ServerCapabilities>,
+    /// Server name and version from the last successful connection
+    pub server_info: Option<ServerInfo>,
+    /// Usage notes the server sent on the last successful connection
+    pub instructions: Option<String>,
+    /// Time budget for tool calls that don't set their own
+    pub default_tool_timeout_ms: Option<u64>,
+    /// Seconds of idle time between keepalive pings (0 = disabled)
+    pub keepalive_interval_secs: u64,
+    /// Extra directories offered to the server in answer to roots/list
+    pub roots: Vec<String>,
+    /// Run the server's sampling requests without asking the user first
+    pub sampling_auto_approve: bool,
+    /// Record requests and responses for debugging
+    pub debug_trace: bool,
+    /// Headers sent with every request to the server (auth headers win on conflicts)
+    pub extra_headers: HashMap<String, String>,
+    /// Group for managing servers together, e.g. "staging" or "prod"
+    pub group: Option<String>,
+    /// Server tool calls are retried on when this one can't be reached
+    pub fallback_server_id: Option<String>,
+    /// Problem found while adding the server that didn't prevent saving it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -48,10 +215,41 @@ pub struct AddRemoteServerRequest {
     pub api_key_value: Option<String>,
     /// Custom headers as JSON (if auth_type is "custom-header")
     pub custom_headers: Option<String>,
+    /// OAuth2 access token (if auth_type is "oauth2")
+    pub oauth_access_token: Option<String>,
+    /// OAuth2 refresh token (if auth_type is "oauth2")
+    pub oauth_refresh_token: Option<String>,
+    /// OAuth2 token endpoint URL (if auth_type is "oauth2")
+    pub oauth_token_endpoint: Option<String>,
+    /// OAuth2 client ID (if auth_type is "oauth2")
+    pub oauth_client_id: Option<String>,
+    /// OAuth2 client secret for confidential clients (if auth_type is "oauth2")
+    pub oauth_client_secret: Option<String>,
+    /// Access token expiry as RFC3339 (if auth_type is "oauth2")
+    pub oauth_expires_at: Option<String>,
     /// Health check enabled
     pub health_enabled: Option<bool>,
     /// Health check interval in seconds
     pub health_interval: Option<u64>,
+    /// Retry policy for transient connection failures (defaults apply if omitted)
+    pub retry_policy: Option<RetryPolicy>,
+    /// PEM file with a private CA certificate to trust
+    pub ca_cert_path: Option<String>,
+    /// PEM client certificate for mutual TLS
+    pub client_cert_path: Option<String>,
+    /// PEM (PKCS#8) private key for the client certificate
+    pub client_key_path: Option<String>,
+    /// Skip certificate validation (development only)
+    pub accept_invalid_certs: Option<bool>,
+    /// Headers sent with every request, e.g. a custom User-Agent or tenant ID
+    #[serde(default)]
+    pub extra_headers: Option<HashMap<String, String>>,
+    /// Group for managing servers together
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Server tool calls are retried on when this one can't be reached
+    #[serde(default)]
+    pub fallback_server_id: Option<String>,
 }
 
 /// Initialize remote MCP servers table
@@ -71,195 +269,1180 @@ pub fn init_remote_mcp_table(conn: &rusqlite::Connection) -> Result<(), rusqlite
             last_health_check TEXT,
             latency_ms INTEGER,
             capabilities TEXT,
+            retry_policy TEXT,
+            ca_cert_path TEXT,
+            client_cert_path TEXT,
+            client_key_path TEXT,
+            accept_invalid_certs BOOLEAN DEFAULT 0,
+            degraded_multiplier REAL,
+            degraded_floor_ms INTEGER,
+            default_tool_timeout_ms INTEGER,
+            keepalive_interval_secs INTEGER DEFAULT 45,
+            needs_credentials BOOLEAN DEFAULT 0,
+            sampling_auto_approve BOOLEAN DEFAULT 0,
+            debug_trace BOOLEAN DEFAULT 0,
+            extra_headers TEXT,
+            group_name TEXT,
+            fallback_server_id TEXT REFERENCES remote_mcp_servers(id) ON DELETE SET NULL,
             created_at TEXT DEFAULT CURRENT_TIMESTAMP,
             updated_at TEXT DEFAULT CURRENT_TIMESTAMP
         )",
         [],
     )?;
 
+    // Add columns introduced after the table was first created
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN retry_policy TEXT", []);
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN ca_cert_path TEXT", []);
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN client_cert_path TEXT", []);
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN client_key_path TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE remote_mcp_servers ADD COLUMN accept_invalid_certs BOOLEAN DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN degraded_multiplier REAL", []);
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN degraded_floor_ms INTEGER", []);
+    let _ = conn.execute(
+        "ALTER TABLE remote_mcp_servers ADD COLUMN default_tool_timeout_ms INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE remote_mcp_servers ADD COLUMN keepalive_interval_secs INTEGER DEFAULT 45",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE remote_mcp_servers ADD COLUMN needs_credentials BOOLEAN DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE remote_mcp_servers ADD COLUMN sampling_auto_approve BOOLEAN DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN debug_trace BOOLEAN DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN extra_headers TEXT", []);
+    let _ = conn.execute("ALTER TABLE remote_mcp_servers ADD COLUMN group_name TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE remote_mcp_servers ADD COLUMN fallback_server_id TEXT
+         REFERENCES remote_mcp_servers(id) ON DELETE SET NULL",
+        [],
+    );
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mcp_health_samples (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            server_id TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            status TEXT NOT NULL,
+            latency_ms INTEGER
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_mcp_health_samples_server ON mcp_health_samples(server_id, id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS remote_mcp_roots (
+            server_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (server_id, path)
+        )",
+        [],
+    )?;
+
     info!("Remote MCP servers table initialized");
     Ok(())
 }
 
-/// List all remote MCP servers
-#[tauri::command]
-pub async fn list_remote_mcp_servers(db: State<'_, AgentDb>) -> Result<Vec<RemoteMcpServerInfo>, String> {
+/// Check that an endpoint is an absolute http(s) or ws(s) URL with a host
+fn validate_endpoint(endpoint: &str) -> Result<(), String> {
+    let url = url::Url::parse(endpoint).map_err(|e| format!("Invalid endpoint URL: {}", e))?;
+
+    if !matches!(url.scheme(), "http" | "https" | "ws" | "wss") {
+        return Err(format!(
+            "Invalid endpoint URL: unsupported scheme '{}' (expected http, https, ws or wss)",
+            url.scheme()
+        ));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("Invalid endpoint URL: missing host".to_string());
+    }
+
+    Ok(())
+}
+
+/// Whether an endpoint is served over WebSocket rather than Streamable HTTP
+fn is_websocket_endpoint(endpoint: &str) -> bool {
+    url::Url::parse(endpoint).is_ok_and(|url| matches!(url.scheme(), "ws" | "wss"))
+}
+
+/// Trim a group name, treating blank as no group
+fn normalize_group(group: Option<String>) -> Option<String> {
+    group.map(|g| g.trim().to_string()).filter(|g| !g.is_empty())
+}
+
+/// IDs of the servers in a group
+fn group_server_ids(conn: &rusqlite::Connection, group: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM remote_mcp_servers WHERE group_name = ?1 ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![group], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+/// Check that `fallback` exists and that following fallbacks from it never leads back to `id`
+fn validate_fallback(conn: &rusqlite::Connection, id: Option<&str>, fallback: &str) -> Result<(), String> {
+    if Some(fallback) == id {
+        return Err("A server can't be its own fallback".to_string());
+    }
+
+    let mut seen = HashSet::from([fallback.to_string()]);
+    let mut current = fallback.to_string();
+    loop {
+        let next: Option<Option<String>> = conn
+            .query_row(
+                "SELECT fallback_server_id FROM remote_mcp_servers WHERE id = ?1",
+                params![current],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let next = match next {
+            Some(next) => next,
+            None if current == fallback => return Err(format!("Fallback server not found: {}", fallback)),
+            None => return Ok(()),
+        };
+        match next {
+            Some(next) if Some(next.as_str()) == id => {
+                return Err("Fallback chain would loop back to this server".to_string());
+            }
+            Some(next) if seen.insert(next.clone()) => current = next,
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// The fallback configured for a server, if any
+fn fallback_server_id(app: &AppHandle, id: &str) -> Option<String> {
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().ok()?;
+    conn.query_row(
+        "SELECT fallback_server_id FROM remote_mcp_servers WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| warn!("Failed to look up the fallback for {}: {}", id, e))
+    .ok()
+    .flatten()
+    .flatten()
+}
+
+/// Check that no other server uses a name, ignoring case
+fn ensure_unique_name(conn: &rusqlite::Connection, name: &str, exclude_id: Option<&str>) -> Result<(), String> {
+    let taken: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM remote_mcp_servers WHERE name = ?1 COLLATE NOCASE AND id IS NOT ?2)",
+            params![name, exclude_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if taken {
+        return Err(format!("{}: A server named '{}' already exists", DUPLICATE_NAME_ERROR, name));
+    }
+    Ok(())
+}
+
+/// Check that an endpoint answers HTTP requests, returning a warning if it doesn't
+///
+/// Any response counts, since MCP endpoints often reject HEAD. WebSocket endpoints are
+/// probed over plain HTTP, which they answer with an error until the connection is upgraded.
+async fn probe_endpoint(endpoint: &str, accept_invalid_certs: bool) -> Option<String> {
+    let mut url = match url::Url::parse(endpoint) {
+        Ok(url) => url,
+        Err(e) => return Some(format!("Could not check endpoint: {}", e)),
+    };
+    let http_scheme = match url.scheme() {
+        "ws" => Some("http"),
+        "wss" => Some("https"),
+        _ => None,
+    };
+    if let Some(scheme) = http_scheme {
+        let _ = url.set_scheme(scheme);
+    }
+
+    let client = match crate::http::client_builder(ENDPOINT_PROBE_TIMEOUT, &crate::http::proxy_settings())
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return Some(format!("Could not check endpoint: {}", e)),
+    };
+    match client.head(url).send().await {
+        Ok(_) => None,
+        Err(e) => Some(format!("Endpoint is not reachable: {}", McpError::from(e))),
+    }
+}
+
+/// Trim whitespace that is easy to pick up when pasting credentials
+fn trim_secret(value: String) -> String {
+    value.trim().to_string()
+}
+
+/// Check that TLS certificate paths point at readable files and come in usable combinations
+///
+/// Empty strings are treated as unset.
+fn validate_tls_paths(
+    ca_cert_path: Option<&str>,
+    client_cert_path: Option<&str>,
+    client_key_path: Option<&str>,
+) -> Result<(), String> {
+    fn present(path: Option<&str>) -> Option<&str> {
+        path.filter(|p| !p.is_empty())
+    }
+
+    for path in [ca_cert_path, client_cert_path, client_key_path].into_iter().flat_map(present) {
+        if !std::path::Path::new(path).is_file() {
+            return Err(format!("Certificate file not found: {}", path));
+        }
+    }
+    if present(client_cert_path).is_some() != present(client_key_path).is_some() {
+        return Err("Client certificate and key must be provided together".to_string());
+    }
+
+    Ok(())
+}
+
+/// Keychain entry name for a server's auth config
+fn auth_secret_name(id: &str) -> String {
+    format!("remote-mcp/{}", id)
+}
+
+/// Store an auth config, returning the value for the auth_config column
+///
+/// Secrets go to the OS keychain and only a reference is kept in the database.
+/// Without a keychain (e.g. headless Linux) the config is encrypted in place.
+fn store_auth_config(id: &str, config: &impl Serialize) -> McpResult<String> {
+    let config_str = serde_json::to_string(config)?;
+    match store_in_keychain(&auth_secret_name(id), &config_str) {
+        Ok(reference) => Ok(reference),
+        Err(e) => {
+            debug!("Keychain unavailable for {} ({}), encrypting in database", id, e);
+            encrypt_secret(&config_str)
+        }
+    }
+}
+
+/// Resolve a stored auth_config value to the real auth config
+///
+/// Rows written before keychain support are migrated on first read: plaintext
+/// configs move to the keychain (or are encrypted), encrypted ones move to the
+/// keychain once it is available.
+fn resolve_auth_config(conn: &rusqlite::Connection, id: &str, stored: &str) -> McpResult<McpAuthConfig> {
+    let config_str = match keychain_ref(stored) {
+        Some(name) => load_from_keychain(name)?,
+        None if is_encrypted(stored) => decrypt_secret(stored)?,
+        None => stored.to_string(),
+    };
+    let config: McpAuthConfig = serde_json::from_str(&config_str)
+        .map_err(|e| McpError::InvalidConfig(format!("Invalid auth config: {}", e)))?;
+
+    if keychain_ref(stored).is_none() {
+        let migrated = if is_encrypted(stored) {
+            store_in_keychain(&auth_secret_name(id), &config_str)
+        } else {
+            store_auth_config(id, &config)
+        };
+        let updated = migrated.and_then(|new_stored| {
+            conn.execute(
+                "UPDATE remote_mcp_servers SET auth_config = ?1 WHERE id = ?2",
+                params![new_stored, id],
+            )
+            .map_err(|e| McpError::Internal(e.to_string()))
+        });
+        match updated {
+            Ok(_) => info!("Migrated stored credentials for remote MCP server {}", id),
+            Err(e) => debug!("Left stored credentials for {} in the database: {}", id, e),
+        }
+    }
+
+    Ok(config)
+}
+
+/// Move any credentials still stored in the database into the keychain
+///
+/// Returns the number of servers whose auth config could be resolved.
+pub fn migrate_auth_secrets(db: &AgentDb) -> Result<usize, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_remote_mcp_table(&conn);
 
-    // Ensure table exists
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, auth_config FROM remote_mcp_servers
+             WHERE auth_config IS NOT NULL AND auth_config NOT LIKE 'keychain:%'",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+
+    Ok(rows
+        .iter()
+        .filter(|(id, stored)| match resolve_auth_config(&conn, id, stored) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Failed to read stored credentials for {}: {}", id, e);
+                false
+            }
+        })
+        .count())
+}
+
+/// Load servers with health checks enabled
+pub fn load_monitored_servers(db: &AgentDb) -> Result<Vec<MonitoredServer>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
     let _ = init_remote_mcp_table(&conn);
 
     let mut stmt = conn
         .prepare(
-            "SELECT id, name, description, endpoint, auth_type, status, health_enabled,
-             health_interval, last_health_check, latency_ms, created_at, updated_at
-             FROM remote_mcp_servers ORDER BY created_at DESC",
+            "SELECT id, endpoint, health_interval, health_timeout, auth_config,
+                    degraded_multiplier, degraded_floor_ms, extra_headers, group_name
+             FROM remote_mcp_servers WHERE health_enabled = 1 AND needs_credentials = 0",
         )
         .map_err(|e| e.to_string())?;
 
-    let servers = stmt
+    let rows = stmt
         .query_map([], |row| {
-            Ok(RemoteMcpServerInfo {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                endpoint: row.get(3)?,
-                auth_type: row.get(4)?,
-                status: row.get::<_, String>(5).unwrap_or_else(|_| "unknown".to_string()),
-                health_enabled: row.get::<_, bool>(6).unwrap_or(true),
-                health_interval: row.get::<_, u64>(7).unwrap_or(60),
-                last_health_check: row.get(8).ok(),
-                latency_ms: row.get(9).ok(),
-                created_at: row.get(10)?,
-                updated_at: row.get(11)?,
-            })
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<i64>>(2)?.unwrap_or(60) as u64,
+                row.get::<_, Option<i64>>(3)?.unwrap_or(10) as u64,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<f64>>(5)?,
+                row.get::<_, Option<i64>>(6)?.map(|ms| ms as u64),
+                extra_headers_from_column(row.get(7)?),
+                row.get::<_, Option<String>>(8)?,
+            ))
         })
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
 
+    let servers = rows
+        .into_iter()
+        .map(|(server_id, endpoint, interval_secs, timeout_secs, auth_config, multiplier, floor_ms, headers, group)| {
+            let auth = auth_config.and_then(|stored| {
+                resolve_auth_config(&conn, &server_id, &stored)
+                    .map_err(|e| warn!("Failed to load auth config for {}: {}", server_id, e))
+                    .ok()
+            });
+            MonitoredServer {
+                server_id,
+                endpoint,
+                interval_secs,
+                timeout_secs,
+                auth,
+                degraded_multiplier: multiplier,
+                degraded_floor_ms: floor_ms,
+                headers,
+                group,
+            }
+        })
+        .collect();
+
     Ok(servers)
 }
 
-/// Add a new remote MCP server
-#[tauri::command]
-pub async fn add_remote_mcp_server(
-    db: State<'_, AgentDb>,
-    request: AddRemoteServerRequest,
-) -> Result<RemoteMcpServerInfo, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+/// Reload the monitored server list if health monitoring is running
+pub(crate) fn restart_health_monitoring(db: &AgentDb, monitor: &McpHealthMonitor) {
+    if !monitor.is_running() {
+        return;
+    }
+    match load_monitored_servers(db) {
+        Ok(servers) => monitor.start_monitoring(servers),
+        Err(e) => warn!("Failed to reload health-monitored servers: {}", e),
+    }
+}
 
-    // Ensure table exists
-    let _ = init_remote_mcp_table(&conn);
+fn health_status_str(status: HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Healthy => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Unhealthy => "unhealthy",
+        HealthStatus::Unknown => "unknown",
+    }
+}
 
-    // Generate ID
-    let id = uuid::Uuid::new_v4().to_string();
+fn parse_health_status(status: &str) -> HealthStatus {
+    match status {
+        "healthy" => HealthStatus::Healthy,
+        "degraded" => HealthStatus::Degraded,
+        "unhealthy" => HealthStatus::Unhealthy,
+        _ => HealthStatus::Unknown,
+    }
+}
 
-    // Build auth config JSON
-    let auth_config = match request.auth_type.as_str() {
-        "bearer" => {
-            let token = request.token.ok_or("Bearer token is required")?;
-            serde_json::json!({
-                "type": "bearer",
-                "token": token
-            })
-        }
-        "api-key" => {
-            let header = request.api_key_header.unwrap_or_else(|| "X-API-Key".to_string());
-            let value = request.api_key_value.ok_or("API key value is required")?;
-            serde_json::json!({
-                "type": "api-key",
-                "header": header,
-                "value": value
-            })
-        }
-        "custom-header" => {
-            let headers_str = request.custom_headers.ok_or("Custom headers are required")?;
-            let headers: HashMap<String, String> = serde_json::from_str(&headers_str)
-                .map_err(|e| format!("Invalid custom headers JSON: {}", e))?;
-            serde_json::json!({
-                "type": "custom-header",
-                "headers": headers
-            })
-        }
-        _ => serde_json::json!({ "type": "none" }),
+/// Write the latest health check result back to the server's row and sample history
+fn persist_server_health(db: &AgentDb, health: &ServerHealth) -> Result<(), String> {
+    let status = match health.status {
+        HealthStatus::Healthy | HealthStatus::Degraded => "connected",
+        HealthStatus::Unhealthy => "error",
+        HealthStatus::Unknown => "unknown",
     };
+    let sample = HealthSample::from_health(health);
 
-    let auth_config_str = serde_json::to_string(&auth_config).map_err(|e| e.to_string())?;
-    let health_enabled = request.health_enabled.unwrap_or(true);
-    let health_interval = request.health_interval.unwrap_or(60);
-
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
     conn.execute(
-        "INSERT INTO remote_mcp_servers (id, name, description, endpoint, auth_type, auth_config, health_enabled, health_interval)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "UPDATE remote_mcp_servers SET status = ?1, latency_ms = ?2, last_health_check = ?3 WHERE id = ?4",
         params![
-            id,
-            request.name,
-            request.description,
-            request.endpoint,
-            request.auth_type,
-            auth_config_str,
-            health_enabled,
-            health_interval
+            status,
+            health.latency_ms.map(|l| l as i64),
+            health.last_check,
+            health.server_id
         ],
     )
     .map_err(|e| e.to_string())?;
 
-    info!("Added remote MCP server: {} ({})", request.name, id);
+    conn.execute(
+        "INSERT INTO mcp_health_samples (server_id, timestamp, status, latency_ms) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            health.server_id,
+            sample.timestamp,
+            health_status_str(sample.status),
+            sample.latency_ms.map(|l| l as i64)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM mcp_health_samples WHERE server_id = ?1 AND id NOT IN (
+            SELECT id FROM mcp_health_samples WHERE server_id = ?1 ORDER BY id DESC LIMIT ?2
+        )",
+        params![health.server_id, HEALTH_SAMPLE_RETENTION],
+    )
+    .map_err(|e| e.to_string())?;
 
-    // Return the created server
-    Ok(RemoteMcpServerInfo {
-        id,
-        name: request.name,
-        description: request.description,
-        endpoint: request.endpoint,
-        auth_type: request.auth_type,
-        status: "unknown".to_string(),
-        health_enabled,
-        health_interval,
-        last_health_check: None,
-        latency_ms: None,
-        created_at: chrono::Utc::now().to_rfc3339(),
-        updated_at: chrono::Utc::now().to_rfc3339(),
-    })
+    Ok(())
 }
 
-/// Remove a remote MCP server
-#[tauri::command]
-pub async fn remove_remote_mcp_server(db: State<'_, AgentDb>, id: String) -> Result<(), String> {
+/// Load each server's most recent persisted health samples into the monitor
+pub fn restore_health_history(db: &AgentDb, monitor: &McpHealthMonitor) -> Result<(), String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_remote_mcp_table(&conn);
 
-    conn.execute("DELETE FROM remote_mcp_servers WHERE id = ?1", params![id])
+    let mut stmt = conn
+        .prepare(
+            "SELECT server_id, timestamp, status, latency_ms FROM (
+                SELECT *, ROW_NUMBER() OVER (PARTITION BY server_id ORDER BY id DESC) AS rn
+                FROM mcp_health_samples
+             ) WHERE rn <= ?1 ORDER BY id",
+        )
         .map_err(|e| e.to_string())?;
 
-    info!("Removed remote MCP server: {}", id);
+    let rows = stmt
+        .query_map(params![HEALTH_HISTORY_CAPACITY as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                HealthSample {
+                    timestamp: row.get(1)?,
+                    status: parse_health_status(&row.get::<_, String>(2)?),
+                    latency_ms: row.get::<_, Option<i64>>(3)?.map(|l| l as u64),
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut by_server: HashMap<String, Vec<HealthSample>> = HashMap::new();
+    for (server_id, sample) in rows {
+        by_server.entry(server_id).or_default().push(sample);
+    }
+    for (server_id, samples) in by_server {
+        monitor.restore_history(&server_id, samples);
+    }
+
     Ok(())
 }
 
-/// Test connection to a remote MCP server
-#[tauri::command]
-pub async fn test_remote_mcp_connection(
-    db: State<'_, AgentDb>,
-    id: String,
-) -> Result<ServerHealth, String> {
-    // Get server details in a scoped block to release the lock
-    let (endpoint, auth_config_str) = {
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        let result: (String, Option<String>) = conn
+/// Forward health events to the frontend as `mcp-health:<server_id>` and persist each check
+pub fn spawn_health_event_bridge(app: AppHandle, monitor: Arc<McpHealthMonitor>) {
+    let mut events = monitor.subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    if let HealthEvent::CheckCompleted { ref health, .. } = event {
+                        let db = app.state::<AgentDb>();
+                        if let Err(e) = persist_server_health(&db, health) {
+                            warn!("Failed to persist health for {}: {}", health.server_id, e);
+                        }
+                    }
+                    match event {
+                        HealthEvent::MetricsUpdated { ref metrics, .. } => {
+                            let _ = app.emit("mcp-metrics-updated", metrics);
+                        }
+                        _ => {
+                            let _ = app.emit(&format!("mcp-health:{}", event.server_id()), &event);
+                        }
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Skipped {} MCP health events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Callback that writes refreshed OAuth tokens back to the server's row
+fn oauth_persist_callback(app: &AppHandle, id: &str) -> OAuthRefreshCallback {
+    let app = app.clone();
+    let id = id.to_string();
+
+    Arc::new(move |auth: &McpOAuthAuth| {
+        let config_str = match store_auth_config(&id, &auth.to_config()) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Failed to store refreshed OAuth config for {}: {}", id, e);
+                return;
+            }
+        };
+
+        let db = app.state::<AgentDb>();
+        let result = db.0.lock().map_err(|e| e.to_string()).and_then(|conn| {
+            conn.execute(
+                "UPDATE remote_mcp_servers SET auth_config = ?1, updated_at = ?2 WHERE id = ?3",
+                params![config_str, chrono::Utc::now().to_rfc3339(), id],
+            )
+            .map_err(|e| e.to_string())
+        });
+
+        match result {
+            Ok(_) => info!("Persisted refreshed OAuth tokens for remote MCP server {}", id),
+            Err(e) => warn!("Failed to persist refreshed OAuth tokens for {}: {}", id, e),
+        }
+    })
+}
+
+/// Build a transport for a stored server from its endpoint and auth config
+fn create_server_transport(app: &AppHandle, id: &str, timeout_ms: u64) -> McpResult<Box<dyn McpTransport>> {
+    let (endpoint, auth_config, retry, tls, keepalive, configured_roots, debug_trace, headers) = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| McpError::Internal(e.to_string()))?;
+        let configured_roots = load_roots(&conn, id).map_err(|e| McpError::Internal(e.to_string()))?;
+        let (endpoint, stored, retry_str, tls, keepalive, needs_credentials, debug_trace, headers): (
+            String,
+            Option<String>,
+            Option<String>,
+            TlsOptions,
+            Option<u64>,
+            Option<bool>,
+            Option<bool>,
+            Option<String>,
+        ) = conn
             .query_row(
-                "SELECT endpoint, auth_config FROM remote_mcp_servers WHERE id = ?1",
+                "SELECT endpoint, auth_config, retry_policy, ca_cert_path, client_cert_path,
+                 client_key_path, accept_invalid_certs, keepalive_interval_secs, needs_credentials, debug_trace,
+                 extra_headers
+                 FROM remote_mcp_servers WHERE id = ?1",
                 params![id],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                |row| {
+                    let tls = TlsOptions {
+                        ca_cert_path: row.get(3)?,
+                        client_cert_path: row.get(4)?,
+                        client_key_path: row.get(5)?,
+                        accept_invalid_certs: row.get::<_, Option<bool>>(6)?.unwrap_or(false),
+                    };
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        tls,
+                        row.get(7)?,
+                        row.get(8)?,
+                        row.get(9)?,
+                        row.get(10)?,
+                    ))
+                },
             )
-            .map_err(|e| format!("Server not found: {}", e))?;
-        result
+            .map_err(|_| McpError::ServerNotFound(id.to_string()))?;
+        if needs_credentials.unwrap_or(false) {
+            return Err(McpError::AuthenticationFailed(
+                "credentials were not included when this server was imported; add them in the server settings"
+                    .to_string(),
+            ));
+        }
+        let auth_config = stored
+            .map(|stored| resolve_auth_config(&conn, id, &stored))
+            .transpose()?;
+        let retry = retry_str
+            .map(|s| serde_json::from_str::<RetryPolicy>(&s))
+            .transpose()
+            .map_err(|e| McpError::InvalidConfig(format!("Invalid retry policy: {}", e)))?;
+        (
+            endpoint,
+            auth_config,
+            retry,
+            Some(tls).filter(TlsOptions::is_configured),
+            keepalive.unwrap_or(DEFAULT_KEEPALIVE_SECS),
+            configured_roots,
+            debug_trace.unwrap_or(false),
+            extra_headers_from_column(headers),
+        )
     }; // conn is dropped here
 
-    // Parse auth config
-    let auth: Option<Box<dyn McpAuth>> = if let Some(config_str) = auth_config_str {
-        let config: McpAuthConfig = serde_json::from_str(&config_str)
-            .map_err(|e| format!("Invalid auth config: {}", e))?;
-        Some(create_auth_from_config(&config))
+    // Picked up by the pool when this transport becomes the server's pooled connection
+    let keepalive = Some(keepalive)
+        .filter(|secs| *secs > 0)
+        .map(std::time::Duration::from_secs);
+    app.state::<McpConnectionPoolState>().0.set_keepalive(id, keepalive);
+
+    let auth: Option<Box<dyn McpAuth>> = if let Some(config) = auth_config {
+        // OAuth tokens rotate, so refreshed credentials are persisted back to the database
+        match create_oauth_from_config(&config, Some(oauth_persist_callback(app, id))) {
+            Some(oauth) => Some(Box::new(oauth)),
+            None => Some(create_auth_from_config(&config)),
+        }
     } else {
         None
     };
 
-    // Create transport and test connection
-    let mut transport = StreamableHttpTransport::new(&endpoint, auth, 30000)
-        .map_err(|e| format!("Failed to create transport: {}", e))?;
+    let config = if is_websocket_endpoint(&endpoint) {
+        TransportConfig::WebSocket {
+            url: endpoint,
+            timeout_ms: Some(timeout_ms),
+            retry,
+            tls,
+            headers,
+        }
+    } else {
+        TransportConfig::StreamableHttp {
+            endpoint,
+            timeout_ms: Some(timeout_ms),
+            retry,
+            tls,
+            headers,
+        }
+    };
+    let transport = TransportFactory::create(config, auth)?;
+    let active_project = app.state::<McpRootsState>().0.read().clone();
+    transport.set_roots(server_roots(active_project.as_deref(), &configured_roots));
+    transport.set_sampling_handler(Arc::new(AppSamplingHandler::new(app.clone(), id)));
+    let trace = app.state::<McpTraceState>().for_server(id);
+    trace.set_enabled(debug_trace);
+    transport.set_trace(trace);
+    if let Some(notifications) = transport.notifications() {
+        spawn_log_forwarder(app.clone(), id.to_string(), notifications);
+    }
+    Ok(transport)
+}
+
+/// Keep a server's log messages and emit them as `mcp-server-log:<server_id>` events
+///
+/// Exits once the transport is dropped.
+fn spawn_log_forwarder(
+    app: AppHandle,
+    server_id: String,
+    mut notifications: tokio::sync::broadcast::Receiver<TransportEvent>,
+) {
+    tokio::spawn(async move {
+        let logs = app.state::<McpServerLogsState>().0.clone();
+        loop {
+            match notifications.recv().await {
+                Ok(TransportEvent::Log { level, logger, message }) => {
+                    let entry = McpServerLogEntry {
+                        timestamp: chrono::Utc::now().to_rfc3339(),
+                        level,
+                        logger,
+                        message,
+                    };
+                    let _ = app.emit(&format!("mcp-server-log:{}", server_id), &entry);
+                    push_log_entry(&logs, &server_id, entry);
+                }
+                Ok(_) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Skipped {} notifications for {}", skipped, server_id);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Append a log entry, dropping the oldest beyond `MCP_LOG_CAPACITY`
+fn push_log_entry(logs: &DashMap<String, VecDeque<McpServerLogEntry>>, server_id: &str, entry: McpServerLogEntry) {
+    let mut lines = logs.entry(server_id.to_string()).or_default();
+    if lines.len() >= MCP_LOG_CAPACITY {
+        lines.pop_front();
+    }
+    lines.push_back(entry);
+}
+
+/// List remote MCP servers, optionally only those in `group`
+#[tauri::command]
+pub async fn list_remote_mcp_servers(
+    db: State<'_, AgentDb>,
+    group: Option<String>,
+) -> Result<Vec<RemoteMcpServerInfo>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    // Ensure table exists
+    let _ = init_remote_mcp_table(&conn);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, description, endpoint, auth_type, status, health_enabled,
+             health_interval, last_health_check, latency_ms, created_at, updated_at, capabilities,
+             default_tool_timeout_ms, keepalive_interval_secs, sampling_auto_approve, debug_trace, extra_headers,
+             group_name, fallback_server_id
+             FROM remote_mcp_servers WHERE ?1 IS NULL OR group_name = ?1 ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut servers = stmt
+        .query_map(params![normalize_group(group)], |row| {
+            let cached = cached_capabilities(row.get(12)?);
+            Ok(RemoteMcpServerInfo {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                endpoint: row.get(3)?,
+                auth_type: row.get(4)?,
+                status: row.get::<_, String>(5).unwrap_or_else(|_| "unknown".to_string()),
+                health_enabled: row.get::<_, bool>(6).unwrap_or(true),
+                health_interval: row.get::<_, u64>(7).unwrap_or(60),
+                last_health_check: row.get(8).ok(),
+                latency_ms: row.get(9).ok(),
+                protocol_version: cached.protocol_version,
+                capabilities: cached.capabilities,
+                server_info: cached.server_info,
+                instructions: cached.instructions,
+                default_tool_timeout_ms: row.get(13)?,
+                keepalive_interval_secs: row.get::<_, Option<u64>>(14)?.unwrap_or(DEFAULT_KEEPALIVE_SECS),
+                roots: Vec::new(),
+                sampling_auto_approve: row.get::<_, Option<bool>>(15)?.unwrap_or(false),
+                debug_trace: row.get::<_, Option<bool>>(16)?.unwrap_or(false),
+                extra_headers: extra_headers_from_column(row.get(17)?),
+                group: row.get(18)?,
+                fallback_server_id: row.get(19)?,
+                warning: None,
+                created_at: row.get(10)?,
+                updated_at: row.get(11)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<RemoteMcpServerInfo>, _>>()
+        .map_err(|e| e.to_string())?;
+    for server in &mut servers {
+        server.roots = load_roots(&conn, &server.id).map_err(|e| e.to_string())?;
+    }
+
+    Ok(servers)
+}
+
+/// Handshake details cached in the capabilities column after a successful connect
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachedServerCapabilities {
+    #[serde(rename = "protocolVersion", default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<ServerCapabilities>,
+    #[serde(rename = "serverInfo", default, skip_serializing_if = "Option::is_none")]
+    pub server_info: Option<ServerInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+}
+
+/// Parse the capabilities column, treating missing or unreadable values as empty
+fn cached_capabilities(stored: Option<String>) -> CachedServerCapabilities {
+    stored
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Read the negotiated protocol version out of the capabilities column
+fn protocol_version_from_capabilities(capabilities: &str) -> Option<String> {
+    cached_capabilities(Some(capabilities.to_string())).protocol_version
+}
+
+/// Parse the extra_headers column, treating missing or unreadable values as empty
+fn extra_headers_from_column(stored: Option<String>) -> HashMap<String, String> {
+    stored
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Validate extra headers and serialize them for the extra_headers column (None when empty)
+fn extra_headers_column(headers: &HashMap<String, String>) -> Result<Option<String>, String> {
+    parse_extra_headers(headers).map_err(|e| e.to_string())?;
+    if headers.is_empty() {
+        return Ok(None);
+    }
+    let trimmed: HashMap<&str, &str> = headers.iter().map(|(k, v)| (k.trim(), v.trim())).collect();
+    serde_json::to_string(&trimmed).map(Some).map_err(|e| e.to_string())
+}
+
+/// Add a new remote MCP server
+#[tauri::command]
+pub async fn add_remote_mcp_server(
+    db: State<'_, AgentDb>,
+    health: State<'_, McpHealthMonitorState>,
+    request: AddRemoteServerRequest,
+) -> Result<RemoteMcpServerInfo, String> {
+    validate_endpoint(&request.endpoint)?;
+    if let Some(ref policy) = request.retry_policy {
+        if !(1..=10).contains(&policy.max_attempts) {
+            return Err("Retry max_attempts must be between 1 and 10".to_string());
+        }
+    }
+    validate_tls_paths(
+        request.ca_cert_path.as_deref(),
+        request.client_cert_path.as_deref(),
+        request.client_key_path.as_deref(),
+    )?;
+    let extra_headers = request.extra_headers.clone().unwrap_or_default();
+    let extra_headers_str = extra_headers_column(&extra_headers)?;
+    let group = normalize_group(request.group.clone());
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Server name is required".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    // Ensure table exists
+    let _ = init_remote_mcp_table(&conn);
+
+    ensure_unique_name(&conn, &name, None)?;
+    let fallback_server_id = request.fallback_server_id.clone().filter(|f| !f.is_empty());
+    if let Some(ref fallback) = fallback_server_id {
+        validate_fallback(&conn, None, fallback)?;
+    }
+
+    // Generate ID
+    let id = uuid::Uuid::new_v4().to_string();
+
+    // Build auth config JSON
+    let auth_config = match request.auth_type.as_str() {
+        "bearer" => {
+            let token = request.token.map(trim_secret).ok_or("Bearer token is required")?;
+            serde_json::json!({
+                "type": "bearer",
+                "token": token
+            })
+        }
+        "api-key" => {
+            let header = request
+                .api_key_header
+                .map(trim_secret)
+                .unwrap_or_else(|| "X-API-Key".to_string());
+            let value = request.api_key_value.map(trim_secret).ok_or("API key value is required")?;
+            serde_json::json!({
+                "type": "api-key",
+                "header": header,
+                "value": value
+            })
+        }
+        "custom-header" => {
+            let headers_str = request.custom_headers.ok_or("Custom headers are required")?;
+            let headers: HashMap<String, String> = serde_json::from_str(&headers_str)
+                .map_err(|e| format!("Invalid custom headers JSON: {}", e))?;
+            let headers: HashMap<String, String> = headers
+                .into_iter()
+                .map(|(name, value)| (trim_secret(name), trim_secret(value)))
+                .collect();
+            serde_json::json!({
+                "type": "custom-header",
+                "headers": headers
+            })
+        }
+        "oauth2" => {
+            let access_token = request
+                .oauth_access_token
+                .map(trim_secret)
+                .ok_or("OAuth access token is required")?;
+            let token_url = request.oauth_token_endpoint.ok_or("OAuth token endpoint is required")?;
+            let client_id = request.oauth_client_id.ok_or("OAuth client ID is required")?;
+            serde_json::json!({
+                "type": "oauth2",
+                "access_token": access_token,
+                "refresh_token": request.oauth_refresh_token.map(trim_secret),
+                "token_url": token_url,
+                "client_id": client_id,
+                "client_secret": request.oauth_client_secret.map(trim_secret),
+                "expires_at": request.oauth_expires_at
+            })
+        }
+        _ => serde_json::json!({ "type": "none" }),
+    };
+
+    let auth_config_str = store_auth_config(&id, &auth_config).map_err(|e| e.to_string())?;
+    let health_enabled = request.health_enabled.unwrap_or(true);
+    let health_interval = request.health_interval.unwrap_or(60);
+    let retry_policy_str = request
+        .retry_policy
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO remote_mcp_servers (id, name, description, endpoint, auth_type, auth_config, health_enabled, health_interval, retry_policy,
+         ca_cert_path, client_cert_path, client_key_path, accept_invalid_certs, extra_headers, group_name,
+         fallback_server_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![
+            id,
+            name,
+            request.description,
+            request.endpoint,
+            request.auth_type,
+            auth_config_str,
+            health_enabled,
+            health_interval,
+            retry_policy_str,
+            request.ca_cert_path.as_deref().filter(|p| !p.is_empty()),
+            request.client_cert_path.as_deref().filter(|p| !p.is_empty()),
+            request.client_key_path.as_deref().filter(|p| !p.is_empty()),
+            request.accept_invalid_certs.unwrap_or(false),
+            extra_headers_str,
+            group,
+            fallback_server_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    drop(conn); // Release lock
+
+    restart_health_monitoring(&db, &health.0);
+
+    info!("Added remote MCP server: {} ({})", name, id);
+
+    // Saved either way; an unreachable endpoint may just be offline for now
+    let warning = probe_endpoint(&request.endpoint, request.accept_invalid_certs.unwrap_or(false)).await;
+
+    // Return the created server
+    Ok(RemoteMcpServerInfo {
+        id,
+        name,
+        description: request.description,
+        endpoint: request.endpoint,
+        auth_type: request.auth_type,
+        status: "unknown".to_string(),
+        health_enabled,
+        health_interval,
+        last_health_check: None,
+        latency_ms: None,
+        protocol_version: None,
+        capabilities: None,
+        server_info: None,
+        instructions: None,
+        default_tool_timeout_ms: None,
+        keepalive_interval_secs: DEFAULT_KEEPALIVE_SECS,
+        roots: Vec::new(),
+        sampling_auto_approve: false,
+        debug_trace: false,
+        extra_headers,
+        group,
+        fallback_server_id,
+        warning,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Remove a remote MCP server
+#[tauri::command]
+pub async fn remove_remote_mcp_server(
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    health: State<'_, McpHealthMonitorState>,
+    tools: State<'_, McpToolCacheState>,
+    traces: State<'_, McpTraceState>,
+    id: String,
+) -> Result<(), String> {
+    delete_server(&db, &pool.0, &tools, &traces, &id).await?;
+    restart_health_monitoring(&db, &health.0);
+    health.0.remove_server(&id);
+
+    info!("Removed remote MCP server: {}", id);
+    Ok(())
+}
+
+/// Delete a server's rows and stored secret and drop its connection, tools and trace
+///
+/// Health monitoring is left to the caller, which should restart it afterwards.
+async fn delete_server(
+    db: &AgentDb,
+    pool: &McpConnectionPool,
+    tools: &McpToolCacheState,
+    traces: &McpTraceState,
+    id: &str,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM remote_mcp_servers WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM mcp_health_samples WHERE server_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM remote_mcp_roots WHERE server_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    // Foreign keys aren't enforced on every connection, so clear references explicitly
+    conn.execute(
+        "UPDATE remote_mcp_servers SET fallback_server_id = NULL WHERE fallback_server_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    drop(conn); // Release lock
+
+    if let Err(e) = delete_from_keychain(&auth_secret_name(id)) {
+        debug!("No keychain entry removed for {}: {}", id, e);
+    }
+
+    pool.disconnect(id).await;
+    tools.0.remove(id);
+    traces.0.remove(id);
+    Ok(())
+}
+
+/// Remove every server in a group
+///
+/// Returns the number of servers removed.
+#[tauri::command]
+pub async fn remove_remote_mcp_group(
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    health: State<'_, McpHealthMonitorState>,
+    tools: State<'_, McpToolCacheState>,
+    traces: State<'_, McpTraceState>,
+    group: String,
+) -> Result<usize, String> {
+    let ids = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        group_server_ids(&conn, group.trim())?
+    };
+    for id in &ids {
+        delete_server(&db, &pool.0, &tools, &traces, id).await?;
+    }
+    restart_health_monitoring(&db, &health.0);
+    for id in &ids {
+        health.0.remove_server(id);
+    }
+
+    info!("Removed {} remote MCP servers in group {}", ids.len(), group.trim());
+    Ok(ids.len())
+}
+
+/// Turn health checks on or off for every server in a group
+///
+/// Returns the number of servers updated.
+#[tauri::command]
+pub async fn set_remote_mcp_group_enabled(
+    db: State<'_, AgentDb>,
+    health: State<'_, McpHealthMonitorState>,
+    group: String,
+    enabled: bool,
+) -> Result<usize, String> {
+    let updated = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE remote_mcp_servers SET health_enabled = ?1, updated_at = ?2 WHERE group_name = ?3",
+            params![enabled, chrono::Utc::now().to_rfc3339(), group.trim()],
+        )
+        .map_err(|e| e.to_string())?
+    };
+    restart_health_monitoring(&db, &health.0);
+
+    info!(
+        "{} health checks for {} remote MCP servers in group {}",
+        if enabled { "Enabled" } else { "Disabled" },
+        updated,
+        group.trim()
+    );
+    Ok(updated)
+}
+
+/// Test connection to a remote MCP server
+///
+/// Always performs a fresh handshake; on success the connection stays pooled.
+#[tauri::command]
+pub async fn test_remote_mcp_connection(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    id: String,
+) -> Result<ServerHealth, String> {
+    connect_and_record(&app, &db, &pool.0, id).await
+}
+
+/// Test connections to every server in a group, a few at a time
+///
+/// Results are in the order the servers were added.
+#[tauri::command]
+pub async fn test_remote_mcp_group(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    group: String,
+) -> Result<Vec<ServerHealth>, String> {
+    let ids = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        group_server_ids(&conn, group.trim())?
+    };
+    futures::stream::iter(ids)
+        .map(|id| connect_and_record(&app, &db, &pool.0, id))
+        .buffered(GROUP_TEST_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Open a fresh pooled connection, recording the outcome and capabilities in the database
+async fn connect_and_record(
+    app: &AppHandle,
+    db: &AgentDb,
+    pool: &McpConnectionPool,
+    id: String,
+) -> Result<ServerHealth, String> {
+    // Drop any pooled connection so the test reflects the current configuration
+    pool.disconnect(&id).await;
 
     let start = std::time::Instant::now();
-    let result = transport.connect().await;
+    let result = pool
+        .get_or_connect(&id, || create_server_transport(app, &id, POOLED_TIMEOUT_MS))
+        .await;
     let latency = start.elapsed().as_millis() as u64;
 
+    if let Err(e @ McpError::ServerNotFound(_)) = &result {
+        return Err(e.to_string());
+    }
+
     let health = match result {
-        Ok(()) => {
+        Ok(conn) => {
+            let cached = CachedServerCapabilities {
+                protocol_version: conn.protocol_version().await,
+                capabilities: conn.server_capabilities().await,
+                server_info: conn.server_info().await,
+                instructions: conn.server_instructions().await,
+            };
+            let capabilities = serde_json::to_string(&cached).map_err(|e| e.to_string())?;
+
             // Update status in database in a scoped block
             {
-                let conn = db.0.lock().map_err(|e| e.to_string())?;
-                conn.execute(
-                    "UPDATE remote_mcp_servers SET status = 'connected', last_health_check = ?1, latency_ms = ?2, updated_at = ?3 WHERE id = ?4",
-                    params![chrono::Utc::now().to_rfc3339(), latency as i64, chrono::Utc::now().to_rfc3339(), id],
+                let db_conn = db.0.lock().map_err(|e| e.to_string())?;
+                db_conn.execute(
+                    "UPDATE remote_mcp_servers SET status = 'connected', last_health_check = ?1, latency_ms = ?2, capabilities = ?3, updated_at = ?4 WHERE id = ?5",
+                    params![chrono::Utc::now().to_rfc3339(), latency as i64, capabilities, chrono::Utc::now().to_rfc3339(), id],
                 ).map_err(|e| e.to_string())?;
             }
 
@@ -272,6 +1455,8 @@ pub async fn test_remote_mcp_connection(
                 consecutive_failures: 0,
                 consecutive_successes: 1,
                 avg_latency_ms: Some(latency),
+                session_id: conn.session_id().await,
+                instructions: cached.instructions,
             }
         }
         Err(e) => {
@@ -293,6 +1478,8 @@ pub async fn test_remote_mcp_connection(
                 consecutive_failures: 1,
                 consecutive_successes: 0,
                 avg_latency_ms: None,
+                session_id: None,
+                instructions: None,
             }
         }
     };
@@ -300,100 +1487,1186 @@ pub async fn test_remote_mcp_connection(
     Ok(health)
 }
 
-/// List tools from a remote MCP server
+/// Get the capabilities a server advertised on its last successful connection
+///
+/// Returns the cached value (None if it never connected) unless `refresh` is
+/// set, which runs a new handshake first.
 #[tauri::command]
-pub async fn list_remote_mcp_tools(db: State<'_, AgentDb>, id: String) -> Result<Vec<Tool>, String> {
-    // Get server details in a scoped block to release the lock
-    let (endpoint, auth_config_str) = {
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        let result: (String, Option<String>) = conn
-            .query_row(
-                "SELECT endpoint, auth_config FROM remote_mcp_servers WHERE id = ?1",
-                params![id],
-                |row| Ok((row.get(0)?, row.get(1)?)),
-            )
-            .map_err(|e| format!("Server not found: {}", e))?;
-        result
-    }; // conn is dropped here
+pub async fn get_remote_mcp_capabilities(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    id: String,
+    refresh: Option<bool>,
+) -> Result<Option<CachedServerCapabilities>, String> {
+    if refresh.unwrap_or(false) {
+        let health = connect_and_record(&app, &db, &pool.0, id.clone()).await?;
+        if let Some(error) = health.last_error {
+            return Err(format!("Failed to connect: {}", error));
+        }
+    }
 
-    // Parse auth config
-    let auth: Option<Box<dyn McpAuth>> = if let Some(config_str) = auth_config_str {
-        let config: McpAuthConfig = serde_json::from_str(&config_str)
-            .map_err(|e| format!("Invalid auth config: {}", e))?;
-        Some(create_auth_from_config(&config))
-    } else {
-        None
-    };
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_remote_mcp_table(&conn);
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT capabilities FROM remote_mcp_servers WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Server not found: {}", id))?;
 
-    // Connect and list tools
-    let mut transport = StreamableHttpTransport::new(&endpoint, auth, 30000)
-        .map_err(|e| format!("Failed to create transport: {}", e))?;
+    Ok(stored.map(|s| cached_capabilities(Some(s))))
+}
 
-    transport
-        .connect()
-        .await
-        .map_err(|e| format!("Failed to connect: {}", e))?;
+/// Session details for a pooled remote MCP connection
+#[derive(Debug, Clone, Serialize)]
+pub struct McpSessionInfo {
+    pub server_id: String,
+    /// Mcp-Session-Id assigned by the server, if it uses sessions
+    pub session_id: Option<String>,
+    pub connected: bool,
+    /// Seconds since the connection was last used
+    pub idle_secs: u64,
+}
 
-    let result = transport
-        .list_tools(None)
-        .await
-        .map_err(|e| format!("Failed to list tools: {}", e))?;
+/// Get the MCP session a server's pooled connection holds (None if not connected)
+///
+/// Lets users correlate client and server logs by session ID.
+#[tauri::command]
+pub async fn get_remote_mcp_session_info(
+    pool: State<'_, McpConnectionPoolState>,
+    id: String,
+) -> Result<Option<McpSessionInfo>, String> {
+    let conn = match pool.0.get(&id) {
+        Some(conn) => conn,
+        None => return Ok(None),
+    };
 
-    Ok(result.tools)
+    Ok(Some(McpSessionInfo {
+        server_id: id,
+        session_id: conn.session_id().await,
+        connected: conn.is_connected().await,
+        idle_secs: conn.idle_for().as_secs(),
+    }))
+}
+
+/// List tools from a remote MCP server, following pagination cursors
+#[tauri::command]
+pub async fn list_remote_mcp_tools(app: AppHandle, id: String) -> Result<Vec<Tool>, String> {
+    fetch_tools(&app, &id)
+        .await
+        .map_err(|e| format!("Failed to list tools: {}", e))
+}
+
+/// List a server's tools and refresh the tool cache
+async fn fetch_tools(app: &AppHandle, server_id: &str) -> McpResult<Vec<Tool>> {
+    let pool = app.state::<McpConnectionPoolState>();
+    let tools = pool
+        .0
+        .call(
+            server_id,
+            || create_server_transport(app, server_id, POOLED_TIMEOUT_MS),
+            |transport| Box::pin(async move { transport.list_all_tools().await }),
+        )
+        .await?;
+
+    app.state::<McpToolCacheState>()
+        .0
+        .insert(server_id.to_string(), tools.clone());
+    Ok(tools)
+}
+
+/// Check tool arguments against the tool's input schema
+///
+/// Uses the cached tool list, listing tools first if the server has none cached.
+/// Returns every violation; an unknown tool is `McpError::ToolNotFound`.
+async fn validate_tool_arguments(
+    app: &AppHandle,
+    server_id: &str,
+    tool_name: &str,
+    arguments: Option<&serde_json::Value>,
+) -> McpResult<Vec<ArgumentError>> {
+    let cached = app
+        .state::<McpToolCacheState>()
+        .0
+        .get(server_id)
+        .map(|tools| tools.clone());
+    let tools = match cached {
+        Some(tools) => tools,
+        None => fetch_tools(app, server_id).await?,
+    };
+
+    let tool = find_tool(&tools, tool_name)?;
+    Ok(validate_arguments(tool, arguments))
+}
+
+/// Validate tool arguments without calling the tool, e.g. as a form is filled in
+///
+/// Returns the schema violations; an empty list means the arguments are valid.
+#[tauri::command]
+pub async fn validate_remote_mcp_tool_args(
+    app: AppHandle,
+    server_id: String,
+    tool_name: String,
+    arguments: Option<serde_json::Value>,
+) -> Result<Vec<ArgumentError>, String> {
+    validate_tool_arguments(&app, &server_id, &tool_name, arguments.as_ref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Run a tool call whose JSON-RPC request ID is `call_id`
+///
+/// Events before the result are passed to `on_event`. A call cancelled through
+/// `cancel_remote_mcp_tool_call` sends notifications/cancelled to the server
+/// and returns `McpError::Cancelled`. The call is limited to `timeout_ms`, or
+/// the server's default tool timeout if not given.
+async fn run_tool_call(
+    app: &AppHandle,
+    server_id: &str,
+    tool_name: &str,
+    arguments: Option<serde_json::Value>,
+    call_id: &str,
+    timeout_ms: Option<u64>,
+    mut on_event: impl FnMut(ToolCallEvent),
+) -> McpResult<ToolCallResult> {
+    let budget_ms = tool_call_budget(app, server_id, timeout_ms)?;
+
+    // Catch schema violations before anything is sent
+    let errors = validate_tool_arguments(app, server_id, tool_name, arguments.as_ref()).await?;
+    if !errors.is_empty() {
+        return Err(McpError::InvalidToolArguments {
+            tool: tool_name.to_string(),
+            errors,
+        });
+    }
+
+    let pool = app.state::<McpConnectionPoolState>();
+    let calls = app.state::<McpToolCallsState>();
+    let request_id = serde_json::Value::String(call_id.to_string());
+
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    match calls.0.entry(call_id.to_string()) {
+        Entry::Occupied(_) => {
+            return Err(McpError::InvalidConfig(format!("Tool call {} is already running", call_id)));
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(cancel_tx);
+        }
+    }
+
+    let budget = std::time::Duration::from_millis(budget_ms);
+    let start = std::time::Instant::now();
+    let call = async {
+        let mut events = pool
+            .0
+            .call(
+                server_id,
+                || create_server_transport(app, server_id, POOLED_TIMEOUT_MS),
+                |transport| {
+                    let tool_name = tool_name.to_string();
+                    let arguments = arguments.clone();
+                    let request_id = request_id.clone();
+                    Box::pin(async move {
+                        transport
+                            .call_tool_streaming(&tool_name, arguments, Some(request_id), Some(budget))
+                            .await
+                    })
+                },
+            )
+            .await?;
+
+        while let Some(event) = events.recv().await {
+            match event? {
+                ToolCallEvent::Complete(result) => return Ok(result),
+                event => on_event(event),
+            }
+        }
+        Err::<ToolCallResult, McpError>(McpError::InvalidResponse(
+            "Tool call ended without a result".to_string(),
+        ))
+    };
+
+    let outcome = tokio::select! {
+        result = tokio::time::timeout(budget, call) => match result {
+            // Report the budget rather than whichever timer fired first
+            Ok(Err(McpError::ConnectionTimeout(_))) | Err(_) => Err(McpError::ConnectionTimeout(budget_ms)),
+            Ok(result) => result,
+        },
+        Ok(()) = cancel_rx => {
+            // Cancellation isn't a connection failure, so the pooled connection is kept
+            info!("Cancelling tool call {} on remote MCP server {}", call_id, server_id);
+            let notified = pool
+                .0
+                .call(
+                    server_id,
+                    || create_server_transport(app, server_id, POOLED_TIMEOUT_MS),
+                    |transport| {
+                        let request_id = request_id.clone();
+                        Box::pin(async move {
+                            transport.cancel_request(request_id, Some("Cancelled by user")).await
+                        })
+                    },
+                )
+                .await;
+            if let Err(e) = notified {
+                warn!("Failed to notify {} of cancelled tool call {}: {}", server_id, call_id, e);
+            }
+            Err(McpError::Cancelled)
+        }
+    };
+
+    calls.0.remove(call_id);
+
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    // Cancelled and rate-limited calls say nothing about the server's health
+    if !matches!(outcome, Err(McpError::Cancelled | McpError::RateLimited { .. })) {
+        app.state::<McpHealthMonitorState>()
+            .0
+            .record_latency(server_id, elapsed_ms, outcome.is_ok());
+    }
+    if elapsed_ms * 5 > budget_ms * 4 {
+        warn!(
+            "Slow MCP tool call: server_id={} tool={} elapsed_ms={} budget_ms={}",
+            server_id, tool_name, elapsed_ms, budget_ms
+        );
+    }
+    outcome
+}
+
+/// Time budget for a tool call: the given timeout, else the server default
+fn tool_call_budget(app: &AppHandle, server_id: &str, timeout_ms: Option<u64>) -> McpResult<u64> {
+    if let Some(ms) = timeout_ms {
+        validate_tool_timeout(ms).map_err(McpError::InvalidConfig)?;
+        return Ok(ms);
+    }
+
+    let db = app.state::<AgentDb>();
+    let conn = db.0.lock().map_err(|e| McpError::Internal(e.to_string()))?;
+    let default_ms: Option<u64> = conn
+        .query_row(
+            "SELECT default_tool_timeout_ms FROM remote_mcp_servers WHERE id = ?1",
+            params![server_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| McpError::Internal(e.to_string()))?
+        .flatten();
+    Ok(default_ms.unwrap_or(POOLED_TIMEOUT_MS))
+}
+
+fn validate_keepalive_interval(secs: u64) -> Result<(), String> {
+    if secs != 0 && !(MIN_KEEPALIVE_SECS..=MAX_KEEPALIVE_SECS).contains(&secs) {
+        return Err(format!(
+            "Keepalive interval must be 0 (disabled) or between {} and {} seconds (got {})",
+            MIN_KEEPALIVE_SECS, MAX_KEEPALIVE_SECS, secs
+        ));
+    }
+    Ok(())
+}
+
+fn validate_tool_timeout(timeout_ms: u64) -> Result<(), String> {
+    if timeout_ms == 0 || timeout_ms > MAX_TOOL_TIMEOUT_MS {
+        return Err(format!(
+            "Tool timeout must be between 1 and {}ms (got {})",
+            MAX_TOOL_TIMEOUT_MS, timeout_ms
+        ));
+    }
+    Ok(())
+}
+
+/// Set the time budget for tool calls on a server that don't pass their own
+///
+/// `None` restores the default of 60 seconds.
+#[tauri::command]
+pub async fn set_server_tool_timeout(
+    db: State<'_, AgentDb>,
+    server_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<(), String> {
+    if let Some(ms) = timeout_ms {
+        validate_tool_timeout(ms)?;
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_remote_mcp_table(&conn);
+    let updated = conn
+        .execute(
+            "UPDATE remote_mcp_servers SET default_tool_timeout_ms = ?1, updated_at = ?2 WHERE id = ?3",
+            params![timeout_ms.map(|ms| ms as i64), chrono::Utc::now().to_rfc3339(), server_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+    if updated == 0 {
+        return Err(format!("Server not found: {}", server_id));
+    }
+    Ok(())
+}
+
+/// Let a server's sampling requests run without asking the user first
+#[tauri::command]
+pub async fn set_mcp_sampling_auto_approve(
+    db: State<'_, AgentDb>,
+    server_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_remote_mcp_table(&conn);
+    let updated = conn
+        .execute(
+            "UPDATE remote_mcp_servers SET sampling_auto_approve = ?1, updated_at = ?2 WHERE id = ?3",
+            params![enabled, chrono::Utc::now().to_rfc3339(), server_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+    if updated == 0 {
+        return Err(format!("Server not found: {}", server_id));
+    }
+    Ok(())
+}
+
+/// Start or stop recording a server's requests and responses
+///
+/// Takes effect on the live connection; recorded exchanges are kept when tracing stops.
+#[tauri::command]
+pub async fn set_remote_mcp_debug_trace(
+    db: State<'_, AgentDb>,
+    traces: State<'_, McpTraceState>,
+    server_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_remote_mcp_table(&conn);
+    let updated = conn
+        .execute(
+            "UPDATE remote_mcp_servers SET debug_trace = ?1, updated_at = ?2 WHERE id = ?3",
+            params![enabled, chrono::Utc::now().to_rfc3339(), server_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+    if updated == 0 {
+        return Err(format!("Server not found: {}", server_id));
+    }
+    traces.for_server(&server_id).set_enabled(enabled);
+    info!(
+        "{} request tracing for remote MCP server {}",
+        if enabled { "Enabled" } else { "Disabled" },
+        server_id
+    );
+    Ok(())
+}
+
+/// Recorded exchanges for a server, oldest first, limited to the most recent `limit`
+#[tauri::command]
+pub async fn get_remote_mcp_trace(
+    traces: State<'_, McpTraceState>,
+    server_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<TraceEntry>, String> {
+    Ok(traces
+        .0
+        .get(&server_id)
+        .map(|trace| trace.entries(limit))
+        .unwrap_or_default())
+}
+
+/// Discard a server's recorded exchanges
+#[tauri::command]
+pub async fn clear_remote_mcp_trace(traces: State<'_, McpTraceState>, server_id: String) -> Result<(), String> {
+    if let Some(trace) = traces.0.get(&server_id) {
+        trace.clear();
+    }
+    Ok(())
+}
+
+/// Save a server's recorded exchanges as JSON to a file the user picks
+///
+/// Returns the written path, or None if the user cancelled the dialog.
+#[tauri::command]
+pub async fn export_remote_mcp_trace(
+    app: AppHandle,
+    traces: State<'_, McpTraceState>,
+    server_id: String,
+) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let entries = traces
+        .0
+        .get(&server_id)
+        .map(|trace| trace.entries(None))
+        .unwrap_or_default();
+    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+
+    // Server IDs are UUIDs; anything else is kept out of the suggested file name
+    let stem: String = server_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("JSON", &["json"])
+        .set_file_name(format!("mcp-trace-{}.json", stem))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let path = match rx.await.map_err(|e| e.to_string())? {
+        Some(path) => path.into_path().map_err(|e| e.to_string())?,
+        None => return Ok(None),
+    };
+
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    info!("Exported {} traced MCP exchanges to {}", entries.len(), path.display());
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+/// Content entry of a tool call response
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolResponseContent {
+    Text {
+        text: String,
+    },
+    /// Image saved to `file_path`; `data` keeps the base64 only if saving failed
+    Image {
+        mime_type: String,
+        file_path: Option<String>,
+        data: Option<String>,
+    },
+    Resource {
+        resource: EmbeddedResource,
+    },
+}
+
+/// Result of a remote tool call, shaped for the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCallResponse {
+    pub content: Vec<ToolResponseContent>,
+    /// Text parts joined with newlines
+    pub combined_text: String,
+    pub is_error: bool,
+    /// Structured result, for servers that return one
+    pub structured_content: Option<serde_json::Value>,
+    /// Server that answered, which differs from the one asked if it failed over
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>,
+}
+
+/// Convert a tool result for the frontend, writing images into `image_dir`
+fn process_tool_result(result: ToolCallResult, image_dir: &std::path::Path) -> ToolCallResponse {
+    let mut texts = Vec::new();
+    let content = result
+        .content
+        .into_iter()
+        .map(|item| match item {
+            ToolResultContent::Text { text } => {
+                texts.push(text.clone());
+                ToolResponseContent::Text { text }
+            }
+            ToolResultContent::Image { data, mime_type } => match save_tool_image(&data, &mime_type, image_dir) {
+                Ok(path) => ToolResponseContent::Image {
+                    mime_type,
+                    file_path: Some(path.to_string_lossy().to_string()),
+                    data: None,
+                },
+                Err(e) => {
+                    warn!("Failed to save tool result image: {}", e);
+                    ToolResponseContent::Image {
+                        mime_type,
+                        file_path: None,
+                        data: Some(data),
+                    }
+                }
+            },
+            ToolResultContent::Resource { resource } => ToolResponseContent::Resource { resource },
+        })
+        .collect();
+
+    ToolCallResponse {
+        content,
+        combined_text: texts.join("\n"),
+        is_error: result.is_error.unwrap_or(false),
+        structured_content: result.structured_content,
+        served_by: None,
+    }
+}
+
+/// Decode a base64 image into a uniquely named file
+fn save_tool_image(data: &str, mime_type: &str, dir: &std::path::Path) -> Result<std::path::PathBuf, String> {
+    let bytes = STANDARD.decode(data.trim()).map_err(|e| format!("Invalid image data: {}", e))?;
+    // Extension comes from a fixed list so the server can't influence the path
+    let extension = match mime_type {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        _ => "bin",
+    };
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let path = dir.join(format!("{}.{}", uuid::Uuid::new_v4(), extension));
+    std::fs::write(&path, bytes).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(path)
+}
+
+/// Directory tool result images are written to
+fn tool_image_dir(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("mcp-tool-images"))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
 }
 
 /// Call a tool on a remote MCP server
+///
+/// Progress notifications are emitted as `mcp-tool-progress` while the call runs.
+/// Passing a `call_id` lets the call be cancelled with `cancel_remote_mcp_tool_call`,
+/// and `timeout_ms` overrides the server's default tool timeout. A rate-limited call
+/// is retried once if the server asks to wait no longer than `max_rate_limit_wait_ms`.
+/// If the server can't be reached, the call moves down its chain of fallback servers.
 #[tauri::command]
 pub async fn call_remote_mcp_tool(
-    db: State<'_, AgentDb>,
+    app: AppHandle,
     server_id: String,
     tool_name: String,
     arguments: Option<serde_json::Value>,
-) -> Result<serde_json::Value, String> {
-    // Get server details in a scoped block to release the lock
-    let (endpoint, auth_config_str) = {
+    call_id: Option<String>,
+    timeout_ms: Option<u64>,
+    max_rate_limit_wait_ms: Option<u64>,
+) -> Result<ToolCallResponse, String> {
+    let call_id = call_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let max_wait_ms = max_rate_limit_wait_ms.unwrap_or(DEFAULT_RATE_LIMIT_WAIT_MS);
+
+    // Forward progress so long-running calls can show a percentage
+    let mut forward_progress = |event: ToolCallEvent| {
+        if let ToolCallEvent::Progress(progress) = event {
+            let _ = app.emit(
+                "mcp-tool-progress",
+                serde_json::json!({
+                    "server_id": server_id,
+                    "token": progress.progress_token,
+                    "progress": progress.progress,
+                    "total": progress.total,
+                }),
+            );
+        }
+    };
+
+    let mut served_by = server_id.clone();
+    let mut tried = HashSet::new();
+    let result = loop {
+        tried.insert(served_by.clone());
+        let mut result = run_tool_call(
+            &app,
+            &served_by,
+            &tool_name,
+            arguments.clone(),
+            &call_id,
+            timeout_ms,
+            &mut forward_progress,
+        )
+        .await;
+        if let Err(McpError::RateLimited {
+            retry_after_ms: Some(wait_ms),
+        }) = result
+        {
+            if wait_ms <= max_wait_ms {
+                info!("Tool {} on {} was rate limited, retrying in {}ms", tool_name, served_by, wait_ms);
+                tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+                result = run_tool_call(
+                    &app,
+                    &served_by,
+                    &tool_name,
+                    arguments.clone(),
+                    &call_id,
+                    timeout_ms,
+                    &mut forward_progress,
+                )
+                .await;
+            }
+        }
+
+        // Only failures to reach the server fail over; errors the server answered with are final
+        let error = match result {
+            Err(ref e) if e.is_transport_error() => e.to_string(),
+            _ => break result,
+        };
+        let fallback = match fallback_server_id(&app, &served_by) {
+            Some(fallback) if tried.contains(&fallback) => {
+                warn!("Fallback chain of {} loops back to {}; not retrying", server_id, fallback);
+                break result;
+            }
+            Some(fallback) => fallback,
+            None => break result,
+        };
+        warn!(
+            "Tool {} failed on {} ({}), retrying on fallback {}",
+            tool_name, served_by, error, fallback
+        );
+        app.state::<McpHealthMonitorState>()
+            .0
+            .record_failover(&served_by, &fallback, &error);
+        served_by = fallback;
+    };
+    let result = result.map_err(|e| format!("Tool call failed: {}", e))?;
+
+    let mut response = process_tool_result(result, &tool_image_dir(&app)?);
+    response.served_by = Some(served_by);
+    Ok(response)
+}
+
+/// Call a tool on a remote MCP server, streaming progress to the frontend
+///
+/// Progress and log events are emitted as `mcp:tool-event:{call_id}`; the
+/// final result is returned.
+#[tauri::command]
+pub async fn call_remote_mcp_tool_streaming(
+    app: AppHandle,
+    server_id: String,
+    tool_name: String,
+    arguments: Option<serde_json::Value>,
+    call_id: String,
+) -> Result<ToolCallResponse, String> {
+    let event_name = format!("mcp:tool-event:{}", call_id);
+    let result = run_tool_call(&app, &server_id, &tool_name, arguments, &call_id, None, |event| {
+        let _ = app.emit(&event_name, &event);
+    })
+    .await
+    .map_err(|e| format!("Tool call failed: {}", e))?;
+
+    Ok(process_tool_result(result, &tool_image_dir(&app)?))
+}
+
+/// Cancel an in-flight tool call by the call ID it was started with
+///
+/// Returns false if no call with that ID is running.
+#[tauri::command]
+pub async fn cancel_remote_mcp_tool_call(
+    calls: State<'_, McpToolCallsState>,
+    call_id: String,
+) -> Result<bool, String> {
+    match calls.0.remove(&call_id) {
+        Some((_, cancel)) => Ok(cancel.send(()).is_ok()),
+        None => Ok(false),
+    }
+}
+
+/// List resources from a remote MCP server, following pagination cursors
+#[tauri::command]
+pub async fn list_remote_mcp_resources(
+    app: AppHandle,
+    pool: State<'_, McpConnectionPoolState>,
+    id: String,
+) -> Result<Vec<Resource>, String> {
+    pool.0
+        .call(
+            &id,
+            || create_server_transport(&app, &id, POOLED_TIMEOUT_MS),
+            |transport| Box::pin(async move { transport.list_all_resources().await }),
+        )
+        .await
+        .map_err(|e| format!("Failed to list resources: {}", e))
+}
+
+/// Read a resource from a remote MCP server
+#[tauri::command]
+pub async fn read_remote_mcp_resource(
+    app: AppHandle,
+    pool: State<'_, McpConnectionPoolState>,
+    server_id: String,
+    uri: String,
+) -> Result<Vec<ResourceContents>, String> {
+    let result = pool
+        .0
+        .call(
+            &server_id,
+            || create_server_transport(&app, &server_id, POOLED_TIMEOUT_MS),
+            |transport| {
+                let uri = uri.clone();
+                Box::pin(async move { transport.read_resource(&uri).await })
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to read resource: {}", e))?;
+
+    let result: ReadResourceResult =
+        serde_json::from_value(result).map_err(|e| format!("Invalid resource contents: {}", e))?;
+
+    Ok(result.contents.into_iter().map(ResourceContents::from).collect())
+}
+
+/// List prompts from a remote MCP server, following pagination cursors
+#[tauri::command]
+pub async fn list_remote_mcp_prompts(
+    app: AppHandle,
+    pool: State<'_, McpConnectionPoolState>,
+    id: String,
+) -> Result<Vec<Prompt>, String> {
+    pool.0
+        .call(
+            &id,
+            || create_server_transport(&app, &id, POOLED_TIMEOUT_MS),
+            |transport| Box::pin(async move { transport.list_all_prompts().await }),
+        )
+        .await
+        .map_err(|e| format!("Failed to list prompts: {}", e))
+}
+
+/// Get a prompt from a remote MCP server
+#[tauri::command]
+pub async fn get_remote_mcp_prompt(
+    app: AppHandle,
+    pool: State<'_, McpConnectionPoolState>,
+    server_id: String,
+    name: String,
+    arguments: Option<HashMap<String, String>>,
+) -> Result<GetPromptResult, String> {
+    let result = pool
+        .0
+        .call(
+            &server_id,
+            || create_server_transport(&app, &server_id, POOLED_TIMEOUT_MS),
+            |transport| {
+                let name = name.clone();
+                let arguments = arguments.clone();
+                Box::pin(async move { transport.get_prompt(&name, arguments).await })
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to get prompt: {}", e))?;
+
+    serde_json::from_value(result).map_err(|e| format!("Invalid prompt response: {}", e))
+}
+
+/// Subscribe to updates for a resource on a remote MCP server
+///
+/// Updates are emitted to the frontend as `mcp:resource-updated` events.
+#[tauri::command]
+pub async fn subscribe_remote_mcp_resource(
+    app: AppHandle,
+    pool: State<'_, McpConnectionPoolState>,
+    forwarders: State<'_, McpResourceForwardersState>,
+    server_id: String,
+    uri: String,
+) -> Result<(), String> {
+    let mut updates = pool
+        .0
+        .call(
+            &server_id,
+            || create_server_transport(&app, &server_id, POOLED_TIMEOUT_MS),
+            |transport| {
+                let uri = uri.clone();
+                Box::pin(async move {
+                    transport
+                        .subscribe_resource(&uri)
+                        .await
+                        .map(|_| transport.resource_updates())
+                })
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to subscribe to resource: {}", e))?;
+
+    // One forwarder per server; it exits once the pooled transport is dropped
+    if !forwarders.0.insert(server_id.clone()) {
+        return Ok(());
+    }
+
+    let forwarders = forwarders.0.clone();
+    tokio::spawn(async move {
+        loop {
+            match updates.recv().await {
+                Ok(update) => {
+                    let _ = app.emit(
+                        "mcp:resource-updated",
+                        serde_json::json!({ "serverId": server_id, "uri": update.uri }),
+                    );
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Skipped {} resource updates for {}", skipped, server_id);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        forwarders.remove(&server_id);
+    });
+
+    Ok(())
+}
+
+/// Unsubscribe from updates for a resource on a remote MCP server
+#[tauri::command]
+pub async fn unsubscribe_remote_mcp_resource(
+    app: AppHandle,
+    pool: State<'_, McpConnectionPoolState>,
+    server_id: String,
+    uri: String,
+) -> Result<(), String> {
+    pool.0
+        .call(
+            &server_id,
+            || create_server_transport(&app, &server_id, POOLED_TIMEOUT_MS),
+            |transport| {
+                let uri = uri.clone();
+                Box::pin(async move { transport.unsubscribe_resource(&uri).await })
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to unsubscribe from resource: {}", e))
+}
+
+/// Close the pooled connection to a remote MCP server
+///
+/// Returns true if a connection was open.
+#[tauri::command]
+pub async fn disconnect_remote_mcp_server(
+    pool: State<'_, McpConnectionPoolState>,
+    id: String,
+) -> Result<bool, String> {
+    Ok(pool.0.disconnect(&id).await)
+}
+
+/// Start periodic health checks for all servers with health checks enabled
+///
+/// Returns the number of monitored servers.
+#[tauri::command]
+pub async fn start_mcp_health_monitoring(
+    db: State<'_, AgentDb>,
+    health: State<'_, McpHealthMonitorState>,
+) -> Result<usize, String> {
+    let servers = load_monitored_servers(&db)?;
+    let count = servers.len();
+    health.0.start_monitoring(servers);
+    Ok(count)
+}
+
+/// Reload the monitored server list and restart health checks
+///
+/// Call after servers are added, removed, or have health settings changed.
+/// Returns the number of monitored servers.
+#[tauri::command]
+pub async fn refresh_health_monitoring(
+    db: State<'_, AgentDb>,
+    health: State<'_, McpHealthMonitorState>,
+) -> Result<usize, String> {
+    let servers = load_monitored_servers(&db)?;
+
+    // Drop cached health for servers that are no longer monitored
+    for stale in health.0.get_all_health() {
+        if !servers.iter().any(|s| s.server_id == stale.server_id) {
+            health.0.remove_server(&stale.server_id);
+        }
+    }
+
+    let count = servers.len();
+    health.0.start_monitoring(servers);
+    info!("Refreshed MCP health monitoring for {} servers", count);
+    Ok(count)
+}
+
+/// Stop periodic health checks
+#[tauri::command]
+pub async fn stop_mcp_health_monitoring(health: State<'_, McpHealthMonitorState>) -> Result<(), String> {
+    health.0.stop_monitoring();
+    Ok(())
+}
+
+/// Start periodic health checks for the servers in a group
+///
+/// Returns the number of servers monitored in the group.
+#[tauri::command]
+pub async fn start_mcp_group_monitoring(
+    db: State<'_, AgentDb>,
+    health: State<'_, McpHealthMonitorState>,
+    group: String,
+) -> Result<usize, String> {
+    let servers = load_monitored_servers(&db)?;
+    Ok(health.0.start_group(group.trim(), servers))
+}
+
+/// Stop periodic health checks for the servers in a group
+///
+/// The group stays stopped across monitoring restarts until started again.
+#[tauri::command]
+pub async fn stop_mcp_group_monitoring(
+    health: State<'_, McpHealthMonitorState>,
+    group: String,
+) -> Result<usize, String> {
+    Ok(health.0.stop_group(group.trim()))
+}
+
+/// Get the latest monitored health of a remote MCP server
+#[tauri::command]
+pub async fn get_mcp_server_health(
+    health: State<'_, McpHealthMonitorState>,
+    id: String,
+) -> Result<Option<ServerHealth>, String> {
+    Ok(health.0.get_health(&id))
+}
+
+/// Get recent health check samples for a server, oldest first
+#[tauri::command]
+pub async fn get_server_health_history(
+    health: State<'_, McpHealthMonitorState>,
+    server_id: String,
+) -> Result<Vec<HealthSample>, String> {
+    Ok(health.0.get_history(&server_id))
+}
+
+/// Get p50/p95/p99 latency and error rate for a server over a time window
+///
+/// Covers health checks and tool calls. Defaults to the last hour.
+#[tauri::command]
+pub async fn get_remote_mcp_metrics(
+    health: State<'_, McpHealthMonitorState>,
+    server_id: String,
+    window_secs: Option<u64>,
+) -> Result<ServerMetrics, String> {
+    Ok(health.0.get_server_metrics(&server_id, window_secs.unwrap_or(3600)))
+}
+
+/// Set the minimum level of log messages a server sends
+///
+/// Levels follow syslog: debug, info, notice, warning, error, critical, alert, emergency.
+#[tauri::command]
+pub async fn set_remote_mcp_log_level(
+    app: AppHandle,
+    pool: State<'_, McpConnectionPoolState>,
+    server_id: String,
+    level: String,
+) -> Result<(), String> {
+    if !MCP_LOG_LEVELS.contains(&level.as_str()) {
+        return Err(format!("Invalid log level: {}", level));
+    }
+
+    pool.0
+        .call(
+            &server_id,
+            || create_server_transport(&app, &server_id, POOLED_TIMEOUT_MS),
+            |transport| {
+                let level = level.clone();
+                Box::pin(async move { transport.set_log_level(&level).await })
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to set log level: {}", e))
+}
+
+/// Get the most recent log messages from a server, oldest first
+#[tauri::command]
+pub async fn get_remote_mcp_logs(
+    logs: State<'_, McpServerLogsState>,
+    server_id: String,
+) -> Result<Vec<McpServerLogEntry>, String> {
+    Ok(logs
+        .0
+        .get(&server_id)
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// Configured root directories for a server, in the order they are offered
+fn load_roots(conn: &rusqlite::Connection, server_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT path FROM remote_mcp_roots WHERE server_id = ?1 ORDER BY position")?;
+    let roots = stmt
+        .query_map(params![server_id], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(roots)
+}
+
+/// Replace a server's configured root directories
+fn store_roots(conn: &rusqlite::Connection, server_id: &str, paths: &[String]) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM remote_mcp_roots WHERE server_id = ?1", params![server_id])?;
+    for (position, path) in paths.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO remote_mcp_roots (server_id, path, position) VALUES (?1, ?2, ?3)",
+            params![server_id, path, position as i64],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Roots offered to a server: the active project first, then its configured directories
+fn server_roots(active_project: Option<&str>, configured: &[String]) -> Vec<Root> {
+    let mut seen = HashSet::new();
+    active_project
+        .into_iter()
+        .chain(configured.iter().map(String::as_str))
+        .filter(|path| seen.insert(*path))
+        .filter_map(Root::from_path)
+        .collect()
+}
+
+/// Check that a root is an existing absolute directory, returning it trimmed
+fn validate_root_path(path: &str) -> Result<String, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("Root path is required".to_string());
+    }
+    let dir = std::path::Path::new(path);
+    if !dir.is_absolute() {
+        return Err(format!("Root path must be absolute: {}", path));
+    }
+    if !dir.is_dir() {
+        return Err(format!("Root path is not a directory: {}", path));
+    }
+    Ok(path.to_string())
+}
+
+/// Validate root paths, dropping duplicates but keeping their order
+fn validate_root_paths(paths: Vec<String>) -> Result<Vec<String>, String> {
+    let mut valid: Vec<String> = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = validate_root_path(&path)?;
+        if !valid.contains(&path) {
+            valid.push(path);
+        }
+    }
+    Ok(valid)
+}
+
+/// Set the extra directories a server may work in, offered after the active project
+///
+/// A connected server is sent notifications/roots/list_changed. Returns the stored paths.
+#[tauri::command]
+pub async fn set_mcp_roots(
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    roots_state: State<'_, McpRootsState>,
+    server_id: String,
+    paths: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let paths = validate_root_paths(paths)?;
+    {
         let conn = db.0.lock().map_err(|e| e.to_string())?;
-        let result: (String, Option<String>) = conn
+        let _ = init_remote_mcp_table(&conn);
+        let exists: bool = conn
             .query_row(
-                "SELECT endpoint, auth_config FROM remote_mcp_servers WHERE id = ?1",
+                "SELECT EXISTS(SELECT 1 FROM remote_mcp_servers WHERE id = ?1)",
                 params![server_id],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                |row| row.get(0),
             )
-            .map_err(|e| format!("Server not found: {}", e))?;
-        result
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("Server not found: {}", server_id));
+        }
+        store_roots(&conn, &server_id, &paths).map_err(|e| e.to_string())?;
+    } // conn is dropped here
+
+    // Servers that aren't pooled get the roots when they next connect
+    if let Some(connection) = pool.0.get(&server_id) {
+        let active_project = roots_state.0.read().clone();
+        if let Err(e) = connection.update_roots(server_roots(active_project.as_deref(), &paths)).await {
+            warn!("Failed to notify {} that its roots changed: {}", server_id, e);
+        }
+    }
+
+    info!("Set {} roots for remote MCP server {}", paths.len(), server_id);
+    Ok(paths)
+}
+
+/// Set the project directory offered to every server as its first root (None to clear)
+#[tauri::command]
+pub async fn set_mcp_active_project(
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    roots_state: State<'_, McpRootsState>,
+    project_path: Option<String>,
+) -> Result<(), String> {
+    let project_path = project_path.map(|path| validate_root_path(&path)).transpose()?;
+    if *roots_state.0.read() == project_path {
+        return Ok(());
+    }
+    *roots_state.0.write() = project_path.clone();
+
+    let pooled: Vec<(String, Vec<String>)> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let _ = init_remote_mcp_table(&conn);
+        let mut stmt = conn
+            .prepare("SELECT id FROM remote_mcp_servers")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let mut pooled = Vec::new();
+        for id in ids.into_iter().filter(|id| pool.0.contains(id)) {
+            let roots = load_roots(&conn, &id).map_err(|e| e.to_string())?;
+            pooled.push((id, roots));
+        }
+        pooled
     }; // conn is dropped here
 
-    // Parse auth config
-    let auth: Option<Box<dyn McpAuth>> = if let Some(config_str) = auth_config_str {
-        let config: McpAuthConfig = serde_json::from_str(&config_str)
-            .map_err(|e| format!("Invalid auth config: {}", e))?;
-        Some(create_auth_from_config(&config))
-    } else {
-        None
-    };
+    for (server_id, roots) in pooled {
+        if let Some(connection) = pool.0.get(&server_id) {
+            if let Err(e) = connection.update_roots(server_roots(project_path.as_deref(), &roots)).await {
+                warn!("Failed to notify {} that its roots changed: {}", server_id, e);
+            }
+        }
+    }
+    Ok(())
+}
 
-    // Connect and call tool
-    let mut transport = StreamableHttpTransport::new(&endpoint, auth, 60000) // Longer timeout for tool calls
-        .map_err(|e| format!("Failed to create transport: {}", e))?;
+/// Set when a slow health check counts as degraded for one server
+///
+/// Latency must exceed both `multiplier` times the moving average and
+/// `floor_ms`. Pass `None` for either to use the monitor default.
+#[tauri::command]
+pub async fn set_server_degraded_thresholds(
+    db: State<'_, AgentDb>,
+    health: State<'_, McpHealthMonitorState>,
+    server_id: String,
+    multiplier: Option<f64>,
+    floor_ms: Option<u64>,
+) -> Result<DegradedThresholds, String> {
+    validate_degraded_thresholds(multiplier, floor_ms)?;
 
-    transport
-        .connect()
-        .await
-        .map_err(|e| format!("Failed to connect: {}", e))?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_remote_mcp_table(&conn);
+    let updated = conn
+        .execute(
+            "UPDATE remote_mcp_servers SET degraded_multiplier = ?1, degraded_floor_ms = ?2, updated_at = ?3 WHERE id = ?4",
+            params![multiplier, floor_ms.map(|ms| ms as i64), chrono::Utc::now().to_rfc3339(), server_id],
+        )
+        .map_err(|e| e.to_string())?;
+    drop(conn); // Release lock
 
-    let result = transport
-        .call_tool(&tool_name, arguments)
-        .await
-        .map_err(|e| format!("Tool call failed: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Server not found: {}", server_id));
+    }
+
+    health.0.set_degraded_thresholds(&server_id, multiplier, floor_ms);
+    Ok(health.0.degraded_thresholds(&server_id))
+}
 
-    // Convert result to JSON
-    serde_json::to_value(&result).map_err(|e| e.to_string())
+/// Reject multipliers that would flag normal checks and absurd floors
+fn validate_degraded_thresholds(multiplier: Option<f64>, floor_ms: Option<u64>) -> Result<(), String> {
+    if let Some(m) = multiplier {
+        if !m.is_finite() || !(1.0..=100.0).contains(&m) {
+            return Err(format!("Degraded multiplier must be between 1 and 100 (got {})", m));
+        }
+    }
+    if let Some(ms) = floor_ms {
+        if ms > 600_000 {
+            return Err(format!("Degraded floor must be at most 600000ms (got {})", ms));
+        }
+    }
+    Ok(())
 }
 
 /// Update remote MCP server configuration
 #[tauri::command]
 pub async fn update_remote_mcp_server(
     db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    health: State<'_, McpHealthMonitorState>,
+    tools: State<'_, McpToolCacheState>,
     id: String,
     name: Option<String>,
     description: Option<String>,
@@ -404,17 +2677,44 @@ pub async fn update_remote_mcp_server(
     api_key_value: Option<String>,
     health_enabled: Option<bool>,
     health_interval: Option<u64>,
+    ca_cert_path: Option<String>,
+    client_cert_path: Option<String>,
+    client_key_path: Option<String>,
+    accept_invalid_certs: Option<bool>,
+    keepalive_interval_secs: Option<u64>,
+    extra_headers: Option<HashMap<String, String>>,
+    group: Option<String>,
+    fallback_server_id: Option<String>,
 ) -> Result<RemoteMcpServerInfo, String> {
+    if let Some(ref endpoint) = endpoint {
+        validate_endpoint(endpoint)?;
+    }
+    if let Some(secs) = keepalive_interval_secs {
+        validate_keepalive_interval(secs)?;
+    }
+    if client_cert_path.is_some() != client_key_path.is_some() {
+        return Err("Client certificate and key must be updated together".to_string());
+    }
+    validate_tls_paths(
+        ca_cert_path.as_deref(),
+        client_cert_path.as_deref(),
+        client_key_path.as_deref(),
+    )?;
+    let extra_headers_str = extra_headers.as_ref().map(extra_headers_column).transpose()?;
+
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     // Get current values
-    let current: RemoteMcpServerInfo = conn
+    let mut current: RemoteMcpServerInfo = conn
         .query_row(
             "SELECT id, name, description, endpoint, auth_type, status, health_enabled,
-             health_interval, last_health_check, latency_ms, created_at, updated_at
+             health_interval, last_health_check, latency_ms, created_at, updated_at, capabilities,
+             default_tool_timeout_ms, keepalive_interval_secs, sampling_auto_approve, debug_trace, extra_headers,
+             group_name, fallback_server_id
              FROM remote_mcp_servers WHERE id = ?1",
             params![id],
             |row| {
+                let cached = cached_capabilities(row.get(12)?);
                 Ok(RemoteMcpServerInfo {
                     id: row.get(0)?,
                     name: row.get(1)?,
@@ -426,12 +2726,36 @@ pub async fn update_remote_mcp_server(
                     health_interval: row.get(7)?,
                     last_health_check: row.get(8).ok(),
                     latency_ms: row.get(9).ok(),
+                    protocol_version: cached.protocol_version,
+                    capabilities: cached.capabilities,
+                    server_info: cached.server_info,
+                    instructions: cached.instructions,
+                    default_tool_timeout_ms: row.get(13)?,
+                    keepalive_interval_secs: row
+                        .get::<_, Option<u64>>(14)?
+                        .unwrap_or(DEFAULT_KEEPALIVE_SECS),
+                    roots: Vec::new(),
+                    sampling_auto_approve: row.get::<_, Option<bool>>(15)?.unwrap_or(false),
+                    debug_trace: row.get::<_, Option<bool>>(16)?.unwrap_or(false),
+                    extra_headers: extra_headers_from_column(row.get(17)?),
+                    group: row.get(18)?,
+                    fallback_server_id: row.get(19)?,
+                    warning: None,
                     created_at: row.get(10)?,
                     updated_at: row.get(11)?,
                 })
             },
         )
         .map_err(|e| format!("Server not found: {}", e))?;
+    current.roots = load_roots(&conn, &id).map_err(|e| e.to_string())?;
+
+    let name = name.map(|name| name.trim().to_string());
+    if let Some(ref name) = name {
+        if name.is_empty() {
+            return Err("Server name is required".to_string());
+        }
+        ensure_unique_name(&conn, name, Some(id.as_str()))?;
+    }
 
     // Build updated values
     let new_name = name.unwrap_or(current.name);
@@ -440,32 +2764,38 @@ pub async fn update_remote_mcp_server(
     let new_auth_type = auth_type.unwrap_or(current.auth_type);
     let new_health_enabled = health_enabled.unwrap_or(current.health_enabled);
     let new_health_interval = health_interval.unwrap_or(current.health_interval);
+    let new_keepalive_interval_secs = keepalive_interval_secs.unwrap_or(current.keepalive_interval_secs);
 
     // Build new auth config if auth changed
     let auth_config = match new_auth_type.as_str() {
         "bearer" => {
             if let Some(t) = token {
-                Some(serde_json::json!({ "type": "bearer", "token": t }))
+                Some(serde_json::json!({ "type": "bearer", "token": t.trim() }))
             } else {
                 None
             }
         }
         "api-key" => {
             if let (Some(h), Some(v)) = (api_key_header, api_key_value) {
-                Some(serde_json::json!({ "type": "api-key", "header": h, "value": v }))
+                Some(serde_json::json!({ "type": "api-key", "header": h.trim(), "value": v.trim() }))
             } else {
                 None
             }
         }
+        // OAuth tokens are only set when adding the server and then rotated by refresh
+        "oauth2" => None,
         _ => Some(serde_json::json!({ "type": "none" })),
     };
 
-    let auth_config_str = auth_config.map(|c| serde_json::to_string(&c).unwrap_or_default());
+    let auth_config_str = auth_config
+        .map(|c| store_auth_config(&id, &c))
+        .transpose()
+        .map_err(|e| e.to_string())?;
 
     // Update database
     if let Some(ref config) = auth_config_str {
         conn.execute(
-            "UPDATE remote_mcp_servers SET name = ?1, description = ?2, endpoint = ?3, auth_type = ?4, auth_config = ?5, health_enabled = ?6, health_interval = ?7, updated_at = ?8 WHERE id = ?9",
+            "UPDATE remote_mcp_servers SET name = ?1, description = ?2, endpoint = ?3, auth_type = ?4, auth_config = ?5, needs_credentials = 0, health_enabled = ?6, health_interval = ?7, updated_at = ?8 WHERE id = ?9",
             params![new_name, new_description, new_endpoint, new_auth_type, config, new_health_enabled, new_health_interval as i64, chrono::Utc::now().to_rfc3339(), id],
         ).map_err(|e| e.to_string())?;
     } else {
@@ -475,6 +2805,71 @@ pub async fn update_remote_mcp_server(
         ).map_err(|e| e.to_string())?;
     }
 
+    // TLS settings: omitted values are kept, empty paths clear them
+    conn.execute(
+        "UPDATE remote_mcp_servers SET
+         ca_cert_path = CASE WHEN ?1 IS NULL THEN ca_cert_path ELSE NULLIF(?1, '') END,
+         client_cert_path = CASE WHEN ?2 IS NULL THEN client_cert_path ELSE NULLIF(?2, '') END,
+         client_key_path = CASE WHEN ?3 IS NULL THEN client_key_path ELSE NULLIF(?3, '') END,
+         accept_invalid_certs = COALESCE(?4, accept_invalid_certs)
+         WHERE id = ?5",
+        params![ca_cert_path, client_cert_path, client_key_path, accept_invalid_certs, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE remote_mcp_servers SET keepalive_interval_secs = ?1 WHERE id = ?2",
+        params![new_keepalive_interval_secs as i64, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // An empty map clears the extra headers
+    if let Some(ref headers) = extra_headers_str {
+        conn.execute(
+            "UPDATE remote_mcp_servers SET extra_headers = ?1 WHERE id = ?2",
+            params![headers, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // A blank group removes the server from its group
+    let new_group = match group {
+        Some(group) => {
+            let group = normalize_group(Some(group));
+            conn.execute(
+                "UPDATE remote_mcp_servers SET group_name = ?1 WHERE id = ?2",
+                params![group, id],
+            )
+            .map_err(|e| e.to_string())?;
+            group
+        }
+        None => current.group,
+    };
+
+    // An empty ID removes the fallback
+    let new_fallback_server_id = match fallback_server_id {
+        Some(fallback) => {
+            let fallback = Some(fallback).filter(|f| !f.is_empty());
+            if let Some(ref fallback) = fallback {
+                validate_fallback(&conn, Some(id.as_str()), fallback)?;
+            }
+            conn.execute(
+                "UPDATE remote_mcp_servers SET fallback_server_id = ?1 WHERE id = ?2",
+                params![fallback, id],
+            )
+            .map_err(|e| e.to_string())?;
+            fallback
+        }
+        None => current.fallback_server_id,
+    };
+
+    drop(conn); // Release lock
+
+    // Pooled connection and cached tools came from the old configuration
+    pool.0.disconnect(&id).await;
+    tools.0.remove(&id);
+    restart_health_monitoring(&db, &health.0);
+
     info!("Updated remote MCP server: {}", id);
 
     // Return updated server
@@ -489,7 +2884,987 @@ pub async fn update_remote_mcp_server(
         health_interval: new_health_interval,
         last_health_check: current.last_health_check,
         latency_ms: current.latency_ms,
+        protocol_version: current.protocol_version,
+        capabilities: current.capabilities,
+        server_info: current.server_info,
+        instructions: current.instructions,
+        default_tool_timeout_ms: current.default_tool_timeout_ms,
+        keepalive_interval_secs: new_keepalive_interval_secs,
+        roots: current.roots,
+        sampling_auto_approve: current.sampling_auto_approve,
+        debug_trace: current.debug_trace,
+        extra_headers: extra_headers.unwrap_or(current.extra_headers),
+        group: new_group,
+        fallback_server_id: new_fallback_server_id,
+        warning: None,
         created_at: current.created_at,
         updated_at: chrono::Utc::now().to_rfc3339(),
     })
 }
+
+/// Servers synced between Opcode and a project's .mcp.json or an export file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct McpSyncResult {
+    /// Names written or imported
+    pub synced: Vec<String>,
+    /// Names already taken by a different server; pass `overwrite` to replace them
+    pub conflicts: Vec<String>,
+    /// Names that can't be synced, e.g. stdio servers on import
+    pub skipped: Vec<String>,
+}
+
+/// Headers carrying an auth config, in the form Claude Code's .mcp.json uses
+fn auth_headers(auth: &McpAuthConfig) -> HashMap<String, String> {
+    match auth {
+        McpAuthConfig::None => HashMap::new(),
+        McpAuthConfig::Bearer { token } => {
+            HashMap::from([("Authorization".to_string(), format!("Bearer {}", token))])
+        }
+        McpAuthConfig::ApiKey { header, value } => HashMap::from([(header.clone(), value.clone())]),
+        McpAuthConfig::CustomHeader { headers } => headers.clone(),
+        // Claude Code can't refresh the token, so this works until it expires
+        McpAuthConfig::OAuth { access_token, .. } => {
+            HashMap::from([("Authorization".to_string(), format!("Bearer {}", access_token))])
+        }
+    }
+}
+
+/// Auth type and config for headers read from .mcp.json
+fn auth_from_headers(headers: &HashMap<String, String>) -> (&'static str, McpAuthConfig) {
+    let entries: Vec<_> = headers.iter().collect();
+    match entries[..] {
+        [] => ("none", McpAuthConfig::None),
+        [(name, value)] if name.eq_ignore_ascii_case("authorization") && value.starts_with("Bearer ") => (
+            "bearer",
+            McpAuthConfig::Bearer {
+                token: value["Bearer ".len()..].to_string(),
+            },
+        ),
+        [(name, value)] if !name.eq_ignore_ascii_case("authorization") => (
+            "api-key",
+            McpAuthConfig::ApiKey {
+                header: name.clone(),
+                value: value.clone(),
+            },
+        ),
+        _ => (
+            "custom-header",
+            McpAuthConfig::CustomHeader {
+                headers: headers.clone(),
+            },
+        ),
+    }
+}
+
+fn validate_project_path(project_path: &str) -> Result<(), String> {
+    if !std::path::Path::new(project_path).is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+    Ok(())
+}
+
+/// Write remote MCP servers into a project's .mcp.json so Claude Code can use them
+///
+/// Servers are added as `http` entries with their auth as headers, so the file
+/// then holds their credentials. Entries that already exist with a different
+/// configuration are reported as conflicts unless `overwrite` is set.
+#[tauri::command]
+pub async fn sync_remote_mcp_to_claude(
+    db: State<'_, AgentDb>,
+    project_path: String,
+    server_ids: Vec<String>,
+    overwrite: Option<bool>,
+) -> Result<McpSyncResult, String> {
+    validate_project_path(&project_path)?;
+
+    let entries = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let _ = init_remote_mcp_table(&conn);
+
+        let mut entries = Vec::with_capacity(server_ids.len());
+        for id in &server_ids {
+            let (name, endpoint, stored): (String, String, Option<String>) = conn
+                .query_row(
+                    "SELECT name, endpoint, auth_config FROM remote_mcp_servers WHERE id = ?1",
+                    params![id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .map_err(|e| format!("Server not found: {} ({})", id, e))?;
+            let auth = stored
+                .map(|stored| resolve_auth_config(&conn, id, &stored))
+                .transpose()
+                .map_err(|e| e.to_string())?
+                .unwrap_or(McpAuthConfig::None);
+
+            entries.push((
+                name,
+                MCPServerConfig {
+                    server_type: Some("http".to_string()),
+                    command: None,
+                    args: Vec::new(),
+                    env: HashMap::new(),
+                    url: Some(endpoint),
+                    headers: auth_headers(&auth),
+                },
+            ));
+        }
+        entries
+    };
+
+    let mut config = mcp_read_project_config(project_path.clone()).await?;
+    let mut result = McpSyncResult::default();
+    for (name, entry) in entries {
+        let conflicting = config
+            .mcp_servers
+            .get(&name)
+            .is_some_and(|existing| *existing != entry);
+        if conflicting && !overwrite.unwrap_or(false) {
+            result.conflicts.push(name);
+            continue;
+        }
+        config.mcp_servers.insert(name.clone(), entry);
+        result.synced.push(name);
+    }
+
+    if !result.synced.is_empty() {
+        mcp_save_project_config(project_path.clone(), config).await?;
+    }
+
+    info!(
+        "Synced {} remote MCP servers to {} ({} conflicts)",
+        result.synced.len(),
+        project_path,
+        result.conflicts.len()
+    );
+    Ok(result)
+}
+
+/// Import the http/sse servers from a project's .mcp.json as remote MCP servers
+///
+/// A name already used by a server with a different endpoint is reported as a
+/// conflict unless `overwrite` is set; stdio servers are skipped.
+#[tauri::command]
+pub async fn import_claude_mcp_servers(
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    health: State<'_, McpHealthMonitorState>,
+    project_path: String,
+    overwrite: Option<bool>,
+) -> Result<McpSyncResult, String> {
+    validate_project_path(&project_path)?;
+    let config = mcp_read_project_config(project_path.clone()).await?;
+    let overwrite = overwrite.unwrap_or(false);
+
+    let mut names: Vec<&String> = config.mcp_servers.keys().collect();
+    names.sort();
+
+    let mut result = McpSyncResult::default();
+    let mut replaced = Vec::new();
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let _ = init_remote_mcp_table(&conn);
+
+        for name in names {
+            let entry = &config.mcp_servers[name];
+            let endpoint = match (entry.server_type.as_deref(), entry.url.as_deref()) {
+                (Some("http") | Some("sse"), Some(url)) if validate_endpoint(url).is_ok() => url,
+                _ => {
+                    result.skipped.push(name.clone());
+                    continue;
+                }
+            };
+            let (auth_type, auth) = auth_from_headers(&entry.headers);
+
+            let existing: Option<(String, String)> = conn
+                .query_row(
+                    "SELECT id, endpoint FROM remote_mcp_servers WHERE name = ?1",
+                    params![name],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+
+            match existing {
+                Some((id, _)) if overwrite => {
+                    let auth_config = store_auth_config(&id, &auth).map_err(|e| e.to_string())?;
+                    conn.execute(
+                        "UPDATE remote_mcp_servers SET endpoint = ?1, auth_type = ?2, auth_config = ?3, updated_at = ?4 WHERE id = ?5",
+                        params![endpoint, auth_type, auth_config, chrono::Utc::now().to_rfc3339(), id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    replaced.push(id);
+                }
+                // Already imported
+                Some((_, current)) if current == endpoint => continue,
+                Some(_) => {
+                    result.conflicts.push(name.clone());
+                    continue;
+                }
+                None => {
+                    let id = uuid::Uuid::new_v4().to_string();
+                    let auth_config = store_auth_config(&id, &auth).map_err(|e| e.to_string())?;
+                    conn.execute(
+                        "INSERT INTO remote_mcp_servers (id, name, endpoint, auth_type, auth_config) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![id, name, endpoint, auth_type, auth_config],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+            }
+            result.synced.push(name.clone());
+        }
+    }
+
+    // Replaced servers may be pooled with their old configuration
+    for id in &replaced {
+        pool.0.disconnect(id).await;
+    }
+    if !result.synced.is_empty() {
+        restart_health_monitoring(&db, &health.0);
+    }
+
+    info!(
+        "Imported {} MCP servers from {} ({} conflicts, {} skipped)",
+        result.synced.len(),
+        project_path,
+        result.conflicts.len(),
+        result.skipped.len()
+    );
+    Ok(result)
+}
+
+/// Remote MCP servers exported for sharing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteMcpExport {
+    pub schema_version: u32,
+    pub exported_at: String,
+    pub servers: Vec<ExportedRemoteServer>,
+}
+
+/// One server in an export
+///
+/// TLS certificate paths are specific to one machine and are left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedRemoteServer {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub endpoint: String,
+    pub auth: McpAuthConfig,
+    /// False when the secrets in `auth` are placeholders
+    #[serde(default)]
+    pub secrets_included: bool,
+    #[serde(default)]
+    pub health_enabled: Option<bool>,
+    #[serde(default)]
+    pub health_interval: Option<u64>,
+    #[serde(default)]
+    pub retry_policy: Option<RetryPolicy>,
+    #[serde(default)]
+    pub default_tool_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub keepalive_interval_secs: Option<u64>,
+}
+
+/// Value of the auth_type column for an auth config
+fn auth_type_name(auth: &McpAuthConfig) -> &'static str {
+    match auth {
+        McpAuthConfig::None => "none",
+        McpAuthConfig::Bearer { .. } => "bearer",
+        McpAuthConfig::ApiKey { .. } => "api-key",
+        McpAuthConfig::CustomHeader { .. } => "custom-header",
+        McpAuthConfig::OAuth { .. } => "oauth2",
+    }
+}
+
+/// Replace the secrets in an auth config with placeholders, keeping header names and OAuth endpoints
+fn redact_auth(auth: &McpAuthConfig) -> McpAuthConfig {
+    let placeholder = || SECRET_PLACEHOLDER.to_string();
+    match auth {
+        McpAuthConfig::None => McpAuthConfig::None,
+        McpAuthConfig::Bearer { .. } => McpAuthConfig::Bearer { token: placeholder() },
+        McpAuthConfig::ApiKey { header, .. } => McpAuthConfig::ApiKey {
+            header: header.clone(),
+            value: placeholder(),
+        },
+        McpAuthConfig::CustomHeader { headers } => McpAuthConfig::CustomHeader {
+            headers: headers.keys().map(|name| (name.clone(), placeholder())).collect(),
+        },
+        McpAuthConfig::OAuth {
+            token_url, client_id, ..
+        } => McpAuthConfig::OAuth {
+            access_token: placeholder(),
+            refresh_token: None,
+            token_url: token_url.clone(),
+            client_id: client_id.clone(),
+            client_secret: None,
+            expires_at: None,
+        },
+    }
+}
+
+/// Build an export document from stored servers
+///
+/// `load_auth` resolves a server's stored auth_config value. Servers still
+/// waiting for credentials are always exported without secrets.
+fn export_servers(
+    conn: &rusqlite::Connection,
+    ids: Option<&[String]>,
+    include_secrets: bool,
+    load_auth: impl Fn(&str, &str) -> McpResult<McpAuthConfig>,
+) -> Result<RemoteMcpExport, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, description, endpoint, auth_config, health_enabled, health_interval, retry_policy,
+             default_tool_timeout_ms, keepalive_interval_secs, needs_credentials
+             FROM remote_mcp_servers ORDER BY name",
+        )
+        .map_err(|e| e.to_string())?;
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(
+        String,
+        String,
+        Option<String>,
+        String,
+        Option<String>,
+        Option<bool>,
+        Option<u64>,
+        Option<String>,
+        Option<u64>,
+        Option<u64>,
+        Option<bool>,
+    )> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+                row.get(8)?,
+                row.get(9)?,
+                row.get(10)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(ids) = ids {
+        if let Some(missing) = ids.iter().find(|id| !rows.iter().any(|row| &row.0 == *id)) {
+            return Err(format!("Server not found: {}", missing));
+        }
+    }
+
+    let mut servers = Vec::new();
+    for (
+        id,
+        name,
+        description,
+        endpoint,
+        stored,
+        health_enabled,
+        health_interval,
+        retry_str,
+        default_tool_timeout_ms,
+        keepalive_interval_secs,
+        needs_credentials,
+    ) in rows
+    {
+        if ids.is_some_and(|ids| !ids.contains(&id)) {
+            continue;
+        }
+        let auth = stored
+            .map(|stored| load_auth(&id, &stored))
+            .transpose()
+            .map_err(|e| format!("Failed to read credentials for {}: {}", name, e))?
+            .unwrap_or(McpAuthConfig::None);
+        let secrets_included = include_secrets && !needs_credentials.unwrap_or(false);
+        let retry_policy = retry_str.and_then(|s| serde_json::from_str(&s).ok());
+
+        servers.push(ExportedRemoteServer {
+            name,
+            description,
+            endpoint,
+            auth: if secrets_included { auth } else { redact_auth(&auth) },
+            secrets_included,
+            health_enabled,
+            health_interval,
+            retry_policy,
+            default_tool_timeout_ms,
+            keepalive_interval_secs,
+        });
+    }
+
+    Ok(RemoteMcpExport {
+        schema_version: EXPORT_SCHEMA_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        servers,
+    })
+}
+
+/// Check the settings of an imported server
+fn validate_exported_server(server: &ExportedRemoteServer) -> Result<(), String> {
+    if server.name.trim().is_empty() {
+        return Err("Server name is required".to_string());
+    }
+    validate_endpoint(&server.endpoint)?;
+    if let Some(ref policy) = server.retry_policy {
+        if !(1..=10).contains(&policy.max_attempts) {
+            return Err("Retry max_attempts must be between 1 and 10".to_string());
+        }
+    }
+    if let Some(timeout_ms) = server.default_tool_timeout_ms {
+        validate_tool_timeout(timeout_ms)?;
+    }
+    if let Some(secs) = server.keepalive_interval_secs {
+        validate_keepalive_interval(secs)?;
+    }
+    Ok(())
+}
+
+/// Insert the servers from an export document, matching existing servers by endpoint
+///
+/// Existing servers are replaced only with `overwrite`. Servers imported
+/// without secrets are marked as needing credentials. `store_auth` returns the
+/// auth_config column value for a server ID. Returns the result and the IDs of
+/// replaced servers.
+fn import_servers(
+    conn: &rusqlite::Connection,
+    export: RemoteMcpExport,
+    overwrite: bool,
+    store_auth: impl Fn(&str, &McpAuthConfig) -> McpResult<String>,
+) -> Result<(McpSyncResult, Vec<String>), String> {
+    if export.schema_version == 0 || export.schema_version > EXPORT_SCHEMA_VERSION {
+        return Err(format!("Unsupported export schema version: {}", export.schema_version));
+    }
+
+    let mut result = McpSyncResult::default();
+    let mut replaced = Vec::new();
+    let mut seen_endpoints = HashSet::new();
+
+    for server in export.servers {
+        let name = server.name.trim().to_string();
+        if let Err(e) = validate_exported_server(&server) {
+            warn!("Skipping imported MCP server {}: {}", name, e);
+            result.skipped.push(name);
+            continue;
+        }
+        // Only the first entry for an endpoint counts
+        if !seen_endpoints.insert(server.endpoint.clone()) {
+            result.skipped.push(name);
+            continue;
+        }
+
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT id FROM remote_mcp_servers WHERE endpoint = ?1",
+                params![server.endpoint],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if existing.is_some() && !overwrite {
+            result.skipped.push(name);
+            continue;
+        }
+        if ensure_unique_name(conn, &name, existing.as_deref()).is_err() {
+            result.conflicts.push(name);
+            continue;
+        }
+
+        let needs_credentials = !server.secrets_included && !matches!(server.auth, McpAuthConfig::None);
+        let status = if needs_credentials { "needs_credentials" } else { "unknown" };
+        let id = existing.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let auth_config = store_auth(&id, &server.auth).map_err(|e| e.to_string())?;
+        let retry_policy = server
+            .retry_policy
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let auth_type = auth_type_name(&server.auth);
+        let health_enabled = server.health_enabled.unwrap_or(true);
+        let health_interval = server.health_interval.unwrap_or(60) as i64;
+        let tool_timeout_ms = server.default_tool_timeout_ms.map(|ms| ms as i64);
+        let keepalive_secs = server.keepalive_interval_secs.unwrap_or(DEFAULT_KEEPALIVE_SECS) as i64;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        if existing.is_some() {
+            conn.execute(
+                "UPDATE remote_mcp_servers SET name = ?2, description = ?3, endpoint = ?4, auth_type = ?5, auth_config = ?6,
+                 health_enabled = ?7, health_interval = ?8, retry_policy = ?9, default_tool_timeout_ms = ?10,
+                 keepalive_interval_secs = ?11, needs_credentials = ?12, status = ?13, updated_at = ?14
+                 WHERE id = ?1",
+                params![
+                    id,
+                    name,
+                    server.description,
+                    server.endpoint,
+                    auth_type,
+                    auth_config,
+                    health_enabled,
+                    health_interval,
+                    retry_policy,
+                    tool_timeout_ms,
+                    keepalive_secs,
+                    needs_credentials,
+                    status,
+                    now
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            replaced.push(id);
+        } else {
+            conn.execute(
+                "INSERT INTO remote_mcp_servers (id, name, description, endpoint, auth_type, auth_config, health_enabled,
+                 health_interval, retry_policy, default_tool_timeout_ms, keepalive_interval_secs, needs_credentials, status,
+                 created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?14)",
+                params![
+                    id,
+                    name,
+                    server.description,
+                    server.endpoint,
+                    auth_type,
+                    auth_config,
+                    health_enabled,
+                    health_interval,
+                    retry_policy,
+                    tool_timeout_ms,
+                    keepalive_secs,
+                    needs_credentials,
+                    status,
+                    now
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        result.synced.push(name);
+    }
+
+    Ok((result, replaced))
+}
+
+/// Export remote MCP server configurations as a JSON document
+///
+/// Exports every server unless `ids` is given. Without `include_secrets`,
+/// tokens and header values are replaced with placeholders.
+#[tauri::command]
+pub async fn export_remote_mcp_servers(
+    db: State<'_, AgentDb>,
+    ids: Option<Vec<String>>,
+    include_secrets: bool,
+) -> Result<String, String> {
+    let export = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let _ = init_remote_mcp_table(&conn);
+        export_servers(&conn, ids.as_deref(), include_secrets, |id, stored| {
+            resolve_auth_config(&conn, id, stored)
+        })?
+    };
+
+    info!("Exported {} remote MCP servers", export.servers.len());
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+/// Import remote MCP servers from a document written by `export_remote_mcp_servers`
+///
+/// Servers whose endpoint is already configured are skipped unless `overwrite`
+/// is set; names taken by a different server are reported as conflicts.
+#[tauri::command]
+pub async fn import_remote_mcp_servers(
+    db: State<'_, AgentDb>,
+    pool: State<'_, McpConnectionPoolState>,
+    health: State<'_, McpHealthMonitorState>,
+    json: String,
+    overwrite: bool,
+) -> Result<McpSyncResult, String> {
+    let export: RemoteMcpExport =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid server export: {}", e))?;
+
+    let (result, replaced) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let _ = init_remote_mcp_table(&conn);
+        import_servers(&conn, export, overwrite, |id, auth| store_auth_config(id, auth))?
+    };
+
+    // Replaced servers may be pooled with their old configuration
+    for id in &replaced {
+        pool.0.disconnect(id).await;
+    }
+    if !result.synced.is_empty() {
+        restart_health_monitoring(&db, &health.0);
+    }
+
+    info!(
+        "Imported {} remote MCP servers ({} conflicts, {} skipped)",
+        result.synced.len(),
+        result.conflicts.len(),
+        result.skipped.len()
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_mixed_tool_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = STANDARD.encode(b"\x89PNG\r\n\x1a\nfake");
+        let result: ToolCallResult = serde_json::from_value(serde_json::json!({
+            "content": [
+                { "type": "text", "text": "Rendered chart" },
+                { "type": "image", "data": png, "mimeType": "image/png" },
+                { "type": "image", "data": "not base64!", "mimeType": "image/png" },
+                { "type": "text", "text": "2 series" }
+            ],
+            "structuredContent": { "series": 2 }
+        }))
+        .unwrap();
+
+        let response = process_tool_result(result, dir.path());
+
+        assert_eq!(response.combined_text, "Rendered chart\n2 series");
+        assert!(!response.is_error);
+        assert_eq!(response.structured_content, Some(serde_json::json!({ "series": 2 })));
+        match &response.content[1] {
+            ToolResponseContent::Image { file_path: Some(path), data: None, .. } => {
+                assert!(path.ends_with(".png"));
+                assert_eq!(std::fs::read(path).unwrap(), b"\x89PNG\r\n\x1a\nfake");
+            }
+            other => panic!("image not saved: {:?}", other),
+        }
+        // Undecodable images are passed through
+        assert!(matches!(
+            &response.content[2],
+            ToolResponseContent::Image { file_path: None, data: Some(_), .. }
+        ));
+    }
+
+    #[test]
+    fn test_server_logs_keep_most_recent() {
+        let logs = DashMap::new();
+        for i in 0..MCP_LOG_CAPACITY + 10 {
+            let entry = McpServerLogEntry {
+                timestamp: String::new(),
+                level: "info".to_string(),
+                logger: None,
+                message: format!("line {}", i),
+            };
+            push_log_entry(&logs, "server", entry);
+        }
+
+        let lines = logs.get("server").unwrap();
+        assert_eq!(lines.len(), MCP_LOG_CAPACITY);
+        assert_eq!(lines.front().unwrap().message, "line 10");
+        assert_eq!(lines.back().unwrap().message, format!("line {}", MCP_LOG_CAPACITY + 9));
+    }
+
+    fn plaintext_auth(_id: &str, auth: &McpAuthConfig) -> McpResult<String> {
+        Ok(serde_json::to_string(auth)?)
+    }
+
+    fn parse_auth(_id: &str, stored: &str) -> McpResult<McpAuthConfig> {
+        serde_json::from_str(stored).map_err(|e| McpError::InvalidConfig(e.to_string()))
+    }
+
+    fn exported_server(name: &str, endpoint: &str, auth: McpAuthConfig) -> ExportedRemoteServer {
+        ExportedRemoteServer {
+            name: name.to_string(),
+            description: None,
+            endpoint: endpoint.to_string(),
+            auth,
+            secrets_included: true,
+            health_enabled: Some(false),
+            health_interval: Some(120),
+            retry_policy: None,
+            default_tool_timeout_ms: Some(30_000),
+            keepalive_interval_secs: Some(0),
+        }
+    }
+
+    fn sample_export() -> RemoteMcpExport {
+        RemoteMcpExport {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            exported_at: String::new(),
+            servers: vec![
+                exported_server(
+                    "Bearer",
+                    "https://bearer.example/mcp",
+                    McpAuthConfig::Bearer { token: "secret-token".to_string() },
+                ),
+                exported_server(
+                    "Api Key",
+                    "https://apikey.example/mcp",
+                    McpAuthConfig::ApiKey {
+                        header: "X-API-Key".to_string(),
+                        value: "secret-key".to_string(),
+                    },
+                ),
+                exported_server(
+                    "Custom",
+                    "https://custom.example/mcp",
+                    McpAuthConfig::CustomHeader {
+                        headers: HashMap::from([("X-Team".to_string(), "secret-team".to_string())]),
+                    },
+                ),
+            ],
+        }
+    }
+
+    fn memory_db() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_remote_mcp_table(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_export_import_round_trip_with_secrets() {
+        let source = memory_db();
+        let (result, _) = import_servers(&source, sample_export(), false, plaintext_auth).unwrap();
+        assert_eq!(result.synced.len(), 3);
+
+        let exported = export_servers(&source, None, true, parse_auth).unwrap();
+        let json = serde_json::to_string(&exported).unwrap();
+
+        let target = memory_db();
+        let parsed: RemoteMcpExport = serde_json::from_str(&json).unwrap();
+        import_servers(&target, parsed, false, plaintext_auth).unwrap();
+        let reexported = export_servers(&target, None, true, parse_auth).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&exported.servers).unwrap(),
+            serde_json::to_value(&reexported.servers).unwrap()
+        );
+        let custom = reexported.servers.iter().find(|s| s.name == "Custom").unwrap();
+        assert!(matches!(
+            &custom.auth,
+            McpAuthConfig::CustomHeader { headers } if headers["X-Team"] == "secret-team"
+        ));
+        assert_eq!(custom.default_tool_timeout_ms, Some(30_000));
+        assert_eq!(custom.keepalive_interval_secs, Some(0));
+    }
+
+    #[test]
+    fn test_export_without_secrets_needs_credentials_on_import() {
+        let source = memory_db();
+        import_servers(&source, sample_export(), false, plaintext_auth).unwrap();
+
+        let exported = export_servers(&source, None, false, parse_auth).unwrap();
+        let json = serde_json::to_string(&exported).unwrap();
+        assert!(!json.contains("secret-"));
+        assert!(json.contains("X-API-Key") && json.contains("X-Team"));
+
+        let target = memory_db();
+        let parsed: RemoteMcpExport = serde_json::from_str(&json).unwrap();
+        import_servers(&target, parsed.clone(), false, plaintext_auth).unwrap();
+        let pending: i64 = target
+            .query_row(
+                "SELECT COUNT(*) FROM remote_mcp_servers WHERE needs_credentials = 1 AND status = 'needs_credentials'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(pending, 3);
+
+        // Existing endpoints are skipped unless overwriting
+        let (again, replaced) = import_servers(&target, parsed.clone(), false, plaintext_auth).unwrap();
+        assert_eq!((again.synced.len(), again.skipped.len()), (0, 3));
+        assert!(replaced.is_empty());
+        let (again, replaced) = import_servers(&target, parsed, true, plaintext_auth).unwrap();
+        assert_eq!((again.synced.len(), replaced.len()), (3, 3));
+    }
+
+    #[test]
+    fn test_import_dedupes_endpoints_and_reports_name_conflicts() {
+        let conn = memory_db();
+        let mut export = sample_export();
+        export.servers.push(exported_server("Bearer Copy", "https://bearer.example/mcp", McpAuthConfig::None));
+        export.servers.push(exported_server("bearer", "https://other.example/mcp", McpAuthConfig::None));
+        export.servers.push(exported_server("Broken", "ftp://broken.example", McpAuthConfig::None));
+
+        let (result, _) = import_servers(&conn, export, false, plaintext_auth).unwrap();
+        assert_eq!(result.synced.len(), 3);
+        assert_eq!(result.conflicts, vec!["bearer".to_string()]);
+        assert_eq!(result.skipped, vec!["Bearer Copy".to_string(), "Broken".to_string()]);
+
+        let mut future = sample_export();
+        future.schema_version = EXPORT_SCHEMA_VERSION + 1;
+        assert!(import_servers(&conn, future, false, plaintext_auth).is_err());
+    }
+
+    #[test]
+    fn test_ensure_unique_name_ignores_case() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_remote_mcp_table(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO remote_mcp_servers (id, name, endpoint, auth_type) VALUES ('a', 'Docs', 'https://docs.example', 'none')",
+            [],
+        )
+        .unwrap();
+
+        let err = ensure_unique_name(&conn, "DOCS", None).unwrap_err();
+        assert!(err.starts_with(DUPLICATE_NAME_ERROR));
+        assert!(ensure_unique_name(&conn, "docs", Some("a")).is_ok());
+        assert!(ensure_unique_name(&conn, "Search", None).is_ok());
+    }
+
+    #[test]
+    fn test_validate_fallback_rejects_loops() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_remote_mcp_table(&conn).unwrap();
+        for (id, fallback) in [("a", Some("b")), ("b", Some("c")), ("c", None)] {
+            conn.execute(
+                "INSERT INTO remote_mcp_servers (id, name, endpoint, auth_type, fallback_server_id)
+                 VALUES (?1, ?1, 'https://mcp.example', 'none', ?2)",
+                params![id, fallback],
+            )
+            .unwrap();
+        }
+
+        assert!(validate_fallback(&conn, None, "a").is_ok());
+        assert!(validate_fallback(&conn, Some("c"), "b").unwrap_err().contains("loop"));
+        assert!(validate_fallback(&conn, Some("c"), "a").unwrap_err().contains("loop"));
+        assert!(validate_fallback(&conn, Some("a"), "a").unwrap_err().contains("own fallback"));
+        assert!(validate_fallback(&conn, Some("a"), "missing").unwrap_err().contains("not found"));
+    }
+
+    #[test]
+    fn test_validate_keepalive_interval() {
+        assert!(validate_keepalive_interval(0).is_ok());
+        assert!(validate_keepalive_interval(DEFAULT_KEEPALIVE_SECS).is_ok());
+        assert!(validate_keepalive_interval(MIN_KEEPALIVE_SECS - 1).is_err());
+        assert!(validate_keepalive_interval(MAX_KEEPALIVE_SECS + 1).is_err());
+    }
+
+    #[test]
+    fn test_validate_tool_timeout() {
+        assert!(validate_tool_timeout(1).is_ok());
+        assert!(validate_tool_timeout(MAX_TOOL_TIMEOUT_MS).is_ok());
+        assert!(validate_tool_timeout(0).is_err());
+        assert!(validate_tool_timeout(MAX_TOOL_TIMEOUT_MS + 1).is_err());
+    }
+
+    #[test]
+    fn test_auth_headers_round_trip() {
+        let bearer = McpAuthConfig::Bearer {
+            token: "secret".to_string(),
+        };
+        let headers = auth_headers(&bearer);
+        assert_eq!(headers["Authorization"], "Bearer secret");
+        assert!(matches!(
+            auth_from_headers(&headers),
+            ("bearer", McpAuthConfig::Bearer { token }) if token == "secret"
+        ));
+
+        let api_key = McpAuthConfig::ApiKey {
+            header: "X-API-Key".to_string(),
+            value: "key".to_string(),
+        };
+        assert!(matches!(
+            auth_from_headers(&auth_headers(&api_key)),
+            ("api-key", McpAuthConfig::ApiKey { header, value }) if header == "X-API-Key" && value == "key"
+        ));
+
+        assert!(matches!(auth_from_headers(&HashMap::new()), ("none", McpAuthConfig::None)));
+
+        // A non-bearer Authorization header is kept verbatim
+        let basic = HashMap::from([("Authorization".to_string(), "Basic abc".to_string())]);
+        assert!(matches!(auth_from_headers(&basic), ("custom-header", _)));
+    }
+
+    #[test]
+    fn test_roots_keep_order_with_active_project_first() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_remote_mcp_table(&conn).unwrap();
+
+        let tmp = std::env::temp_dir().to_string_lossy().into_owned();
+        assert_eq!(validate_root_paths(vec![tmp.clone(), format!(" {} ", tmp)]).unwrap(), vec![tmp]);
+        assert!(validate_root_paths(vec!["relative/dir".to_string()]).is_err());
+
+        store_roots(&conn, "srv", &["/srv/b".to_string(), "/srv/a".to_string()]).unwrap();
+        let configured = load_roots(&conn, "srv").unwrap();
+        assert_eq!(configured, vec!["/srv/b", "/srv/a"]);
+
+        let uris: Vec<String> = server_roots(Some("/srv/a"), &configured)
+            .into_iter()
+            .map(|root| root.uri)
+            .collect();
+        assert_eq!(uris, vec!["file:///srv/a", "file:///srv/b"]);
+    }
+
+    #[test]
+    fn test_validate_endpoint() {
+        assert!(validate_endpoint("https://mcp.example.com/mcp").is_ok());
+        assert!(validate_endpoint("http://127.0.0.1:8080").is_ok());
+        assert!(validate_endpoint("wss://mcp.example.com/ws").is_ok());
+        assert!(is_websocket_endpoint("ws://127.0.0.1:8080"));
+        assert!(!is_websocket_endpoint("https://mcp.example.com/mcp"));
+
+        assert!(validate_endpoint("ftp://mcp.example.com").is_err());
+        assert!(validate_endpoint("file:///etc/passwd").is_err());
+        assert!(validate_endpoint("mcp.example.com").is_err());
+        assert!(validate_endpoint("").is_err());
+    }
+
+    #[test]
+    fn test_validate_tls_paths() {
+        let cert = tempfile::NamedTempFile::new().unwrap();
+        let cert_path = cert.path().to_str().unwrap();
+
+        assert!(validate_tls_paths(None, None, None).is_ok());
+        assert!(validate_tls_paths(Some(cert_path), None, None).is_ok());
+        assert!(validate_tls_paths(Some(""), Some(cert_path), Some(cert_path)).is_ok());
+
+        assert!(validate_tls_paths(Some("/nonexistent/ca.pem"), None, None).is_err());
+        assert!(validate_tls_paths(None, Some(cert_path), None).is_err());
+    }
+
+    #[test]
+    fn test_protocol_version_from_capabilities() {
+        assert_eq!(
+            protocol_version_from_capabilities(r#"{"protocolVersion":"2025-03-26"}"#).as_deref(),
+            Some("2025-03-26")
+        );
+        assert_eq!(protocol_version_from_capabilities(r#"{"protocolVersion":null}"#), None);
+        assert_eq!(protocol_version_from_capabilities("not json"), None);
+    }
+
+    #[test]
+    fn test_cached_capabilities_round_trip() {
+        let stored = r#"{"protocolVersion":"2025-06-18","capabilities":{"tools":{"listChanged":true}},"serverInfo":{"name":"docs","version":"1.2.0"}}"#;
+        let cached = cached_capabilities(Some(stored.to_string()));
+
+        assert!(cached.capabilities.as_ref().is_some_and(|c| c.tools.is_some() && c.prompts.is_none()));
+        assert_eq!(cached.server_info.as_ref().map(|i| i.name.as_str()), Some("docs"));
+
+        let reparsed = cached_capabilities(Some(serde_json::to_string(&cached).unwrap()));
+        assert_eq!(reparsed.protocol_version.as_deref(), Some("2025-06-18"));
+    }
+
+    #[test]
+    fn test_validate_degraded_thresholds() {
+        assert!(validate_degraded_thresholds(None, None).is_ok());
+        assert!(validate_degraded_thresholds(Some(1.5), Some(200)).is_ok());
+        assert!(validate_degraded_thresholds(Some(0.5), None).is_err());
+        assert!(validate_degraded_thresholds(Some(f64::NAN), None).is_err());
+        assert!(validate_degraded_thresholds(None, Some(10_000_000)).is_err());
+    }
+
+    #[test]
+    fn test_health_status_round_trip() {
+        for status in [
+            HealthStatus::Healthy,
+            HealthStatus::Degraded,
+            HealthStatus::Unhealthy,
+            HealthStatus::Unknown,
+        ] {
+            assert_eq!(parse_health_status(health_status_str(status)), status);
+        }
+    }
+}
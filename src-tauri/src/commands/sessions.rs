@@ -0,0 +1,41 @@
+//! Session Commands
+//!
+//! Tauri commands for sessions tracked by the SessionManager.
+
+use tauri::State;
+
+use crate::session::state::SessionMetrics;
+use crate::session::{SessionEvent, SessionManager};
+
+/// Cap a session's input plus output tokens, or lift the cap with None
+///
+/// A session already over the new budget is stopped immediately.
+#[tauri::command]
+pub async fn set_session_token_budget(
+    manager: State<'_, SessionManager>,
+    session_id: String,
+    token_budget: Option<u64>,
+) -> Result<(), String> {
+    if token_budget == Some(0) {
+        return Err("Token budget must be greater than zero".to_string());
+    }
+    manager.set_token_budget(&session_id, token_budget)?;
+    Ok(())
+}
+
+/// Session counts by status, total token usage and the oldest active session's age
+#[tauri::command]
+pub async fn get_session_metrics(manager: State<'_, SessionManager>) -> Result<SessionMetrics, String> {
+    Ok(manager.get_session_metrics())
+}
+
+/// Recent output of a session, for panels that open after it started
+///
+/// Listen for live `session-output:<id>` events first, then call this to fill in what came before.
+#[tauri::command]
+pub async fn get_buffered_output(
+    manager: State<'_, SessionManager>,
+    session_id: String,
+) -> Result<Vec<SessionEvent>, String> {
+    Ok(manager.get_buffered_output(&session_id)?)
+}
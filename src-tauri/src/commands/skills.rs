@@ -2,20 +2,31 @@
 //!
 //! Tauri commands for managing the unified skills system.
 
-use log::info;
-use rusqlite::params;
+use log::{info, warn};
+use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
-use tauri::State;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 use crate::commands::agents::AgentDb;
+use crate::skills::executor::SkillExecutor;
 use crate::skills::registry::SkillRegistry;
 use crate::skills::loader::SkillLoader;
 use crate::skills::types::{
-    Skill, SkillKind, SkillVisibility, SkillConfig, SlashCommandConfig, HookConfig, HookTrigger,
+    Skill, SkillKind, SkillVisibility, SkillConfig, SlashCommandConfig, ArgsConfig, HookConfig, HookTrigger,
+    HookOutcome, SkillBundle, SkillContext, SkillMetadata, SkillResult, TemplateConfig, WorkflowConfig, WorkflowEvent,
+    SKILL_BUNDLE_VERSION,
 };
 
+/// Skill cache used to execute skills, kept in sync with the skills table
+#[derive(Default)]
+pub struct SkillRegistryState(pub Arc<SkillRegistry>);
+
+/// Executor that runs workflows started from the frontend, and holds the runs waiting for input
+pub struct SkillExecutorState(pub SkillExecutor);
+
 /// Skill info for frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillInfo {
@@ -25,6 +36,9 @@ pub struct SkillInfo {
     pub description: String,
     pub visibility: String,
     pub enabled: bool,
+    /// Imported with shell commands and waiting for the user to approve it
+    #[serde(default)]
+    pub needs_approval: bool,
     pub source: String,
     pub project_path: Option<String>,
     pub created_at: String,
@@ -40,6 +54,7 @@ impl From<&Skill> for SkillInfo {
             description: skill.description.clone(),
             visibility: format!("{:?}", skill.visibility).to_lowercase(),
             enabled: skill.enabled,
+            needs_approval: false,
             source: skill.source.clone(),
             project_path: skill.project_path.clone(),
             created_at: skill.created_at.clone(),
@@ -48,6 +63,15 @@ impl From<&Skill> for SkillInfo {
     }
 }
 
+/// Skill found by a search, with the field the query matched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillSearchResult {
+    #[serde(flatten)]
+    pub skill: SkillInfo,
+    /// "name", "description", or "tags"
+    pub matched_field: String,
+}
+
 /// Create slash command request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateSlashCommandRequest {
@@ -56,8 +80,13 @@ pub struct CreateSlashCommandRequest {
     pub prompt: String,
     pub help: Option<String>,
     pub examples: Option<Vec<String>>,
+    /// Positional and named arguments, bound as `$1`/`${name}` in the prompt
+    pub args: Option<ArgsConfig>,
     pub visibility: Option<String>,
     pub project_path: Option<String>,
+    /// Replace an existing command with the same name in the same scope
+    #[serde(default)]
+    pub overwrite: bool,
 }
 
 /// Create hook request
@@ -70,8 +99,39 @@ pub struct CreateHookRequest {
     pub timeout_secs: Option<u64>,
     pub tool_patterns: Option<Vec<String>>,
     pub can_block: Option<bool>,
+    /// Pass the full hook context as JSON on the command's stdin
+    pub stdin_json: Option<bool>,
+    pub visibility: Option<String>,
+    pub project_path: Option<String>,
+    /// Replace an existing hook with the same name in the same scope
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Create workflow request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateWorkflowRequest {
+    pub name: String,
+    pub description: String,
+    pub workflow: WorkflowConfig,
     pub visibility: Option<String>,
     pub project_path: Option<String>,
+    /// Replace an existing workflow with the same name in the same scope
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Create template request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTemplateRequest {
+    pub name: String,
+    pub description: String,
+    pub template: TemplateConfig,
+    pub visibility: Option<String>,
+    pub project_path: Option<String>,
+    /// Replace an existing template with the same name in the same scope
+    #[serde(default)]
+    pub overwrite: bool,
 }
 
 /// Initialize skills table in database
@@ -79,6 +139,79 @@ pub fn init_skills_table(conn: &rusqlite::Connection) -> Result<(), rusqlite::Er
     SkillRegistry::init_database(conn)
 }
 
+/// Parse a hook trigger as the frontend names it
+fn parse_hook_trigger(trigger: &str) -> Result<HookTrigger, String> {
+    match trigger {
+        "pre_tool" => Ok(HookTrigger::PreTool),
+        "post_tool" => Ok(HookTrigger::PostTool),
+        "session_start" => Ok(HookTrigger::SessionStart),
+        "session_end" => Ok(HookTrigger::SessionEnd),
+        "checkpoint_create" => Ok(HookTrigger::CheckpointCreate),
+        "on_error" => Ok(HookTrigger::OnError),
+        _ => Err(format!("Invalid trigger type: {}", trigger)),
+    }
+}
+
+/// Refresh one skill in the registry from its database row
+///
+/// Deleted and disabled skills are dropped from the registry.
+fn sync_registry_skill(registry: &SkillRegistry, conn: &rusqlite::Connection, id: &str) -> Result<(), String> {
+    registry.unregister_skill(id);
+    let skill = conn
+        .query_row(
+            "SELECT id, kind, name, description, visibility, enabled, config, metadata, project_path, source, created_at, updated_at
+             FROM skills WHERE id = ?1",
+            params![id],
+            skill_from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(skill) = skill.filter(|skill| skill.enabled) {
+        registry.register_skill(skill);
+    }
+    Ok(())
+}
+
+/// Rebuild the registry from the database, for writes that may replace other rows
+fn reload_registry(registry: &SkillRegistry, conn: &rusqlite::Connection) -> Result<(), String> {
+    registry.clear_cache();
+    registry.load_from_database(conn).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Make room for a new skill, returning the ID of the one it replaces
+///
+/// Skills conflict when they share a kind, name, and project path. Without
+/// `overwrite` a conflict is an error; with it the old row is deleted and its
+/// ID is reused. Run it in the same transaction as the INSERT that follows, so
+/// a failed insert doesn't lose the replaced skill.
+fn claim_skill_name(
+    conn: &rusqlite::Connection,
+    kind: &str,
+    name: &str,
+    project_path: Option<&str>,
+    overwrite: bool,
+) -> Result<Option<String>, String> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM skills WHERE kind = ?1 AND name = ?2 AND COALESCE(project_path, '') = COALESCE(?3, '')",
+            params![kind, name, project_path],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(ref id) = existing {
+        if !overwrite {
+            let scope = project_path.map_or("globally".to_string(), |path| format!("in {}", path));
+            return Err(format!("{} '{}' already exists {}", kind.replace('_', " "), name, scope));
+        }
+        conn.execute("DELETE FROM skills WHERE id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(existing)
+}
+
 /// List all skills
 #[tauri::command]
 pub async fn list_skills(
@@ -87,11 +220,20 @@ pub async fn list_skills(
     project_path: Option<String>,
 ) -> Result<Vec<SkillInfo>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    query_skills(&conn, kind, project_path)
+}
 
+/// Skills of `kind` visible from `project_path`, newest first
+pub(crate) fn query_skills(
+    conn: &rusqlite::Connection,
+    kind: Option<String>,
+    project_path: Option<String>,
+) -> Result<Vec<SkillInfo>, String> {
     // Ensure table exists
     let _ = init_skills_table(&conn);
 
-    let mut query = "SELECT id, kind, name, description, visibility, enabled, source, project_path, created_at, updated_at FROM skills WHERE 1=1".to_string();
+    let mut query = "SELECT id, kind, name, description, visibility, enabled, source, project_path, created_at, updated_at, needs_approval
+         FROM skills WHERE 1=1".to_string();
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
     if let Some(ref k) = kind {
@@ -121,6 +263,7 @@ pub async fn list_skills(
                 project_path: row.get(7).ok(),
                 created_at: row.get(8)?,
                 updated_at: row.get(9)?,
+                needs_approval: row.get::<_, Option<bool>>(10)?.unwrap_or(false),
             })
         })
         .map_err(|e| e.to_string())?
@@ -130,6 +273,101 @@ pub async fn list_skills(
     Ok(skills)
 }
 
+/// Search skills by name, description, and tags
+///
+/// Matching is a case-insensitive substring match. Name matches come first,
+/// then description, then tag matches.
+#[tauri::command]
+pub async fn search_skills(
+    db: State<'_, AgentDb>,
+    query: String,
+    kind_filter: Option<String>,
+) -> Result<Vec<SkillSearchResult>, String> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    // Ensure table exists
+    let _ = init_skills_table(&conn);
+
+    // metadata is JSON, so it narrows candidates and tags are checked below
+    let mut sql = "SELECT id, kind, name, description, visibility, enabled, source, project_path, created_at, updated_at, metadata,
+         needs_approval
+         FROM skills
+         WHERE (name LIKE ?1 ESCAPE '\\' OR description LIKE ?1 ESCAPE '\\' OR metadata LIKE ?1 ESCAPE '\\')".to_string();
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(format!("%{}%", escape_like(&query)))];
+
+    if let Some(ref k) = kind_filter {
+        sql.push_str(" AND kind = ?2");
+        params_vec.push(Box::new(k.clone()));
+    }
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params_vec.iter().map(|p| p.as_ref())), |row| {
+            let info = SkillInfo {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                name: row.get(2)?,
+                description: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                visibility: row.get(4)?,
+                enabled: row.get(5)?,
+                source: row.get(6)?,
+                project_path: row.get(7).ok(),
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
+                needs_approval: row.get::<_, Option<bool>>(11)?.unwrap_or(false),
+            };
+            let metadata_str: Option<String> = row.get(10)?;
+            Ok((info, metadata_str))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut results: Vec<(usize, SkillSearchResult)> = rows
+        .into_iter()
+        .filter_map(|(info, metadata_str)| {
+            let tags = metadata_str
+                .and_then(|s| serde_json::from_str::<SkillMetadata>(&s).ok())
+                .map(|m| m.tags)
+                .unwrap_or_default();
+            let rank = search_rank(&query, &info.name, &info.description, &tags)?;
+            let matched_field = ["name", "description", "tags"][rank].to_string();
+            Some((rank, SkillSearchResult { skill: info, matched_field }))
+        })
+        .collect();
+    results.sort_by(|(a_rank, a), (b_rank, b)| {
+        a_rank
+            .cmp(b_rank)
+            .then_with(|| a.skill.name.to_lowercase().cmp(&b.skill.name.to_lowercase()))
+    });
+
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Escape LIKE wildcards so the query matches literally
+fn escape_like(query: &str) -> String {
+    query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Index of the first field containing a lowercased query: 0 name, 1 description, 2 tags
+fn search_rank(query: &str, name: &str, description: &str, tags: &[String]) -> Option<usize> {
+    if name.to_lowercase().contains(query) {
+        Some(0)
+    } else if description.to_lowercase().contains(query) {
+        Some(1)
+    } else if tags.iter().any(|tag| tag.to_lowercase().contains(query)) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
 /// Get a skill by ID
 #[tauri::command]
 pub async fn get_skill(db: State<'_, AgentDb>, id: String) -> Result<Skill, String> {
@@ -140,37 +378,41 @@ pub async fn get_skill(db: State<'_, AgentDb>, id: String) -> Result<Skill, Stri
             "SELECT id, kind, name, description, visibility, enabled, config, metadata, project_path, source, created_at, updated_at
              FROM skills WHERE id = ?1",
             params![id],
-            |row| {
-                let kind_str: String = row.get(1)?;
-                let visibility_str: String = row.get(4)?;
-                let config_str: String = row.get(6)?;
-                let metadata_str: Option<String> = row.get(7)?;
-
-                Ok(Skill {
-                    id: row.get(0)?,
-                    kind: serde_json::from_str(&format!("\"{}\"", kind_str)).unwrap_or(SkillKind::SlashCommand),
-                    name: row.get(2)?,
-                    description: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
-                    visibility: serde_json::from_str(&format!("\"{}\"", visibility_str)).unwrap_or(SkillVisibility::Global),
-                    enabled: row.get(5)?,
-                    config: serde_json::from_str(&config_str).unwrap_or_default(),
-                    metadata: metadata_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
-                    project_path: row.get(8).ok(),
-                    source: row.get(9)?,
-                    created_at: row.get(10)?,
-                    updated_at: row.get(11)?,
-                })
-            },
+            skill_from_row,
         )
         .map_err(|e| format!("Skill not found: {}", e))?;
 
     Ok(skill)
 }
 
+/// Build a skill from a row selecting every column of the skills table
+fn skill_from_row(row: &rusqlite::Row) -> rusqlite::Result<Skill> {
+    let kind_str: String = row.get(1)?;
+    let visibility_str: String = row.get(4)?;
+    let config_str: String = row.get(6)?;
+    let metadata_str: Option<String> = row.get(7)?;
+
+    Ok(Skill {
+        id: row.get(0)?,
+        kind: serde_json::from_str(&format!("\"{}\"", kind_str)).unwrap_or(SkillKind::SlashCommand),
+        name: row.get(2)?,
+        description: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+        visibility: serde_json::from_str(&format!("\"{}\"", visibility_str)).unwrap_or(SkillVisibility::Global),
+        enabled: row.get(5)?,
+        config: serde_json::from_str(&config_str).unwrap_or_default(),
+        metadata: metadata_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+        project_path: row.get(8).ok(),
+        source: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
 /// Create a slash command skill
 #[tauri::command]
 pub async fn create_slash_command(
     db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
     request: CreateSlashCommandRequest,
 ) -> Result<SkillInfo, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
@@ -178,26 +420,37 @@ pub async fn create_slash_command(
     // Ensure table exists
     let _ = init_skills_table(&conn);
 
-    let id = uuid::Uuid::new_v4().to_string();
     let visibility = request.visibility.as_deref().unwrap_or("global");
 
+    let mut slash_command = SlashCommandConfig {
+        name: request.name.clone(),
+        description: request.description.clone(),
+        help: request.help,
+        prompt: request.prompt,
+        requires_args: false,
+        args: request.args,
+        examples: request.examples.unwrap_or_default(),
+    };
+    slash_command.requires_args = slash_command.derive_requires_args();
     let config = SkillConfig {
-        slash_command: Some(SlashCommandConfig {
-            name: request.name.clone(),
-            description: request.description.clone(),
-            help: request.help,
-            prompt: request.prompt,
-            requires_args: false, // Will be updated based on prompt content
-            args: None,
-            examples: request.examples.unwrap_or_default(),
-        }),
+        slash_command: Some(slash_command),
         ..Default::default()
     };
 
     let config_str = serde_json::to_string(&config).map_err(|e| e.to_string())?;
     let now = chrono::Utc::now().to_rfc3339();
 
-    conn.execute(
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let replaced = claim_skill_name(
+        &tx,
+        "slash_command",
+        &format!("/{}", request.name),
+        request.project_path.as_deref(),
+        request.overwrite,
+    )?;
+    let id = replaced.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    tx.execute(
         "INSERT INTO skills (id, kind, name, description, visibility, enabled, config, source, project_path, created_at, updated_at)
          VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6, 'local', ?7, ?8, ?9)",
         params![
@@ -212,6 +465,9 @@ pub async fn create_slash_command(
             now
         ],
     ).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    sync_registry_skill(&registry.0, &conn, &id)?;
 
     info!("Created slash command: /{} ({})", request.name, id);
 
@@ -222,6 +478,7 @@ pub async fn create_slash_command(
         description: request.description,
         visibility: visibility.to_string(),
         enabled: true,
+        needs_approval: false,
         source: "local".to_string(),
         project_path: request.project_path,
         created_at: now.clone(),
@@ -230,9 +487,21 @@ pub async fn create_slash_command(
 }
 
 /// Create a hook skill
+///
+/// The command runs with these environment variables set:
+/// - `OPCODE_TRIGGER`: what fired the hook, e.g. `pre_tool` or `session_start`
+/// - `OPCODE_SKILL_ID`: ID of the hook
+/// - `OPCODE_PROJECT_PATH`, `OPCODE_SESSION_ID`: where it runs, and for which session
+/// - `OPCODE_TOOL_NAME`, `OPCODE_TOOL_ARGS`: the tool call, for tool hooks
+/// - `OPCODE_TOOL_OUTPUT`, `OPCODE_TOOL_EXIT_STATUS`: the tool's result, for post_tool hooks
+/// - `OPCODE_ERROR_MESSAGE`: the error, for on_error hooks
+/// - `OPCODE_ARG_<NAME>`: any other context argument
+///
+/// With `stdin_json`, the whole context is also written to its stdin as JSON.
 #[tauri::command]
 pub async fn create_hook(
     db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
     request: CreateHookRequest,
 ) -> Result<SkillInfo, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
@@ -240,18 +509,9 @@ pub async fn create_hook(
     // Ensure table exists
     let _ = init_skills_table(&conn);
 
-    let id = uuid::Uuid::new_v4().to_string();
     let visibility = request.visibility.as_deref().unwrap_or("global");
 
-    let trigger: HookTrigger = match request.trigger.as_str() {
-        "pre_tool" => HookTrigger::PreTool,
-        "post_tool" => HookTrigger::PostTool,
-        "session_start" => HookTrigger::SessionStart,
-        "session_end" => HookTrigger::SessionEnd,
-        "checkpoint_create" => HookTrigger::CheckpointCreate,
-        "on_error" => HookTrigger::OnError,
-        _ => return Err(format!("Invalid trigger type: {}", request.trigger)),
-    };
+    let trigger = parse_hook_trigger(&request.trigger)?;
 
     let config = SkillConfig {
         hook: Some(HookConfig {
@@ -261,6 +521,7 @@ pub async fn create_hook(
             timeout_secs: request.timeout_secs.unwrap_or(30),
             can_block: request.can_block.unwrap_or(false),
             env: HashMap::new(),
+            stdin_json: request.stdin_json.unwrap_or(false),
         }),
         ..Default::default()
     };
@@ -268,7 +529,17 @@ pub async fn create_hook(
     let config_str = serde_json::to_string(&config).map_err(|e| e.to_string())?;
     let now = chrono::Utc::now().to_rfc3339();
 
-    conn.execute(
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let replaced = claim_skill_name(
+        &tx,
+        "hook",
+        &request.name,
+        request.project_path.as_deref(),
+        request.overwrite,
+    )?;
+    let id = replaced.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    tx.execute(
         "INSERT INTO skills (id, kind, name, description, visibility, enabled, config, source, project_path, created_at, updated_at)
          VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6, 'local', ?7, ?8, ?9)",
         params![
@@ -283,6 +554,9 @@ pub async fn create_hook(
             now
         ],
     ).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    sync_registry_skill(&registry.0, &conn, &id)?;
 
     info!("Created hook: {} ({})", request.name, id);
 
@@ -293,6 +567,7 @@ pub async fn create_hook(
         description: request.description,
         visibility: visibility.to_string(),
         enabled: true,
+        needs_approval: false,
         source: "local".to_string(),
         project_path: request.project_path,
         created_at: now.clone(),
@@ -300,10 +575,125 @@ pub async fn create_hook(
     })
 }
 
+/// Create a workflow skill
+///
+/// Step IDs must be unique and every dependency must name another step.
+#[tauri::command]
+pub async fn create_workflow(
+    db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
+    request: CreateWorkflowRequest,
+) -> Result<SkillInfo, String> {
+    request.workflow.validate()?;
+    let config = SkillConfig {
+        workflow: Some(request.workflow),
+        ..Default::default()
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let info = insert_local_skill(
+        &conn,
+        &registry.0,
+        SkillKind::Workflow,
+        &request.name,
+        &request.description,
+        request.visibility.as_deref(),
+        request.project_path,
+        request.overwrite,
+        &config,
+    )?;
+    info!("Created workflow: {} ({})", info.name, info.id);
+    Ok(info)
+}
+
+/// Create a template skill
+///
+/// Every variable the content refers to must be declared.
+#[tauri::command]
+pub async fn create_template(
+    db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
+    request: CreateTemplateRequest,
+) -> Result<SkillInfo, String> {
+    request.template.validate()?;
+    let config = SkillConfig {
+        template: Some(request.template),
+        ..Default::default()
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let info = insert_local_skill(
+        &conn,
+        &registry.0,
+        SkillKind::Template,
+        &request.name,
+        &request.description,
+        request.visibility.as_deref(),
+        request.project_path,
+        request.overwrite,
+        &config,
+    )?;
+    info!("Created template: {} ({})", info.name, info.id);
+    Ok(info)
+}
+
+/// Store a skill created in the app and register it with the runtime registry
+#[allow(clippy::too_many_arguments)]
+fn insert_local_skill(
+    conn: &rusqlite::Connection,
+    registry: &SkillRegistry,
+    kind: SkillKind,
+    name: &str,
+    description: &str,
+    visibility: Option<&str>,
+    project_path: Option<String>,
+    overwrite: bool,
+    config: &SkillConfig,
+) -> Result<SkillInfo, String> {
+    let _ = init_skills_table(conn);
+
+    let kind_str = serde_json::to_value(&kind)
+        .ok()
+        .and_then(|kind| kind.as_str().map(String::from))
+        .ok_or("Invalid skill kind")?;
+    let visibility = visibility.unwrap_or("global");
+    let config_str = serde_json::to_string(config).map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let replaced = claim_skill_name(&tx, &kind_str, name, project_path.as_deref(), overwrite)?;
+    let id = replaced.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    tx.execute(
+        "INSERT INTO skills (id, kind, name, description, visibility, enabled, config, source, project_path, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6, 'local', ?7, ?8, ?9)",
+        params![id, kind_str, name, description, visibility, config_str, project_path, now, now],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    sync_registry_skill(registry, conn, &id)?;
+
+    Ok(SkillInfo {
+        id,
+        kind: kind_str,
+        name: name.to_string(),
+        description: description.to_string(),
+        visibility: visibility.to_string(),
+        enabled: true,
+        needs_approval: false,
+        source: "local".to_string(),
+        project_path,
+        created_at: now.clone(),
+        updated_at: now,
+    })
+}
+
 /// Update a skill
 #[tauri::command]
 pub async fn update_skill(
     db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
     id: String,
     name: Option<String>,
     description: Option<String>,
@@ -311,12 +701,14 @@ pub async fn update_skill(
     config: Option<serde_json::Value>,
 ) -> Result<SkillInfo, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_skills_table(&conn);
 
     // Get current skill data
-    let (current_name, current_description, current_enabled, current_config, kind, visibility, source, project_path, created_at):
-        (String, String, bool, String, String, String, String, Option<String>, String) = conn
+    let (current_name, current_description, current_enabled, current_config, kind, visibility, source, project_path, created_at, needs_approval):
+        (String, String, bool, String, String, String, String, Option<String>, String, bool) = conn
         .query_row(
-            "SELECT name, description, enabled, config, kind, visibility, source, project_path, created_at FROM skills WHERE id = ?1",
+            "SELECT name, description, enabled, config, kind, visibility, source, project_path, created_at, needs_approval
+             FROM skills WHERE id = ?1",
             params![id],
             |row| Ok((
                 row.get(0)?,
@@ -328,16 +720,33 @@ pub async fn update_skill(
                 row.get(6)?,
                 row.get(7).ok(),
                 row.get(8)?,
+                row.get::<_, Option<bool>>(9)?.unwrap_or(false),
             )),
         )
         .map_err(|e| format!("Skill not found: {}", e))?;
 
+    if needs_approval && enabled == Some(true) {
+        return Err(format!(
+            "Skill {} runs shell commands and must be approved before it can be enabled",
+            current_name
+        ));
+    }
+
     let new_name = name.unwrap_or(current_name);
     let new_description = description.unwrap_or(current_description);
     let new_enabled = enabled.unwrap_or(current_enabled);
-    let new_config = config
-        .map(|c| serde_json::to_string(&c).unwrap_or(current_config.clone()))
-        .unwrap_or(current_config);
+    let new_config = match config {
+        Some(config) => {
+            // The config must describe a skill of the kind being updated
+            let skill_kind: SkillKind =
+                serde_json::from_value(serde_json::Value::String(kind.clone())).map_err(|e| e.to_string())?;
+            let parsed: SkillConfig =
+                serde_json::from_value(config).map_err(|e| format!("Invalid skill configuration: {}", e))?;
+            parsed.validate_for(&skill_kind)?;
+            serde_json::to_string(&parsed).map_err(|e| e.to_string())?
+        }
+        None => current_config,
+    };
 
     let now = chrono::Utc::now().to_rfc3339();
 
@@ -345,6 +754,7 @@ pub async fn update_skill(
         "UPDATE skills SET name = ?1, description = ?2, enabled = ?3, config = ?4, updated_at = ?5 WHERE id = ?6",
         params![new_name, new_description, new_enabled, new_config, now, id],
     ).map_err(|e| e.to_string())?;
+    sync_registry_skill(&registry.0, &conn, &id)?;
 
     // Return updated skill info directly without re-querying
     Ok(SkillInfo {
@@ -354,6 +764,7 @@ pub async fn update_skill(
         description: new_description,
         visibility,
         enabled: new_enabled,
+        needs_approval,
         source,
         project_path,
         created_at,
@@ -361,13 +772,43 @@ pub async fn update_skill(
     })
 }
 
+/// Approve an imported skill that runs shell commands, enabling it
+#[tauri::command]
+pub async fn approve_skill(
+    db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
+    id: String,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let _ = init_skills_table(&conn);
+
+    let updated = conn
+        .execute(
+            "UPDATE skills SET needs_approval = 0, enabled = 1, updated_at = ?1 WHERE id = ?2",
+            params![chrono::Utc::now().to_rfc3339(), id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Skill not found: {}", id));
+    }
+    sync_registry_skill(&registry.0, &conn, &id)?;
+
+    info!("Approved skill: {}", id);
+    Ok(())
+}
+
 /// Delete a skill
 #[tauri::command]
-pub async fn delete_skill(db: State<'_, AgentDb>, id: String) -> Result<(), String> {
+pub async fn delete_skill(
+    db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
+    id: String,
+) -> Result<(), String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     conn.execute("DELETE FROM skills WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
+    registry.0.unregister_skill(&id);
 
     info!("Deleted skill: {}", id);
     Ok(())
@@ -382,6 +823,17 @@ pub async fn execute_slash_command(
     _project_path: String,
 ) -> Result<serde_json::Value, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    expand_slash_command(&conn, &command_name, &arguments)
+}
+
+/// Look up a slash command and expand its prompt with `arguments`
+pub(crate) fn expand_slash_command(
+    conn: &rusqlite::Connection,
+    command_name: &str,
+    arguments: &str,
+) -> Result<serde_json::Value, String> {
+    // Ensure table exists
+    let _ = init_skills_table(conn);
 
     // Find the skill by command name
     let skill: Skill = conn
@@ -413,8 +865,6 @@ pub async fn execute_slash_command(
         )
         .map_err(|e| format!("Slash command not found: /{} - {}", command_name, e))?;
 
-    drop(conn); // Release lock
-
     if !skill.enabled {
         return Err("Slash command is disabled".to_string());
     }
@@ -423,8 +873,7 @@ pub async fn execute_slash_command(
     let cmd_config = skill.config.slash_command
         .ok_or("Invalid slash command configuration")?;
 
-    let mut prompt = cmd_config.prompt.clone();
-    prompt = prompt.replace("$ARGUMENTS", &arguments);
+    let prompt = cmd_config.expand_prompt(arguments).map_err(|e| e.to_string())?;
 
     info!("Executing slash command /{}: {}", command_name, prompt);
 
@@ -435,6 +884,181 @@ pub async fn execute_slash_command(
     }))
 }
 
+/// Run the PreTool hooks for a tool call and report whether it may proceed
+///
+/// A Block decision means the caller must not run the tool; Modify replaces
+/// its arguments with `tool_args` from the decision.
+#[tauri::command]
+pub async fn run_pre_tool_hooks(
+    registry: State<'_, SkillRegistryState>,
+    project_path: String,
+    session_id: Option<String>,
+    tool_name: String,
+    tool_args: serde_json::Value,
+) -> Result<HookOutcome, String> {
+    if !std::path::Path::new(&project_path).is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+
+    let mut arguments = HashMap::new();
+    arguments.insert("tool_args".to_string(), tool_args);
+
+    let context = SkillContext {
+        project_path,
+        session_id,
+        tool_name: Some(tool_name),
+        arguments,
+        env: std::env::vars().collect(),
+        variables: HashMap::new(),
+    };
+
+    Ok(SkillExecutor::new(registry.0.clone())
+        .execute_hooks_for_trigger(HookTrigger::PreTool, context)
+        .await)
+}
+
+/// Run the hooks registered for `trigger`
+///
+/// Variables the caller leaves out of `context.env` come from Opcode's environment.
+#[tauri::command]
+pub async fn execute_hooks(
+    executor: State<'_, SkillExecutorState>,
+    trigger: String,
+    mut context: SkillContext,
+) -> Result<HookOutcome, String> {
+    let trigger = parse_hook_trigger(&trigger)?;
+    if !std::path::Path::new(&context.project_path).is_dir() {
+        return Err(format!("Project directory not found: {}", context.project_path));
+    }
+
+    let mut env: HashMap<String, String> = std::env::vars().collect();
+    env.extend(context.env);
+    context.env = env;
+
+    Ok(executor.0.execute_hooks_for_trigger(trigger, context).await)
+}
+
+/// Run the SessionStart or SessionEnd hooks for a Claude session, logging any that fail
+pub(crate) async fn run_session_hooks(
+    app: &AppHandle,
+    trigger: HookTrigger,
+    project_path: &str,
+    session_id: Option<String>,
+) {
+    let executor = match app.try_state::<SkillExecutorState>() {
+        Some(executor) => executor.0.clone(),
+        None => return,
+    };
+    session_hooks(&executor, trigger, project_path, session_id).await;
+}
+
+/// Run the hooks for a session lifecycle `trigger`
+async fn session_hooks(
+    executor: &SkillExecutor,
+    trigger: HookTrigger,
+    project_path: &str,
+    session_id: Option<String>,
+) -> HookOutcome {
+    let context = SkillContext {
+        project_path: project_path.to_string(),
+        session_id,
+        tool_name: None,
+        arguments: HashMap::new(),
+        env: std::env::vars().collect(),
+        variables: HashMap::new(),
+    };
+
+    let outcome = executor.execute_hooks_for_trigger(trigger.clone(), context).await;
+    for result in outcome.results.iter().filter(|result| !result.success) {
+        warn!("{:?} hook failed: {}", trigger, result.error.as_deref().unwrap_or("unknown error"));
+    }
+    outcome
+}
+
+/// Preview a workflow's resolved steps and execution order without running it
+#[tauri::command]
+pub async fn dry_run_workflow(
+    registry: State<'_, SkillRegistryState>,
+    skill_id: String,
+    project_path: String,
+    variables: Option<HashMap<String, serde_json::Value>>,
+) -> Result<SkillResult, String> {
+    let context = SkillContext {
+        project_path,
+        session_id: None,
+        tool_name: None,
+        arguments: HashMap::new(),
+        env: HashMap::new(),
+        variables: variables.unwrap_or_default(),
+    };
+
+    Ok(SkillExecutor::new(registry.0.clone()).execute_dry_run(&skill_id, &context))
+}
+
+/// Run a workflow skill
+///
+/// If the workflow reaches a UserInput step, the result carries the run ID in
+/// `resume_token` and a `workflow-input-required` event is emitted.
+#[tauri::command]
+pub async fn execute_workflow(
+    executor: State<'_, SkillExecutorState>,
+    skill_id: String,
+    project_path: String,
+    variables: Option<HashMap<String, serde_json::Value>>,
+) -> Result<SkillResult, String> {
+    if !std::path::Path::new(&project_path).is_dir() {
+        return Err(format!("Project directory not found: {}", project_path));
+    }
+
+    let context = SkillContext {
+        project_path,
+        session_id: None,
+        tool_name: None,
+        arguments: HashMap::new(),
+        env: std::env::vars().collect(),
+        variables: variables.unwrap_or_default(),
+    };
+
+    Ok(executor.0.execute(&skill_id, context).await)
+}
+
+/// Answer the UserInput step a workflow run is waiting at and resume the run
+#[tauri::command]
+pub async fn provide_workflow_input(
+    executor: State<'_, SkillExecutorState>,
+    run_id: String,
+    step_id: String,
+    value: serde_json::Value,
+) -> Result<SkillResult, String> {
+    let result = executor.0.provide_input(&run_id, &step_id, value).await;
+    // A result with no steps means the run couldn't be resumed at all
+    match result.steps {
+        None => Err(result.error.unwrap_or_else(|| format!("Failed to resume run {}", run_id))),
+        Some(_) => Ok(result),
+    }
+}
+
+/// Forward workflow events to the frontend (used internally)
+pub fn setup_workflow_event_emitter(app: AppHandle, executor: SkillExecutor) {
+    let mut rx = executor.subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    if let Err(e) = app.emit(event.event_name(), &event) {
+                        warn!("Failed to emit {}: {}", event.event_name(), e);
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Dropped {} workflow events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
 /// List slash commands
 #[tauri::command]
 pub async fn list_slash_commands(
@@ -473,6 +1097,7 @@ pub async fn list_slash_commands(
                 "description": cmd.description,
                 "help": cmd.help,
                 "requires_args": cmd.requires_args,
+                "usage": cmd.usage(),
                 "examples": cmd.examples,
             }))
         })
@@ -499,6 +1124,7 @@ pub async fn list_slash_commands(
                 "description": cmd.description,
                 "help": cmd.help,
                 "requires_args": cmd.requires_args,
+                "usage": cmd.usage(),
                 "examples": cmd.examples,
             }))
         })
@@ -514,6 +1140,7 @@ pub async fn list_slash_commands(
 #[tauri::command]
 pub async fn import_claude_code_skills(
     db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
     settings_path: String,
 ) -> Result<Vec<SkillInfo>, String> {
     let path = PathBuf::from(&settings_path);
@@ -564,6 +1191,7 @@ pub async fn import_claude_code_skills(
 
         imported.push(SkillInfo::from(&skill));
     }
+    reload_registry(&registry.0, &conn)?;
 
     info!("Imported {} skills from Claude Code settings", imported.len());
     Ok(imported)
@@ -573,24 +1201,43 @@ pub async fn import_claude_code_skills(
 #[tauri::command]
 pub async fn import_skill_from_github(
     db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
     repo: String,
     path: String,
     github_token: Option<String>,
+    git_ref: Option<String>,
+    expected_sha256: Option<String>,
 ) -> Result<SkillInfo, String> {
+    // A blank ref means the repository's default branch
+    let git_ref = git_ref
+        .map(|git_ref| git_ref.trim().to_string())
+        .filter(|git_ref| !git_ref.is_empty());
+    if let Some(ref git_ref) = git_ref {
+        if git_ref.contains("..") || git_ref.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(format!("Invalid git ref: {}", git_ref));
+        }
+    }
+
     let skills_dir = dirs::data_dir()
         .map(|d| d.join("opcode").join("skills"))
         .ok_or("Could not find data directory")?;
 
     // Load skill in a scoped block to avoid holding anything across await
-    let skill: Skill = {
+    let mut skill: Skill = {
         let mut loader = SkillLoader::new(skills_dir);
         if let Some(token) = github_token {
             loader = loader.with_github_token(token);
         }
-        loader.load_from_github(&repo, &path).await
+        loader.load_from_github(&repo, &path, git_ref.as_deref(), expected_sha256.as_deref()).await
             .map_err(|e| format!("Failed to load from GitHub: {:?}", e))?
     };
 
+    // Hooks and shell steps run arbitrary commands, so they wait for the user's approval
+    let needs_approval = skill.config.runs_shell_commands();
+    if needs_approval {
+        skill.enabled = false;
+    }
+
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let _ = init_skills_table(&conn);
 
@@ -600,8 +1247,8 @@ pub async fn import_skill_from_github(
     let visibility_str = format!("{:?}", skill.visibility).to_lowercase();
 
     conn.execute(
-        "INSERT OR REPLACE INTO skills (id, kind, name, description, visibility, enabled, config, metadata, project_path, source, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        "INSERT OR REPLACE INTO skills (id, kind, name, description, visibility, enabled, config, metadata, project_path, source, created_at, updated_at, needs_approval)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
         params![
             skill.id,
             kind_str,
@@ -614,10 +1261,574 @@ pub async fn import_skill_from_github(
             skill.project_path,
             skill.source,
             skill.created_at,
-            skill.updated_at
+            skill.updated_at,
+            needs_approval
         ],
     ).map_err(|e| e.to_string())?;
+    reload_registry(&registry.0, &conn)?;
+
+    if needs_approval {
+        info!("Imported skill from GitHub: {} ({}), disabled until approved", skill.name, skill.id);
+    } else {
+        info!("Imported skill from GitHub: {} ({})", skill.name, skill.id);
+    }
+    Ok(SkillInfo {
+        needs_approval,
+        ..SkillInfo::from(&skill)
+    })
+}
+
+/// File extension for an export format
+fn export_extension(format: &str) -> Result<&'static str, String> {
+    match format.to_lowercase().as_str() {
+        "json" => Ok("json"),
+        "yaml" | "yml" => Ok("yaml"),
+        "toml" => Ok("toml"),
+        other => Err(format!("Unsupported export format: {}", other)),
+    }
+}
+
+/// Skill ID reduced to characters that are safe in a file name
+fn export_file_stem(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
 
-    info!("Imported skill from GitHub: {} ({})", skill.name, skill.id);
-    Ok(SkillInfo::from(&skill))
+/// Export a skill as text in a format the skill loader can import again
+///
+/// `format` is json, yaml, or toml (Claude Code format, slash commands only).
+#[tauri::command]
+pub async fn export_skill(db: State<'_, AgentDb>, id: String, format: String) -> Result<String, String> {
+    let extension = export_extension(&format)?;
+    let skill = get_skill(db, id).await?;
+
+    SkillLoader::new(PathBuf::new())
+        .skill_to_string(&skill, extension)
+        .map_err(|e| format!("Failed to export skill: {}", e))
+}
+
+/// Export a skill to a file that the skill loader can import again
+///
+/// The format follows the file extension (json, yaml, or toml). Returns the written path.
+#[tauri::command]
+pub async fn export_skill_to_file(db: State<'_, AgentDb>, id: String, path: String) -> Result<String, String> {
+    let path = PathBuf::from(path);
+    let extension = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => export_extension(ext)?,
+        None => return Err(format!("Export path needs a .json, .yaml, or .toml extension: {}", path.display())),
+    };
+    let path = path.with_extension(extension);
+    let (dir, filename) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) if path.is_absolute() => (dir.to_path_buf(), name.to_string_lossy().to_string()),
+        _ => return Err(format!("Invalid export path: {}", path.display())),
+    };
+
+    let skill = get_skill(db, id).await?;
+
+    let written = SkillLoader::new(dir)
+        .save_skill_file(&skill, &filename)
+        .await
+        .map_err(|e| format!("Failed to export skill: {}", e))?;
+    Ok(written.to_string_lossy().to_string())
+}
+
+/// Outcome of importing one skill from a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleImportStatus {
+    /// Imported with its original ID
+    Imported,
+    /// Imported under a new ID because the original was already taken
+    IdRegenerated,
+    /// Not imported, see `error`
+    Failed,
+}
+
+/// Per-skill result of `import_skill_bundle`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleImportReport {
+    /// ID the skill had in the bundle
+    pub original_id: String,
+    /// ID the skill was stored under, if it was imported
+    pub id: Option<String>,
+    pub name: String,
+    pub status: BundleImportStatus,
+    /// Imported with shell commands and waiting for the user to approve it
+    pub needs_approval: bool,
+    pub error: Option<String>,
+}
+
+/// Load the given skills plus every skill they depend on, each one once
+///
+/// A missing optional dependency is skipped; a missing required one is an error.
+fn collect_bundle_skills(conn: &rusqlite::Connection, ids: &[String]) -> Result<Vec<Skill>, String> {
+    let mut pending: VecDeque<(String, Option<String>)> = ids.iter().map(|id| (id.clone(), None)).collect();
+    let mut seen = HashSet::new();
+    let mut skills = Vec::new();
+
+    while let Some((id, required_by)) = pending.pop_front() {
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+        let skill = conn
+            .query_row(
+                "SELECT id, kind, name, description, visibility, enabled, config, metadata, project_path, source, created_at, updated_at
+                 FROM skills WHERE id = ?1",
+                params![id],
+                skill_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let skill = match (skill, required_by) {
+            (Some(skill), _) => skill,
+            (None, None) => return Err(format!("Skill not found: {}", id)),
+            (None, Some(name)) => return Err(format!("Skill '{}' depends on missing skill {}", name, id)),
+        };
+
+        for dependency in &skill.metadata.dependencies {
+            if dependency.optional && !skill_exists(conn, &dependency.skill_id)? {
+                warn!("Skipping missing optional dependency {} of {}", dependency.skill_id, skill.name);
+                continue;
+            }
+            pending.push_back((dependency.skill_id.clone(), Some(skill.name.clone())));
+        }
+        skills.push(skill);
+    }
+    Ok(skills)
+}
+
+fn skill_exists(conn: &rusqlite::Connection, id: &str) -> Result<bool, String> {
+    conn.query_row("SELECT 1 FROM skills WHERE id = ?1", params![id], |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+        .map_err(|e| e.to_string())
+}
+
+/// Store the skills of a bundle, keeping their IDs unless already taken
+///
+/// Dependencies between bundled skills follow regenerated IDs. A skill that
+/// fails to import is reported and doesn't stop the others.
+fn import_bundle_skills(
+    conn: &rusqlite::Connection,
+    registry: &SkillRegistry,
+    bundle: SkillBundle,
+) -> Result<Vec<BundleImportReport>, String> {
+    if bundle.version > SKILL_BUNDLE_VERSION {
+        return Err(format!(
+            "Skill bundle version {} is newer than the supported version {}",
+            bundle.version, SKILL_BUNDLE_VERSION
+        ));
+    }
+    let _ = init_skills_table(conn);
+
+    let mut new_ids: HashMap<String, String> = HashMap::new();
+    for skill in &bundle.skills {
+        let taken = new_ids.values().any(|id| id == &skill.id) || skill_exists(conn, &skill.id)?;
+        let id = if taken { uuid::Uuid::new_v4().to_string() } else { skill.id.clone() };
+        new_ids.entry(skill.id.clone()).or_insert(id);
+    }
+
+    let mut reports = Vec::with_capacity(bundle.skills.len());
+    for mut skill in bundle.skills {
+        let original_id = skill.id.clone();
+        let mut report = BundleImportReport {
+            original_id: original_id.clone(),
+            id: None,
+            name: skill.name.clone(),
+            status: BundleImportStatus::Failed,
+            needs_approval: false,
+            error: None,
+        };
+        if reports.iter().any(|r: &BundleImportReport| r.original_id == original_id) {
+            report.error = Some("Skill appears more than once in the bundle".to_string());
+            reports.push(report);
+            continue;
+        }
+
+        skill.id = new_ids[&original_id].clone();
+        for dependency in &mut skill.metadata.dependencies {
+            if let Some(id) = new_ids.get(&dependency.skill_id) {
+                dependency.skill_id = id.clone();
+            }
+        }
+        // Hooks and shell steps run arbitrary commands, so they wait for the user's approval
+        let needs_approval = skill.config.runs_shell_commands();
+        if needs_approval {
+            skill.enabled = false;
+        }
+
+        match insert_bundle_skill(conn, registry, &skill, needs_approval) {
+            Ok(()) => {
+                report.status = if skill.id == original_id {
+                    BundleImportStatus::Imported
+                } else {
+                    BundleImportStatus::IdRegenerated
+                };
+                report.id = Some(skill.id);
+                report.needs_approval = needs_approval;
+            }
+            Err(e) => {
+                warn!("Failed to import skill {} from bundle: {}", skill.name, e);
+                report.error = Some(e);
+            }
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+fn insert_bundle_skill(
+    conn: &rusqlite::Connection,
+    registry: &SkillRegistry,
+    skill: &Skill,
+    needs_approval: bool,
+) -> Result<(), String> {
+    skill.config.validate_for(&skill.kind)?;
+    let kind_str = serde_json::to_value(&skill.kind)
+        .ok()
+        .and_then(|kind| kind.as_str().map(String::from))
+        .ok_or("Invalid skill kind")?;
+    let visibility_str = serde_json::to_value(&skill.visibility)
+        .ok()
+        .and_then(|visibility| visibility.as_str().map(String::from))
+        .ok_or("Invalid skill visibility")?;
+    let config_str = serde_json::to_string(&skill.config).map_err(|e| e.to_string())?;
+    let metadata_str = serde_json::to_string(&skill.metadata).map_err(|e| e.to_string())?;
+
+    claim_skill_name(conn, &kind_str, &skill.name, skill.project_path.as_deref(), false)?;
+    conn.execute(
+        "INSERT INTO skills (id, kind, name, description, visibility, enabled, config, metadata, project_path, source, created_at, updated_at, needs_approval)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            skill.id,
+            kind_str,
+            skill.name,
+            skill.description,
+            visibility_str,
+            skill.enabled,
+            config_str,
+            metadata_str,
+            skill.project_path,
+            skill.source,
+            skill.created_at,
+            skill.updated_at,
+            needs_approval
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    sync_registry_skill(registry, conn, &skill.id)
+}
+
+/// Export skills and the skills they depend on into one JSON bundle file
+///
+/// Returns the written path.
+#[tauri::command]
+pub async fn export_skill_bundle(db: State<'_, AgentDb>, ids: Vec<String>, path: String) -> Result<String, String> {
+    if ids.is_empty() {
+        return Err("No skills selected for export".to_string());
+    }
+    let path = PathBuf::from(path);
+    if !path.is_absolute() || path.file_name().is_none() {
+        return Err(format!("Invalid export path: {}", path.display()));
+    }
+
+    let bundle = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let _ = init_skills_table(&conn);
+        SkillBundle {
+            version: SKILL_BUNDLE_VERSION,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            skills: collect_bundle_skills(&conn, &ids)?,
+        }
+    };
+    let content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+    }
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| format!("Failed to write skill bundle: {}", e))?;
+
+    info!("Exported bundle of {} skills", bundle.skills.len());
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Import a bundle written by `export_skill_bundle`
+///
+/// Skills keep their IDs unless another skill already uses them. Skills that
+/// run shell commands are imported disabled until approved.
+#[tauri::command]
+pub async fn import_skill_bundle(
+    db: State<'_, AgentDb>,
+    registry: State<'_, SkillRegistryState>,
+    path: String,
+) -> Result<Vec<BundleImportReport>, String> {
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read skill bundle: {}", e))?;
+    let bundle: SkillBundle =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid skill bundle: {}", e))?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let reports = import_bundle_skills(&conn, &registry.0, bundle)?;
+
+    let imported = reports.iter().filter(|r| r.status != BundleImportStatus::Failed).count();
+    info!("Imported {} of {} skills from bundle", imported, reports.len());
+    Ok(reports)
+}
+
+/// Export every skill into a directory, one file per skill, for backup
+///
+/// `format` is json (default) or yaml. Returns the written paths.
+#[tauri::command]
+pub async fn export_all_skills(
+    db: State<'_, AgentDb>,
+    dir: String,
+    format: Option<String>,
+) -> Result<Vec<String>, String> {
+    let extension = export_extension(format.as_deref().unwrap_or("json"))?;
+    if extension == "toml" {
+        return Err("TOML export only covers slash commands; export them individually".to_string());
+    }
+    let dir = PathBuf::from(dir);
+    if !dir.is_absolute() {
+        return Err(format!("Invalid export directory: {}", dir.display()));
+    }
+
+    let skills: Vec<Skill> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let _ = init_skills_table(&conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, kind, name, description, visibility, enabled, config, metadata, project_path, source, created_at, updated_at
+                 FROM skills ORDER BY created_at",
+            )
+            .map_err(|e| e.to_string())?;
+        let skills = stmt
+            .query_map([], skill_from_row)
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        skills
+    };
+
+    let loader = SkillLoader::new(dir);
+    let mut written = Vec::with_capacity(skills.len());
+    for skill in &skills {
+        let filename = format!("{}.{}", export_file_stem(&skill.id), extension);
+        let path = loader
+            .save_skill_file(skill, &filename)
+            .await
+            .map_err(|e| format!("Failed to export skill {}: {}", skill.name, e))?;
+        written.push(path.to_string_lossy().to_string());
+    }
+
+    info!("Exported {} skills", written.len());
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skills::types::SkillDependency;
+
+    #[test]
+    fn test_created_command_resolves_through_registry() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        init_skills_table(&conn).unwrap();
+        let registry = SkillRegistry::new();
+
+        let config = SkillConfig {
+            slash_command: Some(SlashCommandConfig {
+                name: "review".to_string(),
+                description: "Review code".to_string(),
+                help: None,
+                prompt: "Review $ARGUMENTS".to_string(),
+                requires_args: false,
+                args: None,
+                examples: vec![],
+            }),
+            ..Default::default()
+        };
+        conn.execute(
+            "INSERT INTO skills (id, kind, name, description, visibility, enabled, config, source)
+             VALUES ('cmd-1', 'slash_command', '/review', 'Review code', 'global', 1, ?1, 'local')",
+            params![serde_json::to_string(&config).unwrap()],
+        )
+        .unwrap();
+        sync_registry_skill(&registry, &conn, "cmd-1").unwrap();
+        assert_eq!(registry.get_slash_command("review").unwrap().id, "cmd-1");
+
+        conn.execute("UPDATE skills SET enabled = 0 WHERE id = 'cmd-1'", []).unwrap();
+        sync_registry_skill(&registry, &conn, "cmd-1").unwrap();
+        assert!(!registry.has_slash_command("review"));
+
+        conn.execute("UPDATE skills SET enabled = 1 WHERE id = 'cmd-1'", []).unwrap();
+        sync_registry_skill(&registry, &conn, "cmd-1").unwrap();
+        conn.execute("DELETE FROM skills WHERE id = 'cmd-1'", []).unwrap();
+        sync_registry_skill(&registry, &conn, "cmd-1").unwrap();
+        assert!(registry.get_skill("cmd-1").is_none());
+    }
+
+    #[test]
+    fn test_created_workflow_is_stored_and_registered() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let registry = SkillRegistry::new();
+        let workflow: WorkflowConfig = serde_json::from_value(serde_json::json!({
+            "steps": [
+                { "id": "build", "kind": "shell", "name": "Build", "config": { "command": "make" }, "depends_on": [] },
+                { "id": "test", "kind": "shell", "name": "Test", "config": { "command": "make test" },
+                  "depends_on": ["build"] },
+            ],
+            "inputs": [],
+            "outputs": {},
+        }))
+        .unwrap();
+        workflow.validate().unwrap();
+        let config = SkillConfig {
+            workflow: Some(workflow),
+            ..Default::default()
+        };
+
+        let info = insert_local_skill(&conn, &registry, SkillKind::Workflow, "ci", "", None, None, false, &config)
+            .unwrap();
+        assert_eq!(info.kind, "workflow");
+        let skill = registry.get_skill(&info.id).unwrap();
+        assert_eq!(skill.kind, SkillKind::Workflow);
+        assert_eq!(skill.config.workflow.unwrap().steps.len(), 2);
+
+        // Same name in the same scope needs overwrite
+        let duplicate = insert_local_skill(&conn, &registry, SkillKind::Workflow, "ci", "", None, None, false, &config);
+        assert!(duplicate.is_err());
+        let replaced =
+            insert_local_skill(&conn, &registry, SkillKind::Workflow, "ci", "", None, None, true, &config).unwrap();
+        assert_eq!(replaced.id, info.id);
+
+        // A failed insert leaves the skill it would have replaced in place
+        conn.execute_batch(
+            "CREATE TRIGGER reject_insert BEFORE INSERT ON skills BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+        )
+        .unwrap();
+        let failed = insert_local_skill(&conn, &registry, SkillKind::Workflow, "ci", "", None, None, true, &config);
+        assert!(failed.is_err());
+        let kept: String = conn
+            .query_row("SELECT id FROM skills WHERE kind = 'workflow' AND name = 'ci'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kept, info.id);
+    }
+
+    #[test]
+    fn test_skill_bundle_round_trip() {
+        let command = |name: &str| SkillConfig {
+            slash_command: Some(SlashCommandConfig {
+                name: name.to_string(),
+                description: String::new(),
+                help: None,
+                prompt: format!("Run {}", name),
+                requires_args: false,
+                args: None,
+                examples: vec![],
+            }),
+            ..Default::default()
+        };
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let registry = SkillRegistry::new();
+        let review = insert_local_skill(&conn, &registry, SkillKind::SlashCommand, "/review", "", None, None, false,
+            &command("review")).unwrap();
+        let lint = insert_local_skill(&conn, &registry, SkillKind::SlashCommand, "/lint", "", None, None, false,
+            &command("lint")).unwrap();
+        let metadata = SkillMetadata {
+            dependencies: vec![SkillDependency { skill_id: lint.id.clone(), version: None, optional: false }],
+            ..Default::default()
+        };
+        conn.execute(
+            "UPDATE skills SET metadata = ?1 WHERE id = ?2",
+            params![serde_json::to_string(&metadata).unwrap(), review.id],
+        )
+        .unwrap();
+
+        // Exporting one skill pulls in its dependency
+        let skills = collect_bundle_skills(&conn, &[review.id.clone()]).unwrap();
+        let ids: Vec<&str> = skills.iter().map(|skill| skill.id.as_str()).collect();
+        assert_eq!(ids, vec![review.id.as_str(), lint.id.as_str()]);
+        assert!(collect_bundle_skills(&conn, &["missing".to_string()]).is_err());
+
+        // The target already uses the dependency's ID for another skill
+        let target = rusqlite::Connection::open_in_memory().unwrap();
+        init_skills_table(&target).unwrap();
+        target
+            .execute(
+                "INSERT INTO skills (id, kind, name, description, visibility, enabled, config, source)
+                 VALUES (?1, 'slash_command', '/other', '', 'global', 1, '{}', 'local')",
+                params![lint.id],
+            )
+            .unwrap();
+        let target_registry = SkillRegistry::new();
+        let bundle = SkillBundle { version: SKILL_BUNDLE_VERSION, exported_at: String::new(), skills };
+        let reports = import_bundle_skills(&target, &target_registry, bundle).unwrap();
+
+        assert_eq!(reports[0].status, BundleImportStatus::Imported);
+        assert_eq!(reports[0].id.as_deref(), Some(review.id.as_str()));
+        assert_eq!(reports[1].status, BundleImportStatus::IdRegenerated);
+        let new_lint_id = reports[1].id.clone().unwrap();
+        assert_ne!(new_lint_id, lint.id);
+        assert!(target_registry.has_slash_command("lint"));
+
+        let imported = target_registry.get_skill(&review.id).unwrap();
+        assert_eq!(imported.metadata.dependencies[0].skill_id, new_lint_id);
+
+        // Importing again conflicts on names and is reported per skill
+        let again = SkillBundle {
+            version: SKILL_BUNDLE_VERSION,
+            exported_at: String::new(),
+            skills: collect_bundle_skills(&conn, &[lint.id.clone()]).unwrap(),
+        };
+        let reports = import_bundle_skills(&target, &target_registry, again).unwrap();
+        assert_eq!(reports[0].status, BundleImportStatus::Failed);
+        assert!(reports[0].error.is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_start_hook_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Arc::new(SkillRegistry::new());
+        registry.register_skill(Skill {
+            id: "hook-1".to_string(),
+            kind: SkillKind::Hook,
+            name: "Mark session".to_string(),
+            description: String::new(),
+            visibility: SkillVisibility::Global,
+            enabled: true,
+            config: SkillConfig {
+                hook: Some(HookConfig {
+                    trigger: parse_hook_trigger("session_start").unwrap(),
+                    tool_patterns: None,
+                    command: "touch \"started-$OPCODE_SESSION_ID\"".to_string(),
+                    timeout_secs: 5,
+                    can_block: false,
+                    env: HashMap::new(),
+                    stdin_json: false,
+                }),
+                ..Default::default()
+            },
+            metadata: SkillMetadata::default(),
+            project_path: None,
+            source: "local".to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        });
+        let executor = SkillExecutor::new(registry);
+        let project_path = dir.path().to_string_lossy().to_string();
+
+        let outcome = session_hooks(&executor, HookTrigger::SessionEnd, &project_path, None).await;
+        assert!(outcome.results.is_empty());
+
+        let outcome =
+            session_hooks(&executor, HookTrigger::SessionStart, &project_path, Some("abc".to_string())).await;
+        assert!(outcome.results[0].success, "hook failed: {:?}", outcome.results[0].error);
+        assert!(dir.path().join("started-abc").exists());
+    }
 }
@@ -5,7 +5,7 @@
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, State};
 
-use crate::tasks::{TaskManager, TaskEvent, TaskInfo};
+use crate::tasks::{TaskFilter, TaskManager, TaskEvent, TaskInfo, TaskResult};
 
 /// Task manager state
 pub struct TaskManagerState(pub Arc<TaskManager>);
@@ -16,26 +16,66 @@ impl Default for TaskManagerState {
     }
 }
 
-/// List all tasks
+/// Filter from the optional list command parameters
+fn task_filter(
+    project_path: Option<String>,
+    session_id: Option<String>,
+    agent_id: Option<i64>,
+    tag: Option<String>,
+) -> TaskFilter {
+    TaskFilter {
+        project_path,
+        session_id,
+        agent_id,
+        tag,
+        ..Default::default()
+    }
+}
+
+/// List all tasks, optionally only those with matching metadata
 #[tauri::command]
-pub async fn list_tasks(task_manager: State<'_, TaskManagerState>) -> Result<Vec<TaskInfo>, String> {
-    Ok(task_manager.0.list_tasks())
+pub async fn list_tasks(
+    task_manager: State<'_, TaskManagerState>,
+    project_path: Option<String>,
+    session_id: Option<String>,
+    agent_id: Option<i64>,
+    tag: Option<String>,
+) -> Result<Vec<TaskInfo>, String> {
+    let filter = task_filter(project_path, session_id, agent_id, tag);
+    Ok(task_manager.0.list_tasks_filtered(&filter))
 }
 
-/// List active tasks
+/// List active tasks, optionally only those with matching metadata
 #[tauri::command]
 pub async fn list_active_tasks(
     task_manager: State<'_, TaskManagerState>,
+    project_path: Option<String>,
+    session_id: Option<String>,
+    agent_id: Option<i64>,
+    tag: Option<String>,
 ) -> Result<Vec<TaskInfo>, String> {
-    Ok(task_manager.0.list_active_tasks())
+    let filter = TaskFilter {
+        active_only: true,
+        ..task_filter(project_path, session_id, agent_id, tag)
+    };
+    Ok(task_manager.0.list_tasks_filtered(&filter))
 }
 
-/// List background tasks
+/// List background tasks, optionally only those with matching metadata
 #[tauri::command]
 pub async fn list_background_tasks(
     task_manager: State<'_, TaskManagerState>,
+    project_path: Option<String>,
+    session_id: Option<String>,
+    agent_id: Option<i64>,
+    tag: Option<String>,
 ) -> Result<Vec<TaskInfo>, String> {
-    Ok(task_manager.0.list_background_tasks())
+    let filter = TaskFilter {
+        active_only: true,
+        background_only: true,
+        ..task_filter(project_path, session_id, agent_id, tag)
+    };
+    Ok(task_manager.0.list_tasks_filtered(&filter))
 }
 
 /// Get task by ID
@@ -50,13 +90,34 @@ pub async fn get_task(
         .ok_or_else(|| format!("Task not found: {}", id))
 }
 
+/// Get the full result of a finished task, including its error and logs
+#[tauri::command]
+pub async fn get_task_result(
+    task_manager: State<'_, TaskManagerState>,
+    id: String,
+) -> Result<TaskResult, String> {
+    task_manager
+        .0
+        .get_task_result(&id)
+        .map_err(|e| format!("Failed to read task result: {}", e))?
+        .ok_or_else(|| format!("No result for task: {}", id))
+}
+
 /// Cancel a task
+///
+/// Cancellation is cooperative: the task only stops once it notices the cancel
+/// signal. With `force`, its future is aborted at its next await point instead.
 #[tauri::command]
 pub async fn cancel_task(
     task_manager: State<'_, TaskManagerState>,
     id: String,
+    force: Option<bool>,
 ) -> Result<(), String> {
-    task_manager.0.cancel_task(&id)
+    if force.unwrap_or(false) {
+        task_manager.0.abort_task(&id)
+    } else {
+        task_manager.0.cancel_task(&id)
+    }
 }
 
 /// Clear completed tasks
@@ -68,6 +129,22 @@ pub async fn clear_completed_tasks(
     Ok(())
 }
 
+/// Most tasks `get_task_history` returns at once
+const MAX_TASK_HISTORY: usize = 1000;
+
+/// Get finished tasks, newest first, including ones no longer kept in memory
+#[tauri::command]
+pub async fn get_task_history(
+    task_manager: State<'_, TaskManagerState>,
+    limit: Option<usize>,
+) -> Result<Vec<TaskInfo>, String> {
+    let limit = limit.unwrap_or(50).clamp(1, MAX_TASK_HISTORY);
+    task_manager
+        .0
+        .get_task_history(limit)
+        .map_err(|e| format!("Failed to read task history: {}", e))
+}
+
 /// Get task count
 #[tauri::command]
 pub async fn get_task_count(
@@ -0,0 +1,95 @@
+//! Shared HTTP client construction
+//!
+//! Every outgoing reqwest client is built here so the app's proxy settings
+//! apply consistently, rather than depending on whether the proxy environment
+//! variables were set before the client was created.
+
+use log::warn;
+use parking_lot::RwLock;
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy};
+use std::time::Duration;
+
+use crate::commands::proxy::ProxySettings;
+
+/// Request timeout for one-off requests (GitHub, token refresh)
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Proxy settings in effect, updated whenever they are applied
+static CURRENT_PROXY: RwLock<Option<ProxySettings>> = RwLock::new(None);
+
+/// Record the proxy settings new clients should use
+pub fn set_proxy_settings(settings: &ProxySettings) {
+    *CURRENT_PROXY.write() = Some(settings.clone());
+}
+
+/// Proxy settings in effect (disabled until settings are applied)
+pub fn proxy_settings() -> ProxySettings {
+    CURRENT_PROXY.read().clone().unwrap_or_default()
+}
+
+/// Client builder with the request timeout and proxies configured
+///
+/// Callers that need more (TLS, pool limits) add it before building.
+pub fn client_builder(timeout: Duration, proxy: &ProxySettings) -> ClientBuilder {
+    let builder = Client::builder().timeout(timeout);
+    apply_proxy(builder, proxy)
+}
+
+/// Build a client with the request timeout and proxies configured
+pub fn build_client(timeout: Duration, proxy: &ProxySettings) -> reqwest::Result<Client> {
+    client_builder(timeout, proxy).build()
+}
+
+fn apply_proxy(builder: ClientBuilder, settings: &ProxySettings) -> ClientBuilder {
+    // Explicit proxies only, so stale environment variables can't leak in
+    let mut builder = builder.no_proxy();
+    if !settings.enabled {
+        return builder;
+    }
+
+    let no_proxy = NoProxy::from_string(&settings.no_proxy_value());
+    let proxies = [
+        ("http", settings.http_proxy.as_deref()),
+        ("https", settings.https_proxy.as_deref()),
+        ("all", settings.all_proxy.as_deref()),
+    ];
+
+    // reqwest uses the first proxy that intercepts a URL, so scheme-specific ones go first
+    for (scheme, url) in proxies {
+        let url = match url {
+            Some(url) if !url.is_empty() => url,
+            _ => continue,
+        };
+        let proxy = match scheme {
+            "http" => Proxy::http(url),
+            "https" => Proxy::https(url),
+            _ => Proxy::all(url),
+        };
+        match proxy {
+            Ok(proxy) => builder = builder.proxy(proxy.no_proxy(no_proxy.clone())),
+            // The URL may carry credentials, so only the scheme is logged
+            Err(_) => warn!("Ignoring invalid {} proxy URL", scheme),
+        }
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_client_with_proxy_settings() {
+        let settings = ProxySettings {
+            http_proxy: Some("http://proxy.internal:3128".to_string()),
+            https_proxy: Some("not a url".to_string()),
+            no_proxy: Some("*.corp.example".to_string()),
+            all_proxy: None,
+            enabled: true,
+        };
+
+        // Invalid entries are skipped rather than failing every client
+        assert!(build_client(Duration::from_secs(5), &settings).is_ok());
+        assert!(build_client(Duration::from_secs(5), &ProxySettings::default()).is_ok());
+    }
+}
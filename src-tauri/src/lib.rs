@@ -8,6 +8,7 @@
 pub mod checkpoint;
 pub mod claude_binary;
 pub mod commands;
+pub mod http;
 pub mod mcp;         // MCP Streamable HTTP transport for remote servers
 pub mod process;
 pub mod session;     // Session management with DashMap
@@ -19,8 +20,15 @@ pub mod web_server;
 pub use checkpoint::state::CheckpointState;
 pub use commands::agents::{AgentDb, init_database};
 pub use commands::claude::ClaudeProcessState;
+pub use commands::mcp_sampling::McpSamplingState;
+pub use commands::remote_mcp::{
+    McpConnectionPoolState, McpHealthMonitorState, McpResourceForwardersState, McpRootsState,
+    McpServerLogsState, McpToolCacheState, McpToolCallsState, McpTraceState,
+};
+pub use commands::skills::SkillRegistryState;
 pub use commands::tasks::TaskManagerState;
 pub use process::ProcessRegistryState;
+pub use session::SessionManager;
 
 use std::sync::Mutex;
 use tauri::Manager;
@@ -28,6 +36,59 @@ use tauri::Manager;
 #[cfg(target_os = "macos")]
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
 
+/// Headless entry point serving Opcode's tools to MCP clients over stdio
+///
+/// stdout carries the protocol, so logging stays on stderr.
+pub fn run_mcp_server() {
+    env_logger::init();
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start async runtime");
+    let result = runtime.block_on(async {
+        let server = mcp::server::OpcodeMcpServer::open().await?;
+        server.serve_stdio().await.map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("Opcode MCP server failed: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Headless entry point Claude runs as its PreToolUse hook while blocking PreTool hooks exist
+///
+/// Reads Claude's hook request from stdin and prints the hooks' decision on stdout.
+pub fn run_pre_tool_hook() {
+    env_logger::init();
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start async runtime");
+    let result = runtime.block_on(async {
+        let mut input = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut tokio::io::stdin(), &mut input)
+            .await
+            .map_err(|e| e.to_string())?;
+        let request: serde_json::Value = serde_json::from_str(&input).map_err(|e| e.to_string())?;
+
+        let app_dir = dirs::data_dir()
+            .ok_or("Could not find data directory")?
+            .join(mcp::server::APP_IDENTIFIER);
+        let conn = commands::agents::init_database_at(&app_dir).map_err(|e| e.to_string())?;
+        let _ = commands::skills::init_skills_table(&conn);
+        let registry = std::sync::Arc::new(skills::SkillRegistry::new());
+        registry.load_from_database(&conn).map_err(|e| e.to_string())?;
+
+        let executor = skills::SkillExecutor::new(registry);
+        Ok::<_, String>(skills::tool_stream::answer_pre_tool_use(&executor, &request).await)
+    });
+    match result {
+        Ok(Some(answer)) => println!("{}", answer),
+        Ok(None) => {}
+        Err(e) => {
+            // Claude treats exit code 1 as a hook error and lets the tool run
+            eprintln!("Opcode PreTool hooks failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 /// Main entry point for the Tauri application
 /// This is called by both desktop (main.rs) and mobile builds
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -127,8 +188,91 @@ pub fn run() {
             // Initialize Claude process state
             app.manage(ClaudeProcessState::default());
 
-            // Initialize task manager (Opcode 2.0)
-            app.manage(TaskManagerState::default());
+            // Initialize task manager and restore task history (Opcode 2.0)
+            let conn = init_database(&app.handle()).expect("Failed to initialize agents database");
+            let task_manager = tasks::TaskManager::new()
+                .with_database(conn)
+                .expect("Failed to initialize tasks table");
+            if let Err(e) = task_manager.load_persisted_tasks() {
+                log::warn!("Failed to restore persisted tasks: {}", e);
+            }
+            app.manage(TaskManagerState(std::sync::Arc::new(task_manager)));
+
+            // Restore session history from the previous run (Opcode 2.0)
+            let conn = init_database(&app.handle()).expect("Failed to initialize agents database");
+            let session_manager = SessionManager::new()
+                .with_database(conn)
+                .expect("Failed to initialize sessions table");
+            if let Err(e) = session_manager.load_persisted_sessions() {
+                log::warn!("Failed to restore persisted sessions: {}", e);
+            }
+            app.manage(session_manager);
+
+            // Cancel running sessions that stopped making progress (Opcode 2.0)
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let emitter = session::SessionEventEmitter::new(app_handle.clone());
+                app_handle.state::<SessionManager>().run_idle_sweep(emitter).await;
+            });
+
+            // Load skills into the registry the skill commands execute from (Opcode 2.0)
+            let skill_registry = SkillRegistryState::default();
+            match app.state::<AgentDb>().0.lock() {
+                Ok(conn) => {
+                    let _ = commands::skills::init_skills_table(&conn);
+                    if let Err(e) = skill_registry.0.load_from_database(&conn) {
+                        log::warn!("Failed to load skills: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to lock database for skills: {}", e),
+            }
+            // Workflows started from the frontend, and their input timeouts (Opcode 2.0)
+            let skill_executor = skills::SkillExecutor::new(skill_registry.0.clone());
+            commands::skills::setup_workflow_event_emitter(app.handle().clone(), skill_executor.clone());
+            let executor = skill_executor.clone();
+            tauri::async_runtime::spawn(async move {
+                executor.run_input_timeouts().await;
+            });
+            app.manage(commands::skills::SkillExecutorState(skill_executor));
+            app.manage(skill_registry);
+
+            // Initialize remote MCP connection pool and evict idle connections (Opcode 2.0)
+            let mcp_pool = McpConnectionPoolState::default();
+            let pool = mcp_pool.0.clone();
+            tauri::async_runtime::spawn(async move {
+                pool.run_idle_eviction().await;
+            });
+            let health_monitor = McpHealthMonitorState::with_pool(mcp_pool.0.clone(), app.handle());
+            app.manage(mcp_pool);
+            app.manage(McpResourceForwardersState::default());
+            app.manage(McpToolCallsState::default());
+            app.manage(McpToolCacheState::default());
+            app.manage(McpServerLogsState::default());
+            app.manage(McpRootsState::default());
+            app.manage(McpSamplingState::default());
+            app.manage(McpTraceState::default());
+
+            // Monitor remote MCP servers with health checks enabled (Opcode 2.0)
+            commands::remote_mcp::spawn_health_event_bridge(
+                app.handle().clone(),
+                health_monitor.0.clone(),
+            );
+            let monitor = health_monitor.0.clone();
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let db = app_handle.state::<AgentDb>();
+                if let Err(e) = commands::remote_mcp::migrate_auth_secrets(&db) {
+                    log::warn!("Failed to migrate MCP credentials: {}", e);
+                }
+                if let Err(e) = commands::remote_mcp::restore_health_history(&db, &monitor) {
+                    log::warn!("Failed to restore MCP health history: {}", e);
+                }
+                match commands::remote_mcp::load_monitored_servers(&db) {
+                    Ok(servers) => monitor.start_monitoring(servers),
+                    Err(e) => log::warn!("Failed to load health-monitored MCP servers: {}", e),
+                }
+            });
+            app.manage(health_monitor);
 
             // Apply window vibrancy with rounded corners on macOS
             #[cfg(target_os = "macos")]
@@ -276,21 +420,78 @@ pub fn run() {
             commands::remote_mcp::list_remote_mcp_servers,
             commands::remote_mcp::add_remote_mcp_server,
             commands::remote_mcp::remove_remote_mcp_server,
+            commands::remote_mcp::remove_remote_mcp_group,
+            commands::remote_mcp::set_remote_mcp_group_enabled,
             commands::remote_mcp::test_remote_mcp_connection,
+            commands::remote_mcp::test_remote_mcp_group,
             commands::remote_mcp::list_remote_mcp_tools,
             commands::remote_mcp::call_remote_mcp_tool,
+            commands::remote_mcp::call_remote_mcp_tool_streaming,
+            commands::remote_mcp::cancel_remote_mcp_tool_call,
+            commands::remote_mcp::validate_remote_mcp_tool_args,
+            commands::remote_mcp::list_remote_mcp_resources,
+            commands::remote_mcp::read_remote_mcp_resource,
+            commands::remote_mcp::list_remote_mcp_prompts,
+            commands::remote_mcp::get_remote_mcp_prompt,
+            commands::remote_mcp::subscribe_remote_mcp_resource,
+            commands::remote_mcp::unsubscribe_remote_mcp_resource,
             commands::remote_mcp::update_remote_mcp_server,
+            commands::remote_mcp::disconnect_remote_mcp_server,
+            commands::remote_mcp::start_mcp_health_monitoring,
+            commands::remote_mcp::stop_mcp_health_monitoring,
+            commands::remote_mcp::start_mcp_group_monitoring,
+            commands::remote_mcp::stop_mcp_group_monitoring,
+            commands::remote_mcp::refresh_health_monitoring,
+            commands::remote_mcp::get_mcp_server_health,
+            commands::remote_mcp::get_remote_mcp_session_info,
+            commands::remote_mcp::get_remote_mcp_capabilities,
+            commands::remote_mcp::sync_remote_mcp_to_claude,
+            commands::remote_mcp::import_claude_mcp_servers,
+            commands::remote_mcp::export_remote_mcp_servers,
+            commands::remote_mcp::import_remote_mcp_servers,
+            commands::remote_mcp::get_server_health_history,
+            commands::remote_mcp::get_remote_mcp_metrics,
+            commands::remote_mcp::set_remote_mcp_log_level,
+            commands::remote_mcp::get_remote_mcp_logs,
+            commands::remote_mcp::set_mcp_roots,
+            commands::remote_mcp::set_mcp_active_project,
+            commands::remote_mcp::set_server_degraded_thresholds,
+            commands::remote_mcp::set_server_tool_timeout,
+            commands::remote_mcp::set_mcp_sampling_auto_approve,
+            commands::remote_mcp::set_remote_mcp_debug_trace,
+            commands::remote_mcp::get_remote_mcp_trace,
+            commands::remote_mcp::clear_remote_mcp_trace,
+            commands::remote_mcp::export_remote_mcp_trace,
+            commands::mcp_sampling::respond_to_mcp_sampling,
+            // Sessions (Opcode 2.0)
+            commands::sessions::set_session_token_budget,
+            commands::sessions::get_session_metrics,
+            commands::sessions::get_buffered_output,
             // Skills System (Opcode 2.0)
             commands::skills::list_skills,
+            commands::skills::search_skills,
             commands::skills::get_skill,
             commands::skills::create_slash_command,
             commands::skills::create_hook,
+            commands::skills::create_workflow,
+            commands::skills::create_template,
             commands::skills::update_skill,
             commands::skills::delete_skill,
+            commands::skills::approve_skill,
             commands::skills::execute_slash_command,
             commands::skills::list_slash_commands,
+            commands::skills::run_pre_tool_hooks,
+            commands::skills::execute_hooks,
+            commands::skills::dry_run_workflow,
+            commands::skills::execute_workflow,
+            commands::skills::provide_workflow_input,
             commands::skills::import_claude_code_skills,
             commands::skills::import_skill_from_github,
+            commands::skills::export_skill,
+            commands::skills::export_skill_to_file,
+            commands::skills::export_skill_bundle,
+            commands::skills::import_skill_bundle,
+            commands::skills::export_all_skills,
             // Parallel Tasks Manager (Opcode 2.0)
             commands::tasks::list_tasks,
             commands::tasks::list_active_tasks,
@@ -299,7 +500,17 @@ pub fn run() {
             commands::tasks::cancel_task,
             commands::tasks::clear_completed_tasks,
             commands::tasks::get_task_count,
+            commands::tasks::get_task_history,
+            commands::tasks::get_task_result,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // End remote MCP sessions so servers don't keep them until they expire
+                if let Some(pool) = app.try_state::<McpConnectionPoolState>() {
+                    tauri::async_runtime::block_on(pool.0.disconnect_all());
+                }
+            }
+        });
 }
@@ -5,6 +5,17 @@
 ///
 /// This is a thin wrapper that delegates to the library crate.
 /// All application code, modules, and Tauri setup lives in lib.rs.
+///
+/// `opcode --mcp-serve` runs headless as an MCP server over stdio instead, and
+/// `opcode --pre-tool-hook` answers a single PreToolUse hook request from Claude.
 fn main() {
+    if std::env::args().skip(1).any(|arg| arg == "--mcp-serve") {
+        opcode_lib::run_mcp_server();
+        return;
+    }
+    if std::env::args().skip(1).any(|arg| arg == "--pre-tool-hook") {
+        opcode_lib::run_pre_tool_hook();
+        return;
+    }
     opcode_lib::run();
 }
@@ -1,12 +1,13 @@
 //! MCP Authentication Module
 //!
-//! Supports Bearer tokens and API keys for remote MCP servers.
+//! Supports Bearer tokens, API keys and OAuth2 for remote MCP servers.
 //! Designed for simplicity and security as per user requirements.
 
 use async_trait::async_trait;
 use reqwest::RequestBuilder;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use super::error::{McpError, McpResult};
 use super::types::McpAuthConfig;
@@ -197,6 +198,195 @@ impl McpAuth for McpCustomHeadersAuth {
     }
 }
 
+/// Refresh tokens this close to expiry rather than waiting for a 401
+const OAUTH_REFRESH_SKEW_SECS: i64 = 60;
+
+/// Callback invoked with the updated credentials after a successful refresh
+pub type OAuthRefreshCallback = Arc<dyn Fn(&McpOAuthAuth) + Send + Sync>;
+
+/// OAuth2 authentication with refresh-token support
+///
+/// Holds a short-lived access token and exchanges the refresh token at the
+/// server's token endpoint when the access token expires or is rejected.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct McpOAuthAuth {
+    access_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    token_url: String,
+    client_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Persists refreshed tokens (e.g. back to the database)
+    #[serde(skip)]
+    on_refresh: Option<OAuthRefreshCallback>,
+}
+
+impl std::fmt::Debug for McpOAuthAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print token values
+        f.debug_struct("McpOAuthAuth")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("has_refresh_token", &self.refresh_token.is_some())
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Token endpoint response (RFC 6749 section 5.1)
+#[derive(Debug, Deserialize)]
+struct OAuthTokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+impl McpOAuthAuth {
+    /// Create a new OAuth authentication
+    pub fn new(
+        access_token: impl Into<String>,
+        refresh_token: Option<String>,
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: Option<String>,
+    ) -> Self {
+        Self {
+            access_token: access_token.into(),
+            refresh_token,
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret,
+            expires_at: None,
+            on_refresh: None,
+        }
+    }
+
+    /// Set the access token expiration time
+    pub fn with_expiry(mut self, expires_at: chrono::DateTime<chrono::Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Set a callback to persist refreshed tokens
+    pub fn with_refresh_callback(mut self, callback: OAuthRefreshCallback) -> Self {
+        self.on_refresh = Some(callback);
+        self
+    }
+
+    /// Check if the access token is expired
+    pub fn is_expired(&self) -> bool {
+        if let Some(expires_at) = self.expires_at {
+            chrono::Utc::now() >= expires_at
+        } else {
+            false
+        }
+    }
+
+    /// Check if the access token expires within the refresh window
+    pub fn is_expiring_soon(&self) -> bool {
+        if let Some(expires_at) = self.expires_at {
+            chrono::Utc::now() + chrono::Duration::seconds(OAUTH_REFRESH_SKEW_SECS) >= expires_at
+        } else {
+            false
+        }
+    }
+
+    /// Get the current access token
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    /// Get the access token expiration time
+    pub fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.expires_at
+    }
+
+    /// Convert the current credentials back into a storable configuration
+    pub fn to_config(&self) -> McpAuthConfig {
+        McpAuthConfig::OAuth {
+            access_token: self.access_token.clone(),
+            refresh_token: self.refresh_token.clone(),
+            token_url: self.token_url.clone(),
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+            expires_at: self.expires_at.map(|t| t.to_rfc3339()),
+        }
+    }
+
+    /// Store a token endpoint response
+    fn apply_token_response(&mut self, response: OAuthTokenResponse) {
+        self.access_token = response.access_token;
+        self.expires_at = response
+            .expires_in
+            .map(|secs| chrono::Utc::now() + chrono::Duration::seconds(secs));
+        // Servers that rotate refresh tokens return a new one; otherwise keep the old one
+        if let Some(refresh_token) = response.refresh_token {
+            self.refresh_token = Some(refresh_token);
+        }
+    }
+}
+
+#[async_trait]
+impl McpAuth for McpOAuthAuth {
+    fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header("Authorization", format!("Bearer {}", self.access_token))
+    }
+
+    async fn is_valid(&self) -> bool {
+        !self.is_expiring_soon()
+    }
+
+    async fn refresh(&mut self) -> McpResult<bool> {
+        let refresh_token = match self.refresh_token {
+            Some(ref t) => t.clone(),
+            None => return Err(McpError::TokenExpired),
+        };
+
+        let mut form = vec![
+            ("grant_type", "refresh_token".to_string()),
+            ("refresh_token", refresh_token),
+            ("client_id", self.client_id.clone()),
+        ];
+        if let Some(ref secret) = self.client_secret {
+            form.push(("client_secret", secret.clone()));
+        }
+
+        let response = crate::http::build_client(crate::http::DEFAULT_TIMEOUT, &crate::http::proxy_settings())?
+            .post(&self.token_url)
+            .header("Accept", "application/json")
+            .form(&form)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(McpError::AuthenticationFailed(format!(
+                "Token refresh failed with HTTP {}: {}",
+                status, error_text
+            )));
+        }
+
+        let token: OAuthTokenResponse = response.json().await?;
+        self.apply_token_response(token);
+
+        if let Some(ref callback) = self.on_refresh {
+            callback(self);
+        }
+
+        Ok(true)
+    }
+
+    fn auth_type(&self) -> &'static str {
+        "OAuth"
+    }
+}
+
 /// No authentication
 #[derive(Debug, Clone, Default)]
 pub struct McpNoAuth;
@@ -231,6 +421,43 @@ pub fn create_auth_from_config(config: &McpAuthConfig) -> Box<dyn McpAuth> {
         McpAuthConfig::CustomHeader { headers } => {
             Box::new(McpCustomHeadersAuth::new(headers.clone()))
         }
+        McpAuthConfig::OAuth { .. } => create_oauth_from_config(config, None)
+            .map(|auth| Box::new(auth) as Box<dyn McpAuth>)
+            .unwrap_or_else(|| Box::new(McpNoAuth)),
+    }
+}
+
+/// Create OAuth authentication from configuration with an optional refresh callback
+///
+/// Returns None if `config` is not an OAuth configuration.
+pub fn create_oauth_from_config(
+    config: &McpAuthConfig,
+    on_refresh: Option<OAuthRefreshCallback>,
+) -> Option<McpOAuthAuth> {
+    match config {
+        McpAuthConfig::OAuth {
+            access_token,
+            refresh_token,
+            token_url,
+            client_id,
+            client_secret,
+            expires_at,
+        } => {
+            let mut auth = McpOAuthAuth::new(
+                access_token.clone(),
+                refresh_token.clone(),
+                token_url.clone(),
+                client_id.clone(),
+                client_secret.clone(),
+            );
+            auth.expires_at = expires_at
+                .as_deref()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&chrono::Utc));
+            auth.on_refresh = on_refresh;
+            Some(auth)
+        }
+        _ => None,
     }
 }
 
@@ -257,4 +484,62 @@ mod tests {
         let auth = McpBearerAuth::with_expiry("token", past);
         assert!(auth.is_expired());
     }
+
+    #[test]
+    fn test_oauth_token_response() {
+        let mut auth = McpOAuthAuth::new(
+            "old_token",
+            Some("refresh".to_string()),
+            "https://auth.example.com/token",
+            "client",
+            None,
+        )
+        .with_expiry(chrono::Utc::now() - chrono::Duration::hours(1));
+        assert!(auth.is_expired());
+
+        let response: OAuthTokenResponse = serde_json::from_str(
+            r#"{"access_token": "new_token", "token_type": "Bearer", "expires_in": 3600}"#,
+        )
+        .unwrap();
+        auth.apply_token_response(response);
+
+        assert_eq!(auth.access_token(), "new_token");
+        assert!(!auth.is_expired());
+        assert_eq!(auth.refresh_token.as_deref(), Some("refresh"));
+    }
+
+    #[test]
+    fn test_oauth_expiring_soon() {
+        let soon = chrono::Utc::now() + chrono::Duration::seconds(10);
+        let auth = McpOAuthAuth::new("token", None, "https://auth.example.com/token", "client", None)
+            .with_expiry(soon);
+        assert!(!auth.is_expired());
+        assert!(auth.is_expiring_soon());
+    }
+
+    #[test]
+    fn test_oauth_config_round_trip() {
+        let expires = chrono::Utc::now() + chrono::Duration::hours(1);
+        let auth = McpOAuthAuth::new(
+            "token",
+            Some("refresh".to_string()),
+            "https://auth.example.com/token",
+            "client",
+            None,
+        )
+        .with_expiry(expires);
+
+        let restored = create_oauth_from_config(&auth.to_config(), None).unwrap();
+        assert_eq!(restored.access_token(), "token");
+        assert_eq!(
+            restored.expires_at().map(|t| t.timestamp()),
+            Some(expires.timestamp())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oauth_refresh_without_refresh_token() {
+        let mut auth = McpOAuthAuth::new("token", None, "https://auth.example.com/token", "client", None);
+        assert!(matches!(auth.refresh().await, Err(McpError::TokenExpired)));
+    }
 }
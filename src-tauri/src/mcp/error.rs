@@ -4,6 +4,8 @@
 
 use thiserror::Error;
 
+use super::schema::ArgumentError;
+
 /// MCP-specific errors
 #[derive(Error, Debug)]
 pub enum McpError {
@@ -20,6 +22,15 @@ pub enum McpError {
     #[error("Transport error: {0}")]
     TransportError(String),
 
+    #[error("Session expired or not found on server")]
+    SessionExpired,
+
+    #[error("Rate limited by server{}", retry_after_hint(.retry_after_ms))]
+    RateLimited { retry_after_ms: Option<u64> },
+
+    #[error("TLS certificate error: {0}")]
+    CertificateError(String),
+
     // Protocol errors
     #[error("Protocol version mismatch: expected {expected}, got {actual}")]
     ProtocolVersionMismatch { expected: String, actual: String },
@@ -43,6 +54,9 @@ pub enum McpError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    #[error("Credential storage error: {0}")]
+    CredentialStore(String),
+
     // Operation errors
     #[error("Tool not found: {0}")]
     ToolNotFound(String),
@@ -50,6 +64,9 @@ pub enum McpError {
     #[error("Tool execution failed: {0}")]
     ToolExecutionFailed(String),
 
+    #[error("Invalid arguments for tool {tool}: {}", join_errors(.errors))]
+    InvalidToolArguments { tool: String, errors: Vec<ArgumentError> },
+
     #[error("Resource not found: {0}")]
     ResourceNotFound(String),
 
@@ -85,9 +102,29 @@ pub enum McpError {
     Cancelled,
 }
 
+impl McpError {
+    /// Whether the request failed on the way to or from the server, rather than
+    /// being answered with an error
+    pub fn is_transport_error(&self) -> bool {
+        matches!(
+            self,
+            McpError::ConnectionFailed(_)
+                | McpError::ConnectionTimeout(_)
+                | McpError::NotConnected
+                | McpError::TransportError(_)
+                | McpError::SessionExpired
+                | McpError::RateLimited { .. }
+                | McpError::CertificateError(_)
+                | McpError::ServerUnhealthy(_)
+        )
+    }
+}
+
 impl From<reqwest::Error> for McpError {
     fn from(err: reqwest::Error) -> Self {
-        if err.is_timeout() {
+        if is_certificate_error(&err) {
+            McpError::CertificateError(error_chain(&err))
+        } else if err.is_timeout() {
             McpError::ConnectionTimeout(30000)
         } else if err.is_connect() {
             McpError::ConnectionFailed(err.to_string())
@@ -97,6 +134,40 @@ impl From<reqwest::Error> for McpError {
     }
 }
 
+fn retry_after_hint(retry_after_ms: &Option<u64>) -> String {
+    match retry_after_ms {
+        Some(ms) => format!(" (retry after {}ms)", ms),
+        None => String::new(),
+    }
+}
+
+fn join_errors(errors: &[ArgumentError]) -> String {
+    errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+/// Render an error with its sources, which is where TLS failures explain themselves
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Whether a request failed because the server's certificate couldn't be verified
+pub(crate) fn is_certificate_error(err: &reqwest::Error) -> bool {
+    if !err.is_connect() {
+        return false;
+    }
+    let message = error_chain(err).to_lowercase();
+    ["certificate", "self signed", "self-signed", "unknown ca", "unable to get local issuer"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
 impl From<serde_json::Error> for McpError {
     fn from(err: serde_json::Error) -> Self {
         McpError::DeserializationError(err.to_string())
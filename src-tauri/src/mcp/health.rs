@@ -10,15 +10,22 @@
 use dashmap::DashMap;
 use log::{debug, error, info, warn};
 use parking_lot::RwLock;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 use tokio::time::interval;
 
+use super::auth::{create_auth_from_config, McpAuth};
 use super::error::{McpError, McpResult};
+use super::pool::McpConnectionPool;
 use super::transport::McpTransport;
-use super::types::RemoteMcpServer;
+use super::types::{McpAuthConfig, RemoteMcpServer};
+
+/// Builds a transport for a server ID, used to open pooled connections for MCP pings
+pub type TransportConnector = Arc<dyn Fn(&str) -> McpResult<Box<dyn McpTransport>> + Send + Sync>;
 
 /// Health status levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -57,6 +64,11 @@ pub struct ServerHealth {
     pub consecutive_successes: u32,
     /// Average latency over last N checks
     pub avg_latency_ms: Option<u64>,
+    /// MCP session the check ran over, when known
+    pub session_id: Option<String>,
+    /// Usage notes the server sent on initialize, filled in by connection tests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
 }
 
 impl ServerHealth {
@@ -70,10 +82,12 @@ impl ServerHealth {
             consecutive_failures: 0,
             consecutive_successes: 0,
             avg_latency_ms: None,
+            session_id: None,
+            instructions: None,
         }
     }
 
-    fn record_success(&mut self, latency_ms: u64) {
+    fn record_success(&mut self, latency_ms: u64, thresholds: DegradedThresholds) {
         self.status = HealthStatus::Healthy;
         self.latency_ms = Some(latency_ms);
         self.last_check = Some(chrono::Utc::now().to_rfc3339());
@@ -88,9 +102,9 @@ impl ServerHealth {
             self.avg_latency_ms = Some(latency_ms);
         }
 
-        // Check for degraded performance (latency > 2x average)
+        // Check for degraded performance (latency well above average and above the floor)
         if let Some(avg) = self.avg_latency_ms {
-            if latency_ms > avg * 2 && latency_ms > 1000 {
+            if latency_ms as f64 > avg as f64 * thresholds.multiplier && latency_ms > thresholds.floor_ms {
                 self.status = HealthStatus::Degraded;
             }
         }
@@ -106,8 +120,149 @@ impl ServerHealth {
     }
 }
 
+/// When a successful check counts as degraded
+///
+/// A check is degraded if its latency exceeds both `multiplier` times the
+/// moving average and `floor_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DegradedThresholds {
+    pub multiplier: f64,
+    pub floor_ms: u64,
+}
+
+impl Default for DegradedThresholds {
+    fn default() -> Self {
+        Self {
+            multiplier: 2.0,
+            floor_ms: 1000,
+        }
+    }
+}
+
+/// Number of health samples kept in memory per server
+pub const HEALTH_HISTORY_CAPACITY: usize = 100;
+
+/// A single health check result, for latency-over-time charts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthSample {
+    /// When the check ran (RFC3339)
+    pub timestamp: String,
+    pub status: HealthStatus,
+    /// None if the check failed
+    pub latency_ms: Option<u64>,
+}
+
+impl HealthSample {
+    /// Sample the outcome of the latest check
+    pub fn from_health(health: &ServerHealth) -> Self {
+        Self {
+            timestamp: health
+                .last_check
+                .clone()
+                .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            status: health.status,
+            latency_ms: health.latency_ms,
+        }
+    }
+}
+
+/// Ring buffer of the most recent health samples for a server
+#[derive(Debug, Clone, Default)]
+pub struct HealthHistory {
+    samples: VecDeque<HealthSample>,
+}
+
+impl HealthHistory {
+    /// Add a sample, dropping the oldest once full
+    pub fn push(&mut self, sample: HealthSample) {
+        if self.samples.len() == HEALTH_HISTORY_CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Samples from oldest to newest
+    pub fn samples(&self) -> Vec<HealthSample> {
+        self.samples.iter().cloned().collect()
+    }
+}
+
+/// Number of latency samples kept in memory per server for metrics
+pub const METRICS_CAPACITY: usize = 1000;
+
+/// A metrics event is sent after this many samples for a server
+pub const METRICS_EVENT_INTERVAL: u64 = 20;
+
+/// One timed request (ping, probe, or tool call)
+#[derive(Debug, Clone, Copy)]
+struct LatencySample {
+    at: Instant,
+    latency_ms: u64,
+    success: bool,
+}
+
+/// Ring buffer of recent latency samples for a server
+#[derive(Debug, Default)]
+struct MetricsBuffer {
+    samples: VecDeque<LatencySample>,
+    /// Samples recorded since the server was added, for pacing events
+    recorded: u64,
+}
+
+/// Latency percentiles and error rate over a time window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServerMetrics {
+    pub server_id: String,
+    /// Percentiles of successful requests; None without any
+    pub p50: Option<u64>,
+    pub p95: Option<u64>,
+    pub p99: Option<u64>,
+    /// Fraction of requests that failed (0.0 without samples)
+    pub error_rate: f64,
+    pub sample_count: usize,
+}
+
+impl ServerMetrics {
+    fn from_samples<'a>(server_id: &str, samples: impl Iterator<Item = &'a LatencySample>) -> Self {
+        let mut latencies = Vec::new();
+        let mut failures = 0usize;
+        for sample in samples {
+            if sample.success {
+                latencies.push(sample.latency_ms);
+            } else {
+                failures += 1;
+            }
+        }
+        latencies.sort_unstable();
+
+        let sample_count = latencies.len() + failures;
+        Self {
+            server_id: server_id.to_string(),
+            p50: percentile(&latencies, 50.0),
+            p95: percentile(&latencies, 95.0),
+            p99: percentile(&latencies, 99.0),
+            error_rate: if sample_count == 0 {
+                0.0
+            } else {
+                failures as f64 / sample_count as f64
+            },
+            sample_count,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
 /// Health check event types
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum HealthEvent {
     /// Health status changed for a server
     StatusChanged {
@@ -129,12 +284,62 @@ pub enum HealthEvent {
     ServerRecovered {
         server_id: String,
     },
+    /// Latency metrics over the last hour, sent every `METRICS_EVENT_INTERVAL` samples
+    MetricsUpdated {
+        server_id: String,
+        metrics: ServerMetrics,
+    },
+    /// A tool call failed on this server and was retried on its fallback
+    FailedOver {
+        server_id: String,
+        fallback_server_id: String,
+        error: String,
+    },
+}
+
+impl HealthEvent {
+    /// Server the event refers to
+    pub fn server_id(&self) -> &str {
+        match self {
+            Self::StatusChanged { server_id, .. }
+            | Self::CheckCompleted { server_id, .. }
+            | Self::ServerUnreachable { server_id, .. }
+            | Self::ServerRecovered { server_id }
+            | Self::MetricsUpdated { server_id, .. }
+            | Self::FailedOver { server_id, .. } => server_id,
+        }
+    }
+}
+
+/// A server registered for periodic monitoring
+#[derive(Debug, Clone)]
+pub struct MonitoredServer {
+    pub server_id: String,
+    pub endpoint: String,
+    /// Check interval in seconds
+    pub interval_secs: u64,
+    /// Check timeout in seconds
+    pub timeout_secs: u64,
+    /// Auth applied to HTTP health checks
+    pub auth: Option<McpAuthConfig>,
+    /// Overrides the monitor's degraded latency multiplier
+    pub degraded_multiplier: Option<f64>,
+    /// Overrides the monitor's degraded latency floor
+    pub degraded_floor_ms: Option<u64>,
+    /// Default headers sent with every probe
+    pub headers: HashMap<String, String>,
+    /// Group the server belongs to, so its checks can be stopped and started together
+    pub group: Option<String>,
 }
 
 /// MCP Health Monitor
 pub struct McpHealthMonitor {
     /// Server health status map
     health_status: Arc<DashMap<String, ServerHealth>>,
+    /// Recent check results per server
+    history: Arc<DashMap<String, HealthHistory>>,
+    /// Recent request latencies per server
+    metrics: Arc<DashMap<String, MetricsBuffer>>,
     /// Event broadcaster
     event_tx: broadcast::Sender<HealthEvent>,
     /// Whether the monitor is running
@@ -145,6 +350,22 @@ pub struct McpHealthMonitor {
     default_timeout_secs: u64,
     /// Threshold for marking server as unreachable
     unreachable_threshold: u32,
+    /// Latency above this multiple of the average counts as degraded
+    degraded_multiplier: f64,
+    /// Latency at or below this never counts as degraded
+    degraded_floor_ms: u64,
+    /// Per-server (multiplier, floor) overrides
+    degraded_overrides: Arc<DashMap<String, (Option<f64>, Option<u64>)>>,
+    /// Per-server monitoring tasks, tagged with the server's group
+    tasks: parking_lot::Mutex<Vec<(Option<String>, tokio::task::JoinHandle<()>)>>,
+    /// Groups whose checks were stopped; skipped until started again
+    stopped_groups: RwLock<HashSet<String>>,
+    /// Pooled connections, pinged instead of probing over HTTP when open
+    pool: Option<Arc<McpConnectionPool>>,
+    /// Opens pooled connections so authenticated servers get a real MCP ping
+    connector: Option<TransportConnector>,
+    /// Whether authenticated servers are checked with an MCP ping rather than HEAD
+    mcp_ping: bool,
 }
 
 impl McpHealthMonitor {
@@ -153,27 +374,87 @@ impl McpHealthMonitor {
         let (event_tx, _) = broadcast::channel(100);
         Self {
             health_status: Arc::new(DashMap::new()),
+            history: Arc::new(DashMap::new()),
+            metrics: Arc::new(DashMap::new()),
             event_tx,
             running: Arc::new(RwLock::new(false)),
             default_interval_secs: 60,
             default_timeout_secs: 10,
             unreachable_threshold: 3,
+            degraded_multiplier: DegradedThresholds::default().multiplier,
+            degraded_floor_ms: DegradedThresholds::default().floor_ms,
+            degraded_overrides: Arc::new(DashMap::new()),
+            tasks: parking_lot::Mutex::new(Vec::new()),
+            stopped_groups: RwLock::new(HashSet::new()),
+            pool: None,
+            connector: None,
+            mcp_ping: true,
         }
     }
 
+    /// Ping servers over their pooled connection when one is open
+    pub fn with_connection_pool(mut self, pool: Arc<McpConnectionPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Connect authenticated servers through the pool so checks use an MCP ping
+    ///
+    /// Needs `with_connection_pool`. Unauthenticated servers are still probed
+    /// with HEAD unless they already have a pooled connection.
+    pub fn with_transport_connector(mut self, connector: TransportConnector) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+
+    /// Enable or disable MCP pings for authenticated servers (enabled by default)
+    pub fn with_mcp_ping(mut self, enabled: bool) -> Self {
+        self.mcp_ping = enabled;
+        self
+    }
+
     /// Create with custom settings
     pub fn with_settings(
         default_interval_secs: u64,
         default_timeout_secs: u64,
         unreachable_threshold: u32,
+        degraded_multiplier: f64,
+        degraded_floor_ms: u64,
     ) -> Self {
         let mut monitor = Self::new();
         monitor.default_interval_secs = default_interval_secs;
         monitor.default_timeout_secs = default_timeout_secs;
         monitor.unreachable_threshold = unreachable_threshold;
+        monitor.degraded_multiplier = degraded_multiplier;
+        monitor.degraded_floor_ms = degraded_floor_ms;
         monitor
     }
 
+    /// Override the degraded thresholds for one server; `None` uses the monitor default
+    pub fn set_degraded_thresholds(
+        &self,
+        server_id: &str,
+        multiplier: Option<f64>,
+        floor_ms: Option<u64>,
+    ) {
+        if multiplier.is_none() && floor_ms.is_none() {
+            self.degraded_overrides.remove(server_id);
+        } else {
+            self.degraded_overrides
+                .insert(server_id.to_string(), (multiplier, floor_ms));
+        }
+    }
+
+    /// Effective degraded thresholds for a server
+    pub fn degraded_thresholds(&self, server_id: &str) -> DegradedThresholds {
+        resolve_thresholds(
+            &self.degraded_overrides,
+            self.degraded_multiplier,
+            self.degraded_floor_ms,
+            server_id,
+        )
+    }
+
     /// Subscribe to health events
     pub fn subscribe(&self) -> broadcast::Receiver<HealthEvent> {
         self.event_tx.subscribe()
@@ -184,6 +465,56 @@ impl McpHealthMonitor {
         self.health_status.get(server_id).map(|h| h.clone())
     }
 
+    /// Forget the health history of a server
+    pub fn remove_server(&self, server_id: &str) {
+        self.health_status.remove(server_id);
+        self.history.remove(server_id);
+        self.metrics.remove(server_id);
+        self.degraded_overrides.remove(server_id);
+    }
+
+    /// Recent health samples for a server, oldest first
+    pub fn get_history(&self, server_id: &str) -> Vec<HealthSample> {
+        self.history
+            .get(server_id)
+            .map(|h| h.samples())
+            .unwrap_or_default()
+    }
+
+    /// Seed a server's history, e.g. with samples persisted before a restart
+    pub fn restore_history(&self, server_id: &str, samples: Vec<HealthSample>) {
+        let mut history = self.history.entry(server_id.to_string()).or_default();
+        for sample in samples {
+            history.push(sample);
+        }
+    }
+
+    /// Record the latency of a request to a server, e.g. a tool call
+    pub fn record_latency(&self, server_id: &str, latency_ms: u64, success: bool) {
+        record_metric(&self.metrics, &self.event_tx, server_id, latency_ms, success);
+    }
+
+    /// Report that a request to `server_id` was sent to its fallback instead
+    pub fn record_failover(&self, server_id: &str, fallback_server_id: &str, error: &str) {
+        let _ = self.event_tx.send(HealthEvent::FailedOver {
+            server_id: server_id.to_string(),
+            fallback_server_id: fallback_server_id.to_string(),
+            error: error.to_string(),
+        });
+    }
+
+    /// Latency percentiles and error rate over the last `window_secs`
+    pub fn get_server_metrics(&self, server_id: &str, window_secs: u64) -> ServerMetrics {
+        let window = Duration::from_secs(window_secs);
+        match self.metrics.get(server_id) {
+            Some(buffer) => ServerMetrics::from_samples(
+                server_id,
+                buffer.samples.iter().filter(|s| s.at.elapsed() <= window),
+            ),
+            None => ServerMetrics::from_samples(server_id, std::iter::empty()),
+        }
+    }
+
     /// Get all health statuses
     pub fn get_all_health(&self) -> Vec<ServerHealth> {
         self.health_status.iter().map(|r| r.clone()).collect()
@@ -205,12 +536,13 @@ impl McpHealthMonitor {
             .clone();
 
         let old_status = health.status;
+        let thresholds = self.degraded_thresholds(server_id);
 
         // Perform ping
         match transport.ping().await {
             Ok(()) => {
                 let latency = start.elapsed().as_millis() as u64;
-                health.record_success(latency);
+                health.record_success(latency, thresholds);
 
                 // Check for recovery
                 if old_status == HealthStatus::Unhealthy && health.status == HealthStatus::Healthy {
@@ -224,6 +556,11 @@ impl McpHealthMonitor {
                     server_id, latency, health.status
                 );
             }
+            // The server is up and only asking us to slow down, so leave its health as it was
+            Err(McpError::RateLimited { .. }) => {
+                debug!("Health check for {} was rate limited, keeping status {:?}", server_id, health.status);
+                return Ok(health);
+            }
             Err(e) => {
                 health.record_failure(e.to_string());
 
@@ -258,6 +595,14 @@ impl McpHealthMonitor {
         });
 
         // Update stored health
+        record_metric(
+            &self.metrics,
+            &self.event_tx,
+            server_id,
+            start.elapsed().as_millis() as u64,
+            health.consecutive_failures == 0,
+        );
+        record_sample(&self.history, &health);
         self.health_status.insert(server_id.to_string(), health.clone());
 
         Ok(health)
@@ -268,6 +613,7 @@ impl McpHealthMonitor {
         &self,
         server_id: &str,
         endpoint: &str,
+        auth: Option<&dyn McpAuth>,
         timeout_secs: Option<u64>,
     ) -> McpResult<ServerHealth> {
         let timeout = timeout_secs.unwrap_or(self.default_timeout_secs);
@@ -281,25 +627,37 @@ impl McpHealthMonitor {
             .clone();
 
         let old_status = health.status;
+        let thresholds = self.degraded_thresholds(server_id);
 
         // Try to reach the endpoint
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(timeout))
-            .build()
+        let client = crate::http::build_client(Duration::from_secs(timeout), &crate::http::proxy_settings())
             .map_err(|e| McpError::TransportError(e.to_string()))?;
 
-        match client.head(endpoint).send().await {
+        let mut request = client.head(endpoint);
+        if let Some(auth) = auth {
+            request = auth.apply(request);
+        }
+
+        match request.send().await {
             Ok(response) => {
                 let latency = start.elapsed().as_millis() as u64;
 
-                if response.status().is_success() || response.status().as_u16() == 405 {
-                    // 405 Method Not Allowed is OK - endpoint exists but doesn't support HEAD
-                    health.record_success(latency);
+                if response.status().is_success()
+                    || response.status().as_u16() == 405
+                    || response.status() == StatusCode::TOO_MANY_REQUESTS
+                {
+                    // 405 Method Not Allowed is OK - endpoint exists but doesn't support HEAD;
+                    // 429 means it's up and only asking us to slow down
+                    health.record_success(latency, thresholds);
+                } else if response.status() == StatusCode::UNAUTHORIZED {
+                    health.record_failure(
+                        McpError::AuthenticationFailed(format!("HTTP {}", response.status())).to_string(),
+                    );
                 } else if response.status().is_server_error() {
                     health.record_failure(format!("Server error: {}", response.status()));
                 } else {
                     // Other status codes - mark as degraded
-                    health.record_success(latency);
+                    health.record_success(latency, thresholds);
                     health.status = HealthStatus::Degraded;
                 }
             }
@@ -323,103 +681,241 @@ impl McpHealthMonitor {
         });
 
         // Update stored health
+        record_metric(
+            &self.metrics,
+            &self.event_tx,
+            server_id,
+            start.elapsed().as_millis() as u64,
+            health.consecutive_failures == 0,
+        );
+        record_sample(&self.history, &health);
         self.health_status.insert(server_id.to_string(), health.clone());
 
         Ok(health)
     }
 
-    /// Start periodic health monitoring for all registered servers
-    pub fn start_monitoring(&self, servers: Vec<(String, String)>) -> tokio::task::JoinHandle<()> {
+    /// Start periodic health monitoring for the given servers
+    ///
+    /// Replaces any servers already being monitored. Each server is checked
+    /// on its own interval.
+    ///
+    /// Servers in a group stopped with `stop_group` are skipped.
+    pub fn start_monitoring(&self, servers: Vec<MonitoredServer>) {
+        self.stop_monitoring();
+        *self.running.write() = true;
+
+        let stopped = self.stopped_groups.read().clone();
+        let mut tasks = self.tasks.lock();
+        for server in servers {
+            if server.group.as_ref().is_some_and(|group| stopped.contains(group)) {
+                continue;
+            }
+            tasks.push(self.spawn_tracked(server));
+        }
+        info!("Health monitoring started for {} servers", tasks.len());
+    }
+
+    /// Start (or restart) checks for the members of one group
+    ///
+    /// Servers outside the group are ignored. Returns the number of servers now monitored in the group.
+    pub fn start_group(&self, group: &str, servers: Vec<MonitoredServer>) -> usize {
+        self.stopped_groups.write().remove(group);
+        self.abort_group(group);
+        *self.running.write() = true;
+
+        let mut tasks = self.tasks.lock();
+        let before = tasks.len();
+        for server in servers {
+            if server.group.as_deref() == Some(group) {
+                tasks.push(self.spawn_tracked(server));
+            }
+        }
+        let started = tasks.len() - before;
+        info!("Health monitoring started for {} servers in group {}", started, group);
+        started
+    }
+
+    /// Stop checks for the members of one group until `start_group` is called
+    ///
+    /// Returns the number of monitoring tasks stopped.
+    pub fn stop_group(&self, group: &str) -> usize {
+        self.stopped_groups.write().insert(group.to_string());
+        let stopped = self.abort_group(group);
+        info!("Health monitoring stopped for {} servers in group {}", stopped, group);
+        stopped
+    }
+
+    /// Abort the monitoring tasks of a group's members
+    fn abort_group(&self, group: &str) -> usize {
+        let mut tasks = self.tasks.lock();
+        let before = tasks.len();
+        tasks.retain(|(task_group, task)| {
+            if task_group.as_deref() == Some(group) {
+                task.abort();
+                false
+            } else {
+                true
+            }
+        });
+        before - tasks.len()
+    }
+
+    /// Apply a server's threshold overrides and spawn its check loop
+    fn spawn_tracked(&self, server: MonitoredServer) -> (Option<String>, tokio::task::JoinHandle<()>) {
+        self.set_degraded_thresholds(
+            &server.server_id,
+            server.degraded_multiplier,
+            server.degraded_floor_ms,
+        );
+        (server.group.clone(), self.spawn_server_monitor(server))
+    }
+
+    /// Spawn the periodic check loop for one server
+    fn spawn_server_monitor(&self, server: MonitoredServer) -> tokio::task::JoinHandle<()> {
         let health_status = self.health_status.clone();
+        let history = self.history.clone();
+        let metrics = self.metrics.clone();
         let event_tx = self.event_tx.clone();
         let running = self.running.clone();
-        let interval_secs = self.default_interval_secs;
-        let timeout_secs = self.default_timeout_secs;
+        let pool = self.pool.clone();
+        let authenticated = server
+            .auth
+            .as_ref()
+            .is_some_and(|auth| !matches!(auth, McpAuthConfig::None));
+        let connector = self.connector.clone().filter(|_| self.mcp_ping && authenticated);
         let unreachable_threshold = self.unreachable_threshold;
-
-        *running.write() = true;
+        let degraded_overrides = self.degraded_overrides.clone();
+        let degraded_multiplier = self.degraded_multiplier;
+        let degraded_floor_ms = self.degraded_floor_ms;
+        let interval_secs = if server.interval_secs > 0 {
+            server.interval_secs
+        } else {
+            self.default_interval_secs
+        };
 
         tokio::spawn(async move {
+            let headers = match super::transport::parse_extra_headers(&server.headers) {
+                Ok(headers) => headers,
+                Err(e) => {
+                    error!("Skipping health checks for {}: {}", server.server_id, e);
+                    return;
+                }
+            };
+            let client = match crate::http::client_builder(
+                Duration::from_secs(server.timeout_secs),
+                &crate::http::proxy_settings(),
+            )
+            .default_headers(headers)
+            .build()
+            {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("Failed to create HTTP client: {}", e);
+                    return;
+                }
+            };
+            let auth = server.auth.as_ref().map(create_auth_from_config);
+            let server_id = &server.server_id;
             let mut ticker = interval(Duration::from_secs(interval_secs));
 
             while *running.read() {
                 ticker.tick().await;
+                if !*running.read() {
+                    break;
+                }
 
-                for (server_id, endpoint) in &servers {
-                    if !*running.read() {
-                        break;
+                let start = Instant::now();
+
+                let mut health = health_status
+                    .entry(server_id.clone())
+                    .or_insert_with(|| ServerHealth::new(server_id))
+                    .clone();
+
+                let old_status = health.status;
+
+                // Prefer an MCP ping so "healthy" means the server completed the handshake.
+                // Authenticated servers are connected if needed; others are only pinged
+                // over an already-open session and otherwise probed with HEAD.
+                let pinged = match (&pool, &connector) {
+                    (Some(pool), Some(connector)) => Some(
+                        pool.ping_or_connect(server_id, || connector(server_id))
+                            .await,
+                    ),
+                    (Some(pool), None) => pool.ping(server_id).await,
+                    (None, _) => None,
+                };
+                let outcome = match pinged {
+                    // A 429 means the server is up; skip rather than record a latency padded by our backoff
+                    Some(Err(McpError::RateLimited { .. })) => {
+                        debug!("{} rate limited its health check, skipping this round", server_id);
+                        continue;
                     }
-
-                    let start = Instant::now();
-
-                    let mut health = health_status
-                        .entry(server_id.clone())
-                        .or_insert_with(|| ServerHealth::new(server_id))
-                        .clone();
-
-                    let old_status = health.status;
-
-                    // Perform health check
-                    let client = match reqwest::Client::builder()
-                        .timeout(Duration::from_secs(timeout_secs))
-                        .build()
-                    {
-                        Ok(c) => c,
-                        Err(e) => {
-                            error!("Failed to create HTTP client: {}", e);
-                            continue;
-                        }
-                    };
-
-                    match client.head(endpoint).send().await {
-                        Ok(response) => {
-                            let latency = start.elapsed().as_millis() as u64;
-                            if response.status().is_success() || response.status().as_u16() == 405 {
-                                health.record_success(latency);
-                                if old_status == HealthStatus::Unhealthy {
-                                    let _ = event_tx.send(HealthEvent::ServerRecovered {
-                                        server_id: server_id.clone(),
-                                    });
-                                }
-                            } else {
-                                health.record_failure(format!("HTTP {}", response.status()));
-                            }
-                        }
-                        Err(e) => {
-                            health.record_failure(e.to_string());
-                            if health.consecutive_failures >= unreachable_threshold {
-                                let _ = event_tx.send(HealthEvent::ServerUnreachable {
-                                    server_id: server_id.clone(),
-                                    consecutive_failures: health.consecutive_failures,
-                                });
-                            }
+                    Some(result) => result.map_err(|e| e.to_string()),
+                    None => probe_endpoint(&client, &server.endpoint, auth.as_deref()).await,
+                };
+
+                match outcome {
+                    Ok(()) => {
+                        let latency = start.elapsed().as_millis() as u64;
+                        // Looked up per check so overrides apply without a restart
+                        let thresholds = resolve_thresholds(
+                            &degraded_overrides,
+                            degraded_multiplier,
+                            degraded_floor_ms,
+                            server_id,
+                        );
+                        health.record_success(latency, thresholds);
+                        if old_status == HealthStatus::Unhealthy {
+                            let _ = event_tx.send(HealthEvent::ServerRecovered {
+                                server_id: server_id.clone(),
+                            });
                         }
                     }
-
-                    if old_status != health.status {
-                        let _ = event_tx.send(HealthEvent::StatusChanged {
-                            server_id: server_id.clone(),
-                            old_status,
-                            new_status: health.status,
-                        });
+                    Err(e) => {
+                        health.record_failure(e);
+                        if health.consecutive_failures >= unreachable_threshold {
+                            let _ = event_tx.send(HealthEvent::ServerUnreachable {
+                                server_id: server_id.clone(),
+                                consecutive_failures: health.consecutive_failures,
+                            });
+                        }
                     }
+                }
 
-                    let _ = event_tx.send(HealthEvent::CheckCompleted {
+                if old_status != health.status {
+                    let _ = event_tx.send(HealthEvent::StatusChanged {
                         server_id: server_id.clone(),
-                        health: health.clone(),
+                        old_status,
+                        new_status: health.status,
                     });
-
-                    health_status.insert(server_id.clone(), health);
                 }
+
+                let _ = event_tx.send(HealthEvent::CheckCompleted {
+                    server_id: server_id.clone(),
+                    health: health.clone(),
+                });
+
+                record_metric(
+                    &metrics,
+                    &event_tx,
+                    server_id,
+                    start.elapsed().as_millis() as u64,
+                    health.consecutive_failures == 0,
+                );
+                record_sample(&history, &health);
+                health_status.insert(server_id.clone(), health);
             }
 
-            info!("Health monitoring stopped");
+            debug!("Health monitoring stopped for {}", server_id);
         })
     }
 
     /// Stop health monitoring
     pub fn stop_monitoring(&self) {
         *self.running.write() = false;
+        for (_, task) in self.tasks.lock().drain(..) {
+            task.abort();
+        }
     }
 
     /// Check if monitoring is running
@@ -428,6 +924,86 @@ impl McpHealthMonitor {
     }
 }
 
+/// Merge a server's degraded overrides with the monitor defaults
+fn resolve_thresholds(
+    overrides: &DashMap<String, (Option<f64>, Option<u64>)>,
+    default_multiplier: f64,
+    default_floor_ms: u64,
+    server_id: &str,
+) -> DegradedThresholds {
+    let (multiplier, floor_ms) = overrides.get(server_id).map(|o| *o).unwrap_or_default();
+    DegradedThresholds {
+        multiplier: multiplier.unwrap_or(default_multiplier),
+        floor_ms: floor_ms.unwrap_or(default_floor_ms),
+    }
+}
+
+/// Append the latest check result to a server's history
+fn record_sample(history: &DashMap<String, HealthHistory>, health: &ServerHealth) {
+    history
+        .entry(health.server_id.clone())
+        .or_default()
+        .push(HealthSample::from_health(health));
+}
+
+/// Add a latency sample, sending a metrics event every `METRICS_EVENT_INTERVAL` samples
+fn record_metric(
+    metrics: &DashMap<String, MetricsBuffer>,
+    event_tx: &broadcast::Sender<HealthEvent>,
+    server_id: &str,
+    latency_ms: u64,
+    success: bool,
+) {
+    let updated = {
+        let mut buffer = metrics.entry(server_id.to_string()).or_default();
+        if buffer.samples.len() == METRICS_CAPACITY {
+            buffer.samples.pop_front();
+        }
+        buffer.samples.push_back(LatencySample {
+            at: Instant::now(),
+            latency_ms,
+            success,
+        });
+        buffer.recorded += 1;
+
+        (buffer.recorded % METRICS_EVENT_INTERVAL == 0).then(|| {
+            let hour = Duration::from_secs(3600);
+            ServerMetrics::from_samples(server_id, buffer.samples.iter().filter(|s| s.at.elapsed() <= hour))
+        })
+    };
+
+    // Sent after the entry guard is released so subscribers can read the metrics
+    if let Some(metrics) = updated {
+        let _ = event_tx.send(HealthEvent::MetricsUpdated {
+            server_id: server_id.to_string(),
+            metrics,
+        });
+    }
+}
+
+/// Probe an endpoint with an authenticated HEAD request
+async fn probe_endpoint(
+    client: &reqwest::Client,
+    endpoint: &str,
+    auth: Option<&dyn McpAuth>,
+) -> Result<(), String> {
+    let mut request = client.head(endpoint);
+    if let Some(auth) = auth {
+        request = auth.apply(request);
+    }
+
+    let status = request.send().await.map_err(|e| e.to_string())?.status();
+    if status.is_success() || status == StatusCode::METHOD_NOT_ALLOWED || status == StatusCode::TOO_MANY_REQUESTS {
+        // 405 Method Not Allowed is OK - endpoint exists but doesn't support HEAD;
+        // 429 means it's up and only asking us to slow down
+        Ok(())
+    } else if status == StatusCode::UNAUTHORIZED {
+        Err(McpError::AuthenticationFailed(format!("HTTP {}", status)).to_string())
+    } else {
+        Err(format!("HTTP {}", status))
+    }
+}
+
 impl Default for McpHealthMonitor {
     fn default() -> Self {
         Self::new()
@@ -441,7 +1017,7 @@ mod tests {
     #[test]
     fn test_server_health_success() {
         let mut health = ServerHealth::new("test");
-        health.record_success(100);
+        health.record_success(100, DegradedThresholds::default());
 
         assert_eq!(health.status, HealthStatus::Healthy);
         assert_eq!(health.latency_ms, Some(100));
@@ -461,18 +1037,181 @@ mod tests {
         assert!(health.last_error.is_some());
     }
 
+    #[test]
+    fn test_health_event_serialization() {
+        let event = HealthEvent::ServerRecovered {
+            server_id: "srv".to_string(),
+        };
+        assert_eq!(event.server_id(), "srv");
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "server_recovered");
+        assert_eq!(json["server_id"], "srv");
+    }
+
     #[test]
     fn test_degraded_detection() {
         let mut health = ServerHealth::new("test");
 
         // Build up an average
         for _ in 0..5 {
-            health.record_success(100);
+            health.record_success(100, DegradedThresholds::default());
         }
 
         // Spike in latency should trigger degraded
-        health.record_success(5000);
+        health.record_success(5000, DegradedThresholds::default());
 
         assert_eq!(health.status, HealthStatus::Degraded);
     }
+
+    #[test]
+    fn test_custom_degraded_thresholds() {
+        let sensitive = DegradedThresholds {
+            multiplier: 1.5,
+            floor_ms: 200,
+        };
+        let mut default_health = ServerHealth::new("default");
+        let mut custom_health = ServerHealth::new("custom");
+
+        for _ in 0..5 {
+            default_health.record_success(100, DegradedThresholds::default());
+            custom_health.record_success(100, sensitive);
+        }
+
+        // 400ms is under the default 1000ms floor but over the custom one
+        default_health.record_success(400, DegradedThresholds::default());
+        custom_health.record_success(400, sensitive);
+
+        assert_eq!(default_health.status, HealthStatus::Healthy);
+        assert_eq!(custom_health.status, HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn test_degraded_threshold_overrides() {
+        let monitor = McpHealthMonitor::with_settings(60, 10, 3, 3.0, 500);
+        assert_eq!(
+            monitor.degraded_thresholds("srv"),
+            DegradedThresholds { multiplier: 3.0, floor_ms: 500 }
+        );
+
+        monitor.set_degraded_thresholds("srv", None, Some(50));
+        assert_eq!(
+            monitor.degraded_thresholds("srv"),
+            DegradedThresholds { multiplier: 3.0, floor_ms: 50 }
+        );
+
+        monitor.set_degraded_thresholds("srv", None, None);
+        assert_eq!(monitor.degraded_thresholds("srv").floor_ms, 500);
+    }
+
+    #[test]
+    fn test_health_history_is_bounded() {
+        let monitor = McpHealthMonitor::new();
+        let mut health = ServerHealth::new("test");
+
+        for latency in 0..(HEALTH_HISTORY_CAPACITY as u64 + 10) {
+            health.record_success(latency, DegradedThresholds::default());
+            record_sample(&monitor.history, &health);
+        }
+
+        let samples = monitor.get_history("test");
+        assert_eq!(samples.len(), HEALTH_HISTORY_CAPACITY);
+        assert_eq!(samples[0].latency_ms, Some(10));
+        assert_eq!(samples.last().unwrap().latency_ms, Some(HEALTH_HISTORY_CAPACITY as u64 + 9));
+
+        monitor.remove_server("test");
+        assert!(monitor.get_history("test").is_empty());
+    }
+
+    #[test]
+    fn test_server_metrics_percentiles() {
+        let monitor = McpHealthMonitor::new();
+        let mut events = monitor.subscribe();
+
+        for latency in 1..=100 {
+            monitor.record_latency("test", latency, true);
+        }
+        for _ in 0..20 {
+            monitor.record_latency("test", 5000, false);
+        }
+
+        let metrics = monitor.get_server_metrics("test", 3600);
+        assert_eq!(metrics.sample_count, 120);
+        assert_eq!(metrics.p50, Some(50));
+        assert_eq!(metrics.p95, Some(95));
+        assert_eq!(metrics.p99, Some(99));
+        assert!((metrics.error_rate - 20.0 / 120.0).abs() < 1e-9);
+
+        // One event per METRICS_EVENT_INTERVAL samples
+        let mut updates = 0;
+        while let Ok(event) = events.try_recv() {
+            assert!(matches!(event, HealthEvent::MetricsUpdated { .. }));
+            updates += 1;
+        }
+        assert_eq!(updates, 120 / METRICS_EVENT_INTERVAL);
+
+        let empty = monitor.get_server_metrics("unknown", 3600);
+        assert_eq!(empty.sample_count, 0);
+        assert_eq!(empty.p50, None);
+    }
+
+    #[test]
+    fn test_record_failover_emits_event() {
+        let monitor = McpHealthMonitor::new();
+        let mut events = monitor.subscribe();
+        monitor.record_failover("primary", "mirror", "Connection failed: refused");
+
+        match events.try_recv().unwrap() {
+            HealthEvent::FailedOver {
+                server_id,
+                fallback_server_id,
+                error,
+            } => {
+                assert_eq!(server_id, "primary");
+                assert_eq!(fallback_server_id, "mirror");
+                assert!(error.contains("refused"));
+            }
+            other => panic!("expected FailedOver, got {:?}", other),
+        }
+    }
+
+    fn monitored(server_id: &str, group: Option<&str>) -> MonitoredServer {
+        MonitoredServer {
+            server_id: server_id.to_string(),
+            endpoint: "http://127.0.0.1:9/mcp".to_string(),
+            interval_secs: 3600,
+            timeout_secs: 1,
+            auth: None,
+            degraded_multiplier: None,
+            degraded_floor_ms: None,
+            headers: HashMap::new(),
+            group: group.map(String::from),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_group_monitoring() {
+        let monitor = McpHealthMonitor::new();
+        let servers = || {
+            vec![
+                monitored("a", Some("prod")),
+                monitored("b", Some("prod")),
+                monitored("c", Some("staging")),
+                monitored("d", None),
+            ]
+        };
+        monitor.start_monitoring(servers());
+        assert_eq!(monitor.tasks.lock().len(), 4);
+
+        assert_eq!(monitor.stop_group("prod"), 2);
+        assert_eq!(monitor.tasks.lock().len(), 2);
+
+        // A stopped group stays stopped when the whole monitor restarts
+        monitor.start_monitoring(servers());
+        assert_eq!(monitor.tasks.lock().len(), 2);
+
+        assert_eq!(monitor.start_group("prod", servers()), 2);
+        assert_eq!(monitor.tasks.lock().len(), 4);
+        monitor.stop_monitoring();
+    }
 }
@@ -2,23 +2,38 @@
 //!
 //! This module implements the MCP specification 2025-11-25 with support for:
 //! - Streamable HTTP transport (primary for remote servers)
+//! - WebSocket transport (for ws:// and wss:// endpoints)
 //! - STDIO transport (for local servers)
-//! - Bearer token / API key authentication
+//! - Bearer token / API key / OAuth authentication
+//! - Encrypted credential storage
+//! - Client-side tool argument validation
 //! - Health monitoring
+//! - Connection pooling
 //! - Session management
+//! - Opt-in request/response tracing
+//! - Serving Opcode's own tools over stdio (`opcode --mcp-serve`)
 //!
 //! Opcode 2.0 - World's Greatest Claude Code Wrapper
 
 pub mod transport;
 pub mod streamable_http;
+pub mod websocket;
 pub mod auth;
 pub mod health;
+pub mod pool;
+pub mod schema;
+pub mod secrets;
+pub mod server;
+pub mod trace;
 pub mod types;
 pub mod error;
 
-pub use transport::{McpTransport, TransportConfig};
+pub use transport::{McpTransport, RetryPolicy, SamplingHandler, TlsOptions, TransportConfig};
 pub use streamable_http::StreamableHttpTransport;
-pub use auth::{McpAuth, McpBearerAuth, McpApiKeyAuth};
-pub use health::{McpHealthMonitor, HealthStatus, ServerHealth};
+pub use websocket::WebSocketTransport;
+pub use auth::{McpAuth, McpBearerAuth, McpApiKeyAuth, McpOAuthAuth};
+pub use health::{McpHealthMonitor, DegradedThresholds, HealthEvent, HealthSample, HealthStatus, MonitoredServer, ServerHealth, ServerMetrics};
+pub use pool::McpConnectionPool;
+pub use trace::{McpTrace, TraceEntry};
 pub use types::*;
 pub use error::McpError;
@@ -0,0 +1,795 @@
+//! MCP Connection Pool
+//!
+//! Keeps initialized transports alive between commands so warm calls cost a
+//! single HTTP round trip instead of a full initialize handshake.
+//! Features:
+//! - One connection per server, shared across commands
+//! - Idle eviction after a configurable timeout
+//! - Ping validation before reusing a connection that sat idle
+//! - Eviction of connections that fail at the transport level
+//! - Automatic re-initialize when the server expires the session
+//! - Optional keepalive pings so load balancers don't drop idle sessions
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use futures::future::BoxFuture;
+use log::{debug, info, warn};
+use parking_lot::Mutex;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::error::{McpError, McpResult};
+use super::transport::McpTransport;
+use super::types::{Root, ServerCapabilities, ServerInfo};
+
+/// Default idle timeout before a pooled connection is closed
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 300;
+
+/// Default idle time after which a connection is pinged before reuse
+pub const DEFAULT_VALIDATE_AFTER_SECS: u64 = 30;
+
+/// Default idle time between keepalive pings
+pub const DEFAULT_KEEPALIVE_SECS: u64 = 45;
+
+/// Whether an error means the connection itself is unusable
+fn is_connection_error(error: &McpError) -> bool {
+    matches!(
+        error,
+        McpError::ConnectionFailed(_)
+            | McpError::ConnectionTimeout(_)
+            | McpError::NotConnected
+            | McpError::TransportError(_)
+    )
+}
+
+/// A pooled, initialized connection to a remote MCP server
+pub struct PooledConnection {
+    /// Server ID this connection belongs to
+    server_id: String,
+    /// The connected transport (write-locked only to reconnect/disconnect)
+    transport: tokio::sync::RwLock<Box<dyn McpTransport>>,
+    /// Last time the connection was used
+    last_used: Mutex<Instant>,
+    /// Keepalive ping task, if keepalive is enabled for the server
+    keepalive: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl PooledConnection {
+    fn new(server_id: impl Into<String>, transport: Box<dyn McpTransport>) -> Self {
+        Self {
+            server_id: server_id.into(),
+            transport: tokio::sync::RwLock::new(transport),
+            last_used: Mutex::new(Instant::now()),
+            keepalive: Mutex::new(None),
+        }
+    }
+
+    /// Get the server ID
+    pub fn server_id(&self) -> &str {
+        &self.server_id
+    }
+
+    /// Mark the connection as used
+    fn touch(&self) {
+        *self.last_used.lock() = Instant::now();
+    }
+
+    /// Time since the connection was last used
+    pub fn idle_for(&self) -> Duration {
+        self.last_used.lock().elapsed()
+    }
+
+    /// MCP session ID held by the transport
+    pub async fn session_id(&self) -> Option<String> {
+        self.transport.read().await.session_id()
+    }
+
+    /// Protocol version negotiated with the server
+    pub async fn protocol_version(&self) -> Option<String> {
+        self.transport.read().await.protocol_version()
+    }
+
+    /// Capabilities the server advertised when the connection was initialized
+    pub async fn server_capabilities(&self) -> Option<ServerCapabilities> {
+        self.transport.read().await.server_capabilities()
+    }
+
+    /// Server name and version from initialize
+    pub async fn server_info(&self) -> Option<ServerInfo> {
+        self.transport.read().await.server_info()
+    }
+
+    /// Usage guidance the server sent with its initialize result
+    pub async fn server_instructions(&self) -> Option<String> {
+        self.transport.read().await.server_instructions()
+    }
+
+    /// Whether the transport is currently connected
+    pub async fn is_connected(&self) -> bool {
+        self.transport.read().await.is_connected()
+    }
+
+    /// Replace the roots offered to the server, telling it if the connection is up
+    pub async fn update_roots(&self, roots: Vec<Root>) -> McpResult<()> {
+        let transport = self.transport.read().await;
+        transport.set_roots(roots);
+        if transport.is_connected() {
+            transport.send_notification("notifications/roots/list_changed", None).await?;
+        }
+        Ok(())
+    }
+
+    /// Tear down the session and run the initialize handshake again
+    async fn reconnect(&self) -> McpResult<()> {
+        let mut transport = self.transport.write().await;
+        transport.disconnect().await?;
+        transport.connect().await
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(task) = self.keepalive.lock().take() {
+            task.abort();
+        }
+    }
+}
+
+/// Ping a connection whenever it has been quiet for `interval`
+///
+/// Real traffic resets the timer. A session the server has dropped is
+/// re-initialized so the next call doesn't fail. Exits once the connection
+/// is closed or dropped.
+async fn run_keepalive(conn: Weak<PooledConnection>, interval: Duration) {
+    let mut last_ping = Instant::now();
+
+    loop {
+        // Only a weak reference is held while waiting, so dropping the connection ends the task
+        let wait = match conn.upgrade() {
+            Some(conn) => interval.saturating_sub(conn.idle_for().min(last_ping.elapsed())),
+            None => return,
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+            continue;
+        }
+
+        let conn = match conn.upgrade() {
+            Some(conn) => conn,
+            None => return,
+        };
+        if !conn.is_connected().await {
+            return;
+        }
+
+        last_ping = Instant::now();
+        let result = conn.transport.read().await.ping().await;
+        match result {
+            Ok(()) => debug!("Keepalive ping to {} succeeded", conn.server_id),
+            Err(McpError::SessionExpired) => {
+                info!("MCP session for {} expired while idle, re-initializing", conn.server_id);
+                if let Err(e) = conn.reconnect().await {
+                    warn!("Failed to re-initialize MCP session for {}: {}", conn.server_id, e);
+                }
+            }
+            Err(e) => debug!("Keepalive ping to {} failed: {}", conn.server_id, e),
+        }
+    }
+}
+
+/// Connection pool for remote MCP servers
+pub struct McpConnectionPool {
+    /// Pooled connections (server_id -> connection)
+    connections: DashMap<String, Arc<PooledConnection>>,
+    /// Idle time after which a connection is evicted
+    idle_timeout: Duration,
+    /// Idle time after which a connection is pinged before reuse
+    validate_after: Duration,
+    /// Keepalive intervals for servers that have keepalive enabled
+    keepalive: DashMap<String, Duration>,
+}
+
+impl McpConnectionPool {
+    /// Create a new pool with the given idle timeout
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            connections: DashMap::new(),
+            idle_timeout,
+            validate_after: Duration::from_secs(DEFAULT_VALIDATE_AFTER_SECS),
+            keepalive: DashMap::new(),
+        }
+    }
+
+    /// Set the idle time after which connections are pinged before reuse
+    pub fn with_validate_after(mut self, validate_after: Duration) -> Self {
+        self.validate_after = validate_after;
+        self
+    }
+
+    /// Enable keepalive pings for a server, or disable them with `None`
+    ///
+    /// Applies to the pooled connection right away if there is one.
+    pub fn set_keepalive(&self, server_id: &str, interval: Option<Duration>) {
+        let changed = match interval {
+            Some(interval) => self.keepalive.insert(server_id.to_string(), interval) != Some(interval),
+            None => self.keepalive.remove(server_id).is_some(),
+        };
+        if changed {
+            if let Some(conn) = self.get(server_id) {
+                self.start_keepalive(&conn);
+            }
+        }
+    }
+
+    /// (Re)start a connection's keepalive task with the server's current interval
+    fn start_keepalive(&self, conn: &Arc<PooledConnection>) {
+        let task = self
+            .keepalive
+            .get(conn.server_id())
+            .map(|interval| tokio::spawn(run_keepalive(Arc::downgrade(conn), *interval)));
+        if let Some(previous) = std::mem::replace(&mut *conn.keepalive.lock(), task) {
+            previous.abort();
+        }
+    }
+
+    /// Get the idle timeout
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Check if a server has a pooled connection
+    pub fn contains(&self, server_id: &str) -> bool {
+        self.connections.contains_key(server_id)
+    }
+
+    /// Number of pooled connections
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Check if the pool is empty
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Get a pooled connection, creating and connecting one if needed
+    pub async fn get_or_connect<C>(&self, server_id: &str, create: C) -> McpResult<Arc<PooledConnection>>
+    where
+        C: FnOnce() -> McpResult<Box<dyn McpTransport>>,
+    {
+        let existing = self.connections.get(server_id).map(|c| c.clone());
+        if let Some(conn) = existing {
+            // Warm connections are trusted; ones that sat idle are checked first
+            if conn.idle_for() < self.validate_after {
+                conn.touch();
+                return Ok(conn);
+            }
+
+            let ping = conn.transport.read().await.ping().await;
+            match ping {
+                Ok(()) => {
+                    conn.touch();
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    warn!("Pooled MCP connection for {} failed validation: {}", server_id, e);
+                    self.evict(server_id, &conn).await;
+                }
+            }
+        }
+
+        debug!("Opening pooled MCP connection for {}", server_id);
+        let mut transport = create()?;
+        transport.connect().await?;
+
+        // Another command may have connected concurrently; keep whichever landed first
+        let (conn, unused) = match self.connections.entry(server_id.to_string()) {
+            Entry::Occupied(entry) => (entry.get().clone(), Some(transport)),
+            Entry::Vacant(entry) => (entry.insert(Arc::new(PooledConnection::new(server_id, transport))).clone(), None),
+        };
+
+        match unused {
+            Some(mut transport) => {
+                debug!("Closing duplicate MCP connection for {}", server_id);
+                if let Err(e) = transport.disconnect().await {
+                    warn!("Error disconnecting duplicate MCP connection for {}: {}", server_id, e);
+                }
+                conn.touch();
+            }
+            None => {
+                self.start_keepalive(&conn);
+                info!("Pooled MCP connection for {}", server_id);
+            }
+        }
+        Ok(conn)
+    }
+
+    /// Run an operation on a pooled connection
+    ///
+    /// If the server reports the session as expired, the connection is
+    /// re-initialized and the operation retried once.
+    pub async fn call<T, C, F>(&self, server_id: &str, create: C, op: F) -> McpResult<T>
+    where
+        C: FnOnce() -> McpResult<Box<dyn McpTransport>>,
+        F: for<'a> Fn(&'a dyn McpTransport) -> BoxFuture<'a, McpResult<T>>,
+    {
+        let conn = self.get_or_connect(server_id, create).await?;
+
+        let result = {
+            let transport = conn.transport.read().await;
+            op(&**transport).await
+        };
+
+        match result {
+            Err(e) if is_connection_error(&e) => {
+                warn!("Evicting MCP connection for {} after error: {}", server_id, e);
+                self.evict(server_id, &conn).await;
+                Err(e)
+            }
+            Err(McpError::SessionExpired) => {
+                let session_id = conn.transport.read().await.session_id();
+                warn!(
+                    "MCP session {} expired for {}, re-initializing",
+                    session_id.as_deref().unwrap_or("<none>"),
+                    server_id
+                );
+                if let Err(e) = conn.reconnect().await {
+                    self.evict(server_id, &conn).await;
+                    return Err(e);
+                }
+                conn.touch();
+                let transport = conn.transport.read().await;
+                op(&**transport).await
+            }
+            other => {
+                conn.touch();
+                other
+            }
+        }
+    }
+
+    /// Remove a connection from the pool if it is still the pooled one, and close it
+    async fn evict(&self, server_id: &str, conn: &Arc<PooledConnection>) {
+        let removed = self
+            .connections
+            .remove_if(server_id, |_, pooled| Arc::ptr_eq(pooled, conn))
+            .is_some();
+        if removed {
+            if let Err(e) = conn.transport.write().await.disconnect().await {
+                debug!("Error disconnecting evicted MCP connection for {}: {}", server_id, e);
+            }
+        }
+    }
+
+    /// Get a pooled connection without opening one or counting it as use
+    pub fn get(&self, server_id: &str) -> Option<Arc<PooledConnection>> {
+        self.connections.get(server_id).map(|c| c.clone())
+    }
+
+    /// Ping a pooled connection without opening one (None if the server isn't pooled)
+    ///
+    /// Does not count as use, so health checks don't keep idle connections alive.
+    pub async fn ping(&self, server_id: &str) -> Option<McpResult<()>> {
+        let conn = self.connections.get(server_id)?.clone();
+        let transport = conn.transport.read().await;
+        Some(transport.ping().await)
+    }
+
+    /// Ping a server over its pooled connection, connecting first if needed
+    ///
+    /// Like `ping`, this does not count as use. A connection that fails the
+    /// ping with a connection error is evicted.
+    pub async fn ping_or_connect<C>(&self, server_id: &str, create: C) -> McpResult<()>
+    where
+        C: FnOnce() -> McpResult<Box<dyn McpTransport>>,
+    {
+        let existing = self.connections.get(server_id).map(|c| c.clone());
+        let conn = match existing {
+            Some(conn) => conn,
+            None => self.get_or_connect(server_id, create).await?,
+        };
+
+        let result = conn.transport.read().await.ping().await;
+        if let Err(ref e) = result {
+            if is_connection_error(e) {
+                self.evict(server_id, &conn).await;
+            }
+        }
+        result
+    }
+
+    /// Close and remove a pooled connection (returns true if one existed)
+    pub async fn disconnect(&self, server_id: &str) -> bool {
+        match self.connections.remove(server_id) {
+            Some((_, conn)) => {
+                if let Err(e) = conn.transport.write().await.disconnect().await {
+                    warn!("Error disconnecting MCP server {}: {}", server_id, e);
+                }
+                info!("Closed pooled MCP connection for {}", server_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close all pooled connections
+    pub async fn disconnect_all(&self) {
+        let server_ids: Vec<String> = self.connections.iter().map(|c| c.key().clone()).collect();
+        for server_id in server_ids {
+            self.disconnect(&server_id).await;
+        }
+    }
+
+    /// Evict connections idle longer than the timeout (returns the number evicted)
+    pub async fn evict_idle(&self) -> usize {
+        let idle: Vec<String> = self
+            .connections
+            .iter()
+            .filter(|c| c.idle_for() >= self.idle_timeout)
+            .map(|c| c.key().clone())
+            .collect();
+
+        let mut evicted = 0;
+        for server_id in idle {
+            if self.disconnect(&server_id).await {
+                evicted += 1;
+            }
+        }
+
+        if evicted > 0 {
+            debug!("Evicted {} idle MCP connections", evicted);
+        }
+        evicted
+    }
+
+    /// Periodically evict idle connections (runs until the task is dropped)
+    pub async fn run_idle_eviction(self: Arc<Self>) {
+        let period = (self.idle_timeout / 2).max(Duration::from_secs(1));
+        let mut ticker = tokio::time::interval(period);
+
+        loop {
+            ticker.tick().await;
+            self.evict_idle().await;
+        }
+    }
+}
+
+impl Default for McpConnectionPool {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::types::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    /// Transport that counts handshakes, disconnects and pings, and can expire its session once
+    struct MockTransport {
+        connected: bool,
+        connects: Arc<AtomicU32>,
+        disconnects: Arc<AtomicU32>,
+        expire_once: Arc<AtomicU32>,
+        dead: Arc<AtomicBool>,
+        pings: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl McpTransport for MockTransport {
+        async fn connect(&mut self) -> McpResult<()> {
+            self.connects.fetch_add(1, Ordering::SeqCst);
+            self.connected = true;
+            Ok(())
+        }
+        async fn disconnect(&mut self) -> McpResult<()> {
+            self.disconnects.fetch_add(1, Ordering::SeqCst);
+            self.connected = false;
+            Ok(())
+        }
+        fn is_connected(&self) -> bool {
+            self.connected
+        }
+        fn session_id(&self) -> Option<String> {
+            None
+        }
+        async fn initialize(&mut self, _params: InitializeParams) -> McpResult<InitializeResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_initialized(&self) -> McpResult<()> {
+            Ok(())
+        }
+        async fn list_tools(&self, _cursor: Option<&str>) -> McpResult<ToolsListResult> {
+            if self.dead.load(Ordering::SeqCst) {
+                return Err(McpError::ConnectionFailed("dead".to_string()));
+            }
+            if self.expire_once.swap(0, Ordering::SeqCst) > 0 {
+                return Err(McpError::SessionExpired);
+            }
+            Ok(ToolsListResult { tools: vec![], next_cursor: None })
+        }
+        async fn call_tool(&self, _name: &str, _arguments: Option<serde_json::Value>) -> McpResult<ToolCallResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn list_resources(&self, _cursor: Option<&str>) -> McpResult<ResourcesListResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn read_resource(&self, _uri: &str) -> McpResult<serde_json::Value> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn list_prompts(&self, _cursor: Option<&str>) -> McpResult<PromptsListResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn get_prompt(&self, _name: &str, _arguments: Option<HashMap<String, String>>) -> McpResult<serde_json::Value> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn subscribe_resource(&self, _uri: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn unsubscribe_resource(&self, _uri: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        fn resource_updates(&self) -> tokio::sync::broadcast::Receiver<ResourceUpdatedNotification> {
+            tokio::sync::broadcast::channel(1).1
+        }
+        async fn set_log_level(&self, _level: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_request(&self, _request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_notification(&self, _method: &str, _params: Option<serde_json::Value>) -> McpResult<()> {
+            Ok(())
+        }
+        async fn ping(&self) -> McpResult<()> {
+            self.pings.fetch_add(1, Ordering::SeqCst);
+            if self.dead.load(Ordering::SeqCst) {
+                return Err(McpError::ConnectionFailed("dead".to_string()));
+            }
+            if self.expire_once.swap(0, Ordering::SeqCst) > 0 {
+                return Err(McpError::SessionExpired);
+            }
+            Ok(())
+        }
+        fn transport_type(&self) -> &'static str {
+            "mock"
+        }
+    }
+
+    fn mock(connects: &Arc<AtomicU32>, expire_once: u32) -> McpResult<Box<dyn McpTransport>> {
+        mock_with_liveness(connects, expire_once, &Arc::new(AtomicBool::new(false)))
+    }
+
+    fn mock_with_liveness(
+        connects: &Arc<AtomicU32>,
+        expire_once: u32,
+        dead: &Arc<AtomicBool>,
+    ) -> McpResult<Box<dyn McpTransport>> {
+        Ok(Box::new(MockTransport {
+            connected: false,
+            connects: connects.clone(),
+            disconnects: Arc::new(AtomicU32::new(0)),
+            expire_once: Arc::new(AtomicU32::new(expire_once)),
+            dead: dead.clone(),
+            pings: Arc::new(AtomicU32::new(0)),
+        }))
+    }
+
+    fn mock_with_pings(
+        connects: &Arc<AtomicU32>,
+        expire_once: u32,
+        pings: &Arc<AtomicU32>,
+    ) -> McpResult<Box<dyn McpTransport>> {
+        Ok(Box::new(MockTransport {
+            connected: false,
+            connects: connects.clone(),
+            disconnects: Arc::new(AtomicU32::new(0)),
+            expire_once: Arc::new(AtomicU32::new(expire_once)),
+            dead: Arc::new(AtomicBool::new(false)),
+            pings: pings.clone(),
+        }))
+    }
+
+    fn mock_with_disconnects(
+        connects: &Arc<AtomicU32>,
+        disconnects: &Arc<AtomicU32>,
+        dead: &Arc<AtomicBool>,
+    ) -> McpResult<Box<dyn McpTransport>> {
+        Ok(Box::new(MockTransport {
+            connected: false,
+            connects: connects.clone(),
+            disconnects: disconnects.clone(),
+            expire_once: Arc::new(AtomicU32::new(0)),
+            dead: dead.clone(),
+            pings: Arc::new(AtomicU32::new(0)),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_connection_reused() {
+        let pool = McpConnectionPool::default();
+        let connects = Arc::new(AtomicU32::new(0));
+
+        for _ in 0..3 {
+            pool.call("server", || mock(&connects, 0), |t| Box::pin(async move { t.list_tools(None).await }))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_reconnects() {
+        let pool = McpConnectionPool::default();
+        let connects = Arc::new(AtomicU32::new(0));
+
+        let result = pool
+            .call("server", || mock(&connects, 1), |t| Box::pin(async move { t.list_tools(None).await }))
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_connection_error_evicts() {
+        let pool = McpConnectionPool::default();
+        let connects = Arc::new(AtomicU32::new(0));
+        let dead = Arc::new(AtomicBool::new(true));
+
+        let result = pool
+            .call(
+                "server",
+                || mock_with_liveness(&connects, 0, &dead),
+                |t| Box::pin(async move { t.list_tools(None).await }),
+            )
+            .await;
+
+        assert!(matches!(result, Err(McpError::ConnectionFailed(_))));
+        assert!(pool.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_evicted_connection_is_closed() {
+        let pool = McpConnectionPool::default();
+        let connects = Arc::new(AtomicU32::new(0));
+        let disconnects = Arc::new(AtomicU32::new(0));
+        let dead = Arc::new(AtomicBool::new(false));
+
+        pool.get_or_connect("server", || mock_with_disconnects(&connects, &disconnects, &dead))
+            .await
+            .unwrap();
+        dead.store(true, Ordering::SeqCst);
+        let result = pool
+            .call("server", || mock(&connects, 0), |t| Box::pin(async move { t.list_tools(None).await }))
+            .await;
+
+        assert!(result.is_err());
+        assert!(pool.is_empty());
+        assert_eq!(disconnects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_losing_concurrent_connect_is_closed() {
+        let pool = McpConnectionPool::default();
+        let connects = Arc::new(AtomicU32::new(0));
+        let disconnects = Arc::new(AtomicU32::new(0));
+        let alive = Arc::new(AtomicBool::new(false));
+
+        // Another command pools a connection while this one is still connecting
+        let winner = Arc::new(PooledConnection::new("server", mock(&connects, 0).unwrap()));
+        let conn = pool
+            .get_or_connect("server", || {
+                pool.connections.insert("server".to_string(), winner.clone());
+                mock_with_disconnects(&connects, &disconnects, &alive)
+            })
+            .await
+            .unwrap();
+
+        assert!(Arc::ptr_eq(&conn, &winner));
+        assert_eq!(disconnects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_validation_reconnects() {
+        let pool = McpConnectionPool::default().with_validate_after(Duration::ZERO);
+        let connects = Arc::new(AtomicU32::new(0));
+        let dead = Arc::new(AtomicBool::new(false));
+
+        pool.get_or_connect("server", || mock_with_liveness(&connects, 0, &dead))
+            .await
+            .unwrap();
+        dead.store(true, Ordering::SeqCst);
+
+        // The stale connection fails its ping and a fresh one replaces it
+        let alive = Arc::new(AtomicBool::new(false));
+        pool.get_or_connect("server", || mock_with_liveness(&connects, 0, &alive))
+            .await
+            .unwrap();
+
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+        assert!(matches!(pool.ping("server").await, Some(Ok(()))));
+    }
+
+    #[tokio::test]
+    async fn test_ping_only_pooled() {
+        let pool = McpConnectionPool::default();
+        let connects = Arc::new(AtomicU32::new(0));
+
+        assert!(pool.ping("server").await.is_none());
+
+        pool.get_or_connect("server", || mock(&connects, 0)).await.unwrap();
+        assert!(matches!(pool.ping("server").await, Some(Ok(()))));
+    }
+
+    #[tokio::test]
+    async fn test_ping_or_connect() {
+        let pool = McpConnectionPool::default();
+        let connects = Arc::new(AtomicU32::new(0));
+        let dead = Arc::new(AtomicBool::new(false));
+
+        pool.ping_or_connect("server", || mock_with_liveness(&connects, 0, &dead))
+            .await
+            .unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+        assert!(pool.contains("server"));
+
+        // A failed ping drops the connection so the next check reconnects
+        dead.store(true, Ordering::SeqCst);
+        assert!(pool.ping_or_connect("server", || mock(&connects, 0)).await.is_err());
+        assert!(pool.is_empty());
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_evict_idle() {
+        let pool = McpConnectionPool::new(Duration::ZERO);
+        let connects = Arc::new(AtomicU32::new(0));
+
+        pool.get_or_connect("server", || mock(&connects, 0)).await.unwrap();
+        assert!(pool.contains("server"));
+
+        assert_eq!(pool.evict_idle().await, 1);
+        assert!(pool.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keepalive_pings_idle_connection() {
+        let pool = McpConnectionPool::default();
+        pool.set_keepalive("server", Some(Duration::from_millis(30)));
+        let connects = Arc::new(AtomicU32::new(0));
+        let pings = Arc::new(AtomicU32::new(0));
+
+        // The session expires while idle, so the first keepalive ping (at 30ms) re-initializes it
+        pool.get_or_connect("server", || mock_with_pings(&connects, 1, &pings))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(45)).await;
+
+        assert_eq!(pings.load(Ordering::SeqCst), 1);
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+
+        // The next call goes straight through on the fresh session
+        pool.call("server", || mock_with_pings(&connects, 0, &pings), |t| {
+            Box::pin(async move { t.list_tools(None).await })
+        })
+        .await
+        .unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+
+        // Using the connection at 45ms pushes the next ping back to 75ms
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(pings.load(Ordering::SeqCst), 2);
+
+        // Disabling keepalive stops the pings
+        pool.set_keepalive("server", None);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(pings.load(Ordering::SeqCst), 2);
+    }
+}
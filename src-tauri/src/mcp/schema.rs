@@ -0,0 +1,106 @@
+//! Tool argument validation
+//!
+//! Checks tool call arguments against the tool's `inputSchema` before the call
+//! is sent, so mistakes come back per field instead of as a server error.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::error::{McpError, McpResult};
+use super::types::Tool;
+
+/// A single schema violation in a tool's arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgumentError {
+    /// JSON Pointer to the offending value ("" for the arguments object itself)
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ArgumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Find a tool by name, listing the available tools if it doesn't exist
+pub fn find_tool<'a>(tools: &'a [Tool], name: &str) -> McpResult<&'a Tool> {
+    tools.iter().find(|t| t.name == name).ok_or_else(|| {
+        let available: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        McpError::ToolNotFound(format!("{} (available: {})", name, available.join(", ")))
+    })
+}
+
+/// Validate arguments against a tool's input schema, returning every violation
+///
+/// A schema the validator can't compile is the server's problem, so it is
+/// logged and the arguments are passed through.
+pub fn validate_arguments(tool: &Tool, arguments: Option<&serde_json::Value>) -> Vec<ArgumentError> {
+    let validator = match jsonschema::validator_for(&tool.input_schema) {
+        Ok(validator) => validator,
+        Err(e) => {
+            warn!("Skipping argument validation for tool {}: invalid input schema ({})", tool.name, e);
+            return Vec::new();
+        }
+    };
+
+    // Omitted arguments are sent as an empty object
+    let empty = serde_json::json!({});
+    validator
+        .iter_errors(arguments.unwrap_or(&empty))
+        .map(|e| ArgumentError {
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search_tool() -> Tool {
+        Tool {
+            name: "search".to_string(),
+            description: None,
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    #[test]
+    fn test_validate_arguments() {
+        let tool = search_tool();
+
+        assert!(validate_arguments(&tool, Some(&serde_json::json!({ "query": "rust" }))).is_empty());
+
+        let errors = validate_arguments(&tool, Some(&serde_json::json!({ "limit": 0 })));
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|e| e.path.is_empty() && e.message.contains("query")));
+        assert!(errors.iter().any(|e| e.path == "/limit"));
+
+        // Missing arguments are validated as an empty object
+        assert_eq!(validate_arguments(&tool, None).len(), 1);
+    }
+
+    #[test]
+    fn test_find_tool_lists_available_names() {
+        let tools = vec![search_tool()];
+
+        assert!(find_tool(&tools, "search").is_ok());
+        match find_tool(&tools, "fetch") {
+            Err(McpError::ToolNotFound(message)) => assert_eq!(message, "fetch (available: search)"),
+            other => panic!("expected ToolNotFound, got {:?}", other.map(|t| &t.name)),
+        }
+    }
+}
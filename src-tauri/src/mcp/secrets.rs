@@ -0,0 +1,264 @@
+//! Secret storage for MCP credentials
+//!
+//! Secrets are kept in the OS keychain and referenced from SQLite as
+//! `keychain:<name>`. Where no keychain is available they are instead sealed
+//! with AES-256-GCM and stored in the database, keyed from the keychain or a
+//! user-only key file in the app data directory.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use log::{info, warn};
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+
+use super::error::{McpError, McpResult};
+use super::server::APP_IDENTIFIER;
+
+/// Marks a value as ciphertext, versioned so the scheme can change later
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Marks a value as a reference to a keychain entry
+pub const KEYCHAIN_PREFIX: &str = "keychain:";
+
+const KEYRING_SERVICE: &str = "opcode";
+const KEYRING_USER: &str = "mcp-auth-key";
+const KEY_FILE_NAME: &str = "mcp-auth.key";
+const NONCE_LEN: usize = 12;
+
+static MASTER_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+/// Keychain entry name if a stored value is a keychain reference
+pub fn keychain_ref(stored: &str) -> Option<&str> {
+    stored.strip_prefix(KEYCHAIN_PREFIX)
+}
+
+/// Save a secret in the OS keychain, returning the reference to store in its place
+pub fn store_in_keychain(name: &str, secret: &str) -> McpResult<String> {
+    keychain_entry(name)?
+        .set_password(secret)
+        .map_err(|e| McpError::CredentialStore(e.to_string()))?;
+    Ok(format!("{}{}", KEYCHAIN_PREFIX, name))
+}
+
+/// Read a secret saved with `store_in_keychain`
+pub fn load_from_keychain(name: &str) -> McpResult<String> {
+    keychain_entry(name)?.get_password().map_err(|e| match e {
+        keyring::Error::NoEntry => {
+            McpError::CredentialStore(format!("No keychain entry for {}", name))
+        }
+        e => McpError::CredentialStore(e.to_string()),
+    })
+}
+
+/// Remove a keychain entry; a missing entry is not an error
+pub fn delete_from_keychain(name: &str) -> McpResult<()> {
+    match keychain_entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(McpError::CredentialStore(e.to_string())),
+    }
+}
+
+fn keychain_entry(name: &str) -> McpResult<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, name).map_err(|e| McpError::CredentialStore(e.to_string()))
+}
+
+/// Whether a stored value was produced by `encrypt_secret`
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Encrypt a secret for storage
+pub fn encrypt_secret(plaintext: &str) -> McpResult<String> {
+    seal(&master_key()?, plaintext)
+}
+
+/// Decrypt a value produced by `encrypt_secret`
+pub fn decrypt_secret(stored: &str) -> McpResult<String> {
+    open(&master_key()?, stored)
+}
+
+fn seal(key: &[u8; 32], plaintext: &str) -> McpResult<String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| McpError::CredentialStore("Encryption failed".to_string()))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(sealed)))
+}
+
+fn open(key: &[u8; 32], stored: &str) -> McpResult<String> {
+    let encoded = stored
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .ok_or_else(|| McpError::CredentialStore("Value is not encrypted".to_string()))?;
+    let sealed = STANDARD
+        .decode(encoded)
+        .map_err(|e| McpError::CredentialStore(format!("Invalid ciphertext encoding: {}", e)))?;
+    if sealed.len() < NONCE_LEN {
+        return Err(McpError::CredentialStore("Ciphertext too short".to_string()));
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| McpError::CredentialStore("Decryption failed (wrong key or corrupted data)".to_string()))?;
+
+    String::from_utf8(plaintext)
+        .map_err(|e| McpError::CredentialStore(format!("Decrypted value is not UTF-8: {}", e)))
+}
+
+/// Load the master key, creating and storing one on first use
+fn master_key() -> McpResult<[u8; 32]> {
+    // Held across the load so concurrent first uses can't each create a key
+    let mut cached = MASTER_KEY.lock();
+    if let Some(key) = *cached {
+        return Ok(key);
+    }
+
+    let key = match load_keychain_key()? {
+        Some(key) => key,
+        None => load_file_key()?,
+    };
+
+    *cached = Some(key);
+    Ok(key)
+}
+
+fn decode_key(encoded: &str) -> McpResult<[u8; 32]> {
+    STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| McpError::CredentialStore("Stored encryption key is malformed".to_string()))
+}
+
+fn generate_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&Aes256Gcm::generate_key(OsRng));
+    key
+}
+
+/// Load the key from the keychain, or None when it lives in the key file instead
+///
+/// The key file is used when the system has no keychain to store the key in,
+/// or already holds the key from such a system. Any other keychain error is
+/// returned, since a new key couldn't open values sealed with the current one.
+fn load_keychain_key() -> McpResult<Option<[u8; 32]>> {
+    let entry = keychain_entry(KEYRING_USER)?;
+
+    match entry.get_password() {
+        Ok(encoded) => decode_key(&encoded).map(Some),
+        Err(keyring::Error::NoEntry) if key_file_path()?.exists() => Ok(None),
+        Err(keyring::Error::NoEntry) => {
+            let key = generate_key();
+            entry
+                .set_password(&STANDARD.encode(key))
+                .map_err(|e| McpError::CredentialStore(e.to_string()))?;
+            info!("Created MCP credential encryption key in OS keychain");
+            Ok(Some(key))
+        }
+        Err(keyring::Error::NoStorageAccess(e)) => {
+            warn!("OS keychain unavailable ({}), using key file for MCP credentials", e);
+            Ok(None)
+        }
+        Err(e) => Err(McpError::CredentialStore(format!(
+            "Failed to read MCP credential key from OS keychain: {}",
+            e
+        ))),
+    }
+}
+
+/// Key file in the app data directory
+///
+/// A key file left in the data directory's old `opcode` folder is moved here first.
+fn key_file_path() -> McpResult<PathBuf> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| McpError::CredentialStore("Could not find data directory".to_string()))?;
+    let path = data_dir.join(APP_IDENTIFIER).join(KEY_FILE_NAME);
+
+    let legacy = data_dir.join("opcode").join(KEY_FILE_NAME);
+    if !path.exists() && legacy.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| McpError::CredentialStore(format!("Failed to create data directory: {}", e)))?;
+        }
+        std::fs::rename(&legacy, &path)
+            .map_err(|e| McpError::CredentialStore(format!("Failed to move key file: {}", e)))?;
+        info!("Moved MCP credential key file to {:?}", path);
+    }
+    Ok(path)
+}
+
+fn load_file_key() -> McpResult<[u8; 32]> {
+    let path = key_file_path()?;
+    if path.exists() {
+        let encoded = std::fs::read_to_string(&path)
+            .map_err(|e| McpError::CredentialStore(format!("Failed to read key file: {}", e)))?;
+        return decode_key(&encoded);
+    }
+
+    let key = generate_key();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| McpError::CredentialStore(format!("Failed to create data directory: {}", e)))?;
+    }
+    write_key_file(&path, &STANDARD.encode(key))
+        .map_err(|e| McpError::CredentialStore(format!("Failed to write key file: {}", e)))?;
+    info!("Created MCP credential encryption key at {:?}", path);
+    Ok(key)
+}
+
+#[cfg(unix)]
+fn write_key_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_key_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let key = generate_key();
+        let sealed = seal(&key, r#"{"type":"bearer","token":"secret"}"#).unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("secret"));
+        assert_eq!(open(&key, &sealed).unwrap(), r#"{"type":"bearer","token":"secret"}"#);
+
+        // Fresh nonce per value
+        assert_ne!(sealed, seal(&key, r#"{"type":"bearer","token":"secret"}"#).unwrap());
+    }
+
+    #[test]
+    fn test_keychain_ref() {
+        assert_eq!(keychain_ref("keychain:remote-mcp/abc"), Some("remote-mcp/abc"));
+        assert_eq!(keychain_ref("enc:v1:AAAA"), None);
+        assert_eq!(keychain_ref(r#"{"type":"none"}"#), None);
+    }
+
+    #[test]
+    fn test_open_rejects_wrong_key_and_plaintext() {
+        let sealed = seal(&generate_key(), "value").unwrap();
+
+        assert!(open(&generate_key(), &sealed).is_err());
+        assert!(open(&generate_key(), r#"{"type":"none"}"#).is_err());
+    }
+}
@@ -0,0 +1,427 @@
+//! MCP Server
+//!
+//! Serves Opcode's own projects, sessions, checkpoints and skills as MCP tools
+//! over stdio, so other clients can use them:
+//! `claude mcp add opcode -- opcode --mcp-serve`.
+
+use log::{debug, info};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::types::{
+    InitializeResult, JsonRpcError, JsonRpcRequest, JsonRpcResponse, ServerCapabilities, ServerInfo,
+    ServerToolsCapability, Tool, ToolCallParams, ToolCallResult, ToolResultContent, ToolsListResult,
+    MCP_PROTOCOL_VERSION, MCP_SUPPORTED_PROTOCOL_VERSIONS,
+};
+use crate::checkpoint::state::CheckpointState;
+use crate::commands::agents::init_database_at;
+use crate::commands::claude::{create_session_checkpoint, get_project_sessions, list_projects};
+use crate::commands::skills::{expand_slash_command, query_skills};
+
+/// Tauri bundle identifier, which names the app data directory; must match tauri.conf.json
+pub(crate) const APP_IDENTIFIER: &str = "opcode.asterisk.so";
+
+/// JSON-RPC error code for messages that aren't valid JSON
+const PARSE_ERROR: i32 = -32700;
+
+/// JSON-RPC error code for messages that aren't valid requests
+const INVALID_REQUEST: i32 = -32600;
+
+/// JSON-RPC error code for unknown methods
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// JSON-RPC error code for unknown tools and malformed arguments
+const INVALID_PARAMS: i32 = -32602;
+
+/// JSON-RPC error code for failures answering a valid request
+const INTERNAL_ERROR: i32 = -32603;
+
+/// Opcode's capabilities served as MCP tools
+pub struct OpcodeMcpServer {
+    db: Mutex<Connection>,
+    checkpoints: CheckpointState,
+}
+
+impl OpcodeMcpServer {
+    pub fn new(db: Connection, checkpoints: CheckpointState) -> Self {
+        Self {
+            db: Mutex::new(db),
+            checkpoints,
+        }
+    }
+
+    /// Open the desktop app's database and ~/.claude without a running app
+    pub async fn open() -> Result<Self, String> {
+        let app_dir = dirs::data_dir()
+            .ok_or("Could not find data directory")?
+            .join(APP_IDENTIFIER);
+        let db = init_database_at(&app_dir).map_err(|e| e.to_string())?;
+
+        let checkpoints = CheckpointState::new();
+        if let Some(claude_dir) = dirs::home_dir().and_then(|home| home.join(".claude").canonicalize().ok()) {
+            checkpoints.set_claude_dir(claude_dir).await;
+        }
+        Ok(Self::new(db, checkpoints))
+    }
+
+    /// Answer requests from stdin on stdout until stdin closes
+    pub async fn serve_stdio(&self) -> std::io::Result<()> {
+        info!("Serving Opcode tools over MCP stdio");
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await
+    }
+
+    /// Answer newline-delimited JSON-RPC messages from `reader` on `writer`
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> std::io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line).await {
+                let mut frame = serde_json::to_vec(&response)?;
+                frame.push(b'\n');
+                writer.write_all(&frame).await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer one JSON-RPC message; notifications and responses get no reply
+    pub async fn handle_message(&self, line: &str) -> Option<JsonRpcResponse> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, format!("Parse error: {}", e))),
+        };
+        if !message.is_object() {
+            return Some(error_response(Value::Null, INVALID_REQUEST, "Expected a single JSON-RPC object"));
+        }
+
+        let id = match message.get("id") {
+            Some(id) if message.get("method").is_some() => id.clone(),
+            _ => {
+                if let Some(method) = message.get("method").and_then(|m| m.as_str()) {
+                    debug!("Received MCP notification {}", method);
+                }
+                return None;
+            }
+        };
+        let request: JsonRpcRequest = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(id, INVALID_REQUEST, format!("Invalid request: {}", e))),
+        };
+        if request.jsonrpc != "2.0" {
+            return Some(error_response(id, INVALID_REQUEST, "Unsupported JSON-RPC version"));
+        }
+
+        let result = match request.method.as_str() {
+            "initialize" => Ok(initialize(request.params.as_ref())),
+            "ping" => Ok(json!({})),
+            "tools/list" => serde_json::to_value(ToolsListResult {
+                tools: tools(),
+                next_cursor: None,
+            })
+            .map_err(|e| (INTERNAL_ERROR, e.to_string())),
+            "tools/call" => self.call_tool(request.params).await,
+            method => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result: Some(result),
+                error: None,
+                id,
+            },
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    /// Run a tool; failures inside the tool are reported in the result with isError
+    async fn call_tool(&self, params: Option<Value>) -> Result<Value, (i32, String)> {
+        let params: ToolCallParams = params
+            .and_then(|params| serde_json::from_value(params).ok())
+            .ok_or((INVALID_PARAMS, "Expected tool name and arguments".to_string()))?;
+        let args = params.arguments.unwrap_or_else(|| json!({}));
+        debug!("MCP client called tool {}", params.name);
+
+        let output = match params.name.as_str() {
+            "list_projects" => list_projects().await.map(|projects| json!({ "projects": projects })),
+            "list_sessions" => {
+                let project_id = id_arg(&args, "project_id")?;
+                get_project_sessions(project_id)
+                    .await
+                    .map(|sessions| json!({ "sessions": sessions }))
+            }
+            "create_checkpoint" => self.create_checkpoint(&args).await?,
+            "list_skills" => {
+                let kind = optional_arg(&args, "kind")?;
+                let project_path = optional_arg(&args, "project_path")?;
+                self.db
+                    .lock()
+                    .map_err(|e| e.to_string())
+                    .and_then(|conn| query_skills(&conn, kind, project_path))
+                    .map(|skills| json!({ "skills": skills }))
+            }
+            "execute_slash_command" => {
+                let command = required_arg(&args, "command")?;
+                let arguments = optional_arg(&args, "arguments")?.unwrap_or_default();
+                self.db
+                    .lock()
+                    .map_err(|e| e.to_string())
+                    .and_then(|conn| expand_slash_command(&conn, command.trim_start_matches('/'), &arguments))
+            }
+            name => return Err((INVALID_PARAMS, format!("Unknown tool: {}", name))),
+        };
+
+        let result = match output {
+            Ok(output) => ToolCallResult {
+                content: vec![ToolResultContent::Text {
+                    text: serde_json::to_string_pretty(&output).unwrap_or_default(),
+                }],
+                is_error: None,
+                structured_content: Some(output),
+            },
+            Err(e) => ToolCallResult {
+                content: vec![ToolResultContent::Text { text: e }],
+                is_error: Some(true),
+                structured_content: None,
+            },
+        };
+        serde_json::to_value(result).map_err(|e| (INTERNAL_ERROR, e.to_string()))
+    }
+
+    async fn create_checkpoint(&self, args: &Value) -> Result<Result<Value, String>, (i32, String)> {
+        let session_id = id_arg(args, "session_id")?;
+        let project_id = id_arg(args, "project_id")?;
+        let project_path = required_arg(args, "project_path")?;
+        let description = optional_arg(args, "description")?;
+        let message_index = match args.get("message_index") {
+            None | Some(Value::Null) => None,
+            Some(index) => match index.as_u64() {
+                Some(index) => Some(index as usize),
+                None => return Err((INVALID_PARAMS, "message_index must be a non-negative integer".to_string())),
+            },
+        };
+
+        if !std::path::Path::new(&project_path).is_dir() {
+            return Ok(Err(format!("Project directory not found: {}", project_path)));
+        }
+        Ok(create_session_checkpoint(
+            &self.checkpoints,
+            session_id,
+            project_id,
+            project_path,
+            message_index,
+            description,
+        )
+        .await
+        .and_then(|result| serde_json::to_value(result).map_err(|e| e.to_string())))
+    }
+}
+
+/// Agree on the client's protocol version if supported, otherwise offer ours
+fn initialize(params: Option<&Value>) -> Value {
+    let requested = params
+        .and_then(|params| params.get("protocolVersion"))
+        .and_then(|version| version.as_str());
+    let protocol_version = match requested {
+        Some(version) if MCP_SUPPORTED_PROTOCOL_VERSIONS.contains(&version) => version,
+        _ => MCP_PROTOCOL_VERSION,
+    };
+
+    let result = InitializeResult {
+        protocol_version: protocol_version.to_string(),
+        capabilities: ServerCapabilities {
+            tools: Some(ServerToolsCapability { list_changed: Some(false) }),
+            ..Default::default()
+        },
+        server_info: ServerInfo {
+            name: "opcode".to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        },
+        instructions: Some(
+            "Browse Claude Code projects and sessions, checkpoint sessions, and expand Opcode slash commands."
+                .to_string(),
+        ),
+    };
+    serde_json::to_value(result).unwrap_or_default()
+}
+
+/// Tools served to MCP clients
+fn tools() -> Vec<Tool> {
+    let tool = |name: &str, description: &str, input_schema: Value| Tool {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        input_schema,
+    };
+
+    vec![
+        tool(
+            "list_projects",
+            "List Claude Code projects with their session IDs",
+            json!({ "type": "object", "properties": {} }),
+        ),
+        tool(
+            "list_sessions",
+            "List the sessions of a project",
+            json!({
+                "type": "object",
+                "properties": {
+                    "project_id": { "type": "string", "description": "Project ID from list_projects" }
+                },
+                "required": ["project_id"]
+            }),
+        ),
+        tool(
+            "create_checkpoint",
+            "Checkpoint a session's files and messages",
+            json!({
+                "type": "object",
+                "properties": {
+                    "session_id": { "type": "string" },
+                    "project_id": { "type": "string" },
+                    "project_path": { "type": "string", "description": "Absolute path of the project directory" },
+                    "message_index": { "type": "integer", "minimum": 0, "description": "Last message to include" },
+                    "description": { "type": "string" }
+                },
+                "required": ["session_id", "project_id", "project_path"]
+            }),
+        ),
+        tool(
+            "list_skills",
+            "List Opcode skills, optionally of one kind or visible from one project",
+            json!({
+                "type": "object",
+                "properties": {
+                    "kind": {
+                        "type": "string",
+                        "enum": ["slash_command", "hook", "workflow", "template", "agent"]
+                    },
+                    "project_path": { "type": "string" }
+                }
+            }),
+        ),
+        tool(
+            "execute_slash_command",
+            "Expand an Opcode slash command into its prompt",
+            json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "Command name, with or without the leading /" },
+                    "arguments": { "type": "string" }
+                },
+                "required": ["command"]
+            }),
+        ),
+    ]
+}
+
+fn required_arg(args: &Value, name: &str) -> Result<String, (i32, String)> {
+    match optional_arg(args, name)? {
+        Some(value) => Ok(value),
+        None => Err((INVALID_PARAMS, format!("Missing required argument: {}", name))),
+    }
+}
+
+/// A required ID that names a file or directory under ~/.claude
+fn id_arg(args: &Value, name: &str) -> Result<String, (i32, String)> {
+    let value = required_arg(args, name)?;
+    if value.is_empty() || value.contains(['/', '\\']) || value.contains("..") {
+        return Err((INVALID_PARAMS, format!("Invalid {}: {}", name, value)));
+    }
+    Ok(value)
+}
+
+fn optional_arg(args: &Value, name: &str) -> Result<Option<String>, (i32, String)> {
+    match args.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err((INVALID_PARAMS, format!("Argument {} must be a string", name))),
+    }
+}
+
+fn error_response(id: Value, code: i32, message: impl Into<String>) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(JsonRpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }),
+        id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> OpcodeMcpServer {
+        let db = Connection::open_in_memory().unwrap();
+        OpcodeMcpServer::new(db, CheckpointState::new())
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() {
+        let server = server();
+
+        let parse = server.handle_message("{not json").await.unwrap();
+        assert_eq!(parse.error.unwrap().code, PARSE_ERROR);
+
+        let unknown = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":1,"method":"resources/list"}"#)
+            .await
+            .unwrap();
+        assert_eq!(unknown.id, json!(1));
+        assert_eq!(unknown.error.unwrap().code, METHOD_NOT_FOUND);
+
+        let bad_tool = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"rm_rf"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(bad_tool.error.unwrap().code, INVALID_PARAMS);
+
+        let missing_arg = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"list_sessions"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(missing_arg.error.unwrap().code, INVALID_PARAMS);
+
+        let notification = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+        assert!(server.handle_message(notification).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_protocol_version() {
+        let server = server();
+
+        let supported = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18"}}"#;
+        let result = server.handle_message(supported).await.unwrap().result.unwrap();
+        assert_eq!(result["protocolVersion"], "2025-06-18");
+        assert_eq!(result["serverInfo"]["name"], "opcode");
+        assert!(result["capabilities"]["tools"].is_object());
+
+        let unknown = r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{"protocolVersion":"1999-01-01"}}"#;
+        let result = server.handle_message(unknown).await.unwrap().result.unwrap();
+        assert_eq!(result["protocolVersion"], MCP_PROTOCOL_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_tool_failures_are_reported_in_result() {
+        let server = server();
+
+        let call = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call",
+            "params":{"name":"execute_slash_command","arguments":{"command":"/missing"}}}"#;
+        let result = server.handle_message(call).await.unwrap().result.unwrap();
+        assert_eq!(result["isError"], true);
+        assert!(result["content"][0]["text"].as_str().unwrap().contains("/missing"));
+    }
+}
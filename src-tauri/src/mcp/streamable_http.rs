@@ -5,45 +5,138 @@
 //!
 //! Key features:
 //! - Single endpoint for all operations
-//! - Session management via Mcp-Session-Id header
+//! - Session management via Mcp-Session-Id header, resumable across reconnects
 //! - Support for streaming responses via SSE
+//! - Server-initiated notifications over a standing GET SSE stream
 //! - Bearer token / API key authentication
 
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use log::{debug, error, info, warn};
 use parking_lot::RwLock;
+use reqwest::header::HeaderMap;
 use reqwest::{Client, Response, StatusCode};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
 use url::Url;
 
 use super::auth::McpAuth;
-use super::error::{McpError, McpResult};
-use super::transport::McpTransport;
+use super::error::{is_certificate_error, McpError, McpResult};
+use super::trace::{redact_headers, McpTrace, TraceEntry};
+use super::transport::{McpTransport, RetryPolicy, SamplingHandler, TlsOptions, TransportEvent};
 use super::types::*;
 
+/// Capacity of the resource update and notification broadcast channels
+const NOTIFICATIONS_CAPACITY: usize = 64;
+
+/// Default number of retries after a 429 Too Many Requests
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Initial backoff when the server gives no Retry-After
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Upper bound on any single rate-limit wait
+const MAX_RETRY_DELAY_SECS: u64 = 30;
+
+/// Default number of times a dropped SSE response is resumed
+pub const DEFAULT_MAX_RESUME_ATTEMPTS: u32 = 3;
+
+/// Buffered events for a streaming tool call
+const TOOL_STREAM_CAPACITY: usize = 32;
+
+/// Delay before re-opening a dropped notification stream
+const NOTIFICATION_STREAM_RETRY_SECS: u64 = 2;
+
+/// Methods that are safe to resend after a failure mid-request
+const IDEMPOTENT_METHODS: &[&str] = &[
+    "initialize",
+    "ping",
+    "tools/list",
+    "resources/list",
+    "prompts/list",
+];
+
+/// JSON-RPC "Invalid Request", sent by servers that don't accept batches
+const INVALID_REQUEST: i32 = -32600;
+
+/// JSON-RPC "Method not found", returned for server requests we don't handle
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// JSON-RPC "Invalid params", returned for malformed sampling requests
+const INVALID_PARAMS: i32 = -32602;
+
+/// JSON-RPC "Internal error", returned when sampling fails or times out
+const INTERNAL_ERROR: i32 = -32603;
+
+/// Error code MCP uses for a sampling request the user rejected
+const USER_REJECTED: i32 = -1;
+
+/// Hard limit on a sampling request, including the wait for user approval
+pub const SAMPLING_TIMEOUT: Duration = Duration::from_secs(300);
+
 /// Streamable HTTP Transport implementation
 pub struct StreamableHttpTransport {
     /// HTTP client with configured timeouts
     client: Client,
     /// MCP server endpoint URL
     endpoint: Url,
+    /// TLS settings the client was built with
+    tls: Option<TlsOptions>,
+    /// Per-server headers the client sends by default; headers set per request, auth included, win
+    extra_headers: HeaderMap,
     /// Authentication mechanism (Bearer token, API key, etc.)
-    auth: Option<Box<dyn McpAuth>>,
+    ///
+    /// Behind an async mutex so a rejected token can be refreshed mid-request.
+    auth: Arc<tokio::sync::Mutex<Option<Box<dyn McpAuth>>>>,
     /// Current session ID from server
     session_id: Arc<RwLock<Option<String>>>,
+    /// Protocol version the server chose during initialize
+    protocol_version: Arc<RwLock<Option<String>>>,
     /// Connection status
     connected: Arc<RwLock<bool>>,
     /// Request timeout in milliseconds
     timeout_ms: u64,
+    /// Retries after a 429 Too Many Requests
+    max_retries: u32,
+    /// Retries after transient connection failures
+    retry_policy: RetryPolicy,
+    /// Resume attempts for an SSE response that drops mid-stream
+    max_resume_attempts: u32,
     /// Request ID counter for JSON-RPC
     request_id: AtomicU64,
     /// Server capabilities (cached after initialization)
     server_capabilities: Arc<RwLock<Option<ServerCapabilities>>>,
     /// Server info (cached after initialization)
     server_info: Arc<RwLock<Option<ServerInfo>>>,
+    /// Usage guidance the server sent with its initialize result
+    instructions: Arc<RwLock<Option<String>>>,
+    /// Subscribed resource URIs
+    subscriptions: Arc<RwLock<HashSet<String>>>,
+    /// Broadcasts notifications/resources/updated events
+    resource_updates: broadcast::Sender<ResourceUpdatedNotification>,
+    /// Broadcasts server notifications and connection state changes
+    notifications: broadcast::Sender<TransportEvent>,
+    /// Whether to open the GET notification stream after connecting
+    notification_stream: bool,
+    /// Task holding the GET notification stream open
+    notification_listener: parking_lot::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Set once the server rejects a batch, so later batches go out one by one
+    batch_rejected: AtomicBool,
+    /// First pages of the tools/resources/prompts lists, fetched while connecting
+    prefetched: RwLock<HashMap<String, serde_json::Value>>,
+    /// Prompt definitions from the last prompts/list, by name
+    prompts: RwLock<HashMap<String, Prompt>>,
+    /// Roots returned when the server sends roots/list
+    roots: Arc<RwLock<Vec<Root>>>,
+    /// Answers sampling/createMessage requests (None rejects them)
+    sampling: Arc<RwLock<Option<Arc<dyn SamplingHandler>>>>,
+    /// Held while replacing a session the server dropped, so concurrent requests start only one
+    session_renewal: tokio::sync::Mutex<()>,
+    /// Request/response trace, recorded only while enabled
+    trace: RwLock<Option<Arc<McpTrace>>>,
 }
 
 impl StreamableHttpTransport {
@@ -55,35 +148,304 @@ impl StreamableHttpTransport {
     ) -> McpResult<Self> {
         let endpoint_str = endpoint.into();
         let endpoint = Url::parse(&endpoint_str)?;
-
-        // Build HTTP client with appropriate settings
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_millis(timeout_ms))
-            .connect_timeout(std::time::Duration::from_secs(10))
-            .pool_max_idle_per_host(5)
-            .build()
-            .map_err(|e| McpError::TransportError(e.to_string()))?;
+        let client = build_client(timeout_ms, None, &HeaderMap::new())?;
 
         Ok(Self {
             client,
             endpoint,
-            auth,
+            tls: None,
+            extra_headers: HeaderMap::new(),
+            auth: Arc::new(tokio::sync::Mutex::new(auth)),
             session_id: Arc::new(RwLock::new(None)),
+            protocol_version: Arc::new(RwLock::new(None)),
             connected: Arc::new(RwLock::new(false)),
             timeout_ms,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_policy: RetryPolicy::default(),
+            max_resume_attempts: DEFAULT_MAX_RESUME_ATTEMPTS,
             request_id: AtomicU64::new(1),
             server_capabilities: Arc::new(RwLock::new(None)),
             server_info: Arc::new(RwLock::new(None)),
+            instructions: Arc::new(RwLock::new(None)),
+            subscriptions: Arc::new(RwLock::new(HashSet::new())),
+            resource_updates: broadcast::channel(NOTIFICATIONS_CAPACITY).0,
+            notifications: broadcast::channel(NOTIFICATIONS_CAPACITY).0,
+            notification_stream: true,
+            notification_listener: parking_lot::Mutex::new(None),
+            batch_rejected: AtomicBool::new(false),
+            prefetched: RwLock::new(HashMap::new()),
+            prompts: RwLock::new(HashMap::new()),
+            roots: Arc::new(RwLock::new(Vec::new())),
+            sampling: Arc::new(RwLock::new(None)),
+            session_renewal: tokio::sync::Mutex::new(()),
+            trace: RwLock::new(None),
         })
     }
 
+    /// Create a transport that resumes an existing server session
+    ///
+    /// Call `reconnect()` to re-initialize on the stored session.
+    pub fn with_session_id(
+        endpoint: impl Into<String>,
+        auth: Option<Box<dyn McpAuth>>,
+        timeout_ms: u64,
+        session_id: impl Into<String>,
+    ) -> McpResult<Self> {
+        let transport = Self::new(endpoint, auth, timeout_ms)?;
+        *transport.session_id.write() = Some(session_id.into());
+        Ok(transport)
+    }
+
+    /// Set how many times a rate-limited request is retried
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Trust a private CA, present a client certificate, or relax validation
+    ///
+    /// Fails if a certificate or key file can't be read or parsed.
+    pub fn with_tls_options(mut self, tls: &TlsOptions) -> McpResult<Self> {
+        self.client = build_client(self.timeout_ms, Some(tls), &self.extra_headers)?;
+        self.tls = Some(tls.clone());
+        Ok(self)
+    }
+
+    /// Send these headers with every request, e.g. a tenant ID for a gateway
+    ///
+    /// They never replace headers the transport or auth sets itself.
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> McpResult<Self> {
+        self.client = build_client(self.timeout_ms, self.tls.as_ref(), &headers)?;
+        self.extra_headers = headers;
+        Ok(self)
+    }
+
+    /// Set the backoff policy for transient connection failures
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set how many times a dropped SSE response is resumed
+    pub fn with_max_resume_attempts(mut self, max_resume_attempts: u32) -> Self {
+        self.max_resume_attempts = max_resume_attempts;
+        self
+    }
+
+    /// Enable or disable the GET notification stream opened after connecting
+    ///
+    /// Resource subscriptions open the stream regardless.
+    pub fn with_notification_stream(mut self, enabled: bool) -> Self {
+        self.notification_stream = enabled;
+        self
+    }
+
+    /// Receive server notifications and connection state changes
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<TransportEvent> {
+        self.notifications.subscribe()
+    }
+
+    /// Re-establish a dropped connection, keeping the current session ID
+    ///
+    /// Falls back to a fresh session only if the server answers 404 for the old ID.
+    pub async fn reconnect(&mut self) -> McpResult<()> {
+        let session_id = match self.session_id.read().clone() {
+            Some(sid) => sid,
+            None => return self.connect().await,
+        };
+
+        info!("Resuming MCP session {} at {}", session_id, self.endpoint);
+        *self.connected.write() = false;
+        self.refresh_auth_if_needed().await?;
+
+        // Cached server info and capabilities are kept until the server answers
+        match self.initialize(InitializeParams::default()).await {
+            Ok(result) => self.complete_handshake(result).await,
+            Err(McpError::SessionExpired) => {
+                warn!("MCP session {} expired, starting a new session", session_id);
+                *self.session_id.write() = None;
+                self.connect().await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cache the initialize result and send the initialized notification
+    async fn complete_handshake(&self, result: InitializeResult) -> McpResult<()> {
+        *self.server_info.write() = Some(result.server_info.clone());
+        *self.server_capabilities.write() = Some(result.capabilities.clone());
+        *self.instructions.write() = result.instructions.clone();
+
+        self.send_initialized().await?;
+
+        *self.connected.write() = true;
+        if self.notification_stream {
+            self.ensure_notification_listener();
+        }
+        let _ = self
+            .notifications
+            .send(TransportEvent::ConnectionStateChanged { connected: true });
+        info!(
+            "Connected to MCP server: {} (protocol: {})",
+            result.server_info.name, result.protocol_version
+        );
+
+        Ok(())
+    }
+
+    /// Fetch the lists the server advertises in one batch, saving a round trip per list
+    ///
+    /// Best effort: on failure the lists are fetched on first use instead.
+    async fn prefetch_lists(&self) {
+        if !self.batching_supported() {
+            return;
+        }
+        let capabilities = match self.server_capabilities() {
+            Some(capabilities) => capabilities,
+            None => return,
+        };
+
+        let methods = [
+            ("tools/list", capabilities.tools.is_some()),
+            ("resources/list", capabilities.resources.is_some()),
+            ("prompts/list", capabilities.prompts.is_some()),
+        ];
+        let requests: Vec<JsonRpcRequest> = methods
+            .iter()
+            .filter(|(_, advertised)| *advertised)
+            .map(|(method, _)| JsonRpcRequest::new(*method, None, self.next_request_id()))
+            .collect();
+        if requests.len() < 2 {
+            return;
+        }
+
+        let methods: Vec<String> = requests.iter().map(|r| r.method.clone()).collect();
+        match self.send_batch(requests).await {
+            Ok(responses) => {
+                let mut prefetched = self.prefetched.write();
+                for (method, response) in methods.into_iter().zip(responses) {
+                    if let Some(result) = response.result {
+                        prefetched.insert(method, result);
+                    }
+                }
+            }
+            Err(e) => debug!("Skipping MCP list prefetch: {}", e),
+        }
+    }
+
+    /// Get a prompt, first checking its required arguments against the last prompts/list
+    ///
+    /// Fails with `InvalidConfig` naming the missing arguments instead of sending a
+    /// request the server would reject. Prompts not seen in a listing are sent as-is.
+    pub async fn get_prompt_validated(
+        &self,
+        name: &str,
+        arguments: Option<HashMap<String, String>>,
+    ) -> McpResult<serde_json::Value> {
+        let missing: Vec<String> = match self.prompts.read().get(name) {
+            Some(prompt) => prompt
+                .missing_arguments(arguments.as_ref())
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            None => Vec::new(),
+        };
+        if !missing.is_empty() {
+            return Err(McpError::InvalidConfig(format!(
+                "Prompt {} is missing required arguments: {}",
+                name,
+                missing.join(", ")
+            )));
+        }
+
+        self.get_prompt(name, arguments).await
+    }
+
+    /// Remember a prompts/list page; the first page replaces what was cached
+    fn cache_prompts(&self, cursor: Option<&str>, page: &PromptsListResult) {
+        let mut prompts = self.prompts.write();
+        if cursor.is_none() {
+            prompts.clear();
+        }
+        for prompt in &page.prompts {
+            prompts.insert(prompt.name.clone(), prompt.clone());
+        }
+    }
+
+    /// Result prefetched while connecting, used once for the first page of a list
+    fn take_prefetched(&self, method: &str, cursor: Option<&str>) -> Option<serde_json::Value> {
+        match cursor {
+            Some(_) => None,
+            None => self.prefetched.write().remove(method),
+        }
+    }
+
+    /// Whether requests can be batched with the negotiated protocol and server
+    fn batching_supported(&self) -> bool {
+        // Protocol versions from 2025-06-18 dropped JSON-RPC batching
+        let protocol = self
+            .protocol_version()
+            .and_then(|v| ProtocolFeatures::for_version(&v))
+            .is_none_or(|features| features.batching);
+        protocol && !self.batch_rejected.load(Ordering::SeqCst)
+    }
+
+    /// Send requests one at a time, for servers that don't batch
+    async fn send_sequential(&self, requests: Vec<JsonRpcRequest>) -> McpResult<Vec<JsonRpcResponse>> {
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            responses.push(self.send_and_receive(request).await?);
+        }
+        Ok(responses)
+    }
+
+    /// POST a batch and return the responses in request order
+    async fn post_batch(&self, requests: &[JsonRpcRequest]) -> McpResult<Vec<JsonRpcResponse>> {
+        debug!("Sending MCP batch of {} requests", requests.len());
+        let idempotent = requests
+            .iter()
+            .all(|r| IDEMPOTENT_METHODS.contains(&r.method.as_str()));
+        let response = self.post_json(&serde_json::to_value(requests)?, idempotent, None).await?;
+
+        let status = response.status();
+        if status == StatusCode::BAD_REQUEST {
+            // Servers may reject the batch with an HTTP 400 carrying the JSON-RPC error
+            let body = response.text().await.unwrap_or_default();
+            return match serde_json::from_str::<JsonRpcResponse>(&body) {
+                Ok(JsonRpcResponse { error: Some(error), .. }) => Err(McpError::JsonRpcError {
+                    code: error.code,
+                    message: error.message,
+                }),
+                _ => Err(McpError::InvalidResponse(format!("Bad request: {}", body))),
+            };
+        }
+        if status != StatusCode::OK && status != StatusCode::ACCEPTED {
+            return Err(self.error_for_status(response).await);
+        }
+
+        let is_stream = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("text/event-stream"));
+
+        let responses = if is_stream {
+            let ids: Vec<serde_json::Value> = requests.iter().map(|r| r.id.clone()).collect();
+            self.read_sse_batch(response, &ids).await?
+        } else {
+            parse_batch_body(response.json().await?)?
+        };
+
+        demux_batch_responses(requests, responses)
+    }
+
     /// Get the next request ID
     fn next_request_id(&self) -> u64 {
         self.request_id.fetch_add(1, Ordering::SeqCst)
     }
 
     /// Build a request with proper headers
-    fn build_request(&self, body: &serde_json::Value) -> McpResult<reqwest::RequestBuilder> {
+    async fn build_request(&self, body: &serde_json::Value) -> McpResult<reqwest::RequestBuilder> {
         let mut request = self.client
             .post(self.endpoint.clone())
             .header("Content-Type", "application/json")
@@ -93,9 +455,12 @@ impl StreamableHttpTransport {
         if let Some(ref session_id) = *self.session_id.read() {
             request = request.header("Mcp-Session-Id", session_id.as_str());
         }
+        if let Some(version) = protocol_version_header(&self.protocol_version) {
+            request = request.header("MCP-Protocol-Version", version);
+        }
 
-        // Apply authentication
-        if let Some(ref auth) = self.auth {
+        // Per-server extra headers are client defaults, so auth always wins over them
+        if let Some(ref auth) = *self.auth.lock().await {
             request = auth.apply(request);
         }
 
@@ -103,17 +468,223 @@ impl StreamableHttpTransport {
         Ok(request)
     }
 
+    /// Refresh credentials after the server rejected them (returns true if refreshed)
+    async fn refresh_auth(&self) -> bool {
+        let mut guard = self.auth.lock().await;
+        let auth = match guard.as_mut() {
+            Some(auth) => auth,
+            None => return false,
+        };
+
+        match auth.refresh().await {
+            Ok(refreshed) => {
+                if refreshed {
+                    info!("Refreshed {} credentials for {}", auth.auth_type(), self.endpoint);
+                }
+                refreshed
+            }
+            Err(e) => {
+                warn!("Failed to refresh {} credentials: {}", auth.auth_type(), e);
+                false
+            }
+        }
+    }
+
+    /// Refresh credentials ahead of a request if they have expired or are about to
+    async fn refresh_auth_if_needed(&self) -> McpResult<()> {
+        let auth_valid = match *self.auth.lock().await {
+            Some(ref auth) => auth.is_valid().await,
+            None => true,
+        };
+        if !auth_valid && !self.refresh_auth().await {
+            return Err(McpError::TokenExpired);
+        }
+        Ok(())
+    }
+
     /// Send a request and handle the response
+    ///
+    /// If the server has dropped our session (404), a new session is started and
+    /// the request replayed once. initialize is never replayed; `reconnect` handles it.
     async fn send_and_receive(&self, request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
-        let body = serde_json::to_value(&request)?;
-        let http_request = self.build_request(&body)?;
+        let stale_session = self.session_id.read().clone();
+        match self.send_once(&request).await {
+            Err(McpError::SessionExpired) if request.method != "initialize" => {
+                if let Err(e) = self.renew_session(stale_session.as_deref()).await {
+                    self.mark_disconnected();
+                    return Err(e);
+                }
+                let result = self.send_once(&request).await;
+                if matches!(result, Err(McpError::SessionExpired)) {
+                    self.mark_disconnected();
+                }
+                result
+            }
+            result => result,
+        }
+    }
+
+    async fn send_once(&self, request: &JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+        // A single flag check when tracing is off
+        let trace = self.trace.read().clone().filter(|trace| trace.is_enabled());
+        let trace = match trace {
+            Some(trace) => trace,
+            None => {
+                let response = self.post_request(request, None).await?;
+                return self.handle_response(response, &request.id).await;
+            }
+        };
+
+        let request_headers = match self.build_request(&serde_json::to_value(request)?).await?.build() {
+            Ok(built) => redact_headers(built.headers()),
+            Err(_) => Default::default(),
+        };
+        let started_at = chrono::Utc::now();
+        let started = std::time::Instant::now();
+        let result = match self.post_request(request, None).await {
+            Ok(response) => self.handle_response(response, &request.id).await,
+            Err(e) => Err(e),
+        };
+
+        let (response_result, error) = match result {
+            Ok(ref response) => (response.result.clone(), None),
+            Err(ref e) => (None, Some(e.to_string())),
+        };
+        trace.record(TraceEntry {
+            method: request.method.clone(),
+            params: request.params.clone(),
+            request_headers,
+            result: response_result,
+            error,
+            latency_ms: started.elapsed().as_millis() as u64,
+            started_at: started_at.to_rfc3339(),
+            finished_at: chrono::Utc::now().to_rfc3339(),
+        });
+        result
+    }
+
+    /// Replace a session the server no longer recognizes, restoring resource subscriptions
+    ///
+    /// Does nothing if another request already replaced `stale_session`.
+    async fn renew_session(&self, stale_session: Option<&str>) -> McpResult<()> {
+        let _renewal = self.session_renewal.lock().await;
+        if self.session_id.read().as_deref() != stale_session && self.is_connected() {
+            return Ok(());
+        }
+
+        warn!(
+            "MCP session {} at {} is no longer valid, starting a new session",
+            stale_session.unwrap_or("(none)"),
+            self.endpoint
+        );
+        *self.session_id.write() = None;
+        *self.connected.write() = false;
+        self.prefetched.write().clear();
+
+        let result = self.initialize_session(InitializeParams::default()).await?;
+        self.complete_handshake(result).await?;
+
+        let uris: Vec<String> = self.subscriptions.read().iter().cloned().collect();
+        for uri in uris {
+            let request = JsonRpcRequest::new(
+                "resources/subscribe",
+                Some(serde_json::json!({ "uri": uri })),
+                self.next_request_id(),
+            );
+            if let Err(e) = self.send_once(&request).await {
+                warn!("Failed to restore subscription to {}: {}", uri, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Record that the server can't be reached on any session
+    fn mark_disconnected(&self) {
+        *self.connected.write() = false;
+        let _ = self
+            .notifications
+            .send(TransportEvent::ConnectionStateChanged { connected: false });
+    }
+
+    /// Send initialize and record the negotiated protocol version
+    async fn initialize_session(&self, params: InitializeParams) -> McpResult<InitializeResult> {
+        let request = JsonRpcRequest::new(
+            "initialize",
+            Some(serde_json::to_value(&params)?),
+            self.next_request_id(),
+        );
+
+        let response = self.send_and_receive(request).await?;
+
+        let result: InitializeResult = response
+            .result
+            .ok_or_else(|| McpError::InvalidResponse("Missing result in initialize response".to_string()))
+            .and_then(|v| serde_json::from_value(v).map_err(McpError::from))?;
+
+        // The server answers with our version or an older one it prefers;
+        // anything we don't know how to speak is fatal
+        if ProtocolFeatures::for_version(&result.protocol_version).is_none() {
+            return Err(McpError::ProtocolVersionMismatch {
+                expected: params.protocol_version,
+                actual: result.protocol_version,
+            });
+        }
+        if result.protocol_version != params.protocol_version {
+            info!(
+                "MCP server negotiated older protocol version {} (requested {})",
+                result.protocol_version, params.protocol_version
+            );
+        }
+        *self.protocol_version.write() = Some(result.protocol_version.clone());
 
+        Ok(result)
+    }
+
+    /// POST a request, retrying once after a credential refresh, and track the session ID
+    ///
+    /// `timeout` overrides the client's request timeout for this request only.
+    async fn post_request(&self, request: &JsonRpcRequest, timeout: Option<Duration>) -> McpResult<Response> {
         debug!("Sending MCP request: {} (id: {:?})", request.method, request.id);
+        let idempotent = IDEMPOTENT_METHODS.contains(&request.method.as_str());
+        self.post_json(&serde_json::to_value(request)?, idempotent, timeout).await
+    }
 
-        let response = http_request
-            .send()
-            .await
-            .map_err(McpError::from)?;
+    /// POST a JSON-RPC message or batch, handling auth refresh, rate limits, and session tracking
+    ///
+    /// Transient failures are retried per the retry policy; non-idempotent
+    /// requests are only retried if the connection was never established.
+    async fn post_json(
+        &self,
+        body: &serde_json::Value,
+        idempotent: bool,
+        timeout: Option<Duration>,
+    ) -> McpResult<Response> {
+        self.refresh_auth_if_needed().await?;
+
+        let mut attempt = 0;
+        let response = loop {
+            let mut response = self.send_with_retry(body, idempotent, timeout).await?;
+
+            // A 401 usually means the access token expired early; refresh once and retry
+            if response.status() == StatusCode::UNAUTHORIZED && self.refresh_auth().await {
+                response = self.send_with_retry(body, idempotent, timeout).await?;
+            }
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS || attempt >= self.max_retries {
+                break response;
+            }
+
+            let delay = retry_delay(response.headers(), attempt);
+            warn!(
+                "Rate limited by {}, retrying in {}ms ({}/{})",
+                self.endpoint,
+                delay.as_millis(),
+                attempt + 1,
+                self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
 
         // Check for session ID in response headers
         if let Some(session_id) = response.headers().get("Mcp-Session-Id") {
@@ -126,7 +697,7 @@ impl StreamableHttpTransport {
             }
         }
 
-        self.handle_response(response, &request.id).await
+        Ok(response)
     }
 
     /// Handle the HTTP response
@@ -163,35 +734,156 @@ impl StreamableHttpTransport {
                     }
                 }
             }
-            StatusCode::UNAUTHORIZED => {
-                Err(McpError::AuthenticationFailed("Unauthorized".to_string()))
+            _ => Err(self.error_for_status(response).await),
+        }
+    }
+
+    /// Send a POST, retrying transient failures with backoff
+    async fn send_with_retry(
+        &self,
+        body: &serde_json::Value,
+        idempotent: bool,
+        timeout: Option<Duration>,
+    ) -> McpResult<Response> {
+        let mut attempt = 1;
+        loop {
+            let mut request = self.build_request(body).await?;
+            if let Some(timeout) = timeout {
+                request = request.timeout(timeout);
+            }
+            let result = request.send().await;
+
+            // A failed connect means nothing reached the server, so any request may be resent.
+            // Certificate failures won't fix themselves and are reported straight away.
+            let retryable = match &result {
+                Ok(response) => idempotent && is_transient_status(response.status()),
+                Err(e) if is_certificate_error(e) => false,
+                Err(e) => e.is_connect() || (idempotent && (e.is_timeout() || e.is_request())),
+            };
+
+            if !retryable {
+                if attempt > 1 && result.is_ok() {
+                    debug!("MCP request to {} succeeded after {} attempts", self.endpoint, attempt);
+                }
+                return result.map_err(McpError::from);
+            }
+
+            if attempt >= self.retry_policy.max_attempts {
+                return match result {
+                    Err(e) if attempt > 1 => Err(McpError::ConnectionFailed(format!(
+                        "{} (gave up after {} attempts)",
+                        e, attempt
+                    ))),
+                    Ok(response) if attempt > 1 => Err(McpError::TransportError(format!(
+                        "HTTP {} (gave up after {} attempts)",
+                        response.status(),
+                        attempt
+                    ))),
+                    other => other.map_err(McpError::from),
+                };
+            }
+
+            let delay = self.retry_policy.backoff(attempt);
+            match &result {
+                Ok(response) => debug!(
+                    "MCP request to {} returned HTTP {}, retrying in {}ms ({}/{})",
+                    self.endpoint,
+                    response.status(),
+                    delay.as_millis(),
+                    attempt,
+                    self.retry_policy.max_attempts
+                ),
+                Err(e) => debug!(
+                    "MCP request to {} failed: {}, retrying in {}ms ({}/{})",
+                    self.endpoint,
+                    e,
+                    delay.as_millis(),
+                    attempt,
+                    self.retry_policy.max_attempts
+                ),
             }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Map a non-success HTTP response to an error
+    async fn error_for_status(&self, response: Response) -> McpError {
+        let status = response.status();
+        match status {
+            StatusCode::UNAUTHORIZED => McpError::AuthenticationFailed("Unauthorized".to_string()),
+            StatusCode::TOO_MANY_REQUESTS => McpError::RateLimited {
+                retry_after_ms: parse_retry_after(response.headers()).map(|d| d.as_millis() as u64),
+            },
             StatusCode::NOT_FOUND => {
-                Err(McpError::ConnectionFailed("Endpoint not found".to_string()))
+                // With an active session, 404 means the server dropped it and we must re-initialize
+                if self.session_id.read().is_some() {
+                    McpError::SessionExpired
+                } else {
+                    McpError::ConnectionFailed("Endpoint not found".to_string())
+                }
             }
             StatusCode::BAD_REQUEST => {
                 let error_text = response.text().await.unwrap_or_default();
-                Err(McpError::InvalidResponse(format!("Bad request: {}", error_text)))
+                McpError::InvalidResponse(format!("Bad request: {}", error_text))
             }
             _ => {
                 let error_text = response.text().await.unwrap_or_default();
-                Err(McpError::TransportError(format!(
-                    "HTTP {}: {}",
-                    status, error_text
-                )))
+                McpError::TransportError(format!("HTTP {}: {}", status, error_text))
             }
         }
     }
 
     /// Handle Server-Sent Events (SSE) streaming response
+    ///
+    /// If the stream drops before the response arrives, it is resumed from the
+    /// last event ID up to `max_resume_attempts` times.
     async fn handle_sse_response(
         &self,
         response: Response,
         request_id: &serde_json::Value,
     ) -> McpResult<JsonRpcResponse> {
+        let mut response = response;
+        let mut last_event_id: Option<String> = None;
+        let mut resumes = 0;
+
+        loop {
+            let outcome = match self.read_sse_stream(response, request_id, &mut last_event_id).await {
+                Ok(Some(result)) => return Ok(result),
+                other => other,
+            };
+
+            // Resuming needs an event ID to continue from
+            let resume_from = match last_event_id {
+                Some(ref id) if resumes < self.max_resume_attempts => id.clone(),
+                _ => {
+                    return match outcome {
+                        Err(e) => Err(e),
+                        _ => Err(McpError::InvalidResponse("No result in SSE stream".to_string())),
+                    }
+                }
+            };
+
+            resumes += 1;
+            warn!(
+                "SSE stream for request {} ended early, resuming after event {} ({}/{})",
+                request_id, resume_from, resumes, self.max_resume_attempts
+            );
+            response = self.resume_sse_stream(&resume_from).await?;
+        }
+    }
+
+    /// Read an SSE stream until the response for `request_id` arrives
+    ///
+    /// Returns None if the stream ends first; `last_event_id` tracks progress for resuming.
+    async fn read_sse_stream(
+        &self,
+        response: Response,
+        request_id: &serde_json::Value,
+        last_event_id: &mut Option<String>,
+    ) -> McpResult<Option<JsonRpcResponse>> {
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
-        let mut result: Option<JsonRpcResponse> = None;
 
         while let Some(chunk) = stream.next().await {
             let chunk = chunk.map_err(|e| McpError::TransportError(e.to_string()))?;
@@ -204,44 +896,662 @@ impl StreamableHttpTransport {
                 buffer = buffer[event_end + 2..].to_string();
 
                 if let Some(sse_event) = self.parse_sse_event(&event_str) {
+                    if sse_event.id.is_some() {
+                        *last_event_id = sse_event.id.clone();
+                    }
+
+                    // Servers may interleave notifications and their own requests with the response
+                    if dispatch_notification(&sse_event, &self.notifications, &self.resource_updates) {
+                        continue;
+                    }
+                    if answer_server_request(&sse_event.data, &self.roots, &self.sampling, &self.reply_target()) {
+                        continue;
+                    }
+
                     // Try to parse as JSON-RPC response
                     if let Ok(response) = serde_json::from_str::<JsonRpcResponse>(&sse_event.data) {
                         if response.id == *request_id {
-                            result = Some(response);
+                            return Ok(Some(response));
                         }
                     }
                 }
             }
         }
 
-        result.ok_or_else(|| McpError::InvalidResponse("No result in SSE stream".to_string()))
+        Ok(None)
+    }
+
+    /// Read an SSE stream until a response has arrived for every request in a batch
+    async fn read_sse_batch(
+        &self,
+        response: Response,
+        request_ids: &[serde_json::Value],
+    ) -> McpResult<Vec<JsonRpcResponse>> {
+        let mut stream = response.bytes_stream();
+        let mut buffer = String::new();
+        let mut responses = Vec::with_capacity(request_ids.len());
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| McpError::TransportError(e.to_string()))?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(event_end) = buffer.find("\n\n") {
+                let event_str = buffer[..event_end].to_string();
+                buffer = buffer[event_end + 2..].to_string();
+
+                let sse_event = match self.parse_sse_event(&event_str) {
+                    Some(sse_event) => sse_event,
+                    None => continue,
+                };
+                if dispatch_notification(&sse_event, &self.notifications, &self.resource_updates) {
+                    continue;
+                }
+
+                // Each event may carry one response or a slice of the batch
+                let body = match serde_json::from_str(&sse_event.data) {
+                    Ok(body) => body,
+                    Err(_) => continue,
+                };
+                if let Ok(batch) = parse_batch_body(body) {
+                    responses.extend(batch.into_iter().filter(|r| request_ids.contains(&r.id)));
+                }
+                if responses.len() >= request_ids.len() {
+                    return Ok(responses);
+                }
+            }
+        }
+
+        Ok(responses)
+    }
+
+    /// Re-open a dropped SSE stream after the given event ID
+    async fn resume_sse_stream(&self, last_event_id: &str) -> McpResult<Response> {
+        let mut request = self
+            .client
+            .get(self.endpoint.clone())
+            .header("Accept", "text/event-stream")
+            .header("Last-Event-ID", last_event_id);
+
+        let session_id = self.session_id.read().clone();
+        if let Some(sid) = session_id {
+            request = request.header("Mcp-Session-Id", sid);
+        }
+        if let Some(version) = protocol_version_header(&self.protocol_version) {
+            request = request.header("MCP-Protocol-Version", version);
+        }
+        if let Some(ref auth) = *self.auth.lock().await {
+            request = auth.apply(request);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(McpError::TransportError(format!(
+                "Failed to resume SSE stream: HTTP {}",
+                response.status()
+            )));
+        }
+        Ok(response)
+    }
+
+    /// End a session with an HTTP DELETE, as the Streamable HTTP spec asks of clients
+    ///
+    /// Failures are logged rather than returned; the local session is dropped either way.
+    async fn terminate_session(&self, session_id: &str) {
+        let mut request = self
+            .client
+            .delete(self.endpoint.clone())
+            .header("Mcp-Session-Id", session_id);
+        if let Some(version) = protocol_version_header(&self.protocol_version) {
+            request = request.header("MCP-Protocol-Version", version);
+        }
+        if let Some(ref auth) = *self.auth.lock().await {
+            request = auth.apply(request);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Terminated MCP session {}", session_id);
+            }
+            Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+                // Server doesn't support explicit termination
+                debug!("Server does not allow terminating MCP session {}", session_id);
+            }
+            Ok(response) => {
+                warn!(
+                    "Failed to terminate MCP session {}: HTTP {}",
+                    session_id,
+                    response.status()
+                );
+            }
+            Err(e) => warn!("Failed to terminate MCP session {}: {}", session_id, e),
+        }
+    }
+
+    /// Where replies to server requests are sent
+    fn reply_target(&self) -> ReplyTarget {
+        ReplyTarget {
+            client: self.client.clone(),
+            endpoint: self.endpoint.clone(),
+            session_id: self.session_id.clone(),
+            protocol_version: self.protocol_version.clone(),
+            auth: self.auth.clone(),
+        }
     }
 
     /// Parse an SSE event from text
     fn parse_sse_event(&self, text: &str) -> Option<SseEvent> {
-        let mut event = None;
-        let mut data = String::new();
-        let mut id = None;
-
-        for line in text.lines() {
-            if line.starts_with("event:") {
-                event = Some(line[6..].trim().to_string());
-            } else if line.starts_with("data:") {
-                if !data.is_empty() {
-                    data.push('\n');
+        parse_sse_event(text)
+    }
+
+    /// Start the notification stream listener if it isn't running
+    fn ensure_notification_listener(&self) {
+        let mut listener = self.notification_listener.lock();
+        if listener.as_ref().is_some_and(|handle| !handle.is_finished()) {
+            return;
+        }
+
+        let client = self.client.clone();
+        let endpoint = self.endpoint.clone();
+        let auth = self.auth.clone();
+        let session_id = self.session_id.clone();
+        let protocol_version = self.protocol_version.clone();
+        let notifications = self.notifications.clone();
+        let resource_updates = self.resource_updates.clone();
+        let roots = self.roots.clone();
+        let sampling = self.sampling.clone();
+        let reply_target = self.reply_target();
+
+        *listener = Some(tokio::spawn(async move {
+            let mut last_event_id: Option<String> = None;
+
+            // The client timeout ends each GET stream, so re-open it until stopped,
+            // resuming after the last event we saw
+            loop {
+                let sid = session_id.read().clone();
+                let mut request = client
+                    .get(endpoint.clone())
+                    .header("Accept", "text/event-stream");
+                if let Some(sid) = sid {
+                    request = request.header("Mcp-Session-Id", sid);
+                }
+                if let Some(version) = protocol_version_header(&protocol_version) {
+                    request = request.header("MCP-Protocol-Version", version);
+                }
+                if let Some(ref id) = last_event_id {
+                    request = request.header("Last-Event-ID", id.as_str());
                 }
-                data.push_str(line[5..].trim());
-            } else if line.starts_with("id:") {
-                id = Some(line[3..].trim().to_string());
+                if let Some(ref auth) = *auth.lock().await {
+                    request = auth.apply(request);
+                }
+
+                match request.send().await {
+                    Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+                        info!("MCP server at {} does not offer a notification stream", endpoint);
+                        return;
+                    }
+                    Ok(response) if response.status() == StatusCode::NOT_FOUND => {
+                        debug!("MCP notification stream session ended at {}", endpoint);
+                        return;
+                    }
+                    Ok(response) if response.status().is_success() => {
+                        let mut stream = response.bytes_stream();
+                        let mut buffer = String::new();
+                        while let Some(Ok(chunk)) = stream.next().await {
+                            buffer.push_str(&String::from_utf8_lossy(&chunk));
+                            while let Some(event_end) = buffer.find("\n\n") {
+                                let event_str = buffer[..event_end].to_string();
+                                buffer = buffer[event_end + 2..].to_string();
+                                if let Some(event) = parse_sse_event(&event_str) {
+                                    if event.id.is_some() {
+                                        last_event_id = event.id.clone();
+                                    }
+                                    if dispatch_notification(&event, &notifications, &resource_updates) {
+                                        continue;
+                                    }
+                                    answer_server_request(&event.data, &roots, &sampling, &reply_target);
+                                }
+                            }
+                        }
+                    }
+                    Ok(response) => {
+                        debug!("MCP notification stream returned HTTP {}", response.status());
+                    }
+                    Err(e) => {
+                        debug!("MCP notification stream failed: {}", e);
+                    }
+                }
+
+                tokio::time::sleep(Duration::from_secs(NOTIFICATION_STREAM_RETRY_SECS)).await;
             }
+        }));
+    }
+
+    /// Stop the notification stream listener
+    fn stop_notification_listener(&self) {
+        if let Some(handle) = self.notification_listener.lock().take() {
+            handle.abort();
         }
+    }
+}
 
-        if data.is_empty() {
-            None
-        } else {
-            Some(SseEvent { event, data, id })
+impl Drop for StreamableHttpTransport {
+    fn drop(&mut self) {
+        self.stop_notification_listener();
+    }
+}
+
+/// Wait requested by a Retry-After header, given in seconds or as an HTTP date
+///
+/// A date in the past means no wait.
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    value.parse::<u64>().ok().map(Duration::from_secs).or_else(|| {
+        chrono::DateTime::parse_from_rfc2822(value).ok().map(|at| {
+            (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+        })
+    })
+}
+
+/// Time to wait before retrying a rate-limited request
+///
+/// Honors Retry-After, otherwise backs off exponentially.
+fn retry_delay(headers: &reqwest::header::HeaderMap, attempt: u32) -> Duration {
+    parse_retry_after(headers)
+        .unwrap_or_else(|| Duration::from_millis(RETRY_BASE_DELAY_MS << attempt.min(16)))
+        .min(Duration::from_secs(MAX_RETRY_DELAY_SECS))
+}
+
+/// Parse an SSE event from text
+fn parse_sse_event(text: &str) -> Option<SseEvent> {
+    let mut event = None;
+    let mut data = String::new();
+    let mut id = None;
+
+    for line in text.lines() {
+        if line.starts_with("event:") {
+            event = Some(line[6..].trim().to_string());
+        } else if line.starts_with("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(line[5..].trim());
+        } else if line.starts_with("id:") {
+            id = Some(line[3..].trim().to_string());
+        }
+    }
+
+    if data.is_empty() {
+        None
+    } else {
+        Some(SseEvent { event, data, id })
+    }
+}
+
+/// Interpret an SSE event received while streaming a tool call
+///
+/// Returns None for events unrelated to the call.
+fn tool_call_event_from_sse(
+    event: &SseEvent,
+    request_id: &serde_json::Value,
+) -> Option<McpResult<ToolCallEvent>> {
+    let message: serde_json::Value = serde_json::from_str(&event.data).ok()?;
+
+    match message.get("method").and_then(|m| m.as_str()) {
+        Some("notifications/progress") => {
+            let progress: ProgressNotification =
+                serde_json::from_value(message.get("params")?.clone()).ok()?;
+            (progress.progress_token == *request_id).then(|| Ok(ToolCallEvent::Progress(progress)))
+        }
+        Some("notifications/message") => {
+            let params = message.get("params")?;
+            Some(Ok(ToolCallEvent::Log {
+                level: params.get("level")?.as_str()?.to_string(),
+                data: params.get("data").cloned().unwrap_or(serde_json::Value::Null),
+            }))
+        }
+        Some(_) => None,
+        None => {
+            let response: JsonRpcResponse = serde_json::from_value(message).ok()?;
+            if response.id != *request_id {
+                return None;
+            }
+            if let Some(error) = response.error {
+                return Some(Err(McpError::JsonRpcError {
+                    code: error.code,
+                    message: error.message,
+                }));
+            }
+            Some(
+                response
+                    .result
+                    .ok_or_else(|| McpError::InvalidResponse("Missing result".to_string()))
+                    .and_then(|v| serde_json::from_value(v).map_err(McpError::from))
+                    .map(ToolCallEvent::Complete),
+            )
+        }
+    }
+}
+
+/// Convert a JSON-RPC notification in an SSE event into a transport event
+///
+/// Returns None for responses and requests.
+pub(super) fn transport_event_from_sse(event: &SseEvent) -> Option<TransportEvent> {
+    let message: serde_json::Value = serde_json::from_str(&event.data).ok()?;
+    if message.get("id").is_some() {
+        return None;
+    }
+    let method = message.get("method")?.as_str()?;
+    let params = message.get("params").cloned();
+
+    let transport_event = match method {
+        "notifications/progress" => match params
+            .clone()
+            .and_then(|p| serde_json::from_value::<ProgressNotification>(p).ok())
+        {
+            Some(progress) => TransportEvent::Progress(progress),
+            None => TransportEvent::Notification {
+                method: method.to_string(),
+                params,
+            },
+        },
+        "notifications/message" => {
+            let params = params.unwrap_or(serde_json::Value::Null);
+            let message = match params.get("data") {
+                Some(serde_json::Value::String(text)) => text.clone(),
+                Some(data) => data.to_string(),
+                None => String::new(),
+            };
+            TransportEvent::Log {
+                level: params
+                    .get("level")
+                    .and_then(|l| l.as_str())
+                    .unwrap_or("info")
+                    .to_string(),
+                logger: params.get("logger").and_then(|l| l.as_str()).map(String::from),
+                message,
+            }
         }
+        "notifications/tools/list_changed"
+        | "notifications/resources/list_changed"
+        | "notifications/prompts/list_changed" => TransportEvent::CapabilitiesChanged,
+        _ => TransportEvent::Notification {
+            method: method.to_string(),
+            params,
+        },
+    };
+
+    Some(transport_event)
+}
+
+/// Route a server notification to the notification and resource update channels
+///
+/// Returns false if the event is not a notification.
+pub(super) fn dispatch_notification(
+    event: &SseEvent,
+    notifications: &broadcast::Sender<TransportEvent>,
+    resource_updates: &broadcast::Sender<ResourceUpdatedNotification>,
+) -> bool {
+    let transport_event = match transport_event_from_sse(event) {
+        Some(transport_event) => transport_event,
+        None => return false,
+    };
+
+    if let Some(update) = resource_update_from_event(event) {
+        let _ = resource_updates.send(update);
     }
+    let _ = notifications.send(transport_event);
+    true
+}
+
+/// Build the reply to a request the server sent, or None if the message isn't a request
+///
+/// roots/list and ping are answered; anything else gets "Method not found".
+pub(super) fn server_request_reply(data: &str, roots: &[Root]) -> Option<serde_json::Value> {
+    let message: serde_json::Value = serde_json::from_str(data).ok()?;
+    let id = message.get("id").filter(|id| !id.is_null())?.clone();
+    let method = message.get("method")?.as_str()?;
+
+    let reply = match method {
+        "roots/list" => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": { "roots": roots } }),
+        "ping" => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+        _ => {
+            debug!("Rejecting unsupported server request {}", method);
+            error_reply(id, METHOD_NOT_FOUND, format!("Method not found: {}", method))
+        }
+    };
+    Some(reply)
+}
+
+/// Split a sampling/createMessage request into its id and params
+pub(super) fn sampling_request(data: &str) -> Option<(serde_json::Value, serde_json::Value)> {
+    let mut message: serde_json::Value = serde_json::from_str(data).ok()?;
+    if message.get("method")?.as_str()? != "sampling/createMessage" {
+        return None;
+    }
+    let id = message.get("id").filter(|id| !id.is_null())?.clone();
+    let params = message.get_mut("params").map(serde_json::Value::take).unwrap_or_default();
+    Some((id, params))
+}
+
+/// Run a sampling request through the handler and build the reply
+///
+/// The handler gets SAMPLING_TIMEOUT in total, so a request left waiting for
+/// approval or a hung model call still gets answered.
+pub(super) async fn sampling_reply(
+    handler: Arc<dyn SamplingHandler>,
+    id: serde_json::Value,
+    params: serde_json::Value,
+) -> serde_json::Value {
+    let params: CreateMessageParams = match serde_json::from_value(params) {
+        Ok(params) => params,
+        Err(e) => return error_reply(id, INVALID_PARAMS, format!("Invalid sampling request: {}", e)),
+    };
+
+    match tokio::time::timeout(SAMPLING_TIMEOUT, handler.create_message(params)).await {
+        Ok(Ok(result)) => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Ok(Err(McpError::Cancelled)) => error_reply(id, USER_REJECTED, "User rejected sampling request".to_string()),
+        Ok(Err(e)) => error_reply(id, INTERNAL_ERROR, e.to_string()),
+        Err(_) => {
+            warn!("Sampling request timed out after {}s", SAMPLING_TIMEOUT.as_secs());
+            error_reply(id, INTERNAL_ERROR, "Sampling request timed out".to_string())
+        }
+    }
+}
+
+fn error_reply(id: serde_json::Value, code: i32, message: String) -> serde_json::Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Reply to a request the server sent, returning false if the message isn't a request
+///
+/// Replies go out from a spawned task so a slow sampling request doesn't hold up the stream.
+fn answer_server_request(
+    data: &str,
+    roots: &RwLock<Vec<Root>>,
+    sampling: &RwLock<Option<Arc<dyn SamplingHandler>>>,
+    target: &ReplyTarget,
+) -> bool {
+    let handler = sampling.read().clone();
+    if let Some(handler) = handler {
+        if let Some((id, params)) = sampling_request(data) {
+            let target = target.clone();
+            tokio::spawn(async move {
+                let reply = sampling_reply(handler, id, params).await;
+                target.post(&reply).await;
+            });
+            return true;
+        }
+    }
+
+    let reply = server_request_reply(data, &roots.read());
+    match reply {
+        Some(reply) => {
+            let target = target.clone();
+            tokio::spawn(async move { target.post(&reply).await });
+            true
+        }
+        None => false,
+    }
+}
+
+/// Session a reply to a server request is POSTed on
+#[derive(Clone)]
+struct ReplyTarget {
+    client: Client,
+    endpoint: Url,
+    session_id: Arc<RwLock<Option<String>>>,
+    protocol_version: Arc<RwLock<Option<String>>>,
+    auth: Arc<tokio::sync::Mutex<Option<Box<dyn McpAuth>>>>,
+}
+
+impl ReplyTarget {
+    /// POST a reply to a server request on the session it arrived on
+    ///
+    /// Failures are logged; the server times the request out on its side.
+    async fn post(&self, reply: &serde_json::Value) {
+        let mut request = self
+            .client
+            .post(self.endpoint.clone())
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json, text/event-stream");
+        let sid = self.session_id.read().clone();
+        if let Some(sid) = sid {
+            request = request.header("Mcp-Session-Id", sid);
+        }
+        if let Some(version) = protocol_version_header(&self.protocol_version) {
+            request = request.header("MCP-Protocol-Version", version);
+        }
+        if let Some(ref auth) = *self.auth.lock().await {
+            request = auth.apply(request);
+        }
+
+        match request.json(reply).send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => warn!("MCP server rejected our reply with HTTP {}", response.status()),
+            Err(e) => warn!("Failed to send reply to MCP server: {}", e),
+        }
+    }
+}
+
+/// Extract a resources/updated notification from an SSE event
+fn resource_update_from_event(event: &SseEvent) -> Option<ResourceUpdatedNotification> {
+    let message: serde_json::Value = serde_json::from_str(&event.data).ok()?;
+    if message.get("method")?.as_str()? != "notifications/resources/updated" {
+        return None;
+    }
+    serde_json::from_value(message.get("params")?.clone()).ok()
+}
+
+/// Build the HTTP client, applying the app proxy and TLS options if given
+/// Build the transport's client; `default_headers` go out only where a request doesn't set the same header
+fn build_client(timeout_ms: u64, tls: Option<&TlsOptions>, default_headers: &HeaderMap) -> McpResult<Client> {
+    let mut builder = crate::http::client_builder(
+        std::time::Duration::from_millis(timeout_ms),
+        &crate::http::proxy_settings(),
+    )
+    .connect_timeout(std::time::Duration::from_secs(10))
+    .pool_max_idle_per_host(5)
+    .default_headers(default_headers.clone());
+
+    if let Some(tls) = tls {
+        let read = |path: &str| {
+            std::fs::read(path)
+                .map_err(|e| McpError::InvalidConfig(format!("Failed to read {}: {}", path, e)))
+        };
+
+        if let Some(ref ca_path) = tls.ca_cert_path {
+            let cert = reqwest::Certificate::from_pem(&read(ca_path)?)
+                .map_err(|e| McpError::InvalidConfig(format!("Invalid CA certificate {}: {}", ca_path, e)))?;
+            builder = builder.add_root_certificate(cert);
+        }
+
+        match (&tls.client_cert_path, &tls.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                let identity = reqwest::Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?)
+                    .map_err(|e| McpError::InvalidConfig(format!("Invalid client certificate: {}", e)))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(McpError::InvalidConfig(
+                    "Client certificate and key must be provided together".to_string(),
+                ))
+            }
+        }
+
+        if tls.accept_invalid_certs {
+            warn!("TLS certificate validation is disabled for this MCP server");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| McpError::TransportError(e.to_string()))
+}
+
+/// MCP-Protocol-Version header value, if the negotiated version uses one
+fn protocol_version_header(protocol_version: &RwLock<Option<String>>) -> Option<String> {
+    protocol_version
+        .read()
+        .clone()
+        .filter(|v| ProtocolFeatures::for_version(v).is_some_and(|f| f.version_header))
+}
+
+/// Gateway errors that usually clear up on their own
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Parse a batch response body, accepting a bare object from servers that don't reply with an array
+fn parse_batch_body(body: serde_json::Value) -> McpResult<Vec<JsonRpcResponse>> {
+    match body {
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .map(|item| serde_json::from_value(item).map_err(McpError::from))
+            .collect(),
+        serde_json::Value::Object(_) => Ok(vec![serde_json::from_value(body)?]),
+        other => Err(McpError::InvalidResponse(format!(
+            "Expected a JSON-RPC batch response, got {}",
+            other
+        ))),
+    }
+}
+
+/// Order batch responses to match their requests
+///
+/// A lone error response with a null id means the server rejected the whole batch.
+fn demux_batch_responses(
+    requests: &[JsonRpcRequest],
+    mut responses: Vec<JsonRpcResponse>,
+) -> McpResult<Vec<JsonRpcResponse>> {
+    if let [JsonRpcResponse { error: Some(error), id: serde_json::Value::Null, .. }] = responses.as_slice() {
+        return Err(McpError::JsonRpcError {
+            code: error.code,
+            message: error.message.clone(),
+        });
+    }
+
+    requests
+        .iter()
+        .map(|request| {
+            let index = responses
+                .iter()
+                .position(|response| response.id == request.id)
+                .ok_or_else(|| {
+                    McpError::InvalidResponse(format!(
+                        "No response for batch request {} (id: {})",
+                        request.method, request.id
+                    ))
+                })?;
+            Ok(responses.swap_remove(index))
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -249,30 +1559,17 @@ impl McpTransport for StreamableHttpTransport {
     async fn connect(&mut self) -> McpResult<()> {
         info!("Connecting to MCP server at {}", self.endpoint);
 
-        // Validate authentication
-        if let Some(ref auth) = self.auth {
-            if !auth.is_valid().await {
-                return Err(McpError::TokenExpired);
-            }
-        }
+        // Validate authentication, refreshing expired credentials where supported
+        self.refresh_auth_if_needed().await?;
 
         // Initialize the session
         let params = InitializeParams::default();
         let result = self.initialize(params).await?;
 
-        // Store server info
-        *self.server_info.write() = Some(result.server_info.clone());
-        *self.server_capabilities.write() = Some(result.capabilities.clone());
-
-        // Send initialized notification
-        self.send_initialized().await?;
-
-        *self.connected.write() = true;
-        info!(
-            "Connected to MCP server: {} (protocol: {})",
-            result.server_info.name, result.protocol_version
-        );
+        // Store server info and send initialized notification
+        self.complete_handshake(result).await?;
 
+        self.prefetch_lists().await;
         Ok(())
     }
 
@@ -283,11 +1580,26 @@ impl McpTransport for StreamableHttpTransport {
 
         info!("Disconnecting from MCP server");
 
+        // Ask the server to end the session so it doesn't linger until it expires
+        self.stop_notification_listener();
+        let session_id = self.session_id.read().clone();
+        if let Some(sid) = session_id {
+            self.terminate_session(&sid).await;
+        }
+
         // Clear session state
+        self.subscriptions.write().clear();
         *self.session_id.write() = None;
+        *self.protocol_version.write() = None;
         *self.connected.write() = false;
         *self.server_capabilities.write() = None;
         *self.server_info.write() = None;
+        *self.instructions.write() = None;
+        self.prefetched.write().clear();
+
+        let _ = self
+            .notifications
+            .send(TransportEvent::ConnectionStateChanged { connected: false });
 
         Ok(())
     }
@@ -296,35 +1608,44 @@ impl McpTransport for StreamableHttpTransport {
         *self.connected.read()
     }
 
-    fn session_id(&self) -> Option<&str> {
-        // This is a bit awkward due to the RwLock, but we need it for the trait
-        // In practice, callers should use the Arc<RwLock<Option<String>>> directly
-        None
+    fn session_id(&self) -> Option<String> {
+        self.session_id.read().clone()
     }
 
-    async fn initialize(&mut self, params: InitializeParams) -> McpResult<InitializeResult> {
-        let request = JsonRpcRequest::new(
-            "initialize",
-            Some(serde_json::to_value(&params)?),
-            self.next_request_id(),
-        );
+    fn protocol_version(&self) -> Option<String> {
+        self.protocol_version.read().clone()
+    }
 
-        let response = self.send_and_receive(request).await?;
+    fn server_capabilities(&self) -> Option<ServerCapabilities> {
+        self.server_capabilities.read().clone()
+    }
 
-        let result: InitializeResult = response
-            .result
-            .ok_or_else(|| McpError::InvalidResponse("Missing result in initialize response".to_string()))
-            .and_then(|v| serde_json::from_value(v).map_err(McpError::from))?;
+    fn server_info(&self) -> Option<ServerInfo> {
+        self.server_info.read().clone()
+    }
 
-        // Validate protocol version
-        if result.protocol_version != MCP_PROTOCOL_VERSION {
-            warn!(
-                "Protocol version mismatch: expected {}, got {}",
-                MCP_PROTOCOL_VERSION, result.protocol_version
-            );
-        }
+    fn server_instructions(&self) -> Option<String> {
+        self.instructions.read().clone()
+    }
 
-        Ok(result)
+    fn notifications(&self) -> Option<broadcast::Receiver<TransportEvent>> {
+        Some(self.subscribe_notifications())
+    }
+
+    fn set_roots(&self, roots: Vec<Root>) {
+        *self.roots.write() = roots;
+    }
+
+    fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>) {
+        *self.sampling.write() = Some(handler);
+    }
+
+    fn set_trace(&self, trace: Arc<McpTrace>) {
+        *self.trace.write() = Some(trace);
+    }
+
+    async fn initialize(&mut self, params: InitializeParams) -> McpResult<InitializeResult> {
+        self.initialize_session(params).await
     }
 
     async fn send_initialized(&self) -> McpResult<()> {
@@ -336,6 +1657,10 @@ impl McpTransport for StreamableHttpTransport {
             return Err(McpError::NotConnected);
         }
 
+        if let Some(result) = self.take_prefetched("tools/list", cursor) {
+            return serde_json::from_value(result).map_err(McpError::from);
+        }
+
         let params = if let Some(c) = cursor {
             Some(serde_json::json!({ "cursor": c }))
         } else {
@@ -375,11 +1700,110 @@ impl McpTransport for StreamableHttpTransport {
             .and_then(|v| serde_json::from_value(v).map_err(McpError::from))
     }
 
+    async fn call_tool_streaming(
+        &self,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+        request_id: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> McpResult<mpsc::Receiver<McpResult<ToolCallEvent>>> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+
+        // The request ID doubles as the progress token so notifications can be matched
+        let id = request_id.unwrap_or_else(|| self.next_request_id().into());
+        let params = serde_json::json!({
+            "name": name,
+            "arguments": arguments,
+            "_meta": { "progressToken": id }
+        });
+        let request = JsonRpcRequest::new("tools/call", Some(params), id);
+
+        let response = self.post_request(&request, timeout).await?;
+        let is_stream = response.status() == StatusCode::OK
+            && response
+                .headers()
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|ct| ct.contains("text/event-stream"));
+
+        let (tx, rx) = mpsc::channel(TOOL_STREAM_CAPACITY);
+
+        // Plain JSON responses complete immediately
+        if !is_stream {
+            let result = self
+                .handle_response(response, &request.id)
+                .await
+                .and_then(|r| {
+                    r.result
+                        .ok_or_else(|| McpError::InvalidResponse("Missing result".to_string()))
+                })
+                .and_then(|v| serde_json::from_value(v).map_err(McpError::from))
+                .map(ToolCallEvent::Complete);
+            let _ = tx.send(result).await;
+            return Ok(rx);
+        }
+
+        let request_id = request.id;
+        let notifications = self.notifications.clone();
+        let resource_updates = self.resource_updates.clone();
+        tokio::spawn(async move {
+            let mut stream = response.bytes_stream();
+            let mut buffer = String::new();
+
+            while let Some(chunk) = stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = tx.send(Err(McpError::TransportError(e.to_string()))).await;
+                        return;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+                while let Some(event_end) = buffer.find("\n\n") {
+                    let event_str = buffer[..event_end].to_string();
+                    buffer = buffer[event_end + 2..].to_string();
+
+                    let sse_event = match parse_sse_event(&event_str) {
+                        Some(event) => event,
+                        None => continue,
+                    };
+                    if let Some(item) = tool_call_event_from_sse(&sse_event, &request_id) {
+                        // Log messages also go to the server log, not just the caller
+                        if matches!(item, Ok(ToolCallEvent::Log { .. })) {
+                            dispatch_notification(&sse_event, &notifications, &resource_updates);
+                        }
+                        let done = !matches!(item, Ok(ToolCallEvent::Progress(_)) | Ok(ToolCallEvent::Log { .. }));
+                        // Stop reading if the caller dropped the receiver
+                        if tx.send(item).await.is_err() || done {
+                            return;
+                        }
+                        continue;
+                    }
+
+                    dispatch_notification(&sse_event, &notifications, &resource_updates);
+                }
+            }
+
+            let _ = tx
+                .send(Err(McpError::InvalidResponse("No result in SSE stream".to_string())))
+                .await;
+        });
+
+        Ok(rx)
+    }
+
     async fn list_resources(&self, cursor: Option<&str>) -> McpResult<ResourcesListResult> {
         if !self.is_connected() {
             return Err(McpError::NotConnected);
         }
 
+        if let Some(result) = self.take_prefetched("resources/list", cursor) {
+            return serde_json::from_value(result).map_err(McpError::from);
+        }
+
         let params = if let Some(c) = cursor {
             Some(serde_json::json!({ "cursor": c }))
         } else {
@@ -418,19 +1842,21 @@ impl McpTransport for StreamableHttpTransport {
             return Err(McpError::NotConnected);
         }
 
-        let params = if let Some(c) = cursor {
-            Some(serde_json::json!({ "cursor": c }))
-        } else {
-            None
+        let result = match self.take_prefetched("prompts/list", cursor) {
+            Some(result) => result,
+            None => {
+                let params = cursor.map(|c| serde_json::json!({ "cursor": c }));
+                let request = JsonRpcRequest::new("prompts/list", params, self.next_request_id());
+                let response = self.send_and_receive(request).await?;
+                response
+                    .result
+                    .ok_or_else(|| McpError::InvalidResponse("Missing result".to_string()))?
+            }
         };
 
-        let request = JsonRpcRequest::new("prompts/list", params, self.next_request_id());
-        let response = self.send_and_receive(request).await?;
-
-        response
-            .result
-            .ok_or_else(|| McpError::InvalidResponse("Missing result".to_string()))
-            .and_then(|v| serde_json::from_value(v).map_err(McpError::from))
+        let page: PromptsListResult = serde_json::from_value(result)?;
+        self.cache_prompts(cursor, &page);
+        Ok(page)
     }
 
     async fn get_prompt(&self, name: &str, arguments: Option<HashMap<String, String>>) -> McpResult<serde_json::Value> {
@@ -451,10 +1877,97 @@ impl McpTransport for StreamableHttpTransport {
             .ok_or_else(|| McpError::InvalidResponse("Missing result".to_string()))
     }
 
+    async fn subscribe_resource(&self, uri: &str) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+
+        let request = JsonRpcRequest::new(
+            "resources/subscribe",
+            Some(serde_json::json!({ "uri": uri })),
+            self.next_request_id(),
+        );
+        self.send_and_receive(request).await?;
+
+        self.subscriptions.write().insert(uri.to_string());
+        self.ensure_notification_listener();
+        debug!("Subscribed to MCP resource {}", uri);
+        Ok(())
+    }
+
+    async fn unsubscribe_resource(&self, uri: &str) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+
+        let request = JsonRpcRequest::new(
+            "resources/unsubscribe",
+            Some(serde_json::json!({ "uri": uri })),
+            self.next_request_id(),
+        );
+        self.send_and_receive(request).await?;
+
+        let now_empty = {
+            let mut subscriptions = self.subscriptions.write();
+            subscriptions.remove(uri);
+            subscriptions.is_empty()
+        };
+        if now_empty && !self.notification_stream {
+            self.stop_notification_listener();
+        }
+        debug!("Unsubscribed from MCP resource {}", uri);
+        Ok(())
+    }
+
+    fn resource_updates(&self) -> broadcast::Receiver<ResourceUpdatedNotification> {
+        self.resource_updates.subscribe()
+    }
+
+    async fn set_log_level(&self, level: &str) -> McpResult<()> {
+        if !self.is_connected() {
+            return Err(McpError::NotConnected);
+        }
+        let supported = self
+            .server_capabilities
+            .read()
+            .as_ref()
+            .is_some_and(|caps| caps.logging.is_some());
+        if !supported {
+            return Err(McpError::InvalidConfig("Server does not support logging".to_string()));
+        }
+
+        let request = JsonRpcRequest::new(
+            "logging/setLevel",
+            Some(serde_json::json!({ "level": level })),
+            self.next_request_id(),
+        );
+        self.send_and_receive(request).await?;
+        debug!("Set MCP server log level to {}", level);
+        Ok(())
+    }
+
     async fn send_request(&self, request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
         self.send_and_receive(request).await
     }
 
+    async fn send_batch(&self, requests: Vec<JsonRpcRequest>) -> McpResult<Vec<JsonRpcResponse>> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+        if !self.batching_supported() {
+            return self.send_sequential(requests).await;
+        }
+
+        match self.post_batch(&requests).await {
+            Err(McpError::JsonRpcError { code: INVALID_REQUEST, .. }) => {
+                info!("MCP server at {} rejected a batch, sending requests one by one", self.endpoint);
+                self.batch_rejected.store(true, Ordering::SeqCst);
+                self.send_sequential(requests).await
+            }
+            result => result,
+        }
+    }
+
     async fn send_notification(&self, method: &str, params: Option<serde_json::Value>) -> McpResult<()> {
         let notification = serde_json::json!({
             "jsonrpc": "2.0",
@@ -462,7 +1975,7 @@ impl McpTransport for StreamableHttpTransport {
             "params": params
         });
 
-        let http_request = self.build_request(&notification)?;
+        let http_request = self.build_request(&notification).await?;
         let response = http_request.send().await?;
 
         // Notifications should return 202 Accepted or 204 No Content
@@ -494,7 +2007,8 @@ impl std::fmt::Debug for StreamableHttpTransport {
         f.debug_struct("StreamableHttpTransport")
             .field("endpoint", &self.endpoint)
             .field("connected", &self.connected)
-            .field("session_id", &self.session_id)
+            .field("session_id", &*self.session_id.read())
+            .field("protocol_version", &*self.protocol_version.read())
             .field("timeout_ms", &self.timeout_ms)
             .finish()
     }
@@ -531,4 +2045,795 @@ mod tests {
         assert_eq!(event.data, "{\"test\": true}");
         assert_eq!(event.id, Some("123".to_string()));
     }
+
+    #[test]
+    fn test_parse_resource_updated_event() {
+        let event_text = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/resources/updated\",\"params\":{\"uri\":\"file:///notes.md\"}}";
+        let event = parse_sse_event(event_text).unwrap();
+
+        let update = resource_update_from_event(&event).unwrap();
+        assert_eq!(update.uri, "file:///notes.md");
+
+        let other = parse_sse_event("data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{}}").unwrap();
+        assert!(resource_update_from_event(&other).is_none());
+    }
+
+    #[test]
+    fn test_tool_call_stream_events() {
+        let request_id = serde_json::json!(7);
+
+        let progress = parse_sse_event("data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{\"progressToken\":7,\"progress\":0.5,\"total\":1.0}}").unwrap();
+        match tool_call_event_from_sse(&progress, &request_id) {
+            Some(Ok(ToolCallEvent::Progress(p))) => assert_eq!(p.progress, 0.5),
+            other => panic!("expected progress, got {:?}", other),
+        }
+
+        // Progress for another request is ignored
+        let foreign = parse_sse_event("data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{\"progressToken\":8,\"progress\":1.0}}").unwrap();
+        assert!(tool_call_event_from_sse(&foreign, &request_id).is_none());
+
+        let done = parse_sse_event("data: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"ok\"}]}}").unwrap();
+        match tool_call_event_from_sse(&done, &request_id) {
+            Some(Ok(ToolCallEvent::Complete(result))) => assert_eq!(result.content.len(), 1),
+            other => panic!("expected completion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_delay(&headers, 0), Duration::from_millis(500));
+        assert_eq!(retry_delay(&headers, 2), Duration::from_millis(2000));
+
+        headers.insert(reqwest::header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_delay(&headers, 0), Duration::from_secs(7));
+
+        // Server-requested waits are capped
+        headers.insert(reqwest::header::RETRY_AFTER, "3600".parse().unwrap());
+        assert_eq!(retry_delay(&headers, 0), Duration::from_secs(MAX_RETRY_DELAY_SECS));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(reqwest::header::RETRY_AFTER, " 12 ".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(12)));
+
+        let at = (chrono::Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        headers.insert(reqwest::header::RETRY_AFTER, at.parse().unwrap());
+        let wait = parse_retry_after(&headers).unwrap();
+        assert!(wait > Duration::from_secs(55) && wait <= Duration::from_secs(60));
+
+        headers.insert(reqwest::header::RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(reqwest::header::RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_error_carries_retry_after() {
+        let app = axum::Router::new()
+            .route(
+                "/seconds",
+                axum::routing::post(|| async {
+                    (axum::http::StatusCode::TOO_MANY_REQUESTS, [("retry-after", "5")])
+                }),
+            )
+            .route(
+                "/none",
+                axum::routing::post(|| async { axum::http::StatusCode::TOO_MANY_REQUESTS }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        for (path, expected) in [("seconds", Some(5000)), ("none", None)] {
+            let transport = StreamableHttpTransport::new(format!("http://{}/{}", addr, path), None, 5000)
+                .unwrap()
+                .with_max_retries(0);
+            match transport.ping().await {
+                Err(McpError::RateLimited { retry_after_ms }) => assert_eq!(retry_after_ms, expected),
+                other => panic!("expected rate limit, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_request_is_retried() {
+        let hits = Arc::new(AtomicU64::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/mcp",
+            axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+                let counter = counter.clone();
+                async move {
+                    use axum::response::IntoResponse;
+                    if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return (axum::http::StatusCode::TOO_MANY_REQUESTS, [("retry-after", "0")])
+                            .into_response();
+                    }
+                    axum::Json(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": body["id"],
+                        "result": {}
+                    }))
+                    .into_response()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let transport = StreamableHttpTransport::new(format!("http://{}/mcp", addr), None, 5000).unwrap();
+        transport.ping().await.unwrap();
+
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_retry_only_idempotent_methods() {
+        let hits = Arc::new(AtomicU64::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/mcp",
+            axum::routing::post(move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    axum::http::StatusCode::SERVICE_UNAVAILABLE
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let transport = StreamableHttpTransport::new(format!("http://{}/mcp", addr), None, 5000)
+            .unwrap()
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+                jitter: false,
+            });
+
+        let err = transport.ping().await.unwrap_err();
+        assert!(err.to_string().contains("3 attempts"));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        // tools/call may have side effects, so a server error is not retried
+        hits.store(0, Ordering::SeqCst);
+        let request = JsonRpcRequest::new("tools/call", None, 99);
+        assert!(transport.send_request(request).await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_server_request_reply() {
+        let roots = vec![Root::from_path("/home/dev/project").unwrap()];
+
+        let reply = server_request_reply(r#"{"jsonrpc":"2.0","id":7,"method":"roots/list"}"#, &roots).unwrap();
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["result"]["roots"][0]["uri"], "file:///home/dev/project");
+        assert_eq!(reply["result"]["roots"][0]["name"], "project");
+
+        let reply = server_request_reply(r#"{"jsonrpc":"2.0","id":8,"method":"sampling/createMessage"}"#, &roots);
+        assert_eq!(reply.unwrap()["error"]["code"], METHOD_NOT_FOUND);
+
+        // Notifications and responses aren't requests
+        assert!(server_request_reply(r#"{"jsonrpc":"2.0","method":"notifications/progress"}"#, &roots).is_none());
+        assert!(server_request_reply(r#"{"jsonrpc":"2.0","id":9,"result":{}}"#, &roots).is_none());
+    }
+
+    /// Echoes the last message back, or declines when `approve` is false
+    struct EchoSampler {
+        approve: bool,
+    }
+
+    #[async_trait]
+    impl SamplingHandler for EchoSampler {
+        async fn create_message(&self, params: CreateMessageParams) -> McpResult<CreateMessageResult> {
+            if !self.approve {
+                return Err(McpError::Cancelled);
+            }
+            Ok(CreateMessageResult {
+                role: "assistant".to_string(),
+                content: params.messages.last().unwrap().content.clone(),
+                model: "sonnet".to_string(),
+                stop_reason: Some("endTurn".to_string()),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampling_reply() {
+        let request = r#"{"jsonrpc":"2.0","id":3,"method":"sampling/createMessage","params":{
+            "messages":[{"role":"user","content":{"type":"text","text":"hello"}}],"maxTokens":100}}"#;
+        let (id, params) = sampling_request(request).unwrap();
+        assert!(sampling_request(r#"{"jsonrpc":"2.0","id":4,"method":"roots/list"}"#).is_none());
+
+        let reply = sampling_reply(Arc::new(EchoSampler { approve: true }), id.clone(), params.clone()).await;
+        assert_eq!(reply["id"], 3);
+        assert_eq!(reply["result"]["content"]["text"], "hello");
+        assert_eq!(reply["result"]["stopReason"], "endTurn");
+
+        let reply = sampling_reply(Arc::new(EchoSampler { approve: false }), id.clone(), params).await;
+        assert_eq!(reply["error"]["code"], USER_REJECTED);
+
+        let reply = sampling_reply(Arc::new(EchoSampler { approve: true }), id, serde_json::json!({})).await;
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_transport_event_from_notification() {
+        let changed = parse_sse_event("data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/tools/list_changed\"}").unwrap();
+        assert!(matches!(transport_event_from_sse(&changed), Some(TransportEvent::CapabilitiesChanged)));
+
+        let log = parse_sse_event("data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/message\",\"params\":{\"level\":\"warning\",\"logger\":\"fs\",\"data\":\"disk low\"}}").unwrap();
+        match transport_event_from_sse(&log) {
+            Some(TransportEvent::Log { level, logger, message }) => {
+                assert_eq!(level, "warning");
+                assert_eq!(logger.as_deref(), Some("fs"));
+                assert_eq!(message, "disk low");
+            }
+            other => panic!("expected log, got {:?}", other),
+        }
+
+        // Responses are not notifications
+        let response = parse_sse_event("data: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}").unwrap();
+        assert!(transport_event_from_sse(&response).is_none());
+    }
+
+    /// Minimal MCP server that hands out session IDs and records the ones it receives
+    #[derive(Default)]
+    struct MockServer {
+        last_event_ids: parking_lot::Mutex<Vec<Option<String>>>,
+        seen: parking_lot::Mutex<Vec<Option<String>>>,
+        deleted: parking_lot::Mutex<Vec<Option<String>>>,
+        protocol_headers: parking_lot::Mutex<Vec<Option<String>>>,
+        /// Version to answer initialize with instead of MCP_PROTOCOL_VERSION
+        protocol_version: parking_lot::Mutex<Option<String>>,
+        expired: parking_lot::Mutex<Vec<String>>,
+        sessions: AtomicU64,
+        /// Capabilities to answer initialize with (none by default)
+        capabilities: parking_lot::Mutex<Option<serde_json::Value>>,
+        /// Instructions to answer initialize with (none by default)
+        instructions: parking_lot::Mutex<Option<String>>,
+        /// Answer batches with HTTP 400 and -32600
+        reject_batches: AtomicBool,
+        /// (X-Tenant-Id, Authorization) of every POST
+        tenant_auth: parking_lot::Mutex<Vec<(Option<String>, Option<String>)>>,
+    }
+
+    async fn mock_handler(
+        axum::extract::State(server): axum::extract::State<Arc<MockServer>>,
+        headers: axum::http::HeaderMap,
+        axum::Json(body): axum::Json<serde_json::Value>,
+    ) -> axum::response::Response {
+        use axum::response::IntoResponse;
+
+        let sid = headers
+            .get("Mcp-Session-Id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        server.seen.lock().push(sid.clone());
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(String::from);
+        server.tenant_auth.lock().push((header("X-Tenant-Id"), header("Authorization")));
+        server.protocol_headers.lock().push(
+            headers
+                .get("MCP-Protocol-Version")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from),
+        );
+
+        if let Some(ref sid) = sid {
+            if server.expired.lock().contains(sid) {
+                return axum::http::StatusCode::NOT_FOUND.into_response();
+            }
+        }
+
+        // Batches are answered out of order, and a single-entry batch as a bare object
+        if let Some(batch) = body.as_array() {
+            if server.reject_batches.load(Ordering::SeqCst) {
+                let error = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": INVALID_REQUEST, "message": "Batches not supported" }
+                });
+                return (axum::http::StatusCode::BAD_REQUEST, axum::Json(error)).into_response();
+            }
+            let mut responses: Vec<serde_json::Value> = batch
+                .iter()
+                .rev()
+                .map(|request| {
+                    serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": { "method": request["method"] }
+                    })
+                })
+                .collect();
+            if responses.len() == 1 {
+                return axum::Json(responses.remove(0)).into_response();
+            }
+            return axum::Json(serde_json::Value::Array(responses)).into_response();
+        }
+
+        // Notifications carry no id
+        let id = match body.get("id") {
+            Some(id) => id.clone(),
+            None => return axum::http::StatusCode::ACCEPTED.into_response(),
+        };
+
+        let sid = sid.unwrap_or_else(|| {
+            format!("session-{}", server.sessions.fetch_add(1, Ordering::SeqCst) + 1)
+        });
+        let protocol_version = server
+            .protocol_version
+            .lock()
+            .clone()
+            .unwrap_or_else(|| MCP_PROTOCOL_VERSION.to_string());
+        let mut response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": {
+                "protocolVersion": protocol_version,
+                "capabilities": server.capabilities.lock().clone().unwrap_or_else(|| serde_json::json!({})),
+                "serverInfo": { "name": "mock" }
+            }
+        });
+        if let Some(instructions) = server.instructions.lock().clone() {
+            response["result"]["instructions"] = serde_json::Value::String(instructions);
+        }
+
+        ([("mcp-session-id", sid)], axum::Json(response)).into_response()
+    }
+
+    /// Notification stream that sends one event per connection
+    async fn mock_stream_handler(
+        axum::extract::State(server): axum::extract::State<Arc<MockServer>>,
+        headers: axum::http::HeaderMap,
+    ) -> axum::response::Response {
+        use axum::response::IntoResponse;
+
+        let last_event_id = headers
+            .get("Last-Event-ID")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        server.last_event_ids.lock().push(last_event_id);
+
+        (
+            [("content-type", "text/event-stream")],
+            "id: evt-1\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/tools/list_changed\"}\n\n",
+        )
+            .into_response()
+    }
+
+    /// Session termination, recording the session being ended
+    async fn mock_delete_handler(
+        axum::extract::State(server): axum::extract::State<Arc<MockServer>>,
+        headers: axum::http::HeaderMap,
+    ) -> axum::http::StatusCode {
+        let sid = headers
+            .get("Mcp-Session-Id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        server.deleted.lock().push(sid);
+        axum::http::StatusCode::NO_CONTENT
+    }
+
+    async fn spawn_mock_server() -> (String, Arc<MockServer>) {
+        let server = Arc::new(MockServer::default());
+        let app = axum::Router::new()
+            .route(
+                "/mcp",
+                axum::routing::post(mock_handler)
+                    .get(mock_stream_handler)
+                    .delete(mock_delete_handler),
+            )
+            .with_state(server.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}/mcp", addr), server)
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_reuses_session() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000).unwrap();
+
+        transport.connect().await.unwrap();
+        assert_eq!(transport.session_id().as_deref(), Some("session-1"));
+
+        // Simulate a dropped connection without clearing session state
+        *transport.connected.write() = false;
+        server.seen.lock().clear();
+
+        transport.reconnect().await.unwrap();
+
+        assert!(transport.is_connected());
+        assert_eq!(transport.session_id().as_deref(), Some("session-1"));
+        let seen = server.seen.lock().clone();
+        assert!(!seen.is_empty());
+        assert!(seen.iter().all(|sid| sid.as_deref() == Some("session-1")));
+    }
+
+    #[tokio::test]
+    async fn test_protocol_version_negotiation() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000).unwrap();
+
+        transport.connect().await.unwrap();
+        transport.ping().await.unwrap();
+        assert_eq!(transport.protocol_version().as_deref(), Some(MCP_PROTOCOL_VERSION));
+        assert_eq!(
+            server.protocol_headers.lock().last().cloned().flatten().as_deref(),
+            Some(MCP_PROTOCOL_VERSION)
+        );
+
+        // An older supported version is accepted and sent without the version header
+        transport.disconnect().await.unwrap();
+        *server.protocol_version.lock() = Some("2025-03-26".to_string());
+        server.protocol_headers.lock().clear();
+        transport.connect().await.unwrap();
+        transport.ping().await.unwrap();
+        assert_eq!(transport.protocol_version().as_deref(), Some("2025-03-26"));
+        assert!(server.protocol_headers.lock().iter().all(Option::is_none));
+
+        // Unknown versions fail the handshake
+        transport.disconnect().await.unwrap();
+        *server.protocol_version.lock() = Some("2099-01-01".to_string());
+        match transport.connect().await {
+            Err(McpError::ProtocolVersionMismatch { expected, actual }) => {
+                assert_eq!(expected, MCP_PROTOCOL_VERSION);
+                assert_eq!(actual, "2099-01-01");
+            }
+            other => panic!("expected version mismatch, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_terminates_session() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000).unwrap();
+
+        transport.connect().await.unwrap();
+        transport.disconnect().await.unwrap();
+
+        assert_eq!(*server.deleted.lock(), vec![Some("session-1".to_string())]);
+        assert!(transport.session_id().is_none());
+        assert!(!transport.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_starts_new_session_on_404() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let mut transport =
+            StreamableHttpTransport::with_session_id(endpoint, None, 5000, "stale").unwrap();
+        server.expired.lock().push("stale".to_string());
+
+        transport.reconnect().await.unwrap();
+
+        assert!(transport.is_connected());
+        assert_eq!(transport.session_id().as_deref(), Some("session-1"));
+    }
+
+    #[tokio::test]
+    async fn test_request_renews_invalidated_session() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000)
+            .unwrap()
+            .with_notification_stream(false);
+        transport.connect().await.unwrap();
+        let mut events = transport.subscribe_notifications();
+
+        // The server restarts and forgets the first session
+        server.expired.lock().push("session-1".to_string());
+        transport.ping().await.unwrap();
+
+        assert!(transport.is_connected());
+        assert_eq!(transport.session_id().as_deref(), Some("session-2"));
+        assert!(matches!(
+            events.try_recv(),
+            Ok(TransportEvent::ConnectionStateChanged { connected: true })
+        ));
+
+        // A server that drops every new session too gives up after one renewal
+        server.expired.lock().push("session-2".to_string());
+        server.expired.lock().push("session-3".to_string());
+        assert!(transport.ping().await.is_err());
+        assert!(!transport.is_connected());
+        assert_eq!(server.sessions.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_trace_records_exchanges_only_when_enabled() {
+        let (endpoint, _server) = spawn_mock_server().await;
+        let auth: Box<dyn McpAuth> = Box::new(crate::mcp::auth::McpBearerAuth::new("secret-token"));
+        let mut transport = StreamableHttpTransport::new(endpoint, Some(auth), 5000)
+            .unwrap()
+            .with_notification_stream(false);
+        let trace = Arc::new(McpTrace::new(10));
+        transport.set_trace(trace.clone());
+
+        transport.connect().await.unwrap();
+        assert!(trace.entries(None).is_empty());
+
+        trace.set_enabled(true);
+        transport.ping().await.unwrap();
+
+        let entries = trace.entries(None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].method, "ping");
+        assert_eq!(entries[0].request_headers["authorization"], crate::mcp::trace::REDACTED);
+        assert_eq!(entries[0].request_headers["mcp-session-id"], "session-1");
+        assert!(entries[0].result.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers_sent_and_auth_wins() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let auth: Box<dyn McpAuth> = Box::new(crate::mcp::auth::McpBearerAuth::new("secret-token"));
+        let mut extra = HeaderMap::new();
+        extra.insert("x-tenant-id", "acme".parse().unwrap());
+        extra.insert("authorization", "Bearer spoofed".parse().unwrap());
+        let mut transport = StreamableHttpTransport::new(endpoint, Some(auth), 5000)
+            .unwrap()
+            .with_notification_stream(false)
+            .with_extra_headers(extra)
+            .unwrap();
+
+        transport.connect().await.unwrap();
+        transport.ping().await.unwrap();
+
+        let seen = server.tenant_auth.lock().clone();
+        assert!(!seen.is_empty());
+        for (tenant, authorization) in seen {
+            assert_eq!(tenant.as_deref(), Some("acme"));
+            assert_eq!(authorization.as_deref(), Some("Bearer secret-token"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notification_stream_resumes_with_last_event_id() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000).unwrap();
+        let mut notifications = transport.subscribe_notifications();
+
+        transport.connect().await.unwrap();
+
+        let received = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let TransportEvent::CapabilitiesChanged = notifications.recv().await.unwrap() {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(received.is_ok());
+
+        // Each stream closes after one event, so the listener re-opens it from evt-1
+        let resumed = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if server.last_event_ids.lock().iter().any(|id| id.as_deref() == Some("evt-1")) {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await;
+        assert!(resumed.is_ok());
+
+        transport.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sse_response_resumes_after_cutoff() {
+        let resumed_from = Arc::new(parking_lot::Mutex::new(None::<String>));
+        let recorder = resumed_from.clone();
+
+        // The POST stream cuts off after a progress event; the result only arrives on resume
+        let app = axum::Router::new().route(
+            "/mcp",
+            axum::routing::post(|| async {
+                (
+                    [("content-type", "text/event-stream")],
+                    "id: e1\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{\"progressToken\":1,\"progress\":0.5}}\n\n",
+                )
+            })
+            .get(move |headers: axum::http::HeaderMap| {
+                let recorder = recorder.clone();
+                async move {
+                    *recorder.lock() = headers
+                        .get("Last-Event-ID")
+                        .and_then(|v| v.to_str().ok())
+                        .map(String::from);
+                    (
+                        [("content-type", "text/event-stream")],
+                        "id: e2\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n\n",
+                    )
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let transport = StreamableHttpTransport::new(format!("http://{}/mcp", addr), None, 5000).unwrap();
+        let request = JsonRpcRequest::new("ping", None, 1u64);
+        let response = transport.send_request(request).await.unwrap();
+
+        assert_eq!(response.id, serde_json::json!(1));
+        assert_eq!(resumed_from.lock().as_deref(), Some("e1"));
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_matches_responses_to_requests() {
+        let (endpoint, _server) = spawn_mock_server().await;
+        let transport = StreamableHttpTransport::new(endpoint, None, 5000).unwrap();
+
+        let requests = vec![
+            JsonRpcRequest::new("tools/list", None, 1),
+            JsonRpcRequest::new("resources/list", None, 2),
+            JsonRpcRequest::new("prompts/list", None, 3),
+        ];
+        let responses = transport.send_batch(requests).await.unwrap();
+
+        let methods: Vec<_> = responses
+            .iter()
+            .map(|r| r.result.as_ref().unwrap()["method"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(methods, vec!["tools/list", "resources/list", "prompts/list"]);
+
+        // A single-entry batch comes back as a bare object
+        let responses = transport
+            .send_batch(vec![JsonRpcRequest::new("tools/list", None, 4)])
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].id, serde_json::json!(4));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_batch_falls_back_to_sequential_requests() {
+        let (endpoint, server) = spawn_mock_server().await;
+        server.reject_batches.store(true, Ordering::SeqCst);
+        let transport = StreamableHttpTransport::new(endpoint, None, 5000).unwrap();
+
+        let requests = vec![
+            JsonRpcRequest::new("tools/list", None, 1),
+            JsonRpcRequest::new("prompts/list", None, 2),
+        ];
+        let responses = transport.send_batch(requests).await.unwrap();
+        let ids: Vec<_> = responses.iter().map(|r| r.id.clone()).collect();
+        assert_eq!(ids, vec![serde_json::json!(1), serde_json::json!(2)]);
+        // The rejected batch plus one request each
+        assert_eq!(server.seen.lock().len(), 3);
+
+        // Later batches skip straight to sequential requests
+        transport
+            .send_batch(vec![
+                JsonRpcRequest::new("tools/list", None, 3),
+                JsonRpcRequest::new("prompts/list", None, 4),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(server.seen.lock().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_get_prompt_validated_reports_missing_arguments() {
+        let (endpoint, server) = spawn_mock_server().await;
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000)
+            .unwrap()
+            .with_notification_stream(false);
+        transport.connect().await.unwrap();
+
+        let page: PromptsListResult = serde_json::from_value(serde_json::json!({
+            "prompts": [{
+                "name": "review",
+                "arguments": [
+                    { "name": "file", "required": true },
+                    { "name": "focus", "required": true },
+                    { "name": "tone" },
+                ],
+            }],
+        }))
+        .unwrap();
+        transport.cache_prompts(None, &page);
+        server.seen.lock().clear();
+
+        let arguments = HashMap::from([("tone".to_string(), "terse".to_string())]);
+        match transport.get_prompt_validated("review", Some(arguments)).await {
+            Err(McpError::InvalidConfig(message)) => {
+                assert_eq!(message, "Prompt review is missing required arguments: file, focus");
+            }
+            other => panic!("expected missing arguments, got {:?}", other),
+        }
+        // Nothing was sent to the server
+        assert!(server.seen.lock().is_empty());
+
+        let arguments = HashMap::from([
+            ("file".to_string(), "main.rs".to_string()),
+            ("focus".to_string(), "errors".to_string()),
+        ]);
+        transport.get_prompt_validated("review", Some(arguments)).await.unwrap();
+        assert_eq!(server.seen.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_instructions_survive_connect() {
+        let (endpoint, server) = spawn_mock_server().await;
+        *server.instructions.lock() = Some("Call list_projects before anything else".to_string());
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000)
+            .unwrap()
+            .with_notification_stream(false);
+        assert_eq!(transport.server_instructions(), None);
+
+        transport.connect().await.unwrap();
+        assert_eq!(
+            transport.server_instructions().as_deref(),
+            Some("Call list_projects before anything else")
+        );
+
+        transport.disconnect().await.unwrap();
+        assert_eq!(transport.server_instructions(), None);
+    }
+
+    #[tokio::test]
+    async fn test_connect_prefetches_lists_in_one_batch() {
+        let (endpoint, server) = spawn_mock_server().await;
+        *server.protocol_version.lock() = Some("2025-03-26".to_string());
+        *server.capabilities.lock() = Some(serde_json::json!({ "tools": {}, "prompts": {} }));
+        let mut transport = StreamableHttpTransport::new(endpoint, None, 5000)
+            .unwrap()
+            .with_notification_stream(false);
+
+        transport.connect().await.unwrap();
+
+        // initialize, notifications/initialized, then a single batch
+        assert_eq!(server.seen.lock().len(), 3);
+        let prefetched = transport.prefetched.read();
+        let mut methods: Vec<_> = prefetched.keys().cloned().collect();
+        methods.sort();
+        assert_eq!(methods, vec!["prompts/list", "tools/list"]);
+        assert_eq!(prefetched["tools/list"]["method"], "tools/list");
+    }
+
+    #[test]
+    fn test_demux_batch_responses() {
+        let requests = vec![
+            JsonRpcRequest::new("tools/list", None, 1),
+            JsonRpcRequest::new("prompts/list", None, 2),
+        ];
+
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": { "code": -32600, "message": "Invalid Request" }
+        });
+        let rejected = demux_batch_responses(&requests, parse_batch_body(body).unwrap());
+        assert!(matches!(rejected, Err(McpError::JsonRpcError { code: -32600, .. })));
+
+        let body = serde_json::json!([{ "jsonrpc": "2.0", "id": 2, "result": {} }]);
+        let missing = demux_batch_responses(&requests, parse_batch_body(body).unwrap());
+        assert!(matches!(missing, Err(McpError::InvalidResponse(_))));
+    }
 }
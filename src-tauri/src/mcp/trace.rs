@@ -0,0 +1,161 @@
+//! MCP Traffic Trace
+//!
+//! Opt-in record of the last request/response exchanges with a server, for
+//! debugging it without an external proxy. Nothing is recorded until tracing
+//! is enabled, and header values that may carry credentials are redacted.
+
+use parking_lot::Mutex;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Exchanges kept per server before the oldest are dropped
+pub const DEFAULT_TRACE_CAPACITY: usize = 200;
+
+/// Replacement for header values that aren't known to be safe
+pub const REDACTED: &str = "[redacted]";
+
+/// Request headers recorded as sent; all others are redacted
+const SAFE_HEADERS: &[&str] = &[
+    "accept",
+    "content-type",
+    "content-length",
+    "user-agent",
+    "mcp-session-id",
+    "mcp-protocol-version",
+    "last-event-id",
+];
+
+/// One request and the server's answer
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEntry {
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+    /// Request headers, with credentials redacted
+    pub request_headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+/// Ring buffer of traced exchanges for one server
+pub struct McpTrace {
+    enabled: AtomicBool,
+    capacity: usize,
+    entries: Mutex<VecDeque<TraceEntry>>,
+}
+
+impl McpTrace {
+    /// Create a disabled trace keeping up to `capacity` exchanges
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            capacity: capacity.max(1),
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Start or stop recording; recorded exchanges are kept either way
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Add an exchange, dropping the oldest if the buffer is full
+    pub fn record(&self, entry: TraceEntry) {
+        let mut entries = self.entries.lock();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// The most recent `limit` exchanges (all if None), oldest first
+    pub fn entries(&self, limit: Option<usize>) -> Vec<TraceEntry> {
+        let entries = self.entries.lock();
+        let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+        entries.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+impl Default for McpTrace {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRACE_CAPACITY)
+    }
+}
+
+/// Copy headers for a trace, redacting every value not known to be safe
+pub fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SAFE_HEADERS.contains(&name.as_str()) {
+                value.to_str().unwrap_or(REDACTED).to_string()
+            } else {
+                REDACTED.to_string()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(method: &str) -> TraceEntry {
+        TraceEntry {
+            method: method.to_string(),
+            params: None,
+            request_headers: BTreeMap::new(),
+            result: None,
+            error: None,
+            latency_ms: 0,
+            started_at: String::new(),
+            finished_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_trace_keeps_most_recent_entries() {
+        let trace = McpTrace::new(2);
+        assert!(!trace.is_enabled());
+
+        trace.record(entry("initialize"));
+        trace.record(entry("tools/list"));
+        trace.record(entry("tools/call"));
+
+        let methods: Vec<String> = trace.entries(None).into_iter().map(|e| e.method).collect();
+        assert_eq!(methods, vec!["tools/list", "tools/call"]);
+        assert_eq!(trace.entries(Some(1))[0].method, "tools/call");
+
+        trace.clear();
+        assert!(trace.entries(None).is_empty());
+    }
+
+    #[test]
+    fn test_redact_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        headers.insert("x-custom-key", "secret".parse().unwrap());
+        headers.insert("mcp-session-id", "session-1".parse().unwrap());
+
+        let redacted = redact_headers(&headers);
+        assert_eq!(redacted["authorization"], REDACTED);
+        assert_eq!(redacted["x-custom-key"], REDACTED);
+        assert_eq!(redacted["mcp-session-id"], "session-1");
+    }
+}
@@ -2,16 +2,105 @@
 //!
 //! Provides a unified interface for different transport mechanisms:
 //! - Streamable HTTP (primary for remote servers)
+//! - WebSocket (ws:// and wss:// endpoints)
 //! - STDIO (for local servers)
 
 use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
 
 use super::auth::McpAuth;
-use super::error::McpResult;
+use super::error::{McpError, McpResult};
+use super::trace::McpTrace;
 use super::types::*;
 
+/// Page cap for the list_all_* helpers, guarding against servers that never stop paginating
+pub const MAX_LIST_PAGES: usize = 50;
+
+/// Backoff policy for retrying transient transport failures
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first; 1 disables retries
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on each subsequent one
+    pub initial_backoff_ms: u64,
+    /// Upper bound on any single delay
+    pub max_backoff_ms: u64,
+    /// Randomize each delay between half and all of the backoff
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 200,
+            max_backoff_ms: 5000,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before the retry that follows failed attempt `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay_ms = self
+            .initial_backoff_ms
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff_ms);
+
+        if !self.jitter {
+            return Duration::from_millis(delay_ms);
+        }
+
+        // Clock nanos are plenty to spread out clients retrying in lockstep
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u64)
+            .unwrap_or(0);
+        let half = delay_ms / 2;
+        Duration::from_millis(half + nanos % (delay_ms - half + 1))
+    }
+}
+
+/// TLS settings for servers behind a private CA or requiring client certificates
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsOptions {
+    /// PEM file with an extra root certificate to trust
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<String>,
+    /// PEM client certificate for mutual TLS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<String>,
+    /// PEM (PKCS#8) private key for the client certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<String>,
+    /// Skip certificate validation entirely (development only)
+    pub accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    /// Whether any option differs from the system defaults
+    pub fn is_configured(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
 /// Transport configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -21,6 +110,26 @@ pub enum TransportConfig {
         endpoint: String,
         #[serde(skip_serializing_if = "Option::is_none")]
         timeout_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry: Option<RetryPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tls: Option<TlsOptions>,
+        /// Sent with every request; auth headers win on conflicts
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+    },
+    #[serde(rename = "websocket")]
+    WebSocket {
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry: Option<RetryPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tls: Option<TlsOptions>,
+        /// Sent with every request; auth headers win on conflicts
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
     },
     #[serde(rename = "stdio")]
     Stdio {
@@ -37,6 +146,13 @@ pub enum TransportConfig {
     },
 }
 
+/// Answers sampling/createMessage requests a server sends
+#[async_trait]
+pub trait SamplingHandler: Send + Sync {
+    /// Produce a completion for the request; McpError::Cancelled means the user declined it
+    async fn create_message(&self, params: CreateMessageParams) -> McpResult<CreateMessageResult>;
+}
+
 /// MCP Transport trait - defines the interface for all transport mechanisms
 #[async_trait]
 pub trait McpTransport: Send + Sync {
@@ -50,7 +166,49 @@ pub trait McpTransport: Send + Sync {
     fn is_connected(&self) -> bool;
 
     /// Get the current session ID (if any)
-    fn session_id(&self) -> Option<&str>;
+    ///
+    /// Returned owned since transports keep the session behind a lock.
+    fn session_id(&self) -> Option<String>;
+
+    /// Protocol version negotiated during initialize (None before connecting)
+    fn protocol_version(&self) -> Option<String> {
+        None
+    }
+
+    /// Capabilities the server advertised during initialize
+    fn server_capabilities(&self) -> Option<ServerCapabilities> {
+        None
+    }
+
+    /// Server name and version from initialize
+    fn server_info(&self) -> Option<ServerInfo> {
+        None
+    }
+
+    /// Usage guidance the server sent with its initialize result
+    fn server_instructions(&self) -> Option<String> {
+        None
+    }
+
+    /// Replace the roots offered when the server sends roots/list
+    ///
+    /// Transports that can't receive server requests ignore roots.
+    fn set_roots(&self, _roots: Vec<Root>) {}
+
+    /// Answer sampling/createMessage requests with this handler
+    ///
+    /// Without a handler sampling requests get "Method not found".
+    fn set_sampling_handler(&self, _handler: Arc<dyn SamplingHandler>) {}
+
+    /// Record requests and responses into this trace while it is enabled
+    ///
+    /// Transports without tracing support ignore it.
+    fn set_trace(&self, _trace: Arc<McpTrace>) {}
+
+    /// Receive server notifications, including log messages (None if not supported)
+    fn notifications(&self) -> Option<broadcast::Receiver<TransportEvent>> {
+        None
+    }
 
     /// Initialize the MCP session
     async fn initialize(&mut self, params: InitializeParams) -> McpResult<InitializeResult>;
@@ -64,21 +222,127 @@ pub trait McpTransport: Send + Sync {
     /// Call a tool
     async fn call_tool(&self, name: &str, arguments: Option<serde_json::Value>) -> McpResult<ToolCallResult>;
 
+    /// List all tools, following pagination cursors (deduplicated by name)
+    async fn list_all_tools(&self) -> McpResult<Vec<Tool>> {
+        let mut tools = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_LIST_PAGES {
+            let page = self.list_tools(cursor.as_deref()).await?;
+            tools.extend(page.tools.into_iter().filter(|t| seen.insert(t.name.clone())));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(tools),
+            }
+        }
+
+        log::warn!("Stopped listing tools after {} pages", MAX_LIST_PAGES);
+        Ok(tools)
+    }
+
+    /// Call a tool, yielding progress and log events as they arrive
+    ///
+    /// The final event is the tool result (or an error). `request_id` lets the
+    /// caller pick the JSON-RPC id so the call can be cancelled later; None
+    /// allocates the next id. `timeout` replaces the transport's request timeout
+    /// for this call. Transports without streaming support fall back to a single
+    /// buffered result.
+    async fn call_tool_streaming(
+        &self,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+        _request_id: Option<serde_json::Value>,
+        _timeout: Option<Duration>,
+    ) -> McpResult<mpsc::Receiver<McpResult<ToolCallEvent>>> {
+        let (tx, rx) = mpsc::channel(1);
+        let result = self.call_tool(name, arguments).await.map(ToolCallEvent::Complete);
+        let _ = tx.send(result).await;
+        Ok(rx)
+    }
+
     /// List available resources
     async fn list_resources(&self, cursor: Option<&str>) -> McpResult<ResourcesListResult>;
 
     /// Read a resource
     async fn read_resource(&self, uri: &str) -> McpResult<serde_json::Value>;
 
+    /// List all resources, following pagination cursors (deduplicated by URI)
+    async fn list_all_resources(&self) -> McpResult<Vec<Resource>> {
+        let mut resources = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_LIST_PAGES {
+            let page = self.list_resources(cursor.as_deref()).await?;
+            resources.extend(page.resources.into_iter().filter(|r| seen.insert(r.uri.clone())));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(resources),
+            }
+        }
+
+        log::warn!("Stopped listing resources after {} pages", MAX_LIST_PAGES);
+        Ok(resources)
+    }
+
     /// List available prompts
     async fn list_prompts(&self, cursor: Option<&str>) -> McpResult<PromptsListResult>;
 
+    /// List all prompts, following pagination cursors (deduplicated by name)
+    async fn list_all_prompts(&self) -> McpResult<Vec<Prompt>> {
+        let mut prompts = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_LIST_PAGES {
+            let page = self.list_prompts(cursor.as_deref()).await?;
+            prompts.extend(page.prompts.into_iter().filter(|p| seen.insert(p.name.clone())));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(prompts),
+            }
+        }
+
+        log::warn!("Stopped listing prompts after {} pages", MAX_LIST_PAGES);
+        Ok(prompts)
+    }
+
     /// Get a prompt
     async fn get_prompt(&self, name: &str, arguments: Option<HashMap<String, String>>) -> McpResult<serde_json::Value>;
 
+    /// Subscribe to update notifications for a resource
+    async fn subscribe_resource(&self, uri: &str) -> McpResult<()>;
+
+    /// Unsubscribe from update notifications for a resource
+    async fn unsubscribe_resource(&self, uri: &str) -> McpResult<()>;
+
+    /// Receive notifications/resources/updated events for subscribed resources
+    fn resource_updates(&self) -> broadcast::Receiver<ResourceUpdatedNotification>;
+
+    /// Set the minimum level of log messages the server sends (logging/setLevel)
+    async fn set_log_level(&self, level: &str) -> McpResult<()>;
+
     /// Send a raw JSON-RPC request
     async fn send_request(&self, request: JsonRpcRequest) -> McpResult<JsonRpcResponse>;
 
+    /// Send several requests in one round trip, returning responses in request order
+    ///
+    /// The default sends requests one at a time for transports without batch support.
+    async fn send_batch(&self, requests: Vec<JsonRpcRequest>) -> McpResult<Vec<JsonRpcResponse>> {
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            responses.push(self.send_request(request).await?);
+        }
+        Ok(responses)
+    }
+
+    /// Tell the server to stop working on an in-flight request
+    async fn cancel_request(&self, request_id: serde_json::Value, reason: Option<&str>) -> McpResult<()> {
+        let params = serde_json::json!({ "requestId": request_id, "reason": reason });
+        self.send_notification("notifications/cancelled", Some(params)).await
+    }
+
     /// Send a notification (no response expected)
     async fn send_notification(&self, method: &str, params: Option<serde_json::Value>) -> McpResult<()>;
 
@@ -94,14 +358,23 @@ pub trait McpTransport: Send + Sync {
 pub enum TransportEvent {
     /// Server sent a progress notification
     Progress(ProgressNotification),
-    /// Server sent a log message
-    Log { level: String, message: String },
+    /// Server sent a log message (notifications/message)
+    Log {
+        level: String,
+        logger: Option<String>,
+        message: String,
+    },
     /// Server capabilities changed
     CapabilitiesChanged,
     /// Connection state changed
     ConnectionStateChanged { connected: bool },
     /// Error occurred
     Error(String),
+    /// Any other server notification
+    Notification {
+        method: String,
+        params: Option<serde_json::Value>,
+    },
 }
 
 /// Callback type for transport events
@@ -117,6 +390,21 @@ pub trait McpTransportWithEvents: McpTransport {
     fn unsubscribe_events(&mut self);
 }
 
+/// Parse per-server default headers, rejecting anything that isn't a valid HTTP header
+///
+/// Names and values can't contain CR or LF, so they can't smuggle in extra headers.
+pub fn parse_extra_headers(headers: &HashMap<String, String>) -> McpResult<HeaderMap> {
+    let mut parsed = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| McpError::InvalidConfig(format!("Invalid header name: {:?}", name)))?;
+        let header_value = HeaderValue::from_str(value.trim())
+            .map_err(|_| McpError::InvalidConfig(format!("Invalid value for header {}", header_name)))?;
+        parsed.insert(header_name, header_value);
+    }
+    Ok(parsed)
+}
+
 /// Transport factory for creating transports from configuration
 pub struct TransportFactory;
 
@@ -127,13 +415,28 @@ impl TransportFactory {
         auth: Option<Box<dyn McpAuth>>,
     ) -> McpResult<Box<dyn McpTransport>> {
         match config {
-            TransportConfig::StreamableHttp { endpoint, timeout_ms } => {
+            TransportConfig::StreamableHttp { endpoint, timeout_ms, retry, tls, headers } => {
                 use super::streamable_http::StreamableHttpTransport;
-                let transport = StreamableHttpTransport::new(
+                let mut transport = StreamableHttpTransport::new(
                     endpoint,
                     auth,
                     timeout_ms.unwrap_or(30000),
-                )?;
+                )?
+                .with_retry_policy(retry.unwrap_or_default())
+                .with_extra_headers(parse_extra_headers(&headers)?)?;
+                if let Some(tls) = tls {
+                    transport = transport.with_tls_options(&tls)?;
+                }
+                Ok(Box::new(transport))
+            }
+            TransportConfig::WebSocket { url, timeout_ms, retry, tls, headers } => {
+                use super::websocket::WebSocketTransport;
+                let mut transport = WebSocketTransport::new(url, auth, timeout_ms.unwrap_or(30000))?
+                    .with_retry_policy(retry.unwrap_or_default())
+                    .with_extra_headers(parse_extra_headers(&headers)?);
+                if let Some(tls) = tls {
+                    transport = transport.with_tls_options(&tls)?;
+                }
                 Ok(Box::new(transport))
             }
             TransportConfig::Stdio { command, args, env } => {
@@ -163,8 +466,169 @@ mod tests {
         let config = TransportConfig::StreamableHttp {
             endpoint: "https://mcp.example.com".to_string(),
             timeout_ms: Some(5000),
+            retry: None,
+            tls: None,
+            headers: HashMap::new(),
         };
         let json = serde_json::to_string(&config).unwrap();
         assert!(json.contains("streamable-http"));
+        assert!(!json.contains("retry"));
+        assert!(!json.contains("headers"));
+
+        let config: TransportConfig =
+            serde_json::from_str(r#"{"type":"websocket","url":"wss://mcp.example.com/ws"}"#).unwrap();
+        assert!(matches!(config, TransportConfig::WebSocket { timeout_ms: None, .. }));
+        assert!(TransportFactory::create(config, None).is_ok());
+    }
+
+    #[test]
+    fn test_parse_extra_headers() {
+        let headers = HashMap::from([
+            ("X-Tenant-Id".to_string(), " acme ".to_string()),
+            ("User-Agent".to_string(), "opcode-gateway/1.0".to_string()),
+        ]);
+        let parsed = parse_extra_headers(&headers).unwrap();
+        assert_eq!(parsed["x-tenant-id"], "acme");
+        assert_eq!(parsed["user-agent"], "opcode-gateway/1.0");
+
+        let injected_name = HashMap::from([("X-Tenant\r\nX-Admin".to_string(), "1".to_string())]);
+        assert!(matches!(parse_extra_headers(&injected_name), Err(McpError::InvalidConfig(_))));
+
+        let injected_value = HashMap::from([("X-Tenant-Id".to_string(), "acme\r\nX-Admin: 1".to_string())]);
+        assert!(matches!(parse_extra_headers(&injected_value), Err(McpError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 300,
+            jitter: false,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff(40), Duration::from_millis(300));
+
+        let jittered = RetryPolicy { jitter: true, ..policy };
+        let delay = jittered.backoff(2);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+
+        // Missing fields fall back to defaults
+        let parsed: RetryPolicy = serde_json::from_str(r#"{"max_attempts": 5}"#).unwrap();
+        assert_eq!(parsed.max_attempts, 5);
+        assert_eq!(parsed.initial_backoff_ms, RetryPolicy::default().initial_backoff_ms);
+    }
+
+    /// Transport serving paginated tools; `pages: None` paginates forever
+    struct PagedTransport {
+        pages: Option<usize>,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl McpTransport for PagedTransport {
+        async fn connect(&mut self) -> McpResult<()> {
+            Ok(())
+        }
+        async fn disconnect(&mut self) -> McpResult<()> {
+            Ok(())
+        }
+        fn is_connected(&self) -> bool {
+            true
+        }
+        fn session_id(&self) -> Option<String> {
+            None
+        }
+        async fn initialize(&mut self, _params: InitializeParams) -> McpResult<InitializeResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_initialized(&self) -> McpResult<()> {
+            Ok(())
+        }
+        async fn list_tools(&self, cursor: Option<&str>) -> McpResult<ToolsListResult> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let page: usize = cursor.map(|c| c.parse().unwrap()).unwrap_or(0);
+            let tool = |name: String| Tool {
+                name,
+                description: None,
+                input_schema: serde_json::json!({}),
+            };
+            // Each page repeats the previous page's last tool to exercise dedup
+            let mut tools = vec![tool(format!("tool-{}", page)), tool(format!("tool-{}", page + 1))];
+            if page > 0 {
+                tools.push(tool(format!("tool-{}", page)));
+            }
+            let next_cursor = match self.pages {
+                Some(pages) if page + 1 >= pages => None,
+                _ => Some((page + 1).to_string()),
+            };
+            Ok(ToolsListResult { tools, next_cursor })
+        }
+        async fn call_tool(&self, _name: &str, _arguments: Option<serde_json::Value>) -> McpResult<ToolCallResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn list_resources(&self, _cursor: Option<&str>) -> McpResult<ResourcesListResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn read_resource(&self, _uri: &str) -> McpResult<serde_json::Value> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn list_prompts(&self, _cursor: Option<&str>) -> McpResult<PromptsListResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn get_prompt(&self, _name: &str, _arguments: Option<HashMap<String, String>>) -> McpResult<serde_json::Value> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn subscribe_resource(&self, _uri: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn unsubscribe_resource(&self, _uri: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        fn resource_updates(&self) -> broadcast::Receiver<ResourceUpdatedNotification> {
+            broadcast::channel(1).1
+        }
+        async fn set_log_level(&self, _level: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_request(&self, _request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_notification(&self, _method: &str, _params: Option<serde_json::Value>) -> McpResult<()> {
+            Ok(())
+        }
+        async fn ping(&self) -> McpResult<()> {
+            Ok(())
+        }
+        fn transport_type(&self) -> &'static str {
+            "paged"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_all_tools_follows_cursors() {
+        let transport = PagedTransport {
+            pages: Some(3),
+            calls: Default::default(),
+        };
+
+        let tools = transport.list_all_tools().await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+
+        assert_eq!(names, vec!["tool-0", "tool-1", "tool-2", "tool-3"]);
+        assert_eq!(transport.calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_list_all_tools_page_cap() {
+        let transport = PagedTransport {
+            pages: None,
+            calls: Default::default(),
+        };
+
+        transport.list_all_tools().await.unwrap();
+        assert_eq!(transport.calls.load(std::sync::atomic::Ordering::SeqCst), MAX_LIST_PAGES);
     }
 }
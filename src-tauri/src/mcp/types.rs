@@ -8,6 +8,33 @@ use std::collections::HashMap;
 /// Protocol version supported by Opcode 2.0
 pub const MCP_PROTOCOL_VERSION: &str = "2025-11-25";
 
+/// Protocol versions a server may answer initialize with, newest first
+pub const MCP_SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[MCP_PROTOCOL_VERSION, "2025-06-18", "2025-03-26"];
+
+/// Transport behaviour that differs between supported protocol versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolFeatures {
+    /// Requests after initialize carry an MCP-Protocol-Version header (2025-06-18+)
+    pub version_header: bool,
+    /// JSON-RPC batching is allowed (removed in 2025-06-18)
+    pub batching: bool,
+}
+
+impl ProtocolFeatures {
+    /// Features of a negotiated version, or None if we don't support it
+    pub fn for_version(version: &str) -> Option<Self> {
+        if !MCP_SUPPORTED_PROTOCOL_VERSIONS.contains(&version) {
+            return None;
+        }
+        // Versions are dates, so they order as strings
+        let modern = version >= "2025-06-18";
+        Some(Self {
+            version_header: modern,
+            batching: !modern,
+        })
+    }
+}
+
 /// JSON-RPC request structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
@@ -250,6 +277,21 @@ pub struct ToolCallResult {
     pub content: Vec<ToolResultContent>,
     #[serde(rename = "isError", skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
+    /// Machine-readable result matching the tool's outputSchema (2025-06-18+)
+    #[serde(rename = "structuredContent", default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<serde_json::Value>,
+}
+
+/// Incremental output from a streaming tool call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ToolCallEvent {
+    /// notifications/progress for the call
+    Progress(ProgressNotification),
+    /// notifications/message emitted while the tool runs
+    Log { level: String, data: serde_json::Value },
+    /// Final tool result
+    Complete(ToolCallResult),
 }
 
 /// MCP Resource definition
@@ -271,6 +313,149 @@ pub struct ResourcesListResult {
     pub next_cursor: Option<String>,
 }
 
+/// Directory offered to servers in answer to roots/list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Root {
+    /// Root for a local directory, named after its last component (None if the path isn't absolute)
+    pub fn from_path(path: &str) -> Option<Self> {
+        let uri = url::Url::from_file_path(path).ok()?;
+        let name = std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        Some(Self {
+            uri: uri.to_string(),
+            name,
+        })
+    }
+}
+
+/// Content of a sampling message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SamplingContent {
+    Text {
+        text: String,
+    },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+}
+
+/// Message in a sampling/createMessage conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    pub role: String,
+    pub content: SamplingContent,
+}
+
+/// Model name hint; servers list them in order of preference
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelHint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Server preferences for choosing a sampling model, priorities from 0 to 1
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelPreferences {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<ModelHint>,
+    #[serde(rename = "costPriority", default, skip_serializing_if = "Option::is_none")]
+    pub cost_priority: Option<f64>,
+    #[serde(rename = "speedPriority", default, skip_serializing_if = "Option::is_none")]
+    pub speed_priority: Option<f64>,
+    #[serde(rename = "intelligencePriority", default, skip_serializing_if = "Option::is_none")]
+    pub intelligence_priority: Option<f64>,
+}
+
+/// sampling/createMessage parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMessageParams {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(rename = "modelPreferences", default, skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<ModelPreferences>,
+    #[serde(rename = "systemPrompt", default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(rename = "stopSequences", default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+}
+
+/// sampling/createMessage result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMessageResult {
+    pub role: String,
+    pub content: SamplingContent,
+    pub model: String,
+    #[serde(rename = "stopReason", skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+/// notifications/resources/updated parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceUpdatedNotification {
+    pub uri: String,
+}
+
+/// Raw resources/read response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceResult {
+    pub contents: Vec<EmbeddedResource>,
+}
+
+/// Resource contents decoded into text or base64 blob
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResourceContents {
+    #[serde(rename = "text")]
+    Text {
+        uri: String,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        text: String,
+    },
+    #[serde(rename = "blob")]
+    Blob {
+        uri: String,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        blob: String,
+    },
+}
+
+impl From<EmbeddedResource> for ResourceContents {
+    fn from(resource: EmbeddedResource) -> Self {
+        match resource.blob {
+            Some(blob) => Self::Blob {
+                uri: resource.uri,
+                mime_type: resource.mime_type,
+                blob,
+            },
+            None => Self::Text {
+                uri: resource.uri,
+                mime_type: resource.mime_type,
+                text: resource.text.unwrap_or_default(),
+            },
+        }
+    }
+}
+
 /// MCP Prompt definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prompt {
@@ -281,6 +466,19 @@ pub struct Prompt {
     pub arguments: Option<Vec<PromptArgument>>,
 }
 
+impl Prompt {
+    /// Required arguments with no value in `arguments`
+    pub fn missing_arguments(&self, arguments: Option<&HashMap<String, String>>) -> Vec<&str> {
+        self.arguments
+            .iter()
+            .flatten()
+            .filter(|arg| arg.required == Some(true))
+            .filter(|arg| arguments.is_none_or(|given| !given.contains_key(&arg.name)))
+            .map(|arg| arg.name.as_str())
+            .collect()
+    }
+}
+
 /// Prompt argument definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptArgument {
@@ -299,6 +497,21 @@ pub struct PromptsListResult {
     pub next_cursor: Option<String>,
 }
 
+/// Message returned by prompts/get
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMessage {
+    pub role: String,
+    pub content: ToolResultContent,
+}
+
+/// prompts/get response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPromptResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub messages: Vec<PromptMessage>,
+}
+
 /// Remote MCP server configuration for Opcode
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteMcpServer {
@@ -340,6 +553,20 @@ pub enum McpAuthConfig {
     CustomHeader {
         headers: HashMap<String, String>
     },
+    #[serde(rename = "oauth2")]
+    OAuth {
+        access_token: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        refresh_token: Option<String>,
+        #[serde(alias = "token_endpoint")]
+        token_url: String,
+        client_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        client_secret: Option<String>,
+        /// Access token expiry (RFC3339)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expires_at: Option<String>,
+    },
 }
 
 /// Health check configuration
@@ -0,0 +1,967 @@
+//! WebSocket Transport Implementation
+//!
+//! Speaks JSON-RPC over a single ws:// or wss:// connection, one message per
+//! text frame. Responses are matched to requests by id, so requests may be in
+//! flight concurrently. A dropped socket is reopened in the background and the
+//! session re-initialized, including resource subscriptions.
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, error, info, warn};
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+use super::auth::McpAuth;
+use super::error::{McpError, McpResult};
+use super::streamable_http::{dispatch_notification, sampling_reply, sampling_request, server_request_reply};
+use super::transport::{McpTransport, RetryPolicy, SamplingHandler, TlsOptions, TransportEvent};
+use super::types::*;
+
+/// Capacity of the notification broadcast channels
+const NOTIFICATIONS_CAPACITY: usize = 64;
+
+/// Time allowed for the TCP, TLS and WebSocket handshakes
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default interval between WebSocket pings
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// State shared between the transport and its socket and reconnect tasks
+struct Shared {
+    /// ws:// or wss:// endpoint
+    url: Url,
+    /// Authentication applied to the opening handshake
+    auth: tokio::sync::Mutex<Option<Box<dyn McpAuth>>>,
+    /// Connector carrying custom TLS options (None uses the system defaults)
+    tls_connector: Option<native_tls::TlsConnector>,
+    /// Per-server headers sent with the opening handshake; auth headers win
+    extra_headers: reqwest::header::HeaderMap,
+    /// Request timeout in milliseconds
+    timeout_ms: u64,
+    /// Backoff between reconnect attempts after the socket drops
+    retry_policy: RetryPolicy,
+    /// Interval between WebSocket pings; a ping unanswered for a full interval drops the socket
+    keepalive_interval: Duration,
+    /// Handshake completed and the socket is open
+    connected: RwLock<bool>,
+    /// Set by disconnect so the closing socket isn't reopened
+    closing: AtomicBool,
+    /// Protocol version the server chose during initialize
+    protocol_version: RwLock<Option<String>>,
+    /// Server capabilities (cached after initialization)
+    server_capabilities: RwLock<Option<ServerCapabilities>>,
+    /// Server info (cached after initialization)
+    server_info: RwLock<Option<ServerInfo>>,
+    /// Usage guidance the server sent with its initialize result
+    instructions: RwLock<Option<String>>,
+    /// Subscribed resource URIs, restored after a reconnect
+    subscriptions: RwLock<HashSet<String>>,
+    /// Roots returned when the server sends roots/list
+    roots: RwLock<Vec<Root>>,
+    /// Answers sampling/createMessage requests (None rejects them)
+    sampling: RwLock<Option<Arc<dyn SamplingHandler>>>,
+    /// Request ID counter for JSON-RPC
+    request_id: AtomicU64,
+    /// Frames for the socket task to write (None while the socket is closed)
+    outgoing: RwLock<Option<mpsc::UnboundedSender<Message>>>,
+    /// Requests waiting for a response, keyed by serialized JSON-RPC id
+    pending: DashMap<String, oneshot::Sender<JsonRpcResponse>>,
+    /// Broadcasts notifications/resources/updated events
+    resource_updates: broadcast::Sender<ResourceUpdatedNotification>,
+    /// Broadcasts server notifications and connection state changes
+    notifications: broadcast::Sender<TransportEvent>,
+    /// Task reading and writing the open socket
+    socket_task: parking_lot::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Task reopening a dropped socket
+    reconnect_task: parking_lot::Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl Shared {
+    fn next_request_id(&self) -> u64 {
+        self.request_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    fn is_connected(&self) -> bool {
+        *self.connected.read()
+    }
+
+    /// Refresh credentials after the server rejected them (returns true if refreshed)
+    async fn refresh_auth(&self) -> bool {
+        let mut guard = self.auth.lock().await;
+        let auth = match guard.as_mut() {
+            Some(auth) => auth,
+            None => return false,
+        };
+
+        match auth.refresh().await {
+            Ok(refreshed) => {
+                if refreshed {
+                    info!("Refreshed {} credentials for {}", auth.auth_type(), self.url);
+                }
+                refreshed
+            }
+            Err(e) => {
+                warn!("Failed to refresh {} credentials: {}", auth.auth_type(), e);
+                false
+            }
+        }
+    }
+
+    /// Refresh credentials ahead of the handshake if they have expired or are about to
+    async fn refresh_auth_if_needed(&self) -> McpResult<()> {
+        let auth_valid = match *self.auth.lock().await {
+            Some(ref auth) => auth.is_valid().await,
+            None => true,
+        };
+        if !auth_valid && !self.refresh_auth().await {
+            return Err(McpError::TokenExpired);
+        }
+        Ok(())
+    }
+
+    /// Build the opening handshake request, carrying the auth headers
+    async fn handshake_request(&self) -> McpResult<tungstenite::handshake::client::Request> {
+        let mut request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| McpError::InvalidConfig(format!("Invalid WebSocket URL: {}", e)))?;
+
+        // Extra headers first, so auth headers replace any that clash
+        request.headers_mut().extend(self.extra_headers.clone());
+
+        // McpAuth decorates reqwest requests, so borrow the headers from one
+        if let Some(ref auth) = *self.auth.lock().await {
+            let decorated = auth.apply(reqwest::Client::new().get(self.url.clone())).build()?;
+            request.headers_mut().extend(decorated.headers().clone());
+        }
+        Ok(request)
+    }
+
+    /// Open the socket and start the task that reads and writes it
+    async fn open(self: &Arc<Self>) -> McpResult<()> {
+        let request = self.handshake_request().await?;
+        let connector = self.tls_connector.clone().map(Connector::NativeTls);
+        let handshake = tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector);
+
+        let socket = match tokio::time::timeout(CONNECT_TIMEOUT, handshake).await {
+            Ok(Ok((socket, _))) => socket,
+            Ok(Err(e)) => return Err(handshake_error(e)),
+            Err(_) => return Err(McpError::ConnectionTimeout(CONNECT_TIMEOUT.as_millis() as u64)),
+        };
+        debug!("Opened WebSocket to {}", self.url);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.outgoing.write() = Some(tx);
+        let task = tokio::spawn(run_socket(self.clone(), socket, rx));
+        if let Some(previous) = self.socket_task.lock().replace(task) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// Open the socket, retrying once if the server rejects credentials that can be refreshed
+    async fn open_with_auth_retry(self: &Arc<Self>) -> McpResult<()> {
+        match self.open().await {
+            Err(McpError::AuthenticationFailed(reason)) => {
+                if !self.refresh_auth().await {
+                    return Err(McpError::AuthenticationFailed(reason));
+                }
+                self.open().await
+            }
+            result => result,
+        }
+    }
+
+    /// Open the socket and run the initialize handshake
+    async fn establish(self: &Arc<Self>) -> McpResult<()> {
+        self.refresh_auth_if_needed().await?;
+        self.open_with_auth_retry().await?;
+        let result = self.initialize(InitializeParams::default()).await?;
+        self.complete_handshake(&result)
+    }
+
+    async fn initialize(self: &Arc<Self>, params: InitializeParams) -> McpResult<InitializeResult> {
+        let open = self.outgoing.read().is_some();
+        if !open {
+            self.open_with_auth_retry().await?;
+        }
+
+        let result: InitializeResult =
+            serde_json::from_value(self.call("initialize", Some(serde_json::to_value(&params)?)).await?)?;
+
+        // Same negotiation rules as the HTTP transport
+        if ProtocolFeatures::for_version(&result.protocol_version).is_none() {
+            return Err(McpError::ProtocolVersionMismatch {
+                expected: params.protocol_version,
+                actual: result.protocol_version,
+            });
+        }
+        if result.protocol_version != params.protocol_version {
+            info!(
+                "MCP server negotiated older protocol version {} (requested {})",
+                result.protocol_version, params.protocol_version
+            );
+        }
+        *self.protocol_version.write() = Some(result.protocol_version.clone());
+
+        Ok(result)
+    }
+
+    /// Cache the initialize result and send the initialized notification
+    fn complete_handshake(&self, result: &InitializeResult) -> McpResult<()> {
+        *self.server_info.write() = Some(result.server_info.clone());
+        *self.server_capabilities.write() = Some(result.capabilities.clone());
+        *self.instructions.write() = result.instructions.clone();
+
+        self.notify("notifications/initialized", None)?;
+
+        *self.connected.write() = true;
+        let _ = self
+            .notifications
+            .send(TransportEvent::ConnectionStateChanged { connected: true });
+        info!(
+            "Connected to MCP server: {} over WebSocket (protocol: {})",
+            result.server_info.name, result.protocol_version
+        );
+        Ok(())
+    }
+
+    /// Queue a frame for the socket task
+    fn send_frame(&self, frame: Message) -> McpResult<()> {
+        match self.outgoing.read().as_ref() {
+            Some(tx) => tx.send(frame).map_err(|_| McpError::NotConnected),
+            None => Err(McpError::NotConnected),
+        }
+    }
+
+    fn notify(&self, method: &str, params: Option<serde_json::Value>) -> McpResult<()> {
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        });
+        self.send_frame(Message::text(notification.to_string()))
+    }
+
+    /// Send a request and wait for the response with the same id
+    async fn request(&self, request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+        debug!("Sending MCP request: {} (id: {:?})", request.method, request.id);
+        let key = request.id.to_string();
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(key.clone(), tx);
+
+        if let Err(e) = self.send_frame(Message::text(serde_json::to_string(&request)?)) {
+            self.pending.remove(&key);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(Duration::from_millis(self.timeout_ms), rx).await {
+            Ok(Ok(response)) => match response.error {
+                Some(ref error) => Err(McpError::JsonRpcError {
+                    code: error.code,
+                    message: error.message.clone(),
+                }),
+                None => Ok(response),
+            },
+            Ok(Err(_)) => Err(McpError::ConnectionFailed(
+                "WebSocket closed before the response arrived".to_string(),
+            )),
+            Err(_) => {
+                self.pending.remove(&key);
+                Err(McpError::ConnectionTimeout(self.timeout_ms))
+            }
+        }
+    }
+
+    /// Send a request and return its result
+    async fn call(&self, method: &str, params: Option<serde_json::Value>) -> McpResult<serde_json::Value> {
+        let request = JsonRpcRequest::new(method, params, self.next_request_id());
+        self.request(request)
+            .await?
+            .result
+            .ok_or_else(|| McpError::InvalidResponse("Missing result".to_string()))
+    }
+
+    /// Route an incoming text frame, returning the reply to a server request if any
+    fn handle_message(self: &Arc<Self>, text: &str) -> Option<Message> {
+        let message: serde_json::Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Ignoring malformed message from {}: {}", self.url, e);
+                return None;
+            }
+        };
+        let id = message.get("id").filter(|id| !id.is_null()).cloned();
+
+        match (message.get("method").and_then(|m| m.as_str()), id) {
+            (Some(_), Some(_)) => {
+                let handler = self.sampling.read().clone();
+                if let Some((handler, (id, params))) = handler.zip(sampling_request(text)) {
+                    // Answered from its own task so a slow completion doesn't stall the socket
+                    let shared = self.clone();
+                    tokio::spawn(async move {
+                        let reply = sampling_reply(handler, id, params).await;
+                        if let Err(e) = shared.send_frame(Message::text(reply.to_string())) {
+                            warn!("Failed to send sampling reply to {}: {}", shared.url, e);
+                        }
+                    });
+                    return None;
+                }
+                server_request_reply(text, &self.roots.read()).map(|reply| Message::text(reply.to_string()))
+            }
+            (Some(_), None) => {
+                let event = SseEvent {
+                    event: None,
+                    data: text.to_string(),
+                    id: None,
+                };
+                dispatch_notification(&event, &self.notifications, &self.resource_updates);
+                None
+            }
+            (None, Some(id)) => {
+                match serde_json::from_value::<JsonRpcResponse>(message) {
+                    Ok(response) => match self.pending.remove(&id.to_string()) {
+                        Some((_, tx)) => {
+                            let _ = tx.send(response);
+                        }
+                        None => debug!("Dropping response to unknown or timed out request {}", id),
+                    },
+                    Err(e) => warn!("Ignoring malformed response from {}: {}", self.url, e),
+                }
+                None
+            }
+            (None, None) => {
+                debug!("Ignoring message without id or method from {}", self.url);
+                None
+            }
+        }
+    }
+
+    /// Fail in-flight requests and reconnect unless the socket was closed on purpose
+    fn connection_lost(self: &Arc<Self>, reason: &str) {
+        *self.outgoing.write() = None;
+        // Dropping the senders wakes each waiting request with an error
+        self.pending.clear();
+
+        let was_connected = std::mem::replace(&mut *self.connected.write(), false);
+        if !was_connected || self.closing.load(Ordering::SeqCst) {
+            return;
+        }
+
+        warn!("WebSocket connection to {} dropped: {}", self.url, reason);
+        let _ = self
+            .notifications
+            .send(TransportEvent::ConnectionStateChanged { connected: false });
+
+        let shared = self.clone();
+        let task = tokio::spawn(async move { shared.reconnect().await });
+        *self.reconnect_task.lock() = Some(task);
+    }
+
+    /// Reopen the socket with backoff, re-initializing the session and its subscriptions
+    async fn reconnect(self: Arc<Self>) {
+        let max_attempts = self.retry_policy.max_attempts.max(1);
+        for attempt in 1..=max_attempts {
+            tokio::time::sleep(self.retry_policy.backoff(attempt)).await;
+            if self.closing.load(Ordering::SeqCst) {
+                return;
+            }
+
+            match self.establish().await {
+                Ok(()) => {
+                    self.resubscribe().await;
+                    return;
+                }
+                Err(e) => warn!(
+                    "WebSocket reconnect attempt {}/{} to {} failed: {}",
+                    attempt, max_attempts, self.url, e
+                ),
+            }
+        }
+
+        error!("Giving up reconnecting to MCP server at {}", self.url);
+        let _ = self.notifications.send(TransportEvent::Error(format!(
+            "Lost WebSocket connection after {} reconnect attempts",
+            max_attempts
+        )));
+    }
+
+    /// Restore resource subscriptions on a new session
+    async fn resubscribe(&self) {
+        let uris: Vec<String> = self.subscriptions.read().iter().cloned().collect();
+        for uri in uris {
+            if let Err(e) = self.call("resources/subscribe", Some(serde_json::json!({ "uri": uri }))).await {
+                warn!("Failed to restore subscription to {}: {}", uri, e);
+            }
+        }
+    }
+}
+
+/// Read and write the socket until it closes, then report the loss
+async fn run_socket(shared: Arc<Shared>, socket: Socket, mut outgoing: mpsc::UnboundedReceiver<Message>) {
+    let (mut sink, mut stream) = socket.split();
+    let interval = shared.keepalive_interval;
+    let mut keepalive = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    let mut awaiting_pong = false;
+
+    let reason = loop {
+        tokio::select! {
+            frame = outgoing.recv() => match frame {
+                Some(frame) => {
+                    let closing = matches!(frame, Message::Close(_));
+                    if let Err(e) = sink.send(frame).await {
+                        break e.to_string();
+                    }
+                    if closing {
+                        break "closed by client".to_string();
+                    }
+                }
+                None => {
+                    let _ = sink.close().await;
+                    break "closed by client".to_string();
+                }
+            },
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if let Some(reply) = shared.handle_message(text.as_str()) {
+                        if let Err(e) = sink.send(reply).await {
+                            break e.to_string();
+                        }
+                    }
+                }
+                Some(Ok(Message::Pong(_))) => awaiting_pong = false,
+                Some(Ok(Message::Close(frame))) => break format!("closed by server ({:?})", frame),
+                // Pings are answered by tungstenite; binary frames aren't part of the protocol
+                Some(Ok(_)) => {}
+                Some(Err(e)) => break e.to_string(),
+                None => break "connection closed".to_string(),
+            },
+            _ = keepalive.tick() => {
+                if awaiting_pong {
+                    break "no pong within the keepalive interval".to_string();
+                }
+                awaiting_pong = true;
+                if let Err(e) = sink.send(Message::Ping(Default::default())).await {
+                    break e.to_string();
+                }
+            }
+        }
+    };
+
+    shared.connection_lost(&reason);
+}
+
+/// Map a failed opening handshake to an MCP error
+fn handshake_error(err: tungstenite::Error) -> McpError {
+    match err {
+        tungstenite::Error::Http(ref response) if matches!(response.status().as_u16(), 401 | 403) => {
+            McpError::AuthenticationFailed(format!("Server rejected credentials (HTTP {})", response.status()))
+        }
+        other => McpError::ConnectionFailed(other.to_string()),
+    }
+}
+
+/// Build a TLS connector for a private CA, client certificate, or relaxed validation
+fn build_tls_connector(tls: &TlsOptions) -> McpResult<native_tls::TlsConnector> {
+    let read = |path: &str| {
+        std::fs::read(path).map_err(|e| McpError::InvalidConfig(format!("Failed to read {}: {}", path, e)))
+    };
+    let mut builder = native_tls::TlsConnector::builder();
+
+    if let Some(ref ca_path) = tls.ca_cert_path {
+        let cert = native_tls::Certificate::from_pem(&read(ca_path)?)
+            .map_err(|e| McpError::InvalidConfig(format!("Invalid CA certificate {}: {}", ca_path, e)))?;
+        builder.add_root_certificate(cert);
+    }
+
+    match (&tls.client_cert_path, &tls.client_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let identity = native_tls::Identity::from_pkcs8(&read(cert_path)?, &read(key_path)?)
+                .map_err(|e| McpError::InvalidConfig(format!("Invalid client certificate: {}", e)))?;
+            builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(McpError::InvalidConfig(
+                "Client certificate and key must be provided together".to_string(),
+            ))
+        }
+    }
+
+    if tls.accept_invalid_certs {
+        warn!("TLS certificate validation is disabled for this MCP server");
+        builder.danger_accept_invalid_certs(true);
+    }
+
+    builder
+        .build()
+        .map_err(|e| McpError::TransportError(e.to_string()))
+}
+
+/// WebSocket Transport implementation
+pub struct WebSocketTransport {
+    shared: Arc<Shared>,
+}
+
+impl WebSocketTransport {
+    /// Create a new WebSocket transport for a ws:// or wss:// URL
+    pub fn new(url: impl Into<String>, auth: Option<Box<dyn McpAuth>>, timeout_ms: u64) -> McpResult<Self> {
+        let url = Url::parse(&url.into())?;
+        if url.scheme() != "ws" && url.scheme() != "wss" {
+            return Err(McpError::InvalidConfig(format!(
+                "WebSocket transport needs a ws:// or wss:// URL, got {}://",
+                url.scheme()
+            )));
+        }
+
+        Ok(Self {
+            shared: Arc::new(Shared {
+                url,
+                auth: tokio::sync::Mutex::new(auth),
+                tls_connector: None,
+                extra_headers: reqwest::header::HeaderMap::new(),
+                timeout_ms,
+                retry_policy: RetryPolicy::default(),
+                keepalive_interval: DEFAULT_KEEPALIVE_INTERVAL,
+                connected: RwLock::new(false),
+                closing: AtomicBool::new(false),
+                protocol_version: RwLock::new(None),
+                server_capabilities: RwLock::new(None),
+                server_info: RwLock::new(None),
+                instructions: RwLock::new(None),
+                subscriptions: RwLock::new(HashSet::new()),
+                roots: RwLock::new(Vec::new()),
+                sampling: RwLock::new(None),
+                request_id: AtomicU64::new(1),
+                outgoing: RwLock::new(None),
+                pending: DashMap::new(),
+                resource_updates: broadcast::channel(NOTIFICATIONS_CAPACITY).0,
+                notifications: broadcast::channel(NOTIFICATIONS_CAPACITY).0,
+                socket_task: parking_lot::Mutex::new(None),
+                reconnect_task: parking_lot::Mutex::new(None),
+            }),
+        })
+    }
+
+    /// Shared state for the builder methods, which run before any task holds a reference
+    fn shared_mut(&mut self) -> &mut Shared {
+        Arc::get_mut(&mut self.shared).expect("WebSocketTransport configured after connecting")
+    }
+
+    /// Trust a private CA, present a client certificate, or relax validation
+    ///
+    /// Fails if a certificate or key file can't be read or parsed.
+    pub fn with_tls_options(mut self, tls: &TlsOptions) -> McpResult<Self> {
+        self.shared_mut().tls_connector = Some(build_tls_connector(tls)?);
+        Ok(self)
+    }
+
+    /// Send these headers with the opening handshake, e.g. a tenant ID for a gateway
+    pub fn with_extra_headers(mut self, headers: reqwest::header::HeaderMap) -> Self {
+        self.shared_mut().extra_headers = headers;
+        self
+    }
+
+    /// Set the backoff policy for reconnecting after the socket drops
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.shared_mut().retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the interval between WebSocket pings
+    pub fn with_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.shared_mut().keepalive_interval = interval;
+        self
+    }
+
+    /// Receive server notifications and connection state changes
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<TransportEvent> {
+        self.shared.notifications.subscribe()
+    }
+
+    fn ensure_connected(&self) -> McpResult<()> {
+        if self.shared.is_connected() {
+            Ok(())
+        } else {
+            Err(McpError::NotConnected)
+        }
+    }
+}
+
+impl Drop for WebSocketTransport {
+    fn drop(&mut self) {
+        // The tasks hold the shared state, so they must be stopped explicitly
+        self.shared.closing.store(true, Ordering::SeqCst);
+        if let Some(task) = self.shared.reconnect_task.lock().take() {
+            task.abort();
+        }
+        if let Some(task) = self.shared.socket_task.lock().take() {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl McpTransport for WebSocketTransport {
+    async fn connect(&mut self) -> McpResult<()> {
+        info!("Connecting to MCP server at {}", self.shared.url);
+        self.shared.closing.store(false, Ordering::SeqCst);
+        self.shared.establish().await
+    }
+
+    async fn disconnect(&mut self) -> McpResult<()> {
+        let shared = &self.shared;
+        shared.closing.store(true, Ordering::SeqCst);
+        if let Some(task) = shared.reconnect_task.lock().take() {
+            task.abort();
+        }
+
+        let was_connected = std::mem::replace(&mut *shared.connected.write(), false);
+        let outgoing = shared.outgoing.write().take();
+        if let Some(tx) = outgoing {
+            info!("Disconnecting from MCP server at {}", shared.url);
+            let _ = tx.send(Message::Close(None));
+        }
+        shared.pending.clear();
+
+        // Clear session state
+        shared.subscriptions.write().clear();
+        *shared.protocol_version.write() = None;
+        *shared.server_capabilities.write() = None;
+        *shared.server_info.write() = None;
+        *shared.instructions.write() = None;
+
+        if was_connected {
+            let _ = shared
+                .notifications
+                .send(TransportEvent::ConnectionStateChanged { connected: false });
+        }
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.shared.is_connected()
+    }
+
+    fn session_id(&self) -> Option<String> {
+        // The socket itself is the session
+        None
+    }
+
+    fn protocol_version(&self) -> Option<String> {
+        self.shared.protocol_version.read().clone()
+    }
+
+    fn server_capabilities(&self) -> Option<ServerCapabilities> {
+        self.shared.server_capabilities.read().clone()
+    }
+
+    fn server_info(&self) -> Option<ServerInfo> {
+        self.shared.server_info.read().clone()
+    }
+
+    fn server_instructions(&self) -> Option<String> {
+        self.shared.instructions.read().clone()
+    }
+
+    fn notifications(&self) -> Option<broadcast::Receiver<TransportEvent>> {
+        Some(self.subscribe_notifications())
+    }
+
+    fn set_roots(&self, roots: Vec<Root>) {
+        *self.shared.roots.write() = roots;
+    }
+
+    fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>) {
+        *self.shared.sampling.write() = Some(handler);
+    }
+
+    async fn initialize(&mut self, params: InitializeParams) -> McpResult<InitializeResult> {
+        self.shared.initialize(params).await
+    }
+
+    async fn send_initialized(&self) -> McpResult<()> {
+        self.shared.notify("notifications/initialized", None)
+    }
+
+    async fn list_tools(&self, cursor: Option<&str>) -> McpResult<ToolsListResult> {
+        self.ensure_connected()?;
+        let params = cursor.map(|c| serde_json::json!({ "cursor": c }));
+        serde_json::from_value(self.shared.call("tools/list", params).await?).map_err(McpError::from)
+    }
+
+    async fn call_tool(&self, name: &str, arguments: Option<serde_json::Value>) -> McpResult<ToolCallResult> {
+        self.ensure_connected()?;
+        let params = ToolCallParams {
+            name: name.to_string(),
+            arguments,
+        };
+        let result = self.shared.call("tools/call", Some(serde_json::to_value(&params)?)).await?;
+        serde_json::from_value(result).map_err(McpError::from)
+    }
+
+    async fn list_resources(&self, cursor: Option<&str>) -> McpResult<ResourcesListResult> {
+        self.ensure_connected()?;
+        let params = cursor.map(|c| serde_json::json!({ "cursor": c }));
+        serde_json::from_value(self.shared.call("resources/list", params).await?).map_err(McpError::from)
+    }
+
+    async fn read_resource(&self, uri: &str) -> McpResult<serde_json::Value> {
+        self.ensure_connected()?;
+        self.shared.call("resources/read", Some(serde_json::json!({ "uri": uri }))).await
+    }
+
+    async fn list_prompts(&self, cursor: Option<&str>) -> McpResult<PromptsListResult> {
+        self.ensure_connected()?;
+        let params = cursor.map(|c| serde_json::json!({ "cursor": c }));
+        serde_json::from_value(self.shared.call("prompts/list", params).await?).map_err(McpError::from)
+    }
+
+    async fn get_prompt(&self, name: &str, arguments: Option<HashMap<String, String>>) -> McpResult<serde_json::Value> {
+        self.ensure_connected()?;
+        let params = serde_json::json!({
+            "name": name,
+            "arguments": arguments
+        });
+        self.shared.call("prompts/get", Some(params)).await
+    }
+
+    async fn subscribe_resource(&self, uri: &str) -> McpResult<()> {
+        self.ensure_connected()?;
+        self.shared.call("resources/subscribe", Some(serde_json::json!({ "uri": uri }))).await?;
+        self.shared.subscriptions.write().insert(uri.to_string());
+        debug!("Subscribed to MCP resource {}", uri);
+        Ok(())
+    }
+
+    async fn unsubscribe_resource(&self, uri: &str) -> McpResult<()> {
+        self.ensure_connected()?;
+        self.shared.call("resources/unsubscribe", Some(serde_json::json!({ "uri": uri }))).await?;
+        self.shared.subscriptions.write().remove(uri);
+        debug!("Unsubscribed from MCP resource {}", uri);
+        Ok(())
+    }
+
+    fn resource_updates(&self) -> broadcast::Receiver<ResourceUpdatedNotification> {
+        self.shared.resource_updates.subscribe()
+    }
+
+    async fn set_log_level(&self, level: &str) -> McpResult<()> {
+        self.ensure_connected()?;
+        let supported = self
+            .shared
+            .server_capabilities
+            .read()
+            .as_ref()
+            .is_some_and(|caps| caps.logging.is_some());
+        if !supported {
+            return Err(McpError::InvalidConfig("Server does not support logging".to_string()));
+        }
+
+        self.shared.call("logging/setLevel", Some(serde_json::json!({ "level": level }))).await?;
+        debug!("Set MCP server log level to {}", level);
+        Ok(())
+    }
+
+    async fn send_request(&self, request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+        self.shared.request(request).await
+    }
+
+    async fn send_notification(&self, method: &str, params: Option<serde_json::Value>) -> McpResult<()> {
+        self.shared.notify(method, params)
+    }
+
+    async fn ping(&self) -> McpResult<()> {
+        self.shared.call("ping", None).await?;
+        Ok(())
+    }
+
+    fn transport_type(&self) -> &'static str {
+        "websocket"
+    }
+}
+
+impl std::fmt::Debug for WebSocketTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSocketTransport")
+            .field("url", &self.shared.url)
+            .field("connected", &self.shared.is_connected())
+            .field("protocol_version", &*self.shared.protocol_version.read())
+            .field("timeout_ms", &self.shared.timeout_ms)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::ws::{Message as ServerMessage, WebSocket, WebSocketUpgrade};
+
+    #[derive(Default)]
+    struct MockServer {
+        initializations: AtomicU64,
+    }
+
+    fn mock_response(id: &serde_json::Value, result: serde_json::Value) -> ServerMessage {
+        let response = serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result });
+        ServerMessage::Text(response.to_string().into())
+    }
+
+    /// Answers requests in order, except that test/hold is answered after the next request
+    /// and test/drop closes the socket
+    async fn serve_socket(mut socket: WebSocket, server: Arc<MockServer>) {
+        let mut held: Option<ServerMessage> = None;
+        while let Some(Ok(message)) = socket.recv().await {
+            let text = match message {
+                ServerMessage::Text(text) => text,
+                _ => continue,
+            };
+            let request: serde_json::Value = serde_json::from_str(text.as_str()).unwrap();
+            let id = match request.get("id") {
+                Some(id) => id.clone(),
+                None => continue,
+            };
+
+            let result = match request["method"].as_str().unwrap() {
+                "initialize" => {
+                    server.initializations.fetch_add(1, Ordering::SeqCst);
+                    serde_json::json!({
+                        "protocolVersion": MCP_PROTOCOL_VERSION,
+                        "capabilities": {},
+                        "serverInfo": { "name": "mock" }
+                    })
+                }
+                "tools/list" => {
+                    let log = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/message",
+                        "params": { "level": "info", "data": "listing tools" }
+                    });
+                    socket.send(ServerMessage::Text(log.to_string().into())).await.unwrap();
+                    serde_json::json!({ "tools": [] })
+                }
+                "test/hold" => {
+                    held = Some(mock_response(&id, serde_json::json!({ "method": "test/hold" })));
+                    continue;
+                }
+                "test/drop" => return,
+                method => serde_json::json!({ "method": method }),
+            };
+
+            socket.send(mock_response(&id, result)).await.unwrap();
+            if let Some(held) = held.take() {
+                socket.send(held).await.unwrap();
+            }
+        }
+    }
+
+    async fn mock_ws_handler(
+        ws: WebSocketUpgrade,
+        axum::extract::State(server): axum::extract::State<Arc<MockServer>>,
+    ) -> axum::response::Response {
+        ws.on_upgrade(move |socket| serve_socket(socket, server))
+    }
+
+    async fn spawn_mock_server() -> (String, Arc<MockServer>) {
+        let server = Arc::new(MockServer::default());
+        let app = axum::Router::new()
+            .route("/mcp", axum::routing::get(mock_ws_handler))
+            .with_state(server.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("ws://{}/mcp", addr), server)
+    }
+
+    #[test]
+    fn test_rejects_non_websocket_urls() {
+        assert!(WebSocketTransport::new("wss://mcp.example.com/ws", None, 5000).is_ok());
+        assert!(WebSocketTransport::new("https://mcp.example.com/mcp", None, 5000).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_matched_to_requests_by_id() {
+        let (url, _server) = spawn_mock_server().await;
+        let mut transport = WebSocketTransport::new(url, None, 5000).unwrap();
+        transport.connect().await.unwrap();
+
+        // The server answers the second request before the first
+        let (held, next) = tokio::join!(
+            transport.send_request(JsonRpcRequest::new("test/hold", None, 100)),
+            async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                transport.send_request(JsonRpcRequest::new("test/next", None, 101)).await
+            }
+        );
+
+        assert_eq!(held.unwrap().result.unwrap()["method"], "test/hold");
+        assert_eq!(next.unwrap().result.unwrap()["method"], "test/next");
+    }
+
+    #[tokio::test]
+    async fn test_notifications_are_forwarded() {
+        let (url, _server) = spawn_mock_server().await;
+        let mut transport = WebSocketTransport::new(url, None, 5000).unwrap();
+        transport.connect().await.unwrap();
+        let mut events = transport.subscribe_notifications();
+
+        transport.list_tools(None).await.unwrap();
+
+        match events.recv().await.unwrap() {
+            TransportEvent::Log { level, message, .. } => {
+                assert_eq!(level, "info");
+                assert_eq!(message, "listing tools");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_and_reinitializes_after_drop() {
+        let (url, server) = spawn_mock_server().await;
+        let retry = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 50,
+            jitter: false,
+        };
+        let mut transport = WebSocketTransport::new(url, None, 5000)
+            .unwrap()
+            .with_retry_policy(retry);
+        transport.connect().await.unwrap();
+        let mut events = transport.subscribe_notifications();
+
+        // The request in flight fails when the socket drops
+        assert!(transport.send_request(JsonRpcRequest::new("test/drop", None, 100)).await.is_err());
+
+        let reconnected = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(TransportEvent::ConnectionStateChanged { connected: true }) = events.recv().await {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(reconnected.is_ok());
+        assert!(transport.is_connected());
+        assert_eq!(server.initializations.load(Ordering::SeqCst), 2);
+        transport.list_tools(None).await.unwrap();
+    }
+}
@@ -5,7 +5,7 @@
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter};
 
-use super::state::SessionStatus;
+use super::state::{SessionStatus, TokenUsage};
 
 /// Events that can be emitted during a session
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +51,14 @@ pub enum SessionEvent {
         success: bool,
     },
 
+    /// Tool call stopped by a blocking PreTool hook
+    ToolBlocked {
+        session_id: String,
+        tool_name: String,
+        hook_name: String,
+        reason: String,
+    },
+
     /// Token usage update
     TokenUsage {
         session_id: String,
@@ -116,6 +124,7 @@ impl SessionEvent {
             Self::Error { session_id, .. } => session_id,
             Self::ToolStart { session_id, .. } => session_id,
             Self::ToolComplete { session_id, .. } => session_id,
+            Self::ToolBlocked { session_id, .. } => session_id,
             Self::TokenUsage { session_id, .. } => session_id,
             Self::Progress { session_id, .. } => session_id,
             Self::Thinking { session_id, .. } => session_id,
@@ -132,6 +141,7 @@ impl SessionEvent {
             Self::Error { .. } => "session-error",
             Self::ToolStart { .. } => "session-tool-start",
             Self::ToolComplete { .. } => "session-tool-complete",
+            Self::ToolBlocked { .. } => "session-tool-blocked",
             Self::TokenUsage { .. } => "session-tokens",
             Self::Progress { .. } => "session-progress",
             Self::Thinking { .. } => "session-thinking",
@@ -149,6 +159,7 @@ impl SessionEvent {
             Self::Error { .. } => "session-error",
             Self::ToolStart { .. } => "session-tool-start",
             Self::ToolComplete { .. } => "session-tool-complete",
+            Self::ToolBlocked { .. } => "session-tool-blocked",
             Self::TokenUsage { .. } => "session-tokens",
             Self::Progress { .. } => "session-progress",
             Self::Thinking { .. } => "session-thinking",
@@ -232,6 +243,18 @@ impl SessionEventEmitter {
         self.emit_to_session(&event)
     }
 
+    /// Emit cumulative token usage
+    pub fn emit_token_usage(&self, session_id: &str, usage: &TokenUsage) -> Result<(), tauri::Error> {
+        let event = SessionEvent::TokenUsage {
+            session_id: session_id.to_string(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_tokens,
+            cache_write_tokens: usage.cache_write_tokens,
+        };
+        self.emit_to_session(&event)
+    }
+
     /// Emit thinking content (for Ctrl+O mode)
     pub fn emit_thinking(&self, session_id: &str, content: impl Into<String>) -> Result<(), tauri::Error> {
         let event = SessionEvent::Thinking {
@@ -257,5 +280,14 @@ mod tests {
         assert_eq!(event.event_name(), "session-output:test-123");
         assert_eq!(event.global_event_name(), "session-output");
         assert_eq!(event.session_id(), "test-123");
+
+        let event = SessionEvent::ToolBlocked {
+            session_id: "test-123".to_string(),
+            tool_name: "Bash".to_string(),
+            hook_name: "guard".to_string(),
+            reason: "destructive".to_string(),
+        };
+        assert_eq!(event.event_name(), "session-tool-blocked:test-123");
+        assert_eq!(event.global_event_name(), "session-tool-blocked");
     }
 }
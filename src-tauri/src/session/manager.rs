@@ -3,16 +3,29 @@
 //! Lock-free concurrent session management using DashMap.
 //! Supports multiple simultaneous Claude sessions with proper isolation.
 
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use futures::stream::{self, StreamExt};
 use log::{debug, error, info, warn};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use rusqlite::{params, Connection};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::process::Child;
 use tokio::sync::oneshot;
 
 use super::events::{SessionEvent, SessionEventEmitter};
-use super::state::{SessionInfo, SessionState, SessionStatus};
+use super::replay::ReplaySubscription;
+use super::state::{SessionInfo, SessionMetrics, SessionState, SessionStatus, TokenUsage};
+
+/// Time a running session may go without activity before it is cancelled
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 30 * 60;
+
+/// How long a cancelled process gets to exit by default
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most sessions `shutdown_all` stops at once
+const SHUTDOWN_CONCURRENCY: usize = 16;
 
 /// Managed process with kill capability
 pub struct ManagedProcess {
@@ -122,6 +135,12 @@ pub struct SessionManager {
     max_sessions: usize,
     /// Session timeout in seconds (for cleanup)
     session_timeout_secs: u64,
+    /// Time without activity after which a running session is cancelled
+    idle_timeout: Duration,
+    /// Database sessions are saved to on every state change
+    db: Option<Arc<Mutex<Connection>>>,
+    /// How long a cancelled process gets to exit before it is killed
+    shutdown_timeout: Duration,
 }
 
 impl SessionManager {
@@ -132,6 +151,9 @@ impl SessionManager {
             processes: Arc::new(DashMap::new()),
             max_sessions: 10,  // Reasonable default
             session_timeout_secs: 3600, // 1 hour
+            idle_timeout: Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS),
+            db: None,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
         }
     }
 
@@ -142,15 +164,176 @@ impl SessionManager {
             processes: Arc::new(DashMap::new()),
             max_sessions,
             session_timeout_secs,
+            idle_timeout: Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS),
+            db: None,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
         }
     }
 
-    /// Create a new session
+    /// Set the time without activity after which running sessions are cancelled
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Get the idle timeout
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Set how long a cancelled process gets to exit before it is killed
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Save sessions to `conn` on every state change
+    pub fn with_database(mut self, conn: Connection) -> Result<Self, rusqlite::Error> {
+        Self::init_database(&conn)?;
+        self.db = Some(Arc::new(Mutex::new(conn)));
+        Ok(self)
+    }
+
+    /// Initialize the sessions database table
+    pub fn init_database(conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS claude_sessions (
+                id TEXT PRIMARY KEY,
+                project_path TEXT NOT NULL,
+                status TEXT NOT NULL,
+                model TEXT NOT NULL,
+                pid INTEGER,
+                initial_prompt TEXT,
+                error_message TEXT,
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+                cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+                token_budget INTEGER,
+                created_at TEXT NOT NULL,
+                last_activity TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Add columns introduced after the table was first created
+        let _ = conn.execute("ALTER TABLE claude_sessions ADD COLUMN token_budget INTEGER", []);
+        Ok(())
+    }
+
+    /// Restore sessions saved by a previous run
+    ///
+    /// Sessions that were still active are kept as they were if their process
+    /// is alive, and marked Failed otherwise. Returns the number restored.
+    pub fn load_persisted_sessions(&self) -> Result<usize, rusqlite::Error> {
+        let db = match self.db {
+            Some(ref db) => db.clone(),
+            None => return Ok(0),
+        };
+
+        let infos = {
+            let conn = db.lock();
+            let mut stmt = conn.prepare(
+                "SELECT id, project_path, status, model, pid, initial_prompt, error_message, input_tokens,
+                 output_tokens, cache_read_tokens, cache_write_tokens, created_at, last_activity, token_budget
+                 FROM claude_sessions ORDER BY created_at",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                let status: String = row.get(2)?;
+                Ok(SessionInfo {
+                    id: row.get(0)?,
+                    project_path: row.get(1)?,
+                    status: serde_json::from_str(&format!("\"{}\"", status)).unwrap_or(SessionStatus::Failed),
+                    model: row.get(3)?,
+                    pid: row.get(4)?,
+                    initial_prompt: row.get(5)?,
+                    error_message: row.get(6)?,
+                    tokens_used: TokenUsage {
+                        input_tokens: row.get::<_, i64>(7)? as u64,
+                        output_tokens: row.get::<_, i64>(8)? as u64,
+                        cache_read_tokens: row.get::<_, i64>(9)? as u64,
+                        cache_write_tokens: row.get::<_, i64>(10)? as u64,
+                    },
+                    created_at: row.get(11)?,
+                    last_activity: row.get(12)?,
+                    token_budget: row.get::<_, Option<i64>>(13)?.map(|budget| budget as u64),
+                    duration_secs: 0,
+                })
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut restored = 0;
+        for info in infos {
+            if self.sessions.contains_key(&info.id) {
+                continue;
+            }
+
+            let mut state = SessionState::new(&info.id, info.project_path, info.model);
+            state.status = info.status;
+            state.pid = info.pid;
+            state.initial_prompt = info.initial_prompt;
+            state.error_message = info.error_message;
+            state.tokens_used = info.tokens_used;
+            state.token_budget = info.token_budget;
+            state.created_at = parse_timestamp(&info.created_at);
+            state.last_activity = parse_timestamp(&info.last_activity);
+
+            if !state.is_terminal() && !state.pid.map(process_alive).unwrap_or(false) {
+                warn!("Session {} was active but its process is gone; marking it failed", info.id);
+                state.set_failed("Process exited while Opcode was not running");
+                self.persist(&state);
+            }
+
+            self.sessions.insert(info.id, state);
+            restored += 1;
+        }
+
+        info!("Restored {} persisted sessions", restored);
+        Ok(restored)
+    }
+
+    /// Save a session's current state, if persistence is enabled
+    fn persist(&self, state: &SessionState) {
+        let db = match self.db {
+            Some(ref db) => db,
+            None => return,
+        };
+
+        let result = db.lock().execute(
+            "INSERT OR REPLACE INTO claude_sessions (id, project_path, status, model, pid, initial_prompt,
+             error_message, input_tokens, output_tokens, cache_read_tokens, cache_write_tokens, created_at,
+             last_activity, token_budget)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                state.id,
+                state.project_path,
+                state.status.to_string(),
+                state.model,
+                state.pid,
+                state.initial_prompt,
+                state.error_message,
+                state.tokens_used.input_tokens as i64,
+                state.tokens_used.output_tokens as i64,
+                state.tokens_used.cache_read_tokens as i64,
+                state.tokens_used.cache_write_tokens as i64,
+                state.created_at.to_rfc3339(),
+                state.last_activity.to_rfc3339(),
+                state.token_budget.map(|budget| budget as i64),
+            ],
+        );
+        if let Err(e) = result {
+            error!("Failed to save session {}: {}", state.id, e);
+        }
+    }
+
+    /// Create a new session, optionally capped at `token_budget` input plus output tokens
     pub fn create_session(
         &self,
         session_id: impl Into<String>,
         project_path: impl Into<String>,
         model: impl Into<String>,
+        token_budget: Option<u64>,
     ) -> Result<String, SessionError> {
         let session_id = session_id.into();
 
@@ -169,7 +352,9 @@ impl SessionManager {
             return Err(SessionError::SessionExists(session_id));
         }
 
-        let state = SessionState::new(&session_id, project_path, model);
+        let mut state = SessionState::new(&session_id, project_path, model);
+        state.token_budget = token_budget;
+        self.persist(&state);
         self.sessions.insert(session_id.clone(), state);
 
         info!("Created session: {}", session_id);
@@ -191,6 +376,19 @@ impl SessionManager {
         self.sessions.get(session_id).map(|s| s.subscribe())
     }
 
+    /// Subscribe to session events, starting with the session's buffered output
+    pub fn subscribe_with_replay(&self, session_id: &str) -> Option<ReplaySubscription> {
+        self.sessions.get(session_id).map(|s| s.subscribe_with_replay())
+    }
+
+    /// Recent output of a session, oldest first
+    pub fn get_buffered_output(&self, session_id: &str) -> Result<Vec<SessionEvent>, SessionError> {
+        self.sessions
+            .get(session_id)
+            .map(|s| s.buffered_output())
+            .ok_or_else(|| SessionError::SessionNotFound(session_id.to_string()))
+    }
+
     /// Register a managed process for a session
     pub fn register_process(&self, session_id: &str, process: ManagedProcess) -> Result<(), SessionError> {
         let pid = process.pid;
@@ -198,6 +396,7 @@ impl SessionManager {
         // Update session state
         if let Some(mut session) = self.sessions.get_mut(session_id) {
             session.set_running(pid);
+            self.persist(&session);
         } else {
             return Err(SessionError::SessionNotFound(session_id.to_string()));
         }
@@ -214,11 +413,12 @@ impl SessionManager {
         // Update session status first
         if let Some(mut session) = self.sessions.get_mut(session_id) {
             session.set_status(SessionStatus::Terminating);
+            self.persist(&session);
         }
 
         // Shutdown the process
         if let Some((_, mut process)) = self.processes.remove(session_id) {
-            if let Err(e) = process.shutdown(Duration::from_secs(5)).await {
+            if let Err(e) = process.shutdown(self.shutdown_timeout).await {
                 error!("Error shutting down process for session {}: {}", session_id, e);
             }
         }
@@ -226,6 +426,7 @@ impl SessionManager {
         // Mark session as cancelled
         if let Some(mut session) = self.sessions.get_mut(session_id) {
             session.set_cancelled();
+            self.persist(&session);
         }
 
         Ok(())
@@ -243,6 +444,7 @@ impl SessionManager {
 
         if let Some(mut session) = self.sessions.get_mut(session_id) {
             session.set_cancelled();
+            self.persist(&session);
         }
 
         Ok(())
@@ -256,6 +458,7 @@ impl SessionManager {
         // Update session state
         if let Some(mut session) = self.sessions.get_mut(session_id) {
             session.set_completed();
+            self.persist(&session);
             Ok(())
         } else {
             Err(SessionError::SessionNotFound(session_id.to_string()))
@@ -270,12 +473,113 @@ impl SessionManager {
         // Update session state
         if let Some(mut session) = self.sessions.get_mut(session_id) {
             session.set_failed(error);
+            self.persist(&session);
             Ok(())
         } else {
             Err(SessionError::SessionNotFound(session_id.to_string()))
         }
     }
 
+    /// Add to a session's token usage, stopping the session if it goes over its budget
+    pub fn add_tokens(
+        &self,
+        session_id: &str,
+        input: u64,
+        output: u64,
+        cache_read: u64,
+        cache_write: u64,
+    ) -> Result<(), SessionError> {
+        let exhausted = match self.sessions.get_mut(session_id) {
+            Some(mut session) => {
+                session.add_tokens(input, output, cache_read, cache_write);
+                let exhausted = session.check_budget();
+                self.persist(&session);
+                exhausted
+            }
+            None => return Err(SessionError::SessionNotFound(session_id.to_string())),
+        };
+        if exhausted {
+            self.stop_over_budget(session_id);
+        }
+        Ok(())
+    }
+
+    /// Set or clear a session's token budget
+    ///
+    /// A budget already exceeded stops the session straight away.
+    pub fn set_token_budget(&self, session_id: &str, token_budget: Option<u64>) -> Result<(), SessionError> {
+        let exhausted = match self.sessions.get_mut(session_id) {
+            Some(mut session) => {
+                session.token_budget = token_budget;
+                let exhausted = session.check_budget();
+                self.persist(&session);
+                exhausted
+            }
+            None => return Err(SessionError::SessionNotFound(session_id.to_string())),
+        };
+        if exhausted {
+            self.stop_over_budget(session_id);
+        }
+        Ok(())
+    }
+
+    /// Kill the process of a session that used up its token budget
+    fn stop_over_budget(&self, session_id: &str) {
+        warn!("Session {} exceeded its token budget; stopping it", session_id);
+        let (_, mut process) = match self.processes.remove(session_id) {
+            Some(entry) => entry,
+            None => return,
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                let session_id = session_id.to_string();
+                handle.spawn(async move {
+                    if let Err(e) = process.kill().await {
+                        error!("Error killing process for session {}: {}", session_id, e);
+                    }
+                });
+            }
+            Err(_) => {
+                if let Some(tx) = process.kill_tx.take() {
+                    let _ = tx.send(());
+                }
+                if let Err(e) = process.child.start_kill() {
+                    error!("Error killing process for session {}: {}", session_id, e);
+                }
+            }
+        }
+    }
+
+    /// Aggregate counts, token usage and ages over all sessions in one pass
+    pub fn get_session_metrics(&self) -> SessionMetrics {
+        let now = Utc::now();
+        let mut metrics = SessionMetrics::default();
+        let mut total_duration_secs = 0i64;
+
+        for session in self.sessions.iter() {
+            metrics.total_sessions += 1;
+            *metrics.by_status.entry(session.status).or_insert(0) += 1;
+
+            let tokens = &session.tokens_used;
+            metrics.tokens_used.input_tokens += tokens.input_tokens;
+            metrics.tokens_used.output_tokens += tokens.output_tokens;
+            metrics.tokens_used.cache_read_tokens += tokens.cache_read_tokens;
+            metrics.tokens_used.cache_write_tokens += tokens.cache_write_tokens;
+            total_duration_secs += session.duration_secs();
+
+            if session.is_active() {
+                metrics.active_sessions += 1;
+                let age = (now - session.created_at).num_seconds();
+                metrics.oldest_active_age_secs = Some(metrics.oldest_active_age_secs.map_or(age, |a| a.max(age)));
+            }
+        }
+
+        if metrics.total_sessions > 0 {
+            metrics.avg_duration_secs = Some(total_duration_secs as f64 / metrics.total_sessions as f64);
+        }
+        metrics
+    }
+
     /// Get all active sessions
     pub fn list_active_sessions(&self) -> Vec<SessionInfo> {
         self.sessions
@@ -327,6 +631,58 @@ impl SessionManager {
         }
     }
 
+    /// Cancel running sessions with no activity for longer than the idle timeout
+    ///
+    /// Paused sessions are waiting on the user and are left alone. Each
+    /// cancelled session gets an Error event naming the timeout, which is
+    /// also returned so it can be forwarded to the frontend.
+    pub async fn cancel_idle_sessions(&self) -> Vec<SessionEvent> {
+        let now = Utc::now();
+        let idle_ids: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|s| s.status == SessionStatus::Running)
+            .filter(|s| (now - s.last_activity).to_std().is_ok_and(|idle| idle > self.idle_timeout))
+            .map(|s| s.id.clone())
+            .collect();
+
+        let mut events = Vec::with_capacity(idle_ids.len());
+        for id in idle_ids {
+            warn!("Cancelling session {} after {:?} without activity", id, self.idle_timeout);
+            let event = SessionEvent::Error {
+                session_id: id.clone(),
+                message: format!(
+                    "Session cancelled: no activity for longer than the idle timeout of {}s",
+                    self.idle_timeout.as_secs()
+                ),
+                code: Some("idle_timeout".to_string()),
+            };
+            if let Some(session) = self.sessions.get(&id) {
+                let _ = session.emit(event.clone());
+            }
+            if let Err(e) = self.cancel_session(&id).await {
+                error!("Error cancelling idle session {}: {}", id, e);
+            }
+            events.push(event);
+        }
+        events
+    }
+
+    /// Periodically cancel idle sessions, reporting them through `emitter` (runs until the task is dropped)
+    pub async fn run_idle_sweep(&self, emitter: SessionEventEmitter) {
+        let period = (self.idle_timeout / 2).max(Duration::from_secs(1));
+        let mut ticker = tokio::time::interval(period);
+
+        loop {
+            ticker.tick().await;
+            for event in self.cancel_idle_sessions().await {
+                if let Err(e) = emitter.emit_to_session(&event) {
+                    warn!("Failed to emit idle timeout for session {}: {}", event.session_id(), e);
+                }
+            }
+        }
+    }
+
     /// Get count of active sessions
     pub fn active_session_count(&self) -> usize {
         self.sessions.iter().filter(|s| s.is_active()).count()
@@ -358,11 +714,14 @@ impl SessionManager {
 
         let session_ids: Vec<String> = self.sessions.iter().map(|s| s.id.clone()).collect();
 
-        for session_id in session_ids {
-            if let Err(e) = self.cancel_session(&session_id).await {
-                error!("Error cancelling session {}: {:?}", session_id, e);
-            }
-        }
+        // Shut processes down side by side so exit waits about one timeout, not one per session
+        stream::iter(session_ids)
+            .for_each_concurrent(SHUTDOWN_CONCURRENCY, |session_id| async move {
+                if let Err(e) = self.cancel_session(&session_id).await {
+                    error!("Error cancelling session {}: {:?}", session_id, e);
+                }
+            })
+            .await;
 
         self.sessions.clear();
         self.processes.clear();
@@ -377,6 +736,32 @@ impl Default for SessionManager {
     }
 }
 
+/// Parse a saved RFC 3339 timestamp, falling back to now
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Whether a process with this PID is still running
+fn process_alive(pid: u32) -> bool {
+    if cfg!(target_os = "windows") {
+        match std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid)])
+            .args(["/FO", "CSV", "/NH"])
+            .output()
+        {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)),
+            Err(_) => false,
+        }
+    } else {
+        match std::process::Command::new("kill").args(["-0", &pid.to_string()]).output() {
+            Ok(output) => output.status.success(),
+            Err(_) => false,
+        }
+    }
+}
+
 /// Session manager errors
 #[derive(Debug, thiserror::Error)]
 pub enum SessionError {
@@ -412,7 +797,7 @@ mod tests {
     #[test]
     fn test_create_session() {
         let manager = SessionManager::new();
-        let result = manager.create_session("test-1", "/path/to/project", "opus");
+        let result = manager.create_session("test-1", "/path/to/project", "opus", None);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "test-1");
         assert!(manager.session_exists("test-1"));
@@ -421,27 +806,181 @@ mod tests {
     #[test]
     fn test_duplicate_session() {
         let manager = SessionManager::new();
-        let _ = manager.create_session("test-1", "/path", "opus");
-        let result = manager.create_session("test-1", "/path", "opus");
+        let _ = manager.create_session("test-1", "/path", "opus", None);
+        let result = manager.create_session("test-1", "/path", "opus", None);
         assert!(matches!(result, Err(SessionError::SessionExists(_))));
     }
 
     #[test]
     fn test_max_sessions() {
         let manager = SessionManager::with_limits(2, 3600);
-        let _ = manager.create_session("test-1", "/path", "opus");
-        let _ = manager.create_session("test-2", "/path", "opus");
-        let result = manager.create_session("test-3", "/path", "opus");
+        let _ = manager.create_session("test-1", "/path", "opus", None);
+        let _ = manager.create_session("test-2", "/path", "opus", None);
+        let result = manager.create_session("test-3", "/path", "opus", None);
         assert!(matches!(result, Err(SessionError::MaxSessionsReached(2))));
     }
 
     #[test]
     fn test_list_sessions() {
         let manager = SessionManager::new();
-        let _ = manager.create_session("test-1", "/path1", "opus");
-        let _ = manager.create_session("test-2", "/path2", "sonnet");
+        let _ = manager.create_session("test-1", "/path1", "opus", None);
+        let _ = manager.create_session("test-2", "/path2", "sonnet", None);
 
         let all = manager.list_all_sessions();
         assert_eq!(all.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_idle_sessions_are_cancelled() {
+        let manager = SessionManager::new().with_idle_timeout(Duration::from_secs(1));
+        for id in ["hung", "busy", "paused"] {
+            manager.create_session(id, "/path", "opus", None).unwrap();
+            manager.get_session_mut(id).unwrap().set_running(0);
+        }
+        manager.get_session_mut("paused").unwrap().set_status(SessionStatus::Paused);
+        let stale = Utc::now() - chrono::Duration::seconds(10);
+        manager.get_session_mut("hung").unwrap().last_activity = stale;
+        manager.get_session_mut("paused").unwrap().last_activity = stale;
+        let mut events = manager.subscribe("hung").unwrap();
+
+        let cancelled = manager.cancel_idle_sessions().await;
+
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].session_id(), "hung");
+        assert_eq!(manager.get_session("hung").unwrap().status, SessionStatus::Cancelled);
+        assert_eq!(manager.get_session("busy").unwrap().status, SessionStatus::Running);
+        assert_eq!(manager.get_session("paused").unwrap().status, SessionStatus::Paused);
+        match events.try_recv().unwrap() {
+            SessionEvent::Error { message, code, .. } => {
+                assert!(message.contains("idle timeout"));
+                assert_eq!(code.as_deref(), Some("idle_timeout"));
+            }
+            other => panic!("expected an Error event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_session_metrics() {
+        let manager = SessionManager::new();
+        assert_eq!(manager.get_session_metrics().total_sessions, 0);
+        assert!(manager.get_session_metrics().avg_duration_secs.is_none());
+
+        for (id, hours_ago) in [("old", 3), ("new", 1), ("done", 5), ("broken", 2)] {
+            manager.create_session(id, "/path", "opus", None).unwrap();
+            let mut session = manager.get_session_mut(id).unwrap();
+            session.created_at = Utc::now() - chrono::Duration::hours(hours_ago);
+            session.last_activity = session.created_at + chrono::Duration::seconds(60);
+        }
+        manager.get_session_mut("old").unwrap().set_running(1);
+        manager.get_session_mut("new").unwrap().set_running(2);
+        manager.add_tokens("old", 100, 10, 1, 0).unwrap();
+        manager.add_tokens("done", 200, 20, 0, 5).unwrap();
+        manager.complete_session("done").unwrap();
+        manager.fail_session("broken", "crashed").unwrap();
+
+        let metrics = manager.get_session_metrics();
+        assert_eq!(metrics.total_sessions, 4);
+        assert_eq!(metrics.active_sessions, 2);
+        assert_eq!(metrics.by_status[&SessionStatus::Running], 2);
+        assert_eq!(metrics.by_status[&SessionStatus::Completed], 1);
+        assert_eq!(metrics.by_status[&SessionStatus::Failed], 1);
+        assert!(!metrics.by_status.contains_key(&SessionStatus::Cancelled));
+        assert_eq!(metrics.tokens_used.input_tokens, 300);
+        assert_eq!(metrics.tokens_used.output_tokens, 30);
+        assert_eq!(metrics.tokens_used.cache_read_tokens, 1);
+        assert_eq!(metrics.tokens_used.cache_write_tokens, 5);
+        assert!(metrics.avg_duration_secs.unwrap() > 0.0);
+
+        // The completed session is older but no longer active
+        let oldest = metrics.oldest_active_age_secs.unwrap();
+        assert!((3 * 3600..3 * 3600 + 60).contains(&oldest));
+    }
+
+    #[test]
+    fn test_token_budget_fails_session() {
+        let manager = SessionManager::new();
+        manager.create_session("capped", "/path", "opus", Some(1000)).unwrap();
+        manager.get_session_mut("capped").unwrap().set_running(12345);
+
+        manager.add_tokens("capped", 600, 400, 0, 0).unwrap();
+        assert_eq!(manager.get_session("capped").unwrap().status, SessionStatus::Running);
+
+        manager.add_tokens("capped", 0, 1, 0, 0).unwrap();
+        let session = manager.get_session("capped").unwrap();
+        assert_eq!(session.status, SessionStatus::Failed);
+        assert!(session.error_message.as_deref().unwrap().starts_with("Token budget exceeded"));
+        drop(session);
+
+        // Lowering the budget below what a session has used stops it too
+        manager.create_session("lowered", "/path", "opus", None).unwrap();
+        manager.get_session_mut("lowered").unwrap().set_running(12345);
+        manager.add_tokens("lowered", 50, 50, 0, 0).unwrap();
+        manager.set_token_budget("lowered", Some(10)).unwrap();
+        assert_eq!(manager.get_session("lowered").unwrap().status, SessionStatus::Failed);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_all_stops_processes_concurrently() {
+        let timeout = Duration::from_millis(500);
+        let manager = SessionManager::new().with_shutdown_timeout(timeout);
+        for i in 0..4 {
+            let id = format!("session-{}", i);
+            manager.create_session(&id, "/path", "opus", None).unwrap();
+            // No kill switch, so each process only stops once the timeout runs out
+            let child = tokio::process::Command::new("sleep").arg("30").spawn().unwrap();
+            manager.register_process(&id, ManagedProcess::new(&id, child)).unwrap();
+        }
+
+        // The clock is paused, so only the shutdown timeouts move it: one timeout
+        // when the processes are stopped side by side, four when one at a time
+        let start = tokio::time::Instant::now();
+        manager.shutdown_all().await;
+        let elapsed = start.elapsed();
+
+        assert_eq!(elapsed, timeout);
+        assert_eq!(manager.total_session_count(), 0);
+    }
+
+    #[test]
+    fn test_sessions_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("sessions.db");
+        let open = || {
+            SessionManager::new()
+                .with_database(Connection::open(&db_path).unwrap())
+                .unwrap()
+        };
+
+        let manager = open();
+        manager.create_session("done", "/path1", "opus", None).unwrap();
+        manager.get_session_mut("done").unwrap().initial_prompt = Some("fix the tests".to_string());
+        manager.add_tokens("done", 100, 50, 20, 10).unwrap();
+        manager.complete_session("done").unwrap();
+
+        manager.create_session("orphaned", "/path2", "sonnet", None).unwrap();
+        manager.get_session_mut("orphaned").unwrap().set_running(i32::MAX as u32);
+        manager.add_tokens("orphaned", 1, 2, 0, 0).unwrap();
+        drop(manager);
+
+        let restarted = open();
+        assert_eq!(restarted.load_persisted_sessions().unwrap(), 2);
+
+        let done = SessionInfo::from(restarted.get_session("done").unwrap().value());
+        assert_eq!(done.status, SessionStatus::Completed);
+        assert_eq!(done.model, "opus");
+        assert_eq!(done.initial_prompt.as_deref(), Some("fix the tests"));
+        assert_eq!(done.tokens_used.input_tokens, 100);
+        assert_eq!(done.tokens_used.cache_write_tokens, 10);
+
+        // The PID can't belong to a live process, so the session didn't survive
+        let orphaned = SessionInfo::from(restarted.get_session("orphaned").unwrap().value());
+        assert_eq!(orphaned.status, SessionStatus::Failed);
+        assert!(orphaned.pid.is_none());
+        assert_eq!(orphaned.tokens_used.output_tokens, 2);
+
+        // Restoring again neither duplicates sessions nor overwrites live state
+        assert_eq!(restarted.load_persisted_sessions().unwrap(), 0);
+        assert_eq!(restarted.total_session_count(), 2);
+    }
 }
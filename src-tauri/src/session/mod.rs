@@ -11,6 +11,7 @@
 pub mod manager;
 pub mod state;
 pub mod events;
+pub mod replay;
 
 pub use manager::SessionManager;
 pub use state::{SessionState, SessionStatus};
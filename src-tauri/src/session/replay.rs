@@ -0,0 +1,177 @@
+//! Session Output Replay
+//!
+//! Keeps a bounded buffer of recent Output events per session so subscribers
+//! that attach late, or fall behind the broadcast channel, can catch up.
+
+use log::warn;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError, error::SendError};
+
+use super::events::SessionEvent;
+
+/// Output events kept per session
+pub const OUTPUT_BUFFER_CAPACITY: usize = 1000;
+
+/// Ring buffer of a session's most recent Output events
+#[derive(Debug)]
+pub struct OutputBuffer {
+    events: VecDeque<SessionEvent>,
+    capacity: usize,
+    /// Sequence number the next pushed event gets
+    next_seq: u64,
+}
+
+impl OutputBuffer {
+    /// Create a buffer holding at most `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity.min(OUTPUT_BUFFER_CAPACITY)),
+            capacity: capacity.max(1),
+            next_seq: 0,
+        }
+    }
+
+    /// Add an event, dropping the oldest once full
+    pub fn push(&mut self, event: SessionEvent) {
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+        self.next_seq += 1;
+    }
+
+    /// Buffered events, oldest first
+    pub fn events(&self) -> Vec<SessionEvent> {
+        self.events.iter().cloned().collect()
+    }
+
+    /// Buffered events numbered `seq` or later
+    fn since(&self, seq: u64) -> VecDeque<SessionEvent> {
+        let first = self.next_seq - self.events.len() as u64;
+        let skip = seq.saturating_sub(first) as usize;
+        self.events.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Buffer `event` if it is output, then broadcast it
+///
+/// Both happen under the buffer lock, so a replaying subscriber sees each output exactly once.
+pub fn send_buffered(
+    buffer: &Mutex<OutputBuffer>,
+    tx: &broadcast::Sender<SessionEvent>,
+    event: SessionEvent,
+) -> Result<usize, SendError<SessionEvent>> {
+    let mut buffer = buffer.lock();
+    if matches!(event, SessionEvent::Output { .. }) {
+        buffer.push(event.clone());
+    }
+    tx.send(event)
+}
+
+/// Session events starting with the buffered output, then live events
+pub struct ReplaySubscription {
+    buffer: Arc<Mutex<OutputBuffer>>,
+    receiver: broadcast::Receiver<SessionEvent>,
+    replay: VecDeque<SessionEvent>,
+    /// Sequence number of the next output the receiver will deliver
+    next_seq: u64,
+}
+
+impl ReplaySubscription {
+    /// Snapshot the buffer and attach to `tx` without missing or repeating output
+    pub fn new(buffer: Arc<Mutex<OutputBuffer>>, tx: &broadcast::Sender<SessionEvent>) -> Self {
+        let (receiver, replay, next_seq) = {
+            let guard = buffer.lock();
+            (tx.subscribe(), guard.events.clone(), guard.next_seq)
+        };
+        Self {
+            buffer,
+            receiver,
+            replay,
+            next_seq,
+        }
+    }
+
+    /// Wait for the next event, or None once the session's channel closes
+    ///
+    /// If the subscriber falls behind, missed output is re-read from the buffer;
+    /// other events dropped by the channel are lost.
+    pub async fn recv(&mut self) -> Option<SessionEvent> {
+        loop {
+            if let Some(event) = self.replay.pop_front() {
+                return Some(event);
+            }
+
+            match self.receiver.recv().await {
+                Ok(event) => {
+                    if matches!(event, SessionEvent::Output { .. }) {
+                        self.next_seq += 1;
+                    }
+                    return Some(event);
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Session subscriber lagged by {} events, replaying buffered output", skipped);
+                    let buffer = self.buffer.lock();
+                    self.replay = buffer.since(self.next_seq);
+                    self.next_seq = buffer.next_seq;
+                    self.receiver = self.receiver.resubscribe();
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::events::OutputType;
+
+    fn output(content: &str) -> SessionEvent {
+        SessionEvent::Output {
+            session_id: "test".to_string(),
+            content: content.to_string(),
+            message_type: OutputType::Assistant,
+        }
+    }
+
+    fn content(event: SessionEvent) -> String {
+        match event {
+            SessionEvent::Output { content, .. } => content,
+            other => panic!("expected Output, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lagged_subscriber_replays_from_buffer() {
+        let buffer = Arc::new(Mutex::new(OutputBuffer::new(100)));
+        let (tx, _) = broadcast::channel(2);
+        let mut subscription = ReplaySubscription::new(buffer.clone(), &tx);
+
+        // Overflows the channel, but not the buffer
+        for i in 0..5 {
+            send_buffered(&buffer, &tx, output(&i.to_string())).unwrap();
+        }
+        for i in 0..5 {
+            assert_eq!(content(subscription.recv().await.unwrap()), i.to_string());
+        }
+
+        send_buffered(&buffer, &tx, output("5")).unwrap();
+        assert_eq!(content(subscription.recv().await.unwrap()), "5");
+    }
+
+    #[test]
+    fn test_buffer_drops_oldest() {
+        let mut buffer = OutputBuffer::new(2);
+        for i in 0..3 {
+            buffer.push(output(&i.to_string()));
+        }
+
+        let kept: Vec<_> = buffer.events().into_iter().map(content).collect();
+        assert_eq!(kept, vec!["1", "2"]);
+        assert_eq!(buffer.since(0).len(), 2);
+        assert_eq!(buffer.since(2).len(), 1);
+    }
+}
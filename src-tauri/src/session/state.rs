@@ -3,14 +3,16 @@
 //! Tracks the state of individual Claude Code sessions.
 
 use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
 use super::events::SessionEvent;
+use super::replay::{send_buffered, OutputBuffer, ReplaySubscription, OUTPUT_BUFFER_CAPACITY};
 
 /// Status of a session
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SessionStatus {
     /// Session is being created
@@ -64,6 +66,8 @@ pub struct SessionState {
     pub pid: Option<u32>,
     /// Event broadcaster for this session
     pub event_tx: broadcast::Sender<SessionEvent>,
+    /// Recent Output events, replayed to late subscribers
+    output_buffer: Arc<Mutex<OutputBuffer>>,
     /// When the session was created
     pub created_at: DateTime<Utc>,
     /// When the session was last active
@@ -74,6 +78,8 @@ pub struct SessionState {
     pub error_message: Option<String>,
     /// Token usage tracking
     pub tokens_used: TokenUsage,
+    /// Input plus output tokens the session may use before it is failed (None = unlimited)
+    pub token_budget: Option<u64>,
     /// Metadata for extensibility
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
 }
@@ -100,11 +106,13 @@ impl SessionState {
             model: model.into(),
             pid: None,
             event_tx,
+            output_buffer: Arc::new(Mutex::new(OutputBuffer::new(OUTPUT_BUFFER_CAPACITY))),
             created_at: now,
             last_activity: now,
             initial_prompt: None,
             error_message: None,
             tokens_used: TokenUsage::default(),
+            token_budget: None,
             metadata: std::collections::HashMap::new(),
         }
     }
@@ -114,9 +122,19 @@ impl SessionState {
         self.event_tx.subscribe()
     }
 
-    /// Emit an event to all subscribers
+    /// Subscribe to session events, starting with the buffered output
+    pub fn subscribe_with_replay(&self) -> ReplaySubscription {
+        ReplaySubscription::new(self.output_buffer.clone(), &self.event_tx)
+    }
+
+    /// Recent Output events, oldest first
+    pub fn buffered_output(&self) -> Vec<SessionEvent> {
+        self.output_buffer.lock().events()
+    }
+
+    /// Emit an event to all subscribers, buffering it if it is output
     pub fn emit(&self, event: SessionEvent) -> Result<usize, broadcast::error::SendError<SessionEvent>> {
-        self.event_tx.send(event)
+        send_buffered(&self.output_buffer, &self.event_tx, event)
     }
 
     /// Update status and emit event
@@ -157,13 +175,44 @@ impl SessionState {
         self.set_status(SessionStatus::Cancelled);
     }
 
-    /// Update token usage
+    /// Update token usage and emit the new totals
     pub fn add_tokens(&mut self, input: u64, output: u64, cache_read: u64, cache_write: u64) {
         self.tokens_used.input_tokens += input;
         self.tokens_used.output_tokens += output;
         self.tokens_used.cache_read_tokens += cache_read;
         self.tokens_used.cache_write_tokens += cache_write;
         self.last_activity = Utc::now();
+
+        let _ = self.emit(SessionEvent::TokenUsage {
+            session_id: self.id.clone(),
+            input_tokens: self.tokens_used.input_tokens,
+            output_tokens: self.tokens_used.output_tokens,
+            cache_read_tokens: self.tokens_used.cache_read_tokens,
+            cache_write_tokens: self.tokens_used.cache_write_tokens,
+        });
+    }
+
+    /// Fail the session once input plus output tokens exceed its budget
+    ///
+    /// Emits an Error event first. Returns true if this call failed the session.
+    pub fn check_budget(&mut self) -> bool {
+        let budget = match self.token_budget {
+            Some(budget) => budget,
+            None => return false,
+        };
+        let used = self.tokens_used.input_tokens + self.tokens_used.output_tokens;
+        if used <= budget || self.is_terminal() {
+            return false;
+        }
+
+        let message = format!("Token budget exceeded: used {} of {} tokens", used, budget);
+        let _ = self.emit(SessionEvent::Error {
+            session_id: self.id.clone(),
+            message: message.clone(),
+            code: Some("token_budget_exceeded".to_string()),
+        });
+        self.set_failed(message);
+        true
     }
 
     /// Check if session is active (can receive input)
@@ -198,6 +247,8 @@ pub struct SessionInfo {
     pub initial_prompt: Option<String>,
     pub error_message: Option<String>,
     pub tokens_used: TokenUsage,
+    #[serde(default)]
+    pub token_budget: Option<u64>,
     pub duration_secs: i64,
 }
 
@@ -214,11 +265,27 @@ impl From<&SessionState> for SessionInfo {
             initial_prompt: state.initial_prompt.clone(),
             error_message: state.error_message.clone(),
             tokens_used: state.tokens_used.clone(),
+            token_budget: state.token_budget,
             duration_secs: state.duration_secs(),
         }
     }
 }
 
+/// Totals across all sessions, for dashboards
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionMetrics {
+    pub total_sessions: usize,
+    pub active_sessions: usize,
+    /// Number of sessions in each status (statuses with no sessions are omitted)
+    pub by_status: std::collections::HashMap<SessionStatus, usize>,
+    /// Token usage summed over every session
+    pub tokens_used: TokenUsage,
+    /// Mean time between creation and last activity
+    pub avg_duration_secs: Option<f64>,
+    /// Age of the longest-running active session
+    pub oldest_active_age_secs: Option<i64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,4 +324,85 @@ mod tests {
         assert_eq!(state.tokens_used.output_tokens, 100);
         assert_eq!(state.tokens_used.cache_read_tokens, 20);
     }
+
+    #[test]
+    fn test_token_budget() {
+        let mut state = SessionState::new("test", "/path", "opus");
+        state.set_running(12345);
+        state.token_budget = Some(100);
+        let mut events = state.subscribe();
+
+        // Cache tokens don't count, and reaching the budget exactly is allowed
+        state.add_tokens(60, 40, 500, 500);
+        assert!(!state.check_budget());
+        assert_eq!(state.status, SessionStatus::Running);
+
+        state.add_tokens(1, 0, 0, 0);
+        assert!(state.check_budget());
+        assert_eq!(state.status, SessionStatus::Failed);
+        assert!(state.error_message.as_deref().unwrap().contains("101 of 100"));
+        assert!(!state.check_budget());
+
+        let mut saw_error = false;
+        while let Ok(event) = events.try_recv() {
+            if let SessionEvent::Error { code, .. } = event {
+                assert_eq!(code.as_deref(), Some("token_budget_exceeded"));
+                saw_error = true;
+            }
+        }
+        assert!(saw_error);
+    }
+
+    #[tokio::test]
+    async fn test_late_subscriber_replays_output() {
+        use super::super::events::OutputType;
+
+        let output = |content: &str| SessionEvent::Output {
+            session_id: "test".to_string(),
+            content: content.to_string(),
+            message_type: OutputType::Assistant,
+        };
+        let mut state = SessionState::new("test", "/path", "opus");
+        state.emit(output("first")).ok();
+        state.set_running(12345);
+        state.emit(output("second")).ok();
+
+        // Only output is buffered; the status change went out before anyone listened
+        assert_eq!(state.buffered_output().len(), 2);
+        let mut events = state.subscribe_with_replay();
+        state.emit(output("live")).ok();
+
+        for expected in ["first", "second", "live"] {
+            match events.recv().await {
+                Some(SessionEvent::Output { content, .. }) => assert_eq!(content, expected),
+                other => panic!("expected Output, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_tokens_emits_totals() {
+        let mut state = SessionState::new("test", "/path", "opus");
+        let mut events = state.subscribe();
+        state.add_tokens(100, 50, 20, 10);
+        state.add_tokens(1, 2, 3, 4);
+
+        let _ = events.try_recv().unwrap();
+        match events.try_recv().unwrap() {
+            SessionEvent::TokenUsage {
+                session_id,
+                input_tokens,
+                output_tokens,
+                cache_read_tokens,
+                cache_write_tokens,
+            } => {
+                assert_eq!(session_id, "test");
+                assert_eq!(
+                    (input_tokens, output_tokens, cache_read_tokens, cache_write_tokens),
+                    (101, 52, 23, 14)
+                );
+            }
+            other => panic!("expected TokenUsage, got {:?}", other),
+        }
+    }
 }
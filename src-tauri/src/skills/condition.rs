@@ -0,0 +1,367 @@
+//! Workflow Step Conditions
+//!
+//! A small expression language for `WorkflowStep::condition`, e.g.
+//! `steps.build.exit_code == 0 && vars.environment != "prod"`.
+//!
+//! - Paths: `steps.<id>.<field>...` (prior step outputs) and `vars.<name>...` (workflow variables)
+//! - Literals: numbers, `"strings"` or `'strings'`, `true`, `false`, `null`
+//! - Operators: `==`, `!=`, `<`, `<=`, `>`, `>=`, `&&`, `||`, `!` and parentheses
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A parsed condition expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    /// Root (`steps` or `vars`) followed by the keys below it
+    Path(String, Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Dot,
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Compare(CompareOp),
+}
+
+impl Condition {
+    /// Parse a condition expression
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err("Empty condition".to_string());
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            Some(token) => Err(format!("Unexpected {:?} in condition", token)),
+            None => Ok(Self { expr }),
+        }
+    }
+
+    /// Evaluate against completed step outputs and workflow variables
+    pub fn evaluate(
+        &self,
+        steps: &HashMap<String, Value>,
+        vars: &HashMap<String, Value>,
+    ) -> Result<bool, String> {
+        eval(&self.expr, steps, vars).map(|value| truthy(&value))
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '.' => Token::Dot,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, ch)) if ch == c => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => return Err("Unterminated string in condition".to_string()),
+                        },
+                        Some((_, ch)) => text.push(ch),
+                        None => return Err("Unterminated string in condition".to_string()),
+                    }
+                }
+                Token::Str(text)
+            }
+            '&' | '|' => match chars.next() {
+                Some((_, next)) if next == c => {
+                    if c == '&' {
+                        Token::And
+                    } else {
+                        Token::Or
+                    }
+                }
+                _ => return Err(format!("Expected '{}{}' at position {}", c, c, start)),
+            },
+            '=' | '!' | '<' | '>' => {
+                let eq = chars.next_if(|&(_, next)| next == '=').is_some();
+                match (c, eq) {
+                    ('=', true) => Token::Compare(CompareOp::Eq),
+                    ('!', true) => Token::Compare(CompareOp::Ne),
+                    ('!', false) => Token::Not,
+                    ('<', true) => Token::Compare(CompareOp::Le),
+                    ('<', false) => Token::Compare(CompareOp::Lt),
+                    ('>', true) => Token::Compare(CompareOp::Ge),
+                    ('>', false) => Token::Compare(CompareOp::Gt),
+                    _ => return Err(format!("Expected '==' at position {}", start)),
+                }
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, next)) = chars.peek() {
+                    // A dot only continues the number when a digit follows, so `steps.a.0` still splits
+                    let fraction = next == '.' && source[i + 1..].starts_with(|d: char| d.is_ascii_digit());
+                    if !(next.is_ascii_digit() || fraction) {
+                        break;
+                    }
+                    chars.next();
+                    end = i + next.len_utf8();
+                }
+                let text = &source[start..end];
+                match text.parse() {
+                    Ok(number) => Token::Number(number),
+                    Err(_) => return Err(format!("Invalid number '{}' in condition", text)),
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some((_, next)) = chars.next_if(|&(_, n)| n.is_alphanumeric() || n == '_' || n == '-') {
+                    ident.push(next);
+                }
+                Token::Ident(ident)
+            }
+            other => return Err(format!("Unexpected character '{}' at position {}", other, start)),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.comparison()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.comparison()?));
+        }
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.unary()?;
+        match self.tokens.get(self.pos) {
+            Some(&Token::Compare(op)) => {
+                self.pos += 1;
+                Ok(Expr::Compare(op, Box::new(left), Box::new(self.unary()?)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        match self.advance() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::from(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                if !self.eat(&Token::RParen) {
+                    return Err("Expected ')' in condition".to_string());
+                }
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "steps" | "vars" => {
+                    let mut keys = Vec::new();
+                    while self.eat(&Token::Dot) {
+                        match self.advance() {
+                            Some(Token::Ident(key)) => keys.push(key),
+                            Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => keys.push(n.to_string()),
+                            _ => return Err(format!("Expected a name after '{}.'", ident)),
+                        }
+                    }
+                    if keys.is_empty() {
+                        return Err(format!("Expected '{}.<name>'", ident));
+                    }
+                    Ok(Expr::Path(ident, keys))
+                }
+                _ => Err(format!("Unknown name '{}' (use steps.<id> or vars.<name>)", ident)),
+            },
+            Some(token) => Err(format!("Unexpected {:?} in condition", token)),
+            None => Err("Unexpected end of condition".to_string()),
+        }
+    }
+}
+
+fn eval(expr: &Expr, steps: &HashMap<String, Value>, vars: &HashMap<String, Value>) -> Result<Value, String> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Path(root, keys) => {
+            let source = if root == "steps" { steps } else { vars };
+            let mut value = source.get(&keys[0]);
+            for key in &keys[1..] {
+                value = value.and_then(|v| match v {
+                    Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                    _ => v.get(key),
+                });
+            }
+            // Missing paths are null so conditions can test for them
+            Ok(value.cloned().unwrap_or(Value::Null))
+        }
+        Expr::Not(inner) => Ok(Value::Bool(!truthy(&eval(inner, steps, vars)?))),
+        Expr::And(left, right) => {
+            let result = truthy(&eval(left, steps, vars)?) && truthy(&eval(right, steps, vars)?);
+            Ok(Value::Bool(result))
+        }
+        Expr::Or(left, right) => {
+            let result = truthy(&eval(left, steps, vars)?) || truthy(&eval(right, steps, vars)?);
+            Ok(Value::Bool(result))
+        }
+        Expr::Compare(op, left, right) => {
+            let (left, right) = (eval(left, steps, vars)?, eval(right, steps, vars)?);
+            compare(*op, &left, &right).map(Value::Bool)
+        }
+    }
+}
+
+fn compare(op: CompareOp, left: &Value, right: &Value) -> Result<bool, String> {
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => match op {
+            CompareOp::Eq => return Ok(left == right),
+            CompareOp::Ne => return Ok(left != right),
+            _ => return Err(format!("Cannot compare {} with {}", left, right)),
+        },
+    };
+
+    Ok(match (op, ordering) {
+        (_, None) => op == CompareOp::Ne,
+        (CompareOp::Eq, Some(o)) => o.is_eq(),
+        (CompareOp::Ne, Some(o)) => o.is_ne(),
+        (CompareOp::Lt, Some(o)) => o.is_lt(),
+        (CompareOp::Le, Some(o)) => o.is_le(),
+        (CompareOp::Gt, Some(o)) => o.is_gt(),
+        (CompareOp::Ge, Some(o)) => o.is_ge(),
+    })
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str) -> Result<bool, String> {
+        let steps = HashMap::from([(
+            "build".to_string(),
+            serde_json::json!({ "exit_code": 0, "stdout": "ok\n", "artifacts": ["app.tar"] }),
+        )]);
+        let vars = HashMap::from([
+            ("environment".to_string(), serde_json::json!("prod")),
+            ("replicas".to_string(), serde_json::json!(3)),
+            ("dry_run".to_string(), serde_json::json!(false)),
+        ]);
+        Condition::parse(source)?.evaluate(&steps, &vars)
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(check("steps.build.exit_code == 0"), Ok(true));
+        assert_eq!(check("steps.build.exit_code != 0"), Ok(false));
+        assert_eq!(check("vars.environment == \"prod\""), Ok(true));
+        assert_eq!(check("vars.environment == 'staging'"), Ok(false));
+        assert_eq!(check("vars.replicas >= 3"), Ok(true));
+        assert_eq!(check("vars.replicas < 2.5"), Ok(false));
+        assert_eq!(check("vars.replicas > -1"), Ok(true));
+        assert_eq!(check("vars.dry_run == false"), Ok(true));
+        assert_eq!(check("steps.build.artifacts.0 == \"app.tar\""), Ok(true));
+        assert_eq!(check("vars.missing == null"), Ok(true));
+        assert_eq!(check("vars.environment > 'alpha'"), Ok(true));
+        assert!(check("vars.environment < 3").is_err());
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        assert_eq!(check("vars.environment == 'prod' && vars.replicas > 1"), Ok(true));
+        assert_eq!(check("vars.environment == 'prod' && vars.dry_run"), Ok(false));
+        assert_eq!(check("vars.dry_run || steps.build.exit_code == 0"), Ok(true));
+        assert_eq!(check("!vars.dry_run && !(vars.replicas == 1 || vars.missing)"), Ok(true));
+        // && binds tighter than ||
+        assert_eq!(check("true || false && false"), Ok(true));
+        assert_eq!(check("(true || false) && false"), Ok(false));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Condition::parse("").is_err());
+        assert!(Condition::parse("vars.environment = 'prod'").is_err());
+        assert!(Condition::parse("env.HOME == 'x'").is_err());
+        assert!(Condition::parse("(vars.a == 1").is_err());
+        assert!(Condition::parse("vars.a == 'open").is_err());
+        assert!(Condition::parse("vars.a & vars.b").is_err());
+        assert!(Condition::parse("vars.a == 1 2").is_err());
+    }
+}
@@ -2,26 +2,106 @@
 //!
 //! Execute skills including slash commands, hooks, and workflows.
 
+use dashmap::DashMap;
+use handlebars::Handlebars;
 use log::{debug, error, info, warn};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::time::{Duration, Instant};
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
-use tokio::sync::broadcast;
-
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinSet;
+
+use super::condition::Condition;
+use crate::mcp::error::McpResult;
+use crate::mcp::pool::McpConnectionPool;
+use crate::mcp::transport::McpTransport;
+use crate::mcp::types::{ToolCallResult, ToolResultContent};
 use super::registry::SkillRegistry;
 use super::types::{
-    HookConfig, HookTrigger, Skill, SkillConfig, SkillContext, SkillKind, SkillResult,
-    SlashCommandConfig, StepResult, WorkflowConfig, WorkflowStep, WorkflowStepKind,
+    HookConfig, HookDecision, HookOutcome, HookTrigger, Skill, SkillConfig, SkillContext, SkillKind, SkillResult,
+    SlashCommandConfig, StepResult, TemplateConfig, WorkflowConfig, WorkflowEvent, WorkflowStep, WorkflowStepKind,
 };
 
+/// Default cap on captured stdout and stderr, per stream
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Workflow steps run at once when `max_parallel` isn't set
+const DEFAULT_MAX_PARALLEL: u32 = 4;
+
+/// Appended to output cut off at the size limit
+const TRUNCATION_MARKER: &str = "\n[output truncated]";
+
+/// Captured result of a shell command
+struct ShellOutput {
+    stdout: String,
+    stderr: String,
+    exit_code: i32,
+    /// Whether either stream exceeded the output limit
+    truncated: bool,
+}
+
+/// Workflow progress saved while waiting for user input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SuspendedWorkflow {
+    /// Workflow skill ID
+    skill_id: String,
+    /// UserInput step waiting to be answered
+    step_id: String,
+    /// Context the workflow was started with
+    context: SkillContext,
+    /// Outputs of completed steps
+    completed: HashMap<String, serde_json::Value>,
+    /// Results of the steps run so far
+    results: Vec<StepResult>,
+    /// When the run was suspended (RFC 3339), for the step's timeout
+    #[serde(default)]
+    suspended_at: Option<String>,
+}
+
+impl SuspendedWorkflow {
+    /// How long the run has been waiting for input
+    fn waited(&self) -> Duration {
+        self.suspended_at
+            .as_deref()
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+            .and_then(|at| (chrono::Utc::now() - at.with_timezone(&chrono::Utc)).to_std().ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Opens a transport to a remote MCP server by ID, for Tool steps
+pub type McpConnector = std::sync::Arc<dyn Fn(&str) -> McpResult<Box<dyn McpTransport>> + Send + Sync>;
+
+/// Remote MCP servers Tool steps can call
+#[derive(Clone)]
+struct McpServers {
+    pool: std::sync::Arc<McpConnectionPool>,
+    connect: McpConnector,
+}
+
 /// Skill executor for running skills
+#[derive(Clone)]
 pub struct SkillExecutor {
     /// Reference to the skill registry
     registry: std::sync::Arc<SkillRegistry>,
     /// Default timeout for skill execution (seconds)
     default_timeout_secs: u64,
+    /// Where suspended workflows are saved
+    state_dir: Option<PathBuf>,
+    /// Most stdout/stderr kept per command stream
+    max_output_bytes: usize,
+    /// Set once running workflows should stop; shared by clones of this executor
+    cancel_tx: std::sync::Arc<watch::Sender<bool>>,
+    /// Servers for Tool steps (None fails them)
+    mcp_servers: Option<McpServers>,
+    /// Runs waiting at a UserInput step, by run ID; shared by clones of this executor
+    suspended: std::sync::Arc<DashMap<String, SuspendedWorkflow>>,
+    /// Workflow run events
+    event_tx: broadcast::Sender<WorkflowEvent>,
 }
 
 impl SkillExecutor {
@@ -30,15 +110,106 @@ impl SkillExecutor {
         Self {
             registry,
             default_timeout_secs: 300, // 5 minutes
+            state_dir: dirs::data_dir().map(|d| d.join(crate::mcp::server::APP_IDENTIFIER).join("suspended-workflows")),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            cancel_tx: std::sync::Arc::new(watch::channel(false).0),
+            mcp_servers: None,
+            suspended: std::sync::Arc::new(DashMap::new()),
+            event_tx: broadcast::channel(64).0,
         }
     }
 
+    /// Set how much stdout/stderr is kept per command stream
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
     /// Set default timeout
     pub fn with_timeout(mut self, timeout_secs: u64) -> Self {
         self.default_timeout_secs = timeout_secs;
         self
     }
 
+    /// Set the directory suspended workflows are saved in
+    pub fn with_state_dir(mut self, state_dir: impl Into<PathBuf>) -> Self {
+        self.state_dir = Some(state_dir.into());
+        self
+    }
+
+    /// Let Tool steps call remote MCP servers over `pool`, opening connections with `connect`
+    pub fn with_mcp_servers(mut self, pool: std::sync::Arc<McpConnectionPool>, connect: McpConnector) -> Self {
+        self.mcp_servers = Some(McpServers { pool, connect });
+        self
+    }
+
+    /// Stop workflows run by this executor
+    ///
+    /// Steps that haven't started never will, and steps waiting to retry give up at once.
+    pub fn cancel(&self) {
+        self.cancel_tx.send_replace(true);
+    }
+
+    /// Whether `cancel` has been called
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel_tx.borrow()
+    }
+
+    /// Resolves once `cancel` is called
+    async fn cancelled(&self) {
+        let mut cancel_rx = self.cancel_tx.subscribe();
+        let _ = cancel_rx.wait_for(|&cancelled| cancelled).await;
+    }
+
+    /// Subscribe to workflow run events
+    pub fn subscribe(&self) -> broadcast::Receiver<WorkflowEvent> {
+        self.event_tx.subscribe()
+    }
+
+    /// Time out UserInput steps that aren't answered in time, until `cancel` is called
+    ///
+    /// Runs suspended before this started, or by a previous process, time out
+    /// when they are next resumed instead.
+    pub async fn run_input_timeouts(&self) {
+        let mut events = self.subscribe();
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = self.cancelled() => return,
+            };
+            let (run_id, step_id, timeout_secs) = match event {
+                Ok(WorkflowEvent::InputRequired {
+                    run_id,
+                    step_id,
+                    timeout_secs: Some(timeout_secs),
+                    ..
+                }) => (run_id, step_id, timeout_secs),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Missed {} workflow events; some input timeouts won't fire", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+
+            let executor = self.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(timeout_secs)) => {}
+                    _ = executor.cancelled() => return,
+                }
+                let waiting = executor
+                    .suspended
+                    .get(&run_id)
+                    .map(|run| run.step_id == step_id)
+                    .unwrap_or(false);
+                if waiting {
+                    executor.expire_input(&run_id, &step_id).await;
+                }
+            });
+        }
+    }
+
     /// Execute a skill by ID
     pub async fn execute(&self, skill_id: &str, context: SkillContext) -> SkillResult {
         let start = Instant::now();
@@ -52,6 +223,7 @@ impl SkillExecutor {
                     error: Some(format!("Skill not found: {}", skill_id)),
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 };
             }
         };
@@ -63,6 +235,7 @@ impl SkillExecutor {
                 error: Some("Skill is disabled".to_string()),
                 duration_ms: start.elapsed().as_millis() as u64,
                 steps: None,
+                resume_token: None,
             };
         }
 
@@ -77,6 +250,161 @@ impl SkillExecutor {
         }
     }
 
+    /// Preview a workflow without running anything
+    ///
+    /// Each step's output holds its resolved command or prompt, marked with
+    /// `"dry_run": true`. SkillRef steps include the preview of the referenced
+    /// workflow. The result output lists the step IDs in execution order.
+    pub fn execute_dry_run(&self, skill_id: &str, context: &SkillContext) -> SkillResult {
+        let start = Instant::now();
+        let mut stack = Vec::new();
+
+        match self.dry_run_skill(skill_id, context, &mut stack) {
+            Ok((order, steps)) => {
+                let success = steps.iter().all(|s| s.success);
+                SkillResult {
+                    success,
+                    output: Some(serde_json::json!({
+                        "dry_run": true,
+                        "order": order,
+                    })),
+                    error: steps.iter().find(|s| !s.success).and_then(|s| s.error.clone()),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    steps: Some(steps),
+                    resume_token: None,
+                }
+            }
+            Err(e) => SkillResult {
+                success: false,
+                output: None,
+                error: Some(e),
+                duration_ms: start.elapsed().as_millis() as u64,
+                steps: None,
+                resume_token: None,
+            },
+        }
+    }
+
+    /// Resolve a workflow's steps in execution order; `stack` guards against SkillRef cycles
+    fn dry_run_skill(
+        &self,
+        skill_id: &str,
+        context: &SkillContext,
+        stack: &mut Vec<String>,
+    ) -> Result<(Vec<String>, Vec<StepResult>), String> {
+        let skill = self
+            .registry
+            .get_skill(skill_id)
+            .ok_or_else(|| format!("Skill not found: {}", skill_id))?;
+        let workflow = match (&skill.kind, &skill.config.workflow) {
+            (SkillKind::Workflow, Some(workflow)) => workflow,
+            _ => return Err(format!("Skill {} is not a workflow", skill_id)),
+        };
+        if stack.iter().any(|id| id == skill_id) {
+            return Err(format!("Workflow {} references itself", skill_id));
+        }
+
+        if let Some(cycle) = dependency_cycle(workflow) {
+            return Err(format!("Workflow has a dependency cycle: {}", cycle.join(" -> ")));
+        }
+
+        stack.push(skill_id.to_string());
+        let (order, unreachable) = execution_order(workflow);
+        let mut steps = Vec::new();
+        for &i in &order {
+            let step = &workflow.steps[i];
+            let (success, mut output, error) = self.dry_run_step(step, context, stack);
+            output.insert("dry_run".to_string(), serde_json::Value::Bool(true));
+            steps.push(StepResult {
+                step_id: step.id.clone(),
+                step_name: step.name.clone(),
+                success,
+                output: Some(serde_json::Value::Object(output)),
+                error,
+                duration_ms: 0,
+                retries: 0,
+                skipped: false,
+            });
+        }
+        stack.pop();
+
+        // Same error a real run reports for steps it can never start
+        for i in unreachable {
+            let step = &workflow.steps[i];
+            steps.push(StepResult {
+                step_id: step.id.clone(),
+                step_name: step.name.clone(),
+                success: false,
+                output: Some(serde_json::json!({ "dry_run": true })),
+                error: Some("Dependencies not met".to_string()),
+                duration_ms: 0,
+                retries: 0,
+                skipped: false,
+            });
+        }
+
+        let order = order.iter().map(|&i| workflow.steps[i].id.clone()).collect();
+        Ok((order, steps))
+    }
+
+    /// Describe what a step would do, returning (success, output, error)
+    fn dry_run_step(
+        &self,
+        step: &WorkflowStep,
+        context: &SkillContext,
+        stack: &mut Vec<String>,
+    ) -> (
+        bool,
+        serde_json::Map<String, serde_json::Value>,
+        Option<String>,
+    ) {
+        let mut output = serde_json::Map::new();
+        output.insert("kind".to_string(), serde_json::to_value(&step.kind).unwrap_or_default());
+        if let Some(condition) = &step.condition {
+            output.insert("condition".to_string(), condition.clone().into());
+        }
+
+        match step.kind {
+            WorkflowStepKind::Shell => {
+                let command = step_text(step, "command", context);
+                output.insert("command".to_string(), command.into());
+                output.insert("working_dir".to_string(), context.project_path.clone().into());
+            }
+            WorkflowStepKind::Prompt | WorkflowStepKind::UserInput => {
+                let prompt = step_text(step, "prompt", context);
+                output.insert("prompt".to_string(), prompt.into());
+            }
+            WorkflowStepKind::SkillRef => {
+                let skill_id = step
+                    .config
+                    .get("skill_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                output.insert("skill_ref".to_string(), skill_id.into());
+
+                match self.dry_run_skill(skill_id, context, stack) {
+                    Ok((order, steps)) => {
+                        let error = steps.iter().find(|s| !s.success).and_then(|s| s.error.clone());
+                        output.insert("order".to_string(), serde_json::json!(order));
+                        output.insert("steps".to_string(), serde_json::json!(steps));
+                        return (error.is_none(), output, error);
+                    }
+                    Err(e) => return (false, output, Some(e)),
+                }
+            }
+            WorkflowStepKind::Tool => {
+                for key in ["server_id", "tool", "arguments"] {
+                    if let Some(value) = step.config.get(key) {
+                        output.insert(key.to_string(), value.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        (true, output, None)
+    }
+
     /// Execute a slash command by name
     pub async fn execute_slash_command_by_name(
         &self,
@@ -95,6 +423,7 @@ impl SkillExecutor {
                     error: Some(format!("Slash command not found: /{}", command_name)),
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 };
             }
         };
@@ -105,6 +434,7 @@ impl SkillExecutor {
         let context = SkillContext {
             project_path: project_path.to_string(),
             session_id: None,
+            tool_name: None,
             arguments: args,
             env: std::env::vars().collect(),
             variables: HashMap::new(),
@@ -126,18 +456,31 @@ impl SkillExecutor {
                     error: Some("Invalid slash command configuration".to_string()),
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 };
             }
         };
 
-        // Expand template variables in prompt
-        let mut prompt = cmd_config.prompt.clone();
-
-        // Replace $ARGUMENTS with actual arguments
-        if let Some(args) = context.arguments.get("ARGUMENTS") {
-            let args_str = args.as_str().unwrap_or("");
-            prompt = prompt.replace("$ARGUMENTS", args_str);
-        }
+        // Expand $ARGUMENTS and the parsed ${name} arguments
+        let raw_args = context
+            .arguments
+            .get("ARGUMENTS")
+            .and_then(|args| args.as_str())
+            .unwrap_or("");
+        let mut prompt = match cmd_config.expand_prompt(raw_args) {
+            Ok(prompt) => prompt,
+            Err(e) => {
+                // The usage and help let the UI show how to call the command
+                return SkillResult {
+                    success: false,
+                    output: serde_json::to_value(&e).ok(),
+                    error: Some(e.to_string()),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    steps: None,
+                    resume_token: None,
+                };
+            }
+        };
 
         // Replace other variables
         for (key, value) in &context.variables {
@@ -160,10 +503,14 @@ impl SkillExecutor {
             error: None,
             duration_ms: start.elapsed().as_millis() as u64,
             steps: None,
+            resume_token: None,
         }
     }
 
     /// Execute a hook skill
+    ///
+    /// The command runs with the variables from `hook_env` set, and `${VAR}` in the
+    /// command string is replaced with their values (or the context's environment).
     async fn execute_hook(&self, skill: &Skill, context: SkillContext) -> SkillResult {
         let start = Instant::now();
 
@@ -176,33 +523,47 @@ impl SkillExecutor {
                     error: Some("Invalid hook configuration".to_string()),
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 };
             }
         };
 
+        let env = hook_env(skill, hook_config, &context);
+        let command = substitute_vars(&hook_config.command, |name| {
+            env.get(name).or_else(|| context.env.get(name)).cloned()
+        });
+        let stdin = if hook_config.stdin_json {
+            serde_json::to_vec(&context).ok()
+        } else {
+            None
+        };
+
         // Execute the hook command
         let result = self
-            .run_shell_command(
-                &hook_config.command,
+            .run_shell_command_with_stdin(
+                &command,
                 &context.project_path,
                 hook_config.timeout_secs,
-                &hook_config.env,
+                &env,
+                stdin,
             )
             .await;
 
         match result {
-            Ok((stdout, stderr, exit_code)) => {
-                let success = exit_code == 0;
+            Ok(output) => {
+                let success = output.exit_code == 0;
                 SkillResult {
                     success,
                     output: Some(serde_json::json!({
-                        "stdout": stdout,
-                        "stderr": stderr,
-                        "exit_code": exit_code,
+                        "stdout": output.stdout,
+                        "stderr": output.stderr,
+                        "exit_code": output.exit_code,
+                        "truncated": output.truncated,
                     })),
-                    error: if success { None } else { Some(stderr) },
+                    error: if success { None } else { Some(output.stderr) },
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 }
             }
             Err(e) => SkillResult {
@@ -211,26 +572,80 @@ impl SkillExecutor {
                 error: Some(e),
                 duration_ms: start.elapsed().as_millis() as u64,
                 steps: None,
+                resume_token: None,
             },
         }
     }
 
     /// Execute hooks for a specific trigger
+    ///
+    /// For PreTool, `can_block` hooks decide whether the tool may run: the first
+    /// to block stops the remaining hooks, and a modification replaces the
+    /// `tool_args` argument seen by later hooks.
     pub async fn execute_hooks_for_trigger(
         &self,
         trigger: HookTrigger,
-        context: SkillContext,
-    ) -> Vec<SkillResult> {
+        mut context: SkillContext,
+    ) -> HookOutcome {
         let trigger_str = format!("{:?}", trigger).to_lowercase();
-        let hooks = self.registry.get_hooks_for_trigger(&trigger_str);
-
+        let hooks: Vec<Skill> = self
+            .registry
+            .get_hooks_for_trigger(&trigger_str)
+            .into_iter()
+            .filter(|hook| {
+                hook.config
+                    .hook
+                    .as_ref()
+                    .is_some_and(|h| h.matches_tool(context.tool_name.as_deref()))
+            })
+            .collect();
+
+        let mut decision = HookDecision::Allow;
+        let mut decided_by = None;
         let mut results = Vec::new();
         for hook in hooks {
             let result = self.execute(&hook.id, context.clone()).await;
+            let can_block = hook.config.hook.as_ref().is_some_and(|h| h.can_block);
+
+            if can_block && trigger == HookTrigger::PreTool {
+                match hook_decision(&result) {
+                    HookDecision::Allow => {}
+                    HookDecision::Block { reason } => {
+                        info!("Hook {} blocked the tool call: {}", hook.name, reason);
+                        results.push(result);
+                        return HookOutcome {
+                            decision: HookDecision::Block { reason },
+                            decided_by: Some(hook.name),
+                            results,
+                        };
+                    }
+                    HookDecision::Modify { tool_args } => {
+                        debug!("Hook {} modified the tool arguments", hook.name);
+                        context
+                            .arguments
+                            .insert("tool_args".to_string(), tool_args.clone());
+                        decision = HookDecision::Modify { tool_args };
+                        decided_by = Some(hook.name.clone());
+                    }
+                }
+            }
             results.push(result);
         }
 
-        results
+        HookOutcome {
+            decision,
+            decided_by,
+            results,
+        }
+    }
+
+    /// Whether any enabled hook for `trigger` is allowed to block or modify the call
+    pub fn has_blocking_hooks(&self, trigger: HookTrigger) -> bool {
+        let trigger_str = format!("{:?}", trigger).to_lowercase();
+        self.registry
+            .get_hooks_for_trigger(&trigger_str)
+            .iter()
+            .any(|hook| hook.enabled && hook.config.hook.as_ref().is_some_and(|h| h.can_block))
     }
 
     /// Execute a workflow skill
@@ -246,63 +661,449 @@ impl SkillExecutor {
                     error: Some("Invalid workflow configuration".to_string()),
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 };
             }
         };
 
-        // Build dependency graph and execute in order
-        let mut step_results = Vec::new();
-        let mut completed: HashMap<String, serde_json::Value> = HashMap::new();
-        let mut variables = context.variables.clone();
+        let run_id = uuid::Uuid::new_v4().to_string();
+        self.run_workflow(&run_id, skill, workflow, context, HashMap::new(), Vec::new(), start)
+            .await
+    }
 
-        // Simple sequential execution (TODO: parallel execution for independent steps)
-        for step in &workflow.steps {
-            // Check dependencies
-            let deps_met = step
-                .depends_on
-                .iter()
-                .all(|dep| completed.contains_key(dep));
+    /// Resume a workflow suspended at a UserInput step, using `input` as that step's output
+    pub async fn resume_workflow(&self, token: &str, input: serde_json::Value) -> SkillResult {
+        self.resume_run(token, None, Some(input)).await
+    }
+
+    /// Answer the UserInput step `step_id` that run `run_id` is waiting at, and resume the run
+    ///
+    /// The value becomes the step's output and the workflow variable named by the
+    /// step's `variable` config (its ID if unset).
+    pub async fn provide_input(&self, run_id: &str, step_id: &str, value: serde_json::Value) -> SkillResult {
+        self.resume_run(run_id, Some(step_id), Some(value)).await
+    }
+
+    /// Resume run `run_id` as if step `step_id` timed out waiting for input
+    ///
+    /// The step fails, unless its config has `"on_timeout": "default"`, in which
+    /// case it gets its `default` config value (null if unset) as input.
+    pub async fn expire_input(&self, run_id: &str, step_id: &str) -> SkillResult {
+        self.resume_run(run_id, Some(step_id), None).await
+    }
+
+    /// Resume a suspended run with `input`, or as timed out if there is none
+    async fn resume_run(&self, run_id: &str, step_id: Option<&str>, input: Option<serde_json::Value>) -> SkillResult {
+        let start = Instant::now();
+        let fail = |error: String| SkillResult {
+            success: false,
+            output: None,
+            error: Some(error),
+            duration_ms: start.elapsed().as_millis() as u64,
+            steps: None,
+            resume_token: None,
+        };
+
+        let suspended = match self.suspended_run(run_id).await {
+            Ok(suspended) => suspended,
+            Err(e) => return fail(e),
+        };
+        if let Some(step_id) = step_id {
+            if step_id != suspended.step_id {
+                return fail(format!(
+                    "Run {} is waiting for input at step {}, not {}",
+                    run_id, suspended.step_id, step_id
+                ));
+            }
+        }
+
+        // Removed before running so the same run can't be resumed twice
+        match self.take_suspended_run(run_id).await {
+            Ok(true) => {}
+            Ok(false) => return fail(format!("No suspended workflow for run {}", run_id)),
+            Err(e) => return fail(e),
+        }
+
+        let skill = match self.registry.get_skill(&suspended.skill_id) {
+            Some(skill) => skill,
+            None => return fail(format!("Skill not found: {}", suspended.skill_id)),
+        };
+        let workflow = match &skill.config.workflow {
+            Some(w) => w,
+            None => return fail("Invalid workflow configuration".to_string()),
+        };
+        let step = match workflow.steps.iter().find(|s| s.id == suspended.step_id) {
+            Some(step) => step,
+            None => return fail(format!("Workflow step not found: {}", suspended.step_id)),
+        };
 
-            if !deps_met {
-                step_results.push(StepResult {
+        // Input that arrives after the step timed out, e.g. while Opcode wasn't running, is too late
+        let timed_out = input.is_none()
+            || step
+                .timeout_secs
+                .is_some_and(|secs| suspended.waited() >= Duration::from_secs(secs));
+        let input = if timed_out {
+            warn!("Workflow {} step {} timed out waiting for input", skill.id, step.id);
+            match step.config.get("on_timeout").and_then(|v| v.as_str()) {
+                Some("default") => Some(step.config.get("default").cloned().unwrap_or(serde_json::Value::Null)),
+                _ => None,
+            }
+        } else {
+            input
+        };
+
+        info!("Resuming workflow {} at step {}", skill.id, step.id);
+
+        let mut context = suspended.context;
+        let mut completed = suspended.completed;
+        let mut results = suspended.results;
+        match input {
+            Some(input) => {
+                let variable = step.config.get("variable").and_then(|v| v.as_str()).unwrap_or(&step.id);
+                context.variables.insert(variable.to_string(), input.clone());
+                completed.insert(step.id.clone(), input.clone());
+                results.push(StepResult {
                     step_id: step.id.clone(),
                     step_name: step.name.clone(),
-                    success: false,
-                    output: None,
-                    error: Some("Dependencies not met".to_string()),
-                    duration_ms: 0,
+                    success: true,
+                    output: Some(input),
+                    error: None,
+                    duration_ms: suspended.waited().as_millis() as u64,
                     retries: 0,
+                    skipped: false,
                 });
-                continue;
             }
+            None => results.push(StepResult {
+                step_id: step.id.clone(),
+                step_name: step.name.clone(),
+                success: false,
+                output: None,
+                error: Some("Timed out waiting for input".to_string()),
+                duration_ms: suspended.waited().as_millis() as u64,
+                retries: 0,
+                skipped: false,
+            }),
+        }
+
+        let result = self
+            .run_workflow(run_id, &skill, workflow, context, completed, results, start)
+            .await;
+        if result.resume_token.is_none() {
+            let _ = self.event_tx.send(WorkflowEvent::Finished {
+                run_id: run_id.to_string(),
+                result: result.clone(),
+            });
+        }
+        result
+    }
+
+    /// Run the remaining steps of a workflow, given the steps already finished
+    #[allow(clippy::too_many_arguments)]
+    async fn run_workflow(
+        &self,
+        run_id: &str,
+        skill: &Skill,
+        workflow: &WorkflowConfig,
+        context: SkillContext,
+        mut completed: HashMap<String, serde_json::Value>,
+        finished: Vec<StepResult>,
+        start: Instant,
+    ) -> SkillResult {
+        if let Some(cycle) = dependency_cycle(workflow) {
+            return SkillResult {
+                success: false,
+                output: None,
+                error: Some(format!("Workflow has a dependency cycle: {}", cycle.join(" -> "))),
+                duration_ms: start.elapsed().as_millis() as u64,
+                steps: None,
+                resume_token: None,
+            };
+        }
+
+        let mut conditions = Vec::with_capacity(workflow.steps.len());
+        for step in &workflow.steps {
+            match step.condition.as_deref().map(Condition::parse).transpose() {
+                Ok(condition) => conditions.push(condition),
+                Err(e) => {
+                    return SkillResult {
+                        success: false,
+                        output: None,
+                        error: Some(format!("Invalid condition for step {}: {}", step.id, e)),
+                        duration_ms: start.elapsed().as_millis() as u64,
+                        steps: None,
+                        resume_token: None,
+                    };
+                }
+            }
+        }
+
+        // Each step starts as soon as its dependencies succeed, up to max_parallel at a time
+        let max_parallel = workflow.max_parallel.unwrap_or(DEFAULT_MAX_PARALLEL).max(1) as usize;
+
+        let mut results: Vec<Option<StepResult>> = vec![None; workflow.steps.len()];
+        for result in finished {
+            if let Some(i) = workflow.steps.iter().position(|s| s.id == result.step_id) {
+                results[i] = Some(result);
+            }
+        }
+        let mut succeeded: HashSet<String> = results
+            .iter()
+            .flatten()
+            .filter(|r| r.success && !r.skipped)
+            .map(|r| r.step_id.clone())
+            .collect();
+        let mut skipped: HashSet<String> = results
+            .iter()
+            .flatten()
+            .filter(|r| r.skipped)
+            .map(|r| r.step_id.clone())
+            .collect();
+        let mut failed: HashSet<String> = results
+            .iter()
+            .flatten()
+            .filter(|r| !r.success)
+            .map(|r| r.step_id.clone())
+            .collect();
+        let mut pending: Vec<usize> = (0..workflow.steps.len())
+            .filter(|&i| results[i].is_none())
+            .collect();
+        // Declared inputs the caller left out fall back to their defaults
+        let mut variables = context.variables.clone();
+        for input in &workflow.inputs {
+            if let Some(ref default) = input.default {
+                variables.entry(input.name.clone()).or_insert_with(|| default.clone());
+            }
+        }
+
+        let mut tasks = JoinSet::new();
+        let mut running: HashMap<tokio::task::Id, usize> = HashMap::new();
+
+        loop {
+            // Repeat while steps are skipped or fail without running, so that carries down the graph
+            let mut settled = true;
+            let mut waiting = Vec::new();
+            for i in std::mem::take(&mut pending) {
+                let step = &workflow.steps[i];
+
+                if self.is_cancelled() {
+                    results[i] = Some(StepResult {
+                        step_id: step.id.clone(),
+                        step_name: step.name.clone(),
+                        success: false,
+                        output: None,
+                        error: Some("Workflow cancelled".to_string()),
+                        duration_ms: 0,
+                        retries: 0,
+                        skipped: false,
+                    });
+                    failed.insert(step.id.clone());
+                    continue;
+                }
+
+                // Dependents of a failed step never start
+                if let Some(dep) = step.depends_on.iter().find(|dep| failed.contains(*dep)) {
+                    results[i] = Some(StepResult {
+                        step_id: step.id.clone(),
+                        step_name: step.name.clone(),
+                        success: false,
+                        output: None,
+                        error: Some(format!("Skipped: dependency {} failed", dep)),
+                        duration_ms: 0,
+                        retries: 0,
+                        skipped: false,
+                    });
+                    failed.insert(step.id.clone());
+                    settled = false;
+                    continue;
+                }
+
+                // Nor do dependents of a skipped step, unless it's an optional dependency
+                let skipped_dep = step
+                    .depends_on
+                    .iter()
+                    .find(|dep| skipped.contains(*dep) && !step.optional_depends_on.contains(*dep));
+                if let Some(dep) = skipped_dep {
+                    results[i] = Some(skipped_step(step, &format!("Dependency {} was skipped", dep)));
+                    skipped.insert(step.id.clone());
+                    settled = false;
+                    continue;
+                }
+
+                if !step
+                    .depends_on
+                    .iter()
+                    .all(|dep| succeeded.contains(dep) || skipped.contains(dep))
+                {
+                    waiting.push(i);
+                    continue;
+                }
 
-            // Execute step
-            let step_result = self
-                .execute_workflow_step(step, &context, &completed, &mut variables)
-                .await;
+                if let Some(condition) = &conditions[i] {
+                    match condition.evaluate(&completed, &variables) {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            info!("Skipping workflow step {}: condition is false", step.id);
+                            results[i] = Some(skipped_step(step, "Condition is false"));
+                            skipped.insert(step.id.clone());
+                            settled = false;
+                            continue;
+                        }
+                        Err(e) => {
+                            results[i] = Some(StepResult {
+                                step_id: step.id.clone(),
+                                step_name: step.name.clone(),
+                                success: false,
+                                output: None,
+                                error: Some(format!("Condition failed: {}", e)),
+                                duration_ms: 0,
+                                retries: 0,
+                                skipped: false,
+                            });
+                            failed.insert(step.id.clone());
+                            settled = false;
+                            continue;
+                        }
+                    }
+                }
 
-            if step_result.success {
-                if let Some(ref output) = step_result.output {
-                    completed.insert(step.id.clone(), output.clone());
+                // User input is only requested once nothing else can run
+                if running.len() >= max_parallel || matches!(step.kind, WorkflowStepKind::UserInput) {
+                    waiting.push(i);
+                    continue;
                 }
+
+                debug!("Starting workflow step {} ({} running)", step.id, running.len() + 1);
+                let (executor, step, context, completed, variables) = (
+                    self.clone(),
+                    step.clone(),
+                    context.clone(),
+                    completed.clone(),
+                    variables.clone(),
+                );
+                let handle = tasks.spawn(async move {
+                    executor
+                        .execute_workflow_step(&step, &context, &completed, &variables)
+                        .await
+                });
+                running.insert(handle.id(), i);
+            }
+            pending = waiting;
+            if !settled {
+                continue;
             }
 
-            let step_failed = !step_result.success;
-            step_results.push(step_result);
+            let (i, result) = match tasks.join_next_with_id().await {
+                Some(Ok((id, result))) => match running.remove(&id) {
+                    Some(i) => (i, result),
+                    None => continue,
+                },
+                Some(Err(e)) => match running.remove(&e.id()) {
+                    Some(i) => {
+                        let step = &workflow.steps[i];
+                        error!("Workflow step {} panicked: {}", step.id, e);
+                        let result = StepResult {
+                            step_id: step.id.clone(),
+                            step_name: step.name.clone(),
+                            success: false,
+                            output: None,
+                            error: Some(format!("Step panicked: {}", e)),
+                            duration_ms: 0,
+                            retries: 0,
+                            skipped: false,
+                        };
+                        (i, result)
+                    }
+                    None => continue,
+                },
+                None => {
+                    let input = pending.iter().copied().find(|&i| {
+                        let step = &workflow.steps[i];
+                        matches!(step.kind, WorkflowStepKind::UserInput)
+                            && step
+                                .depends_on
+                                .iter()
+                                .all(|dep| succeeded.contains(dep) || skipped.contains(dep))
+                    });
+                    match input {
+                        Some(i) if failed.is_empty() => {
+                            let step_results = results.into_iter().flatten().collect();
+                            return self
+                                .suspend_workflow(
+                                    run_id,
+                                    skill,
+                                    &workflow.steps[i],
+                                    context,
+                                    completed,
+                                    step_results,
+                                    start,
+                                )
+                                .await;
+                        }
+                        _ => break,
+                    }
+                }
+            };
+
+            if result.success {
+                succeeded.insert(result.step_id.clone());
+                if let Some(ref output) = result.output {
+                    completed.insert(result.step_id.clone(), output.clone());
+                }
+            } else {
+                failed.insert(result.step_id.clone());
+            }
+            results[i] = Some(result);
+        }
 
-            if step_failed {
-                break; // Stop on first failure
+        // Steps left over without a failure have missing dependencies
+        if failed.is_empty() {
+            for i in pending {
+                let step = &workflow.steps[i];
+                results[i] = Some(StepResult {
+                    step_id: step.id.clone(),
+                    step_name: step.name.clone(),
+                    success: false,
+                    output: None,
+                    error: Some("Dependencies not met".to_string()),
+                    duration_ms: 0,
+                    retries: 0,
+                    skipped: false,
+                });
             }
         }
 
+        // Results in step order
+        let step_results: Vec<StepResult> = results.into_iter().flatten().collect();
+
         let all_success = step_results.iter().all(|r| r.success);
 
-        SkillResult {
-            success: all_success,
-            output: Some(serde_json::json!({
+        // Declared outputs replace the raw step outputs
+        let output = if workflow.outputs.is_empty() {
+            serde_json::json!({
                 "completed": completed,
                 "variables": variables,
-            })),
+            })
+        } else {
+            let outputs: serde_json::Map<String, serde_json::Value> = workflow
+                .outputs
+                .iter()
+                .map(|(name, expression)| {
+                    let value = match resolve_output(expression, &completed, &variables) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            warn!("Workflow {} output {} is unavailable: {}", skill.id, name, e);
+                            serde_json::Value::Null
+                        }
+                    };
+                    (name.clone(), value)
+                })
+                .collect();
+            serde_json::Value::Object(outputs)
+        };
+
+        SkillResult {
+            success: all_success,
+            output: Some(output),
             error: if all_success {
                 None
             } else {
@@ -313,65 +1114,271 @@ impl SkillExecutor {
             },
             duration_ms: start.elapsed().as_millis() as u64,
             steps: Some(step_results),
+            resume_token: None,
         }
     }
 
-    /// Execute a single workflow step
-    async fn execute_workflow_step(
+    /// Save a workflow waiting at a UserInput step and return its run ID as the resume token
+    ///
+    /// Also announces the step with a `WorkflowEvent::InputRequired`.
+    #[allow(clippy::too_many_arguments)]
+    async fn suspend_workflow(
         &self,
+        run_id: &str,
+        skill: &Skill,
         step: &WorkflowStep,
-        context: &SkillContext,
-        completed: &HashMap<String, serde_json::Value>,
-        variables: &mut HashMap<String, serde_json::Value>,
-    ) -> StepResult {
-        let start = Instant::now();
-        info!("Executing workflow step: {} ({})", step.name, step.id);
+        context: SkillContext,
+        completed: HashMap<String, serde_json::Value>,
+        results: Vec<StepResult>,
+        start: Instant,
+    ) -> SkillResult {
+        let prompt = step
+            .config
+            .get("prompt")
+            .map(|_| step_text(step, "prompt", &context));
+        let output = serde_json::json!({
+            "completed": completed,
+            "variables": context.variables,
+            "waiting_for": step.id,
+            "prompt": prompt,
+        });
+
+        let suspended = SuspendedWorkflow {
+            skill_id: skill.id.clone(),
+            step_id: step.id.clone(),
+            context,
+            completed,
+            results,
+            suspended_at: Some(chrono::Utc::now().to_rfc3339()),
+        };
+        if self.state_dir.is_some() {
+            if let Err(e) = self.save_suspended_workflow(run_id, &suspended).await {
+                error!("Failed to suspend workflow {}: {}", skill.id, e);
+                return SkillResult {
+                    success: false,
+                    output: Some(output),
+                    error: Some(e),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    steps: Some(suspended.results),
+                    resume_token: None,
+                };
+            }
+        }
+        let results = suspended.results.clone();
+        self.suspended.insert(run_id.to_string(), suspended);
+
+        info!(
+            "Workflow {} suspended at step {} waiting for input",
+            skill.id, step.id
+        );
+        let _ = self.event_tx.send(WorkflowEvent::InputRequired {
+            run_id: run_id.to_string(),
+            skill_id: skill.id.clone(),
+            step_id: step.id.clone(),
+            prompt,
+            schema: step.config.get("schema").cloned(),
+            timeout_secs: step.timeout_secs,
+        });
 
-        let result = match step.kind {
-            WorkflowStepKind::Shell => {
-                let command = step
-                    .config
-                    .get("command")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
+        SkillResult {
+            success: true,
+            output: Some(output),
+            error: None,
+            duration_ms: start.elapsed().as_millis() as u64,
+            steps: Some(results),
+            resume_token: Some(run_id.to_string()),
+        }
+    }
+
+    /// Look up a suspended run, in memory or else on disk, without resuming it
+    async fn suspended_run(&self, run_id: &str) -> Result<SuspendedWorkflow, String> {
+        if let Some(suspended) = self.suspended.get(run_id) {
+            return Ok(suspended.clone());
+        }
+
+        let path = self.suspended_workflow_path(run_id)?;
+        match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Corrupt suspended workflow {}: {}", run_id, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(format!("No suspended workflow for run {}", run_id))
+            }
+            Err(e) => Err(format!("Failed to read suspended workflow: {}", e)),
+        }
+    }
+
+    /// Forget a suspended run, returning false if another caller already took it
+    ///
+    /// With a state directory, deleting the saved file decides who takes the run.
+    async fn take_suspended_run(&self, run_id: &str) -> Result<bool, String> {
+        let in_memory = self.suspended.remove(run_id).is_some();
+        if self.state_dir.is_none() {
+            return Ok(in_memory);
+        }
+
+        let path = self.suspended_workflow_path(run_id)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(format!("Failed to remove suspended workflow: {}", e)),
+        }
+    }
+
+    async fn save_suspended_workflow(
+        &self,
+        token: &str,
+        suspended: &SuspendedWorkflow,
+    ) -> Result<(), String> {
+        let path = self.suspended_workflow_path(token)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create workflow state directory: {}", e))?;
+        }
+        let contents = serde_json::to_string(suspended).map_err(|e| e.to_string())?;
+        tokio::fs::write(&path, contents)
+            .await
+            .map_err(|e| format!("Failed to save suspended workflow: {}", e))
+    }
+
+    fn suspended_workflow_path(&self, token: &str) -> Result<PathBuf, String> {
+        // Tokens come from callers, so only accept the UUIDs we hand out
+        let token =
+            uuid::Uuid::parse_str(token).map_err(|_| format!("Invalid resume token: {}", token))?;
+        let state_dir = self
+            .state_dir
+            .as_ref()
+            .ok_or_else(|| "No directory for suspended workflows".to_string())?;
+        Ok(state_dir.join(format!("{}.json", token)))
+    }
+
+    /// Execute a single workflow step
+    async fn execute_workflow_step(
+        &self,
+        step: &WorkflowStep,
+        context: &SkillContext,
+        completed: &HashMap<String, serde_json::Value>,
+        variables: &HashMap<String, serde_json::Value>,
+    ) -> StepResult {
+        let start = Instant::now();
+        info!("Executing workflow step: {} ({})", step.name, step.id);
+
+        let step = &match interpolate_step(step, completed, variables) {
+            Ok(step) => step,
+            Err(e) => {
+                return StepResult {
+                    step_id: step.id.clone(),
+                    step_name: step.name.clone(),
+                    success: false,
+                    output: None,
+                    error: Some(format!("Step {}: {}", step.id, e)),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    retries: 0,
+                    skipped: false,
+                };
+            }
+        };
+
+        let mut retries = 0;
+        let mut attempt_errors = Vec::new();
+        let (success, mut output, error) = loop {
+            let result = self.run_workflow_step_once(step, context).await;
+            if result.0 {
+                break result;
+            }
+
+            // Retry patterns are checked against the error and the step's stderr
+            let stderr = result
+                .1
+                .as_ref()
+                .and_then(|output| output.get("stderr"))
+                .and_then(|stderr| stderr.as_str())
+                .unwrap_or("");
+            let failure = format!("{}\n{}", result.2.as_deref().unwrap_or(""), stderr);
+            let retry = match &step.retry {
+                Some(retry) if retries < retry.max_attempts && retry.matches(&failure) => retry,
+                _ => break result,
+            };
+            attempt_errors.push(result.2.clone().unwrap_or_default());
+
+            retries += 1;
+            let delay = retry.backoff.delay(retries);
+            warn!(
+                "Workflow step {} failed, retrying in {:?} ({}/{})",
+                step.id, delay, retries, retry.max_attempts
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = self.cancelled() => {
+                    info!("Workflow step {} cancelled while waiting to retry", step.id);
+                    break (false, result.1, Some("Workflow cancelled".to_string()));
+                }
+            }
+        };
+
+        // Earlier attempts' errors, oldest first
+        if !attempt_errors.is_empty() {
+            match output {
+                Some(serde_json::Value::Object(ref mut map)) => {
+                    map.insert("attempt_errors".to_string(), attempt_errors.into());
+                }
+                None => output = Some(serde_json::json!({ "attempt_errors": attempt_errors })),
+                Some(_) => {}
+            }
+        }
+
+        StepResult {
+            step_id: step.id.clone(),
+            step_name: step.name.clone(),
+            success,
+            output,
+            error,
+            duration_ms: start.elapsed().as_millis() as u64,
+            retries,
+            skipped: false,
+        }
+    }
+
+    /// Run a workflow step once, returning (success, output, error)
+    async fn run_workflow_step_once(
+        &self,
+        step: &WorkflowStep,
+        context: &SkillContext,
+    ) -> (bool, Option<serde_json::Value>, Option<String>) {
+        match step.kind {
+            WorkflowStepKind::Shell => {
+                let command = step_text(step, "command", context);
 
                 match self
                     .run_shell_command(
-                        command,
+                        &command,
                         &context.project_path,
                         step.timeout_secs.unwrap_or(60),
                         &context.env,
                     )
                     .await
                 {
-                    Ok((stdout, stderr, code)) => {
-                        let success = code == 0;
+                    Ok(output) => {
+                        let success = output.exit_code == 0;
                         (
                             success,
                             Some(serde_json::json!({
-                                "stdout": stdout,
-                                "stderr": stderr,
-                                "exit_code": code,
+                                "stdout": output.stdout,
+                                "stderr": output.stderr,
+                                "exit_code": output.exit_code,
+                                "truncated": output.truncated,
                             })),
-                            if success { None } else { Some(stderr) },
+                            if success { None } else { Some(output.stderr) },
                         )
                     }
                     Err(e) => (false, None, Some(e)),
                 }
             }
             WorkflowStepKind::Prompt => {
-                let prompt = step
-                    .config
-                    .get("prompt")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
+                let prompt = step_text(step, "prompt", context);
 
                 // Just return the prompt for now
-                (
-                    true,
-                    Some(serde_json::json!({ "prompt": prompt })),
-                    None,
-                )
+                (true, Some(serde_json::json!({ "prompt": prompt })), None)
             }
             WorkflowStepKind::SkillRef => {
                 let skill_id = step
@@ -387,17 +1394,57 @@ impl SkillExecutor {
                     None,
                 )
             }
+            WorkflowStepKind::Tool => self.run_tool_step(step).await,
             _ => (true, None, None),
+        }
+    }
+
+    /// Call a remote MCP tool for a Tool step, returning (success, output, error)
+    ///
+    /// Config: `server_id`, `tool` and optional `arguments` (an object).
+    async fn run_tool_step(&self, step: &WorkflowStep) -> (bool, Option<serde_json::Value>, Option<String>) {
+        let config_str = |key: &str| step.config.get(key).and_then(|v| v.as_str()).filter(|v| !v.is_empty());
+        let (server_id, tool) = match (config_str("server_id"), config_str("tool")) {
+            (Some(server_id), Some(tool)) => (server_id, tool),
+            _ => return (false, None, Some("Tool steps need a server_id and a tool".to_string())),
+        };
+        let arguments = step.config.get("arguments").filter(|v| !v.is_null()).cloned();
+        if arguments.as_ref().is_some_and(|v| !v.is_object()) {
+            return (false, None, Some("Tool arguments must be an object".to_string()));
+        }
+        let servers = match &self.mcp_servers {
+            Some(servers) => servers,
+            None => return (false, None, Some("Remote MCP servers aren't available here".to_string())),
         };
 
-        StepResult {
-            step_id: step.id.clone(),
-            step_name: step.name.clone(),
-            success: result.0,
-            output: result.1,
-            error: result.2,
-            duration_ms: start.elapsed().as_millis() as u64,
-            retries: 0,
+        let timeout_secs = step.timeout_secs.unwrap_or(60);
+        let call = servers.pool.call(
+            server_id,
+            || (servers.connect)(server_id),
+            |transport| {
+                let tool = tool.to_string();
+                let arguments = arguments.clone();
+                Box::pin(async move { transport.call_tool(&tool, arguments).await })
+            },
+        );
+        let result = match tokio::time::timeout(Duration::from_secs(timeout_secs), call).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => return (false, None, Some(format!("Tool {} on {} failed: {}", tool, server_id, e))),
+            Err(_) => {
+                let error = format!("Tool {} on {} timed out after {}s", tool, server_id, timeout_secs);
+                return (false, None, Some(error));
+            }
+        };
+
+        let output = tool_step_output(server_id, tool, &result);
+        if result.is_error == Some(true) {
+            let error = match output["text"].as_str() {
+                Some(text) if !text.is_empty() => text.to_string(),
+                _ => format!("Tool {} on {} reported an error", tool, server_id),
+            };
+            (false, Some(output), Some(error))
+        } else {
+            (true, Some(output), None)
         }
     }
 
@@ -414,27 +1461,24 @@ impl SkillExecutor {
                     error: Some("Invalid template configuration".to_string()),
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 };
             }
         };
 
-        // Expand template variables
-        let mut content = template.content.clone();
-
-        for var in &template.variables {
-            let placeholder = format!("{{{{{}}}}}", var.name);
-            let value = context
-                .variables
-                .get(&var.name)
-                .map(|v| match v {
-                    serde_json::Value::String(s) => s.clone(),
-                    other => other.to_string(),
-                })
-                .or(var.default.clone())
-                .unwrap_or_default();
-
-            content = content.replace(&placeholder, &value);
-        }
+        let content = match render_template(template, &context.variables) {
+            Ok(content) => content,
+            Err(e) => {
+                return SkillResult {
+                    success: false,
+                    output: None,
+                    error: Some(e),
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    steps: None,
+                    resume_token: None,
+                };
+            }
+        };
 
         SkillResult {
             success: true,
@@ -444,6 +1488,7 @@ impl SkillExecutor {
             error: None,
             duration_ms: start.elapsed().as_millis() as u64,
             steps: None,
+            resume_token: None,
         }
     }
 
@@ -460,6 +1505,7 @@ impl SkillExecutor {
                     error: Some("Invalid agent configuration".to_string()),
                     duration_ms: start.elapsed().as_millis() as u64,
                     steps: None,
+                    resume_token: None,
                 };
             }
         };
@@ -482,17 +1528,31 @@ impl SkillExecutor {
             error: None,
             duration_ms: start.elapsed().as_millis() as u64,
             steps: None,
+            resume_token: None,
         }
     }
 
-    /// Run a shell command
+    /// Run a shell command, keeping at most `max_output_bytes` of each output stream
     async fn run_shell_command(
         &self,
         command: &str,
         working_dir: &str,
         timeout_secs: u64,
         env: &HashMap<String, String>,
-    ) -> Result<(String, String, i32), String> {
+    ) -> Result<ShellOutput, String> {
+        self.run_shell_command_with_stdin(command, working_dir, timeout_secs, env, None)
+            .await
+    }
+
+    /// Run a shell command like `run_shell_command`, writing `stdin` to its standard input
+    async fn run_shell_command_with_stdin(
+        &self,
+        command: &str,
+        working_dir: &str,
+        timeout_secs: u64,
+        env: &HashMap<String, String>,
+        stdin: Option<Vec<u8>>,
+    ) -> Result<ShellOutput, String> {
         let shell = if cfg!(windows) { "cmd" } else { "sh" };
         let shell_arg = if cfg!(windows) { "/C" } else { "-c" };
 
@@ -502,33 +1562,588 @@ impl SkillExecutor {
             .current_dir(working_dir)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
+        if stdin.is_some() {
+            cmd.stdin(Stdio::piped());
+        }
 
         // Add environment variables
         for (key, value) in env {
             cmd.env(key, value);
         }
 
-        let child = cmd.spawn().map_err(|e| format!("Failed to spawn command: {}", e))?;
+        // Run in a new session so a timeout can kill everything the command started
+        #[cfg(unix)]
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
 
-        let output = tokio::time::timeout(
-            Duration::from_secs(timeout_secs),
-            child.wait_with_output(),
-        )
-        .await
-        .map_err(|_| "Command timed out".to_string())?
-        .map_err(|e| format!("Command failed: {}", e))?;
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to spawn command: {}", e))?;
+
+        let input = child.stdin.take().zip(stdin);
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let limit = self.max_output_bytes;
+        let finished = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+            tokio::join!(
+                write_stdin(input),
+                read_capped(stdout, limit),
+                read_capped(stderr, limit),
+                child.wait()
+            )
+        })
+        .await;
+
+        let ((), stdout, stderr, status) = match finished {
+            Ok(finished) => finished,
+            Err(_) => {
+                kill_process_tree(&mut child).await;
+                return Err("Command timed out".to_string());
+            }
+        };
+        let (stdout, stdout_truncated) = stdout.map_err(|e| format!("Command failed: {}", e))?;
+        let (stderr, stderr_truncated) = stderr.map_err(|e| format!("Command failed: {}", e))?;
+        let status = status.map_err(|e| format!("Command failed: {}", e))?;
+
+        Ok(ShellOutput {
+            stdout,
+            stderr,
+            exit_code: status.code().unwrap_or(-1),
+            truncated: stdout_truncated || stderr_truncated,
+        })
+    }
+}
+
+/// Kill a shell and, on Unix, every process in its group
+async fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // The shell leads its own group (see setsid above), so its pid is the group id
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    if let Err(e) = child.kill().await {
+        debug!("Failed to kill timed out command: {}", e);
+    }
+}
+
+/// Write `input` to a child's stdin, then close it
+async fn write_stdin(input: Option<(ChildStdin, Vec<u8>)>) {
+    if let Some((mut stdin, input)) = input {
+        // A command that exits without reading its input closes the pipe early, which is fine
+        if let Err(e) = stdin.write_all(&input).await {
+            debug!("Failed to write command input: {}", e);
+        }
+    }
+}
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let exit_code = output.status.code().unwrap_or(-1);
+/// Read a stream to the end, keeping the first `limit` bytes
+///
+/// Reading continues past the limit so the child never blocks on a full pipe.
+/// Returns the text (with a marker if cut short) and whether it was truncated.
+async fn read_capped(
+    stream: Option<impl AsyncRead + Unpin>,
+    limit: usize,
+) -> std::io::Result<(String, bool)> {
+    let mut stream = match stream {
+        Some(stream) => stream,
+        None => return Ok((String::new(), false)),
+    };
+
+    let mut captured = Vec::new();
+    let mut truncated = false;
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        let room = limit.saturating_sub(captured.len());
+        if n > room {
+            truncated = true;
+        }
+        captured.extend_from_slice(&chunk[..n.min(room)]);
+    }
 
-        Ok((stdout, stderr, exit_code))
+    let mut text = String::from_utf8_lossy(&captured).to_string();
+    if truncated {
+        text.push_str(TRUNCATION_MARKER);
     }
+    Ok((text, truncated))
+}
+
+/// Environment for a hook command: the context as OPCODE_* variables plus the hook's own env
+///
+/// - `OPCODE_TRIGGER`: what fired the hook, e.g. `pre_tool` or `session_start`
+/// - `OPCODE_SKILL_ID`: ID of the hook itself
+/// - `OPCODE_PROJECT_PATH`: project the hook runs in
+/// - `OPCODE_SESSION_ID`: current Claude session, if any
+/// - `OPCODE_TOOL_NAME`: triggering tool, for PreTool/PostTool hooks
+/// - `OPCODE_TOOL_ARGS`: the tool's arguments as JSON (`tool_args` argument)
+/// - `OPCODE_TOOL_OUTPUT`, `OPCODE_TOOL_EXIT_STATUS`: what the tool returned, for PostTool hooks
+/// - `OPCODE_ERROR_MESSAGE`: the error, for OnError hooks (`error` argument)
+/// - `OPCODE_ARG_<NAME>`: every other argument, strings as-is and other values as JSON
+///
+/// Variables in the hook's `env` take precedence. Values substituted into the command
+/// with `${VAR}` are inserted verbatim, so quote them or read the environment instead
+/// when they may contain tool input.
+fn hook_env(skill: &Skill, hook_config: &HookConfig, context: &SkillContext) -> HashMap<String, String> {
+    let mut env = HashMap::new();
+    let trigger = serde_json::to_value(&hook_config.trigger).unwrap_or_default();
+    env.insert("OPCODE_TRIGGER".to_string(), trigger.as_str().unwrap_or_default().to_string());
+    env.insert("OPCODE_SKILL_ID".to_string(), skill.id.clone());
+    env.insert("OPCODE_PROJECT_PATH".to_string(), context.project_path.clone());
+    if let Some(ref session_id) = context.session_id {
+        env.insert("OPCODE_SESSION_ID".to_string(), session_id.clone());
+    }
+    if let Some(ref tool_name) = context.tool_name {
+        env.insert("OPCODE_TOOL_NAME".to_string(), tool_name.clone());
+    }
+
+    for (key, value) in &context.arguments {
+        let name = match key.as_str() {
+            "tool_args" => "OPCODE_TOOL_ARGS".to_string(),
+            "tool_output" => "OPCODE_TOOL_OUTPUT".to_string(),
+            "tool_exit_status" => "OPCODE_TOOL_EXIT_STATUS".to_string(),
+            "error" if hook_config.trigger == HookTrigger::OnError => "OPCODE_ERROR_MESSAGE".to_string(),
+            _ => format!("OPCODE_ARG_{}", env_var_name(key)),
+        };
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        env.insert(name, value);
+    }
+
+    env.extend(hook_config.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    env
+}
+
+/// Decision reported by a blocking hook: JSON on stdout, or a block if it failed
+fn hook_decision(result: &SkillResult) -> HookDecision {
+    let stdout = result
+        .output
+        .as_ref()
+        .and_then(|o| o.get("stdout"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
+    let reported = serde_json::from_str::<HookDecision>(stdout.trim()).ok();
+
+    match reported {
+        Some(HookDecision::Block { reason }) if reason.is_empty() => HookDecision::Block {
+            reason: "Blocked by hook".to_string(),
+        },
+        Some(decision) if result.success => decision,
+        Some(HookDecision::Block { reason }) => HookDecision::Block { reason },
+        _ if result.success => HookDecision::Allow,
+        _ => HookDecision::Block {
+            reason: result
+                .error
+                .clone()
+                .filter(|e| !e.trim().is_empty())
+                .unwrap_or_else(|| "Hook failed".to_string()),
+        },
+    }
+}
+
+/// Order a dry run lists steps in, matching how `run_workflow` schedules them
+///
+/// Returns the step indices in order, plus steps whose dependencies can never be met.
+fn execution_order(workflow: &WorkflowConfig) -> (Vec<usize>, Vec<usize>) {
+    let mut order = Vec::new();
+    let mut done: HashSet<&str> = HashSet::new();
+    let mut pending: Vec<usize> = (0..workflow.steps.len()).collect();
+
+    loop {
+        let ready: Vec<usize> = pending
+            .iter()
+            .copied()
+            .filter(|&i| {
+                workflow.steps[i]
+                    .depends_on
+                    .iter()
+                    .all(|dep| done.contains(dep.as_str()))
+            })
+            .collect();
+        // User input is only requested once nothing else can run
+        let (inputs, mut batch): (Vec<usize>, Vec<usize>) = ready
+            .into_iter()
+            .partition(|&i| matches!(workflow.steps[i].kind, WorkflowStepKind::UserInput));
+        if batch.is_empty() {
+            match inputs.first() {
+                Some(&i) => batch.push(i),
+                None => break,
+            }
+        }
+
+        pending.retain(|i| !batch.contains(i));
+        for &i in &batch {
+            done.insert(workflow.steps[i].id.as_str());
+        }
+        order.extend(batch);
+    }
+
+    (order, pending)
+}
+
+/// Find a loop in the steps' `depends_on`, returned as the step IDs along it
+///
+/// Dependencies on steps that don't exist are ignored; they surface as unmet dependencies.
+pub(crate) fn dependency_cycle(workflow: &WorkflowConfig) -> Option<Vec<String>> {
+    // 0 = not visited, 1 = on the current path, 2 = finished
+    fn visit(
+        i: usize,
+        workflow: &WorkflowConfig,
+        index: &HashMap<&str, usize>,
+        state: &mut [u8],
+        path: &mut Vec<usize>,
+    ) -> Option<Vec<String>> {
+        state[i] = 1;
+        path.push(i);
+        for dep in &workflow.steps[i].depends_on {
+            let j = match index.get(dep.as_str()) {
+                Some(&j) => j,
+                None => continue,
+            };
+            match state[j] {
+                0 => {
+                    if let Some(cycle) = visit(j, workflow, index, state, path) {
+                        return Some(cycle);
+                    }
+                }
+                1 => {
+                    let from = path.iter().position(|&p| p == j).unwrap_or(0);
+                    let mut cycle: Vec<String> =
+                        path[from..].iter().map(|&p| workflow.steps[p].id.clone()).collect();
+                    cycle.push(workflow.steps[j].id.clone());
+                    return Some(cycle);
+                }
+                _ => {}
+            }
+        }
+        path.pop();
+        state[i] = 2;
+        None
+    }
+
+    let index: HashMap<&str, usize> = workflow
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| (step.id.as_str(), i))
+        .collect();
+    let mut state = vec![0u8; workflow.steps.len()];
+    for i in 0..workflow.steps.len() {
+        if state[i] == 0 {
+            if let Some(cycle) = visit(i, workflow, &index, &mut state, &mut Vec::new()) {
+                return Some(cycle);
+            }
+        }
+    }
+    None
+}
+
+/// Result for a step that didn't run because it was skipped
+fn skipped_step(step: &WorkflowStep, reason: &str) -> StepResult {
+    StepResult {
+        step_id: step.id.clone(),
+        step_name: step.name.clone(),
+        success: true,
+        output: Some(serde_json::json!({ "skipped_reason": reason })),
+        error: None,
+        duration_ms: 0,
+        retries: 0,
+        skipped: true,
+    }
+}
+
+/// Copy of `step` with `${{...}}` references in its config strings resolved
+fn interpolate_step(
+    step: &WorkflowStep,
+    completed: &HashMap<String, serde_json::Value>,
+    inputs: &HashMap<String, serde_json::Value>,
+) -> Result<WorkflowStep, String> {
+    fn walk(
+        value: &serde_json::Value,
+        completed: &HashMap<String, serde_json::Value>,
+        inputs: &HashMap<String, serde_json::Value>,
+    ) -> Result<serde_json::Value, String> {
+        Ok(match value {
+            serde_json::Value::String(text) => serde_json::Value::String(interpolate(text, completed, inputs)?),
+            serde_json::Value::Array(items) => serde_json::Value::Array(
+                items
+                    .iter()
+                    .map(|item| walk(item, completed, inputs))
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(key, item)| Ok((key.clone(), walk(item, completed, inputs)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    let mut step = step.clone();
+    step.config = walk(&step.config, completed, inputs)?;
+    Ok(step)
+}
+
+/// Replace each `${{reference}}` in `text` with the value it resolves to
+fn interpolate(
+    text: &str,
+    completed: &HashMap<String, serde_json::Value>,
+    inputs: &HashMap<String, serde_json::Value>,
+) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("${{") {
+        result.push_str(&rest[..open]);
+        let after = &rest[open + 3..];
+        let close = match after.find("}}") {
+            Some(close) => close,
+            None => return Err(format!("Unclosed ${{{{ in '{}'", text)),
+        };
+        match resolve_reference(&after[..close], completed, inputs)? {
+            serde_json::Value::String(s) => result.push_str(&s),
+            other => result.push_str(&other.to_string()),
+        }
+        rest = &after[close + 2..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// A `WorkflowConfig::outputs` value: a template with `${{...}}` references, or a bare reference
+fn resolve_output(
+    expression: &str,
+    completed: &HashMap<String, serde_json::Value>,
+    inputs: &HashMap<String, serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    if expression.contains("${{") {
+        interpolate(expression, completed, inputs).map(serde_json::Value::String)
+    } else {
+        resolve_reference(expression, completed, inputs)
+    }
+}
+
+/// Resolve `steps.<id>.stdout`, `steps.<id>.output.<path>` or `inputs.<name>`
+///
+/// `output` is a shell step's stdout parsed as JSON, or any other step's output as is.
+fn resolve_reference(
+    reference: &str,
+    completed: &HashMap<String, serde_json::Value>,
+    inputs: &HashMap<String, serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let reference = reference.trim();
+    let parts: Vec<&str> = reference.split('.').collect();
+    fn available<'a>(keys: impl Iterator<Item = &'a String>) -> String {
+        let mut keys: Vec<&str> = keys.map(String::as_str).collect();
+        keys.sort_unstable();
+        if keys.is_empty() {
+            "none".to_string()
+        } else {
+            keys.join(", ")
+        }
+    }
+    let object_keys = |value: &serde_json::Value| match value.as_object() {
+        Some(map) => available(map.keys()),
+        None => "none".to_string(),
+    };
+
+    let (value, path) = match parts.as_slice() {
+        ["inputs", name, path @ ..] => match inputs.get(*name) {
+            Some(value) => (value.clone(), path),
+            None => {
+                return Err(format!(
+                    "Unknown input '{}' in ${{{{{}}}}} (available: {})",
+                    name,
+                    reference,
+                    available(inputs.keys())
+                ));
+            }
+        },
+        ["steps", id, field, path @ ..] => {
+            let output = match completed.get(*id) {
+                Some(output) => output,
+                None => {
+                    return Err(format!(
+                        "No output from step '{}' in ${{{{{}}}}} (available: {})",
+                        id,
+                        reference,
+                        available(completed.keys())
+                    ));
+                }
+            };
+            let stdout = output.get("stdout").and_then(|v| v.as_str());
+            let value = match (*field, stdout) {
+                ("output", Some(stdout)) => serde_json::from_str(stdout)
+                    .map_err(|e| format!("stdout of step '{}' is not JSON in ${{{{{}}}}}: {}", id, reference, e))?,
+                ("output", None) => output.clone(),
+                (field, _) => match output.get(field) {
+                    Some(value) => value.clone(),
+                    None => {
+                        return Err(format!(
+                            "Step '{}' has no '{}' in ${{{{{}}}}} (available: {})",
+                            id,
+                            field,
+                            reference,
+                            object_keys(output)
+                        ));
+                    }
+                },
+            };
+            (value, path)
+        }
+        _ => {
+            return Err(format!(
+                "Invalid reference ${{{{{}}}}} (use steps.<id>.stdout, steps.<id>.output.<path> or inputs.<name>)",
+                reference
+            ));
+        }
+    };
+
+    let mut value = value;
+    for key in path.iter() {
+        let next = match &value {
+            serde_json::Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            other => other.get(*key),
+        };
+        value = match next {
+            Some(next) => next.clone(),
+            None => {
+                return Err(format!(
+                    "No '{}' in ${{{{{}}}}} (available: {})",
+                    key,
+                    reference,
+                    object_keys(&value)
+                ));
+            }
+        };
+    }
+    Ok(value)
+}
+
+/// Output of a Tool step: the raw content, its text joined, and any structured content
+fn tool_step_output(server_id: &str, tool: &str, result: &ToolCallResult) -> serde_json::Value {
+    let text: Vec<&str> = result
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            ToolResultContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    serde_json::json!({
+        "server_id": server_id,
+        "tool": tool,
+        "content": result.content,
+        "text": text.join("\n"),
+        "structured_content": result.structured_content,
+        "is_error": result.is_error.unwrap_or(false),
+    })
+}
+
+/// A step's config string with `{{name}}` replaced by workflow variables
+fn step_text(step: &WorkflowStep, key: &str, context: &SkillContext) -> String {
+    let text = step.config.get(key).and_then(|v| v.as_str()).unwrap_or("");
+
+    let mut text = text.to_string();
+    for (name, value) in &context.variables {
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        text = text.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    text
+}
+
+/// Uppercase a key, replacing anything that isn't valid in a variable name
+fn env_var_name(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// Replace `${NAME}` with `lookup(NAME)`, leaving unknown variables for the shell
+fn substitute_vars(command: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(command.len());
+    let mut rest = command;
+
+    while let Some(open) = rest.find("${") {
+        result.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        match after.find('}') {
+            Some(close) => {
+                let name = &after[..close];
+                match lookup(name) {
+                    Some(value) => result.push_str(&value),
+                    None => result.push_str(&rest[open..open + close + 3]),
+                }
+                rest = &after[close + 1..];
+            }
+            None => {
+                result.push_str(&rest[open..]);
+                rest = "";
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Render a template skill's content with the caller's variables
+///
+/// Supports `{{name}}`, `{{obj.field}}` and `{{#if name}}...{{/if}}`. Declared
+/// defaults fill in for missing values, and values are inserted unescaped.
+fn render_template(
+    template: &TemplateConfig,
+    variables: &HashMap<String, serde_json::Value>,
+) -> Result<String, String> {
+    let missing: Vec<&str> = template
+        .variables
+        .iter()
+        .filter(|var| var.required && var.default.is_none() && !variables.contains_key(&var.name))
+        .map(|var| var.name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing required template variables: {}", missing.join(", ")));
+    }
+
+    let mut data = serde_json::Map::new();
+    for var in &template.variables {
+        if let Some(ref default) = var.default {
+            data.insert(var.name.clone(), serde_json::Value::String(default.clone()));
+        }
+    }
+    data.extend(variables.iter().map(|(name, value)| (name.clone(), value.clone())));
+
+    let mut engine = Handlebars::new();
+    engine.register_escape_fn(handlebars::no_escape);
+    engine
+        .render_template(&template.content, &serde_json::Value::Object(data))
+        .map_err(|e| format!("Invalid template: {}", e))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mcp::error::McpError;
+    use crate::mcp::types::{
+        InitializeParams, InitializeResult, JsonRpcRequest, JsonRpcResponse, PromptsListResult,
+        ResourceUpdatedNotification, ResourcesListResult, ToolsListResult,
+    };
 
     #[tokio::test]
     async fn test_executor_creation() {
@@ -536,4 +2151,996 @@ mod tests {
         let executor = SkillExecutor::new(registry);
         assert_eq!(executor.default_timeout_secs, 300);
     }
+
+    fn shell_step(id: &str, command: &str, depends_on: &[&str]) -> WorkflowStep {
+        WorkflowStep {
+            id: id.to_string(),
+            kind: WorkflowStepKind::Shell,
+            name: id.to_string(),
+            config: serde_json::json!({ "command": command }),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            optional_depends_on: vec![],
+            condition: None,
+            timeout_secs: Some(5),
+            retry: None,
+        }
+    }
+
+    fn test_context(project_path: &str) -> SkillContext {
+        SkillContext {
+            project_path: project_path.to_string(),
+            session_id: None,
+            tool_name: None,
+            arguments: HashMap::new(),
+            env: HashMap::new(),
+            variables: HashMap::new(),
+        }
+    }
+
+    fn workflow_skill(id: &str, workflow: WorkflowConfig) -> Skill {
+        use super::super::types::{SkillMetadata, SkillVisibility};
+
+        Skill {
+            id: id.to_string(),
+            kind: SkillKind::Workflow,
+            name: id.to_string(),
+            description: String::new(),
+            visibility: SkillVisibility::Global,
+            enabled: true,
+            config: SkillConfig {
+                workflow: Some(workflow),
+                ..Default::default()
+            },
+            metadata: SkillMetadata::default(),
+            project_path: None,
+            source: "local".to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_step_retries_until_success() {
+        use super::super::types::{BackoffStrategy, RetryConfig};
+
+        let dir = tempfile::tempdir().unwrap();
+        let context = test_context(&dir.path().to_string_lossy());
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+
+        // Fails on the first run only
+        let mut step = shell_step(
+            "flaky",
+            "n=$(cat runs 2>/dev/null || echo 0); echo $((n + 1)) > runs; [ \"$n\" -ge 1 ] || { echo transient >&2; exit 1; }",
+            &[],
+        );
+        step.retry = Some(RetryConfig {
+            max_attempts: 3,
+            backoff: BackoffStrategy::Fixed { delay_ms: 10 },
+            retry_on: vec!["transient".to_string()],
+        });
+
+        let result = executor
+            .execute_workflow_step(&step, &context, &HashMap::new(), &HashMap::new())
+            .await;
+        assert!(result.success, "step failed: {:?}", result.error);
+        assert_eq!(result.retries, 1);
+
+        // Errors that don't match retry_on fail immediately
+        step.config = serde_json::json!({ "command": "echo fatal >&2; exit 1" });
+        let result = executor
+            .execute_workflow_step(&step, &context, &HashMap::new(), &HashMap::new())
+            .await;
+        assert!(!result.success);
+        assert_eq!(result.retries, 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_step_backs_off_exponentially() {
+        use super::super::types::{BackoffStrategy, RetryConfig};
+
+        let dir = tempfile::tempdir().unwrap();
+        let context = test_context(&dir.path().to_string_lossy());
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+
+        // Fails on the first two runs
+        let mut step = shell_step(
+            "flaky",
+            "n=$(cat runs 2>/dev/null || echo 0); echo $((n+1)) > runs; [ $n -ge 2 ] || { echo busy $n >&2; exit 1; }",
+            &[],
+        );
+        step.retry = Some(RetryConfig {
+            max_attempts: 3,
+            backoff: BackoffStrategy::Exponential {
+                initial_ms: 100,
+                max_ms: 1000,
+                multiplier: 2.0,
+            },
+            retry_on: vec![r"busy \d".to_string()],
+        });
+
+        let result = executor
+            .execute_workflow_step(&step, &context, &HashMap::new(), &HashMap::new())
+            .await;
+        assert!(result.success, "step failed: {:?}", result.error);
+        assert_eq!(result.retries, 2);
+        let attempt_errors = &result.output.unwrap()["attempt_errors"];
+        assert_eq!(attempt_errors, &serde_json::json!(["busy 0\n", "busy 1\n"]));
+        // Waited 100ms, then 200ms, between the attempts
+        let backoff = &step.retry.as_ref().unwrap().backoff;
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(200));
+
+        // Cancelling ends a backoff sleep at once
+        step.config = serde_json::json!({ "command": "echo busy 9 >&2; exit 1" });
+        step.retry.as_mut().unwrap().backoff = BackoffStrategy::Fixed { delay_ms: 60_000 };
+        executor.cancel();
+        let result = executor
+            .execute_workflow_step(&step, &context, &HashMap::new(), &HashMap::new())
+            .await;
+        assert!(!result.success);
+        assert_eq!(result.retries, 1);
+        assert_eq!(result.error.as_deref(), Some("Workflow cancelled"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_runs_independent_steps_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // a and b each wait for the other to start, so they only finish if run together
+        let workflow = WorkflowConfig {
+            steps: vec![
+                shell_step(
+                    "a",
+                    "touch a.started; while [ ! -f b.started ]; do sleep 0.05; done",
+                    &[],
+                ),
+                shell_step(
+                    "b",
+                    "touch b.started; while [ ! -f a.started ]; do sleep 0.05; done",
+                    &[],
+                ),
+                shell_step("c", "test -f a.started && test -f b.started", &["a", "b"]),
+            ],
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: Some(2),
+        };
+        let skill = workflow_skill("parallel-workflow", workflow);
+        let context = test_context(&dir.path().to_string_lossy());
+
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+        let result = executor.execute_workflow(&skill, context).await;
+
+        assert!(result.success, "workflow failed: {:?}", result.error);
+        let step_ids: Vec<_> = result
+            .steps
+            .unwrap()
+            .into_iter()
+            .map(|s| s.step_id)
+            .collect();
+        assert_eq!(step_ids, vec!["a", "b", "c"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_runs_diamond_dag() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = test_context(&dir.path().to_string_lossy());
+        let diamond = |left: &str| WorkflowConfig {
+            steps: vec![
+                shell_step("merge", "test -f left.done && test -f right.done", &["left", "right"]),
+                shell_step("left", left, &["root"]),
+                shell_step(
+                    "right",
+                    "touch right.started; while [ ! -f left.started ]; do sleep 0.05; done; touch right.done",
+                    &["root"],
+                ),
+                shell_step("root", "echo root", &[]),
+            ],
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+
+        // left and right wait for each other, so they only finish if run together
+        let left = "touch left.started; while [ ! -f right.started ]; do sleep 0.05; done; touch left.done";
+        let skill = workflow_skill("diamond", diamond(left));
+        let result = executor.execute_workflow(&skill, context.clone()).await;
+        assert!(result.success, "workflow failed: {:?}", result.error);
+        assert_eq!(result.output.unwrap()["completed"]["root"]["stdout"], "root\n");
+        let step_ids: Vec<_> = result.steps.unwrap().into_iter().map(|s| s.step_id).collect();
+        assert_eq!(step_ids, vec!["merge", "left", "right", "root"]);
+
+        // A failure skips its dependents but lets the independent branch finish
+        std::fs::remove_file(dir.path().join("right.done")).unwrap();
+        let skill = workflow_skill("diamond", diamond("touch left.started; exit 1"));
+        let result = executor.execute_workflow(&skill, context).await;
+        assert!(!result.success);
+        let steps = result.steps.unwrap();
+        assert!(!steps[1].success);
+        assert!(steps[2].success, "right failed: {:?}", steps[2].error);
+        assert!(dir.path().join("right.done").exists());
+        assert_eq!(steps[0].error.as_deref(), Some("Skipped: dependency left failed"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_skips_steps_with_false_conditions() {
+        let dir = tempfile::tempdir().unwrap();
+        let when = |mut step: WorkflowStep, condition: &str| {
+            step.condition = Some(condition.to_string());
+            step
+        };
+        let report = WorkflowStep {
+            optional_depends_on: vec!["staging".to_string()],
+            ..shell_step("report", "touch reported", &["prod", "staging"])
+        };
+        let workflow = WorkflowConfig {
+            steps: vec![
+                shell_step("build", "echo built", &[]),
+                when(
+                    shell_step("prod", "touch prod", &["build"]),
+                    "vars.environment == \"prod\" && steps.build.exit_code == 0",
+                ),
+                when(shell_step("staging", "touch staging", &["build"]), "vars.environment != 'prod'"),
+                shell_step("notify", "touch notified", &["staging"]),
+                report,
+            ],
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+        let context = SkillContext {
+            variables: HashMap::from([("environment".to_string(), serde_json::json!("prod"))]),
+            ..test_context(&dir.path().to_string_lossy())
+        };
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+
+        let result = executor.execute_workflow(&workflow_skill("deploy", workflow.clone()), context.clone()).await;
+        assert!(result.success, "workflow failed: {:?}", result.error);
+        let skipped: Vec<_> = result
+            .steps
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.skipped)
+            .map(|s| s.step_id)
+            .collect();
+        // notify needs staging; report only optionally does
+        assert_eq!(skipped, vec!["staging", "notify"]);
+        assert!(dir.path().join("prod").exists());
+        assert!(dir.path().join("reported").exists());
+        assert!(!dir.path().join("notified").exists());
+
+        // A condition that doesn't parse fails the workflow before anything runs
+        let mut broken = workflow;
+        broken.steps[0].condition = Some("vars.environment ==".to_string());
+        std::fs::remove_file(dir.path().join("prod")).unwrap();
+        let result = executor.execute_workflow(&workflow_skill("deploy", broken), context).await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().starts_with("Invalid condition for step build:"));
+        assert!(!dir.path().join("prod").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_passes_step_outputs_to_later_steps() {
+        use super::super::types::InputDef;
+
+        let dir = tempfile::tempdir().unwrap();
+        let workflow = WorkflowConfig {
+            steps: vec![
+                shell_step("version", r#"echo '{"version": "1.2.3", "tags": ["stable", "lts"]}'"#, &[]),
+                shell_step(
+                    "tag",
+                    "printf v${{steps.version.output.version}}-${{inputs.channel}}",
+                    &["version"],
+                ),
+                shell_step("broken", "echo ${{steps.tags.stdout}}", &["tag"]),
+            ],
+            inputs: vec![InputDef {
+                name: "channel".to_string(),
+                description: String::new(),
+                var_type: "string".to_string(),
+                required: false,
+                default: Some(serde_json::json!("beta")),
+            }],
+            outputs: HashMap::from([
+                ("tag".to_string(), "steps.tag.stdout".to_string()),
+                ("summary".to_string(), "${{steps.tag.stdout}} (${{steps.version.output.tags.1}})".to_string()),
+            ]),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+        let context = test_context(&dir.path().to_string_lossy());
+
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+        let result = executor.execute_workflow(&workflow_skill("release", workflow), context).await;
+
+        let output = result.output.unwrap();
+        assert_eq!(output, serde_json::json!({ "tag": "v1.2.3-beta", "summary": "v1.2.3-beta (lts)" }));
+        let steps = result.steps.unwrap();
+        assert!(steps[1].success, "tag failed: {:?}", steps[1].error);
+
+        // A reference to a step with no output fails that step and lists what is available
+        assert!(!result.success);
+        assert_eq!(
+            steps[2].error.as_deref(),
+            Some("Step broken: No output from step 'tags' in ${{steps.tags.stdout}} (available: tag, version)")
+        );
+    }
+
+    /// MCP transport whose `greet` tool records its arguments and fails when asked to
+    struct GreeterTransport {
+        calls: std::sync::Arc<parking_lot::Mutex<Vec<serde_json::Value>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl McpTransport for GreeterTransport {
+        async fn connect(&mut self) -> McpResult<()> {
+            Ok(())
+        }
+        async fn disconnect(&mut self) -> McpResult<()> {
+            Ok(())
+        }
+        fn is_connected(&self) -> bool {
+            true
+        }
+        fn session_id(&self) -> Option<String> {
+            None
+        }
+        async fn initialize(&mut self, _params: InitializeParams) -> McpResult<InitializeResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_initialized(&self) -> McpResult<()> {
+            Ok(())
+        }
+        async fn list_tools(&self, _cursor: Option<&str>) -> McpResult<ToolsListResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn call_tool(&self, name: &str, arguments: Option<serde_json::Value>) -> McpResult<ToolCallResult> {
+            let arguments = arguments.unwrap_or_default();
+            self.calls.lock().push(arguments.clone());
+            if name != "greet" {
+                return Err(McpError::ToolNotFound(name.to_string()));
+            }
+
+            let failed = arguments["fail"].as_bool().unwrap_or(false);
+            let text = if failed {
+                "No one to greet".to_string()
+            } else {
+                format!("Hello, {}!", arguments["name"].as_str().unwrap_or("?"))
+            };
+            Ok(ToolCallResult {
+                content: vec![ToolResultContent::Text { text }],
+                is_error: Some(failed),
+                structured_content: None,
+            })
+        }
+        async fn list_resources(&self, _cursor: Option<&str>) -> McpResult<ResourcesListResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn read_resource(&self, _uri: &str) -> McpResult<serde_json::Value> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn list_prompts(&self, _cursor: Option<&str>) -> McpResult<PromptsListResult> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn get_prompt(
+            &self,
+            _name: &str,
+            _arguments: Option<HashMap<String, String>>,
+        ) -> McpResult<serde_json::Value> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn subscribe_resource(&self, _uri: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn unsubscribe_resource(&self, _uri: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        fn resource_updates(&self) -> broadcast::Receiver<ResourceUpdatedNotification> {
+            broadcast::channel(1).1
+        }
+        async fn set_log_level(&self, _level: &str) -> McpResult<()> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_request(&self, _request: JsonRpcRequest) -> McpResult<JsonRpcResponse> {
+            Err(McpError::Internal("unused".to_string()))
+        }
+        async fn send_notification(&self, _method: &str, _params: Option<serde_json::Value>) -> McpResult<()> {
+            Ok(())
+        }
+        async fn ping(&self) -> McpResult<()> {
+            Ok(())
+        }
+        fn transport_type(&self) -> &'static str {
+            "mock"
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_tool_steps_call_remote_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool_step = |id: &str, arguments: serde_json::Value| WorkflowStep {
+            kind: WorkflowStepKind::Tool,
+            config: serde_json::json!({ "server_id": "greeter", "tool": "greet", "arguments": arguments }),
+            ..shell_step(id, "", &["who"])
+        };
+        let workflow = WorkflowConfig {
+            steps: vec![
+                shell_step("who", "printf world", &[]),
+                tool_step("greet", serde_json::json!({ "name": "${{steps.who.stdout}}" })),
+                tool_step("refuse", serde_json::json!({ "fail": true })),
+            ],
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+        let context = test_context(&dir.path().to_string_lossy());
+
+        let calls = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let connect_calls = calls.clone();
+        let connect: McpConnector = std::sync::Arc::new(move |server_id: &str| {
+            assert_eq!(server_id, "greeter");
+            Ok(Box::new(GreeterTransport { calls: connect_calls.clone() }) as Box<dyn McpTransport>)
+        });
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()))
+            .with_mcp_servers(std::sync::Arc::new(McpConnectionPool::default()), connect);
+        let result = executor.execute_workflow(&workflow_skill("greeting", workflow), context).await;
+
+        let steps = result.steps.unwrap();
+        assert!(steps[1].success, "greet failed: {:?}", steps[1].error);
+        assert_eq!(steps[1].output.as_ref().unwrap()["text"], "Hello, world!");
+        assert!(calls.lock().contains(&serde_json::json!({ "name": "world" })));
+
+        // isError results fail the step with the tool's message
+        assert!(!result.success);
+        assert!(!steps[2].success);
+        assert_eq!(steps[2].error.as_deref(), Some("No one to greet"));
+        assert_eq!(steps[2].output.as_ref().unwrap()["is_error"], true);
+    }
+
+    #[tokio::test]
+    async fn test_workflow_rejects_dependency_cycle() {
+        let workflow = WorkflowConfig {
+            steps: vec![
+                shell_step("setup", "true", &[]),
+                shell_step("a", "true", &["setup", "c"]),
+                shell_step("b", "true", &["a"]),
+                shell_step("c", "true", &["b"]),
+            ],
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+        let skill = workflow_skill("cyclic", workflow);
+        let context = test_context(&std::env::temp_dir().to_string_lossy());
+
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+        let result = executor.execute_workflow(&skill, context).await;
+        assert!(!result.success);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Workflow has a dependency cycle: a -> c -> b -> a")
+        );
+        assert!(result.steps.is_none());
+    }
+
+    #[test]
+    fn test_dry_run_resolves_steps_without_running() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("ran");
+        let workflow_config = |steps| WorkflowConfig {
+            steps,
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+
+        let lint = WorkflowStep {
+            kind: WorkflowStepKind::SkillRef,
+            config: serde_json::json!({ "skill_id": "lint" }),
+            ..shell_step("lint", "", &[])
+        };
+        let registry = std::sync::Arc::new(SkillRegistry::new());
+        registry.register_skill(workflow_skill(
+            "lint",
+            workflow_config(vec![shell_step("clippy", "cargo clippy -p {{crate}}", &[])]),
+        ));
+        registry.register_skill(workflow_skill(
+            "release",
+            workflow_config(vec![
+                shell_step("publish", "cargo publish -p {{crate}}", &["build", "lint"]),
+                shell_step("build", &format!("touch '{}'", marker.display()), &[]),
+                lint,
+            ]),
+        ));
+        let context = SkillContext {
+            variables: HashMap::from([("crate".to_string(), serde_json::json!("opcode"))]),
+            ..test_context(&dir.path().to_string_lossy())
+        };
+
+        let result = SkillExecutor::new(registry).execute_dry_run("release", &context);
+        assert!(result.success, "dry run failed: {:?}", result.error);
+        assert_eq!(result.output.unwrap()["order"], serde_json::json!(["build", "lint", "publish"]));
+        assert!(!marker.exists());
+
+        let steps = result.steps.unwrap();
+        let publish = steps[2].output.as_ref().unwrap();
+        assert_eq!(publish["dry_run"], true);
+        assert_eq!(publish["command"], "cargo publish -p opcode");
+        let lint = steps[1].output.as_ref().unwrap();
+        assert_eq!(lint["skill_ref"], "lint");
+        assert_eq!(lint["steps"][0]["output"]["command"], "cargo clippy -p opcode");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_suspends_for_user_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().join("state");
+
+        let approve = WorkflowStep {
+            kind: WorkflowStepKind::UserInput,
+            config: serde_json::json!({ "prompt": "Deploy?" }),
+            ..shell_step("approve", "", &["build"])
+        };
+        let workflow = WorkflowConfig {
+            steps: vec![
+                shell_step("build", "touch built", &[]),
+                approve,
+                shell_step("deploy", "test -f built", &["approve"]),
+            ],
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+        let registry = std::sync::Arc::new(SkillRegistry::new());
+        registry.register_skill(workflow_skill("gated-workflow", workflow));
+        let context = test_context(&dir.path().to_string_lossy());
+
+        let executor = SkillExecutor::new(registry.clone()).with_state_dir(&state_dir);
+        let result = executor.execute("gated-workflow", context).await;
+        assert!(result.success, "workflow failed: {:?}", result.error);
+        assert_eq!(result.steps.unwrap().len(), 1);
+        assert_eq!(result.output.unwrap()["prompt"], "Deploy?");
+        let token = result.resume_token.expect("workflow should be suspended");
+
+        // A fresh executor picks the workflow up from the saved state
+        let executor = SkillExecutor::new(registry).with_state_dir(&state_dir);
+        let result = executor
+            .resume_workflow(&token, serde_json::json!({ "approved": true }))
+            .await;
+        assert!(result.success, "workflow failed: {:?}", result.error);
+        assert!(result.resume_token.is_none());
+        let step_ids: Vec<_> = result.steps.unwrap().into_iter().map(|s| s.step_id).collect();
+        assert_eq!(step_ids, vec!["build", "approve", "deploy"]);
+
+        // Each token resumes once
+        assert!(!executor.resume_workflow(&token, serde_json::Value::Null).await.success);
+        assert!(!executor.resume_workflow("../escape", serde_json::Value::Null).await.success);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_input_is_provided_or_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let gate = |id: &str, depends_on: &[&str], config: serde_json::Value, timeout_secs: Option<u64>| WorkflowStep {
+            kind: WorkflowStepKind::UserInput,
+            config,
+            timeout_secs,
+            ..shell_step(id, "", depends_on)
+        };
+        let workflow = |steps: Vec<WorkflowStep>| WorkflowConfig {
+            steps,
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+        let registry = std::sync::Arc::new(SkillRegistry::new());
+        registry.register_skill(workflow_skill(
+            "deploy",
+            workflow(vec![
+                gate(
+                    "target",
+                    &[],
+                    serde_json::json!({ "prompt": "Where to?", "schema": { "type": "string" }, "variable": "env" }),
+                    None,
+                ),
+                shell_step("deploy", "printf '%s' ${{inputs.env}}", &["target"]),
+                gate("confirm", &["deploy"], serde_json::json!({ "on_timeout": "default", "default": "yes" }), Some(1)),
+            ]),
+        ));
+        registry.register_skill(workflow_skill(
+            "strict",
+            workflow(vec![
+                gate("approve", &[], serde_json::json!({ "prompt": "Approve?" }), Some(60)),
+                shell_step("ship", "true", &["approve"]),
+            ]),
+        ));
+        let context = test_context(&dir.path().to_string_lossy());
+
+        let executor = SkillExecutor::new(registry).with_state_dir(dir.path().join("state"));
+        let mut events = executor.subscribe();
+        let watcher = executor.clone();
+        tokio::spawn(async move { watcher.run_input_timeouts().await });
+
+        let result = executor.execute("deploy", context.clone()).await;
+        let run_id = result.resume_token.expect("workflow should wait for the target");
+        match events.recv().await.unwrap() {
+            WorkflowEvent::InputRequired { run_id: id, step_id, prompt, schema, .. } => {
+                assert_eq!(id, run_id);
+                assert_eq!(step_id, "target");
+                assert_eq!(prompt.as_deref(), Some("Where to?"));
+                assert_eq!(schema, Some(serde_json::json!({ "type": "string" })));
+            }
+            other => panic!("expected InputRequired, got {:?}", other),
+        }
+
+        // Input for a step the run isn't waiting at is rejected without consuming the run
+        assert!(!executor.provide_input(&run_id, "confirm", serde_json::json!("yes")).await.success);
+
+        let result = executor.provide_input(&run_id, "target", serde_json::json!("staging")).await;
+        assert_eq!(result.resume_token.as_deref(), Some(run_id.as_str()));
+        let steps = result.steps.unwrap();
+        assert_eq!(steps[1].output.as_ref().unwrap()["stdout"], "staging");
+
+        // Nobody confirms, so the step falls back to its default
+        let finished = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let WorkflowEvent::Finished { run_id: id, result } = events.recv().await.unwrap() {
+                    break (id, result);
+                }
+            }
+        })
+        .await
+        .expect("confirm step should time out");
+        assert_eq!(finished.0, run_id);
+        assert!(finished.1.success, "workflow failed: {:?}", finished.1.error);
+        assert_eq!(finished.1.steps.unwrap()[2].output, Some(serde_json::json!("yes")));
+
+        // Without a default, a timed out step fails and its dependents never run
+        let run_id = executor.execute("strict", context).await.resume_token.unwrap();
+        let result = executor.expire_input(&run_id, "approve").await;
+        assert!(!result.success);
+        let steps = result.steps.unwrap();
+        assert_eq!(steps[0].error.as_deref(), Some("Timed out waiting for input"));
+        assert_eq!(steps[1].error.as_deref(), Some("Skipped: dependency approve failed"));
+
+        executor.cancel();
+    }
+
+    #[test]
+    fn test_substitute_vars() {
+        let vars = HashMap::from([("OPCODE_TOOL_NAME".to_string(), "Bash".to_string())]);
+        let lookup = |name: &str| vars.get(name).cloned();
+
+        assert_eq!(substitute_vars("echo ${OPCODE_TOOL_NAME}!", lookup), "echo Bash!");
+        assert_eq!(substitute_vars("echo ${HOME} ${OPCODE_TOOL_NAME}", lookup), "echo ${HOME} Bash");
+        assert_eq!(substitute_vars("echo ${unterminated", lookup), "echo ${unterminated");
+        assert_eq!(substitute_vars("echo $OPCODE_TOOL_NAME", lookup), "echo $OPCODE_TOOL_NAME");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hook_sees_tool_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let skill = hook_skill(
+            "pre-tool-hook",
+            "printf '%s %s' \"$OPCODE_TOOL_NAME\" ${OPCODE_ARG_MODE}",
+            false,
+        );
+        let context = SkillContext {
+            tool_name: Some("Bash".to_string()),
+            arguments: HashMap::from([("mode".to_string(), serde_json::json!("strict"))]),
+            ..test_context(&dir.path().to_string_lossy())
+        };
+
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+        let result = executor.execute_hook(&skill, context).await;
+
+        assert!(result.success, "hook failed: {:?}", result.error);
+        assert_eq!(result.output.unwrap()["stdout"], "Bash strict");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hook_gets_context_env_and_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut skill = hook_skill(
+            "on-error",
+            concat!(
+                r#"printf '%s|%s|%s|%s|' "$OPCODE_TRIGGER" "$OPCODE_SKILL_ID" "$OPCODE_SESSION_ID" "#,
+                r#""$OPCODE_ERROR_MESSAGE"; cat"#
+            ),
+            false,
+        );
+        if let Some(ref mut hook) = skill.config.hook {
+            hook.trigger = HookTrigger::OnError;
+            hook.stdin_json = true;
+        }
+        let context = SkillContext {
+            session_id: Some("s1".to_string()),
+            arguments: HashMap::from([("error".to_string(), serde_json::json!("disk full"))]),
+            ..test_context(&dir.path().to_string_lossy())
+        };
+
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+        let result = executor.execute_hook(&skill, context).await;
+
+        assert!(result.success, "hook failed: {:?}", result.error);
+        let stdout = result.output.unwrap()["stdout"].as_str().unwrap().to_string();
+        let (env, stdin) = stdout.rsplit_once('|').unwrap();
+        assert_eq!(env, "on_error|on-error|s1|disk full");
+        let piped: SkillContext = serde_json::from_str(stdin).unwrap();
+        assert_eq!(piped.session_id.as_deref(), Some("s1"));
+        assert_eq!(piped.arguments["error"], "disk full");
+    }
+
+    fn hook_skill(id: &str, command: &str, can_block: bool) -> Skill {
+        use super::super::types::{SkillMetadata, SkillVisibility};
+
+        Skill {
+            id: id.to_string(),
+            kind: SkillKind::Hook,
+            name: id.to_string(),
+            description: String::new(),
+            visibility: SkillVisibility::Global,
+            enabled: true,
+            config: SkillConfig {
+                hook: Some(HookConfig {
+                    trigger: HookTrigger::PreTool,
+                    tool_patterns: None,
+                    command: command.to_string(),
+                    timeout_secs: 5,
+                    can_block,
+                    env: HashMap::new(),
+                    stdin_json: false,
+                }),
+                ..Default::default()
+            },
+            metadata: SkillMetadata::default(),
+            project_path: None,
+            source: "local".to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Run PreTool hooks registered in order for a call to `tool_name`
+    #[cfg(unix)]
+    async fn run_pre_tool_hooks(tool_name: &str, hooks: Vec<Skill>) -> HookOutcome {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = std::sync::Arc::new(SkillRegistry::new());
+        for hook in hooks {
+            registry.register_skill(hook);
+        }
+        let context = SkillContext {
+            tool_name: Some(tool_name.to_string()),
+            arguments: HashMap::from([
+                ("tool_args".to_string(), serde_json::json!({ "command": "rm -rf build" })),
+            ]),
+            ..test_context(&dir.path().to_string_lossy())
+        };
+
+        SkillExecutor::new(registry)
+            .execute_hooks_for_trigger(HookTrigger::PreTool, context)
+            .await
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pre_tool_hooks_allow() {
+        // Failures of hooks that can't block are only reported
+        let outcome = run_pre_tool_hooks("Bash", vec![
+            hook_skill("passes", "true", true),
+            hook_skill("fails", "exit 1", false),
+        ])
+        .await;
+
+        assert_eq!(outcome.decision, HookDecision::Allow);
+        assert_eq!(outcome.results.len(), 2);
+        assert!(outcome.decided_by.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pre_tool_hooks_block() {
+        let outcome = run_pre_tool_hooks("Bash", vec![hook_skill(
+            "guard",
+            "echo 'destructive command' >&2; exit 2",
+            true,
+        )])
+        .await;
+        assert!(matches!(outcome.decision, HookDecision::Block { ref reason } if reason.contains("destructive")));
+
+        let outcome = run_pre_tool_hooks("Bash", vec![hook_skill(
+            "policy",
+            r#"echo '{"decision":"block","reason":"not allowed"}'"#,
+            true,
+        )])
+        .await;
+        assert_eq!(
+            outcome.decision,
+            HookDecision::Block {
+                reason: "not allowed".to_string()
+            }
+        );        assert_eq!(outcome.decided_by.as_deref(), Some("policy"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pre_tool_hooks_modify() {
+        let outcome = run_pre_tool_hooks("Bash", vec![hook_skill(
+            "rewrite",
+            r#"echo '{"decision":"modify","tool_args":{"command":"rm -rf build/tmp"}}'"#,
+            true,
+        )])
+        .await;
+
+        assert_eq!(
+            outcome.decision,
+            HookDecision::Modify {
+                tool_args: serde_json::json!({ "command": "rm -rf build/tmp" })
+            }
+        );        assert_eq!(outcome.decided_by.as_deref(), Some("rewrite"));
+
+        // `input` is accepted in place of `tool_args`
+        let outcome = run_pre_tool_hooks("Bash", vec![hook_skill(
+            "rewrite",
+            r#"echo '{"decision":"modify","input":{"command":"ls"}}'"#,
+            true,
+        )])
+        .await;
+        assert_eq!(
+            outcome.decision,
+            HookDecision::Modify {
+                tool_args: serde_json::json!({ "command": "ls" })
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hooks_only_run_for_matching_tools() {
+        let mut write_hook = hook_skill("write-guard", "exit 1", true);
+        if let Some(ref mut hook) = write_hook.config.hook {
+            hook.tool_patterns = Some(vec!["Write".to_string()]);
+        }
+
+        let outcome = run_pre_tool_hooks("Read", vec![write_hook.clone()]).await;
+        assert_eq!(outcome.decision, HookDecision::Allow);
+        assert!(outcome.results.is_empty());
+
+        let outcome = run_pre_tool_hooks("Write", vec![write_hook]).await;
+        assert!(matches!(outcome.decision, HookDecision::Block { .. }));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_output_is_truncated_at_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor =
+            SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new())).with_max_output_bytes(1000);
+
+        let output = executor
+            .run_shell_command(
+                "yes | head -c 100000; echo done >&2",
+                &dir.path().to_string_lossy(),
+                5,
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.exit_code, 0);
+        assert!(output.truncated);
+        assert_eq!(output.stdout.len(), 1000 + TRUNCATION_MARKER.len());
+        assert!(output.stdout.ends_with("[output truncated]"));
+        assert_eq!(output.stderr, "done\n");
+    }
+
+    fn template(content: &str, variables: Vec<(&str, Option<&str>, bool)>) -> TemplateConfig {
+        use super::super::types::TemplateVariable;
+
+        TemplateConfig {
+            content: content.to_string(),
+            variables: variables
+                .into_iter()
+                .map(|(name, default, required)| TemplateVariable {
+                    name: name.to_string(),
+                    description: String::new(),
+                    default: default.map(str::to_string),
+                    required,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_render_template_flat_placeholders() {
+        let config = template("Hello {{name}} from {{place}} <{{tag}}>", vec![("place", Some("home"), false)]);
+        let variables = HashMap::from([
+            ("name".to_string(), serde_json::json!("Ada & co")),
+            ("tag".to_string(), serde_json::json!(42)),
+        ]);
+
+        assert_eq!(render_template(&config, &variables).unwrap(), "Hello Ada & co from home <42>");
+    }
+
+    #[test]
+    fn test_render_template_conditional_and_nested() {
+        let config = template(
+            "Review {{repo.owner}}/{{repo.name}}{{#if branch}} on {{branch}}{{/if}}.",
+            vec![("repo", None, true), ("branch", None, false)],
+        );
+        let repo = serde_json::json!({ "owner": "acme", "name": "widgets" });
+
+        let without_branch = HashMap::from([("repo".to_string(), repo.clone())]);
+        assert_eq!(render_template(&config, &without_branch).unwrap(), "Review acme/widgets.");
+
+        let with_branch = HashMap::from([
+            ("repo".to_string(), repo),
+            ("branch".to_string(), serde_json::json!("main")),
+        ]);
+        assert_eq!(render_template(&config, &with_branch).unwrap(), "Review acme/widgets on main.");
+    }
+
+    #[test]
+    fn test_render_template_reports_missing_required_variables() {
+        let config = template(
+            "{{a}} {{b}} {{c}}",
+            vec![("a", None, true), ("b", Some("x"), true), ("c", None, true)],
+        );
+
+        assert_eq!(
+            render_template(&config, &HashMap::new()).unwrap_err(),
+            "Missing required template variables: a, c"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_kills_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("sleep.pid");
+        let executor = SkillExecutor::new(std::sync::Arc::new(SkillRegistry::new()));
+
+        let result = executor
+            .run_shell_command(
+                &format!("sleep 30 & echo $! > '{}'; wait", pid_file.display()),
+                &dir.path().to_string_lossy(),
+                1,
+                &HashMap::new(),
+            )
+            .await;
+        assert_eq!(result.unwrap_err(), "Command timed out");
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap().trim().to_string();
+        // Gone or a zombie waiting to be reaped both mean it was killed
+        let running = |pid: &str| match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => !stat.contains(") Z "),
+            Err(_) => false,
+        };
+        for _ in 0..20 {
+            if !running(&pid) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("sleep {} survived the timeout", pid);
+    }
 }
@@ -2,18 +2,39 @@
 //!
 //! Load skills from various sources: local files, GitHub, registry.
 
+use futures_util::StreamExt;
 use log::{debug, error, info, warn};
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
 use super::types::{Skill, SkillConfig, SkillKind, SkillMetadata, SkillVisibility};
 
+/// Deepest folder level searched for local skills (files directly in the skills dir are level 0)
+pub const MAX_SKILL_DIR_DEPTH: usize = 4;
+
+/// Folder names skipped when searching for local skills, besides hidden ones
+pub const DEFAULT_IGNORED_DIRS: &[&str] = &["node_modules"];
+
+/// GitHub REST API base URL
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Skill files downloaded at once when loading a GitHub directory
+const GITHUB_FETCH_CONCURRENCY: usize = 4;
+
+/// Page cap when listing a GitHub directory, guarding against Link headers that never end
+const MAX_GITHUB_PAGES: usize = 20;
+
 /// Skill loader for loading skills from various sources
 pub struct SkillLoader {
     /// Base directory for local skills
     skills_dir: PathBuf,
     /// GitHub API token (optional, for private repos)
     github_token: Option<String>,
+    /// GitHub API base URL (differs for GitHub Enterprise)
+    github_api_url: String,
+    /// Folder names skipped when searching the skills directory
+    ignored_dirs: Vec<String>,
 }
 
 impl SkillLoader {
@@ -22,6 +43,8 @@ impl SkillLoader {
         Self {
             skills_dir: skills_dir.into(),
             github_token: None,
+            github_api_url: GITHUB_API_URL.to_string(),
+            ignored_dirs: DEFAULT_IGNORED_DIRS.iter().map(|dir| dir.to_string()).collect(),
         }
     }
 
@@ -31,7 +54,27 @@ impl SkillLoader {
         self
     }
 
-    /// Load skills from the local skills directory
+    /// Use a different GitHub API, such as a GitHub Enterprise server's
+    pub fn with_github_api_url(mut self, url: impl Into<String>) -> Self {
+        self.github_api_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Replace the folder names skipped when searching the skills directory
+    pub fn with_ignored_dirs<I, S>(mut self, dirs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.ignored_dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Load skills from the local skills directory and its subfolders
+    ///
+    /// Folders are searched up to `MAX_SKILL_DIR_DEPTH` levels deep, and a skill's
+    /// folder path (e.g. "git/hooks") is added to its tags as a category. Hidden
+    /// and ignored folders are skipped, as are symlinked ones, which could loop.
     pub async fn load_local_skills(&self) -> Result<Vec<Skill>, LoaderError> {
         let mut skills = Vec::new();
 
@@ -42,21 +85,48 @@ impl SkillLoader {
             return Ok(skills);
         }
 
-        let mut entries = fs::read_dir(&self.skills_dir).await
-            .map_err(|e| LoaderError::IoError(e.to_string()))?;
-
-        while let Some(entry) = entries.next_entry().await
-            .map_err(|e| LoaderError::IoError(e.to_string()))? {
-            let path = entry.path();
-
-            if path.extension().map_or(false, |ext| ext == "json" || ext == "yaml" || ext == "yml") {
-                match self.load_skill_file(&path).await {
-                    Ok(skill) => {
-                        info!("Loaded skill from {:?}: {}", path, skill.name);
-                        skills.push(skill);
+        let mut pending = vec![(self.skills_dir.clone(), 0)];
+        while let Some((dir, depth)) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                // An unreadable subfolder shouldn't hide the rest of the skills
+                Err(e) if depth > 0 => {
+                    warn!("Failed to read skills folder {:?}: {}", dir, e);
+                    continue;
+                }
+                Err(e) => return Err(LoaderError::IoError(e.to_string())),
+            };
+
+            while let Some(entry) = entries.next_entry().await
+                .map_err(|e| LoaderError::IoError(e.to_string()))? {
+                let path = entry.path();
+                let is_dir = entry.file_type().await.is_ok_and(|file_type| file_type.is_dir());
+
+                if is_dir {
+                    if self.is_ignored_dir(&path) {
+                        debug!("Skipping skills folder {:?}", path);
+                    } else if depth < MAX_SKILL_DIR_DEPTH {
+                        pending.push((path, depth + 1));
+                    } else {
+                        warn!("Skipping {:?}: skills are loaded at most {} folders deep", path, MAX_SKILL_DIR_DEPTH);
                     }
-                    Err(e) => {
-                        warn!("Failed to load skill from {:?}: {}", path, e);
+                    continue;
+                }
+
+                if path.extension().is_some_and(|ext| ext == "json" || ext == "yaml" || ext == "yml") {
+                    match self.load_skill_file(&path).await {
+                        Ok(mut skill) => {
+                            if let Some(category) = self.category_for(&path) {
+                                if !skill.metadata.tags.contains(&category) {
+                                    skill.metadata.tags.push(category);
+                                }
+                            }
+                            info!("Loaded skill from {:?}: {}", path, skill.name);
+                            skills.push(skill);
+                        }
+                        Err(e) => {
+                            warn!("Failed to load skill from {:?}: {}", path, e);
+                        }
                     }
                 }
             }
@@ -65,6 +135,30 @@ impl SkillLoader {
         Ok(skills)
     }
 
+    /// Whether a folder is hidden or on the ignore list
+    fn is_ignored_dir(&self, path: &Path) -> bool {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.starts_with('.') || self.ignored_dirs.iter().any(|dir| dir == name),
+            None => true,
+        }
+    }
+
+    /// Folder path of a skill file relative to the skills directory, joined with '/'
+    ///
+    /// None for files directly in the skills directory.
+    fn category_for(&self, path: &Path) -> Option<String> {
+        let relative = path.parent()?.strip_prefix(&self.skills_dir).ok()?;
+        let parts: Vec<String> = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("/"))
+        }
+    }
+
     /// Load a single skill from a file
     pub async fn load_skill_file(&self, path: &Path) -> Result<Skill, LoaderError> {
         let content = fs::read_to_string(path).await
@@ -98,6 +192,9 @@ impl SkillLoader {
     }
 
     /// Save a skill to a local file
+    ///
+    /// The format follows the extension: YAML, Claude Code TOML (slash commands
+    /// only), or JSON for anything else.
     pub async fn save_skill_file(&self, skill: &Skill, filename: &str) -> Result<PathBuf, LoaderError> {
         let path = self.skills_dir.join(filename);
 
@@ -107,13 +204,8 @@ impl SkillLoader {
                 .map_err(|e| LoaderError::IoError(e.to_string()))?;
         }
 
-        let content = if filename.ends_with(".yaml") || filename.ends_with(".yml") {
-            serde_yaml::to_string(skill)
-                .map_err(|e| LoaderError::SerializeError(e.to_string()))?
-        } else {
-            serde_json::to_string_pretty(skill)
-                .map_err(|e| LoaderError::SerializeError(e.to_string()))?
-        };
+        let format = Path::new(filename).extension().and_then(|ext| ext.to_str()).unwrap_or("json");
+        let content = self.skill_to_string(skill, format)?;
 
         fs::write(&path, content).await
             .map_err(|e| LoaderError::IoError(e.to_string()))?;
@@ -122,14 +214,36 @@ impl SkillLoader {
         Ok(path)
     }
 
+    /// Serialize a skill in the file format named by `format`
+    ///
+    /// yaml/yml, toml (Claude Code format, slash commands only), or JSON for anything else.
+    pub fn skill_to_string(&self, skill: &Skill, format: &str) -> Result<String, LoaderError> {
+        match format {
+            "yaml" | "yml" => serde_yaml::to_string(skill).map_err(|e| LoaderError::SerializeError(e.to_string())),
+            "toml" => self.to_claude_code_toml(skill),
+            _ => serde_json::to_string_pretty(skill).map_err(|e| LoaderError::SerializeError(e.to_string())),
+        }
+    }
+
     /// Load a skill from a GitHub repository
-    pub async fn load_from_github(&self, repo: &str, path: &str) -> Result<Skill, LoaderError> {
-        let url = format!(
-            "https://raw.githubusercontent.com/{}/main/{}",
-            repo, path
-        );
+    ///
+    /// `git_ref` is a branch, tag or commit SHA; the repo's default branch is used when it's None.
+    /// With `expected_sha256`, the fetched file is rejected unless its SHA-256 digest matches.
+    pub async fn load_from_github(
+        &self,
+        repo: &str,
+        path: &str,
+        git_ref: Option<&str>,
+        expected_sha256: Option<&str>,
+    ) -> Result<Skill, LoaderError> {
+        let git_ref = match git_ref {
+            Some(git_ref) => git_ref.to_string(),
+            None => self.github_default_branch(repo).await?,
+        };
+        let url = github_raw_url(repo, &git_ref, path);
 
-        let client = reqwest::Client::new();
+        let client = crate::http::build_client(crate::http::DEFAULT_TIMEOUT, &crate::http::proxy_settings())
+            .map_err(|e| LoaderError::NetworkError(e.to_string()))?;
         let mut request = client.get(&url);
 
         if let Some(ref token) = self.github_token {
@@ -148,6 +262,9 @@ impl SkillLoader {
 
         let content = response.text().await
             .map_err(|e| LoaderError::NetworkError(e.to_string()))?;
+        if let Some(expected) = expected_sha256 {
+            verify_sha256(content.as_bytes(), expected)?;
+        }
 
         let mut skill: Skill = if path.ends_with(".yaml") || path.ends_with(".yml") {
             serde_yaml::from_str(&content)
@@ -171,14 +288,95 @@ impl SkillLoader {
     }
 
     /// Load skills from a GitHub repository directory
-    pub async fn load_from_github_dir(&self, repo: &str, dir: &str) -> Result<Vec<Skill>, LoaderError> {
-        let api_url = format!(
-            "https://api.github.com/repos/{}/contents/{}",
-            repo, dir
-        );
+    ///
+    /// `git_ref` is a branch, tag or commit SHA; the repo's default branch is used when it's None.
+    pub async fn load_from_github_dir(
+        &self,
+        repo: &str,
+        dir: &str,
+        git_ref: Option<&str>,
+    ) -> Result<Vec<Skill>, LoaderError> {
+        // Resolved once so every file comes from the same ref
+        let git_ref = match git_ref {
+            Some(git_ref) => git_ref.to_string(),
+            None => self.github_default_branch(repo).await?,
+        };
+        let client = crate::http::build_client(crate::http::DEFAULT_TIMEOUT, &crate::http::proxy_settings())
+            .map_err(|e| LoaderError::NetworkError(e.to_string()))?;
+        let entries = self.list_github_dir(&client, repo, dir, &git_ref).await?;
+
+        let files: Vec<GitHubContent> = entries
+            .into_iter()
+            .filter(|entry| {
+                let ext = entry.name.rsplit('.').next().unwrap_or("");
+                entry.content_type == "file" && (ext == "json" || ext == "yaml" || ext == "yml")
+            })
+            .collect();
+
+        // A few downloads at a time, keeping directory order
+        let git_ref = git_ref.as_str();
+        let results: Vec<_> = futures_util::stream::iter(files.iter().map(|entry| async move {
+            (entry, self.load_from_github(repo, &entry.path, Some(git_ref), None).await)
+        }))
+        .buffered(GITHUB_FETCH_CONCURRENCY)
+        .collect()
+        .await;
 
-        let client = reqwest::Client::new();
-        let mut request = client.get(&api_url)
+        let mut skills = Vec::new();
+        for (entry, result) in results {
+            match result {
+                Ok(skill) => skills.push(skill),
+                // Every later download would be refused too
+                Err(e @ LoaderError::RateLimited { .. }) => return Err(e),
+                Err(e) => warn!("Failed to load skill {}: {}", entry.name, e),
+            }
+        }
+
+        Ok(skills)
+    }
+
+    /// List a repository directory, following the contents API's Link pagination
+    async fn list_github_dir(
+        &self,
+        client: &reqwest::Client,
+        repo: &str,
+        dir: &str,
+        git_ref: &str,
+    ) -> Result<Vec<GitHubContent>, LoaderError> {
+        let mut url = format!("{}/repos/{}/contents/{}", self.github_api_url, repo, dir);
+        let mut query = vec![("ref", git_ref)];
+        let mut entries = Vec::new();
+
+        for _ in 0..MAX_GITHUB_PAGES {
+            let response = self.github_api_get(client, &url, &query).await?;
+            let next = next_page_url(response.headers());
+            let page: Vec<GitHubContent> = response.json().await
+                .map_err(|e| LoaderError::ParseError(e.to_string()))?;
+            entries.extend(page);
+
+            match next {
+                // Next links carry the full query already
+                Some(next) => {
+                    url = next;
+                    query.clear();
+                }
+                None => return Ok(entries),
+            }
+        }
+
+        warn!("Stopped listing {}/{} after {} pages", repo, dir, MAX_GITHUB_PAGES);
+        Ok(entries)
+    }
+
+    /// GET from the GitHub API, turning rate limiting and error statuses into errors
+    async fn github_api_get(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        query: &[(&str, &str)],
+    ) -> Result<reqwest::Response, LoaderError> {
+        let mut request = client.get(url)
+            .query(query)
             .header("Accept", "application/vnd.github.v3+json")
             .header("User-Agent", "opcode/2.0");
 
@@ -189,31 +387,31 @@ impl SkillLoader {
         let response = request.send().await
             .map_err(|e| LoaderError::NetworkError(e.to_string()))?;
 
+        if let Some(error) = rate_limit_error(response.status(), response.headers()) {
+            return Err(error);
+        }
         if !response.status().is_success() {
             return Err(LoaderError::NetworkError(format!(
-                "GitHub API returned status {}",
-                response.status()
+                "GitHub API returned status {} for {}",
+                response.status(),
+                url
             )));
         }
+        Ok(response)
+    }
 
-        let entries: Vec<GitHubContent> = response.json().await
-            .map_err(|e| LoaderError::ParseError(e.to_string()))?;
-
-        let mut skills = Vec::new();
+    /// Look up a repository's default branch through the GitHub API
+    async fn github_default_branch(&self, repo: &str) -> Result<String, LoaderError> {
+        let api_url = format!("{}/repos/{}", self.github_api_url, repo);
 
-        for entry in entries {
-            if entry.content_type == "file" {
-                let ext = entry.name.split('.').last().unwrap_or("");
-                if ext == "json" || ext == "yaml" || ext == "yml" {
-                    match self.load_from_github(repo, &entry.path).await {
-                        Ok(skill) => skills.push(skill),
-                        Err(e) => warn!("Failed to load skill {}: {}", entry.name, e),
-                    }
-                }
-            }
-        }
+        let client = crate::http::build_client(crate::http::DEFAULT_TIMEOUT, &crate::http::proxy_settings())
+            .map_err(|e| LoaderError::NetworkError(e.to_string()))?;
+        let response = self.github_api_get(&client, &api_url, &[]).await?;
 
-        Ok(skills)
+        let info: GitHubRepo = response.json().await
+            .map_err(|e| LoaderError::ParseError(e.to_string()))?;
+        debug!("Default branch of {} is {}", repo, info.default_branch);
+        Ok(info.default_branch)
     }
 
     /// Parse a skill from TOML content (Claude Code format)
@@ -270,6 +468,24 @@ impl SkillLoader {
         Ok(skill)
     }
 
+    /// Serialize a slash command in the Claude Code TOML format read by `parse_claude_code_skill`
+    pub fn to_claude_code_toml(&self, skill: &Skill) -> Result<String, LoaderError> {
+        let cmd = skill.config.slash_command.as_ref().ok_or_else(|| {
+            LoaderError::ValidationError("Only slash commands can be exported as TOML".to_string())
+        })?;
+
+        let mut entry = toml::map::Map::new();
+        entry.insert("prompt".to_string(), toml::Value::String(cmd.prompt.clone()));
+        entry.insert("description".to_string(), toml::Value::String(cmd.description.clone()));
+        let mut commands = toml::map::Map::new();
+        commands.insert(cmd.name.clone(), toml::Value::Table(entry));
+        let mut root = toml::map::Map::new();
+        root.insert("slash_commands".to_string(), toml::Value::Table(commands));
+
+        toml::to_string(&toml::Value::Table(root))
+            .map_err(|e| LoaderError::SerializeError(e.to_string()))
+    }
+
     /// Import all slash commands from a Claude Code settings file
     pub async fn import_claude_code_settings(&self, settings_path: &Path) -> Result<Vec<Skill>, LoaderError> {
         let content = fs::read_to_string(settings_path).await
@@ -293,6 +509,71 @@ impl SkillLoader {
     }
 }
 
+/// Check content against an expected hex SHA-256 digest
+pub fn verify_sha256(content: &[u8], expected: &str) -> Result<(), LoaderError> {
+    let expected = expected.trim().to_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(LoaderError::ValidationError(
+            "Expected SHA-256 must be 64 hex characters".to_string(),
+        ));
+    }
+
+    let actual = format!("{:x}", Sha256::digest(content));
+    if actual != expected {
+        return Err(LoaderError::ChecksumMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+/// The rel="next" URL from a GitHub Link header
+fn next_page_url(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (target, params) = part.split_once(';')?;
+        let is_next = params
+            .split(';')
+            .any(|param| param.trim().replace(' ', "") == "rel=\"next\"");
+        let target = target.trim();
+        if is_next && target.starts_with('<') && target.ends_with('>') {
+            Some(target[1..target.len() - 1].to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// RateLimited if GitHub refused the request for exceeding its rate limit
+fn rate_limit_error(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap) -> Option<LoaderError> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let exhausted = header("x-ratelimit-remaining") == Some("0") || header("retry-after").is_some();
+    let refused = status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+    if !(refused && exhausted) {
+        return None;
+    }
+
+    let reset_at = header("x-ratelimit-reset")
+        .and_then(|reset| reset.parse::<i64>().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|reset| reset.to_rfc3339());
+    Some(LoaderError::RateLimited { reset_at })
+}
+
+/// Raw file URL for a path at a branch, tag or commit
+fn github_raw_url(repo: &str, git_ref: &str, path: &str) -> String {
+    format!(
+        "https://raw.githubusercontent.com/{}/{}/{}",
+        repo,
+        git_ref,
+        path.trim_start_matches('/')
+    )
+}
+
+/// GitHub repository API response (only the fields we use)
+#[derive(Debug, serde::Deserialize)]
+struct GitHubRepo {
+    default_branch: String,
+}
+
 /// GitHub content API response
 #[derive(Debug, serde::Deserialize)]
 struct GitHubContent {
@@ -319,6 +600,12 @@ pub enum LoaderError {
 
     #[error("Validation error: {0}")]
     ValidationError(String),
+
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("GitHub rate limit exceeded; try again after {}", .reset_at.as_deref().unwrap_or("the limit resets"))]
+    RateLimited { reset_at: Option<String> },
 }
 
 #[cfg(test)]
@@ -331,6 +618,93 @@ mod tests {
         assert!(loader.github_token.is_none());
     }
 
+    #[test]
+    fn test_checksum_mismatch_rejects_import() {
+        let content = br#"{"name":"review"}"#;
+        let digest = format!("{:x}", Sha256::digest(content));
+        assert!(verify_sha256(content, &digest.to_uppercase()).is_ok());
+
+        let tampered = br#"{"name":"review","config":{"hook":{}}}"#;
+        assert!(matches!(
+            verify_sha256(tampered, &digest),
+            Err(LoaderError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(verify_sha256(content, "abc"), Err(LoaderError::ValidationError(_))));
+    }
+
+    /// Contents API serving two pages, or a rate-limit refusal for the "limited" directory
+    async fn mock_contents_handler(
+        axum::extract::Path(dir): axum::extract::Path<String>,
+        axum::extract::Query(query): axum::extract::Query<std::collections::HashMap<String, String>>,
+        headers: axum::http::HeaderMap,
+    ) -> axum::response::Response {
+        use axum::response::IntoResponse;
+
+        if dir == "limited" {
+            return (
+                axum::http::StatusCode::FORBIDDEN,
+                [("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1700000000")],
+            )
+                .into_response();
+        }
+
+        let host = headers.get("host").and_then(|h| h.to_str().ok()).unwrap_or_default();
+        let entry = |name: &str| {
+            serde_json::json!({ "name": name, "path": format!("skills/{}", name), "type": "file" })
+        };
+        match query.get("page").map(String::as_str) {
+            Some("2") => axum::Json(serde_json::json!([entry("b.yaml")])).into_response(),
+            _ => {
+                assert_eq!(query.get("ref").map(String::as_str), Some("v1.0"));
+                let link = format!(
+                    "<http://{}/repos/acme/skills/contents/skills?ref=v1.0&page=2>; rel=\"next\", \
+                     <http://{}/repos/acme/skills/contents/skills?ref=v1.0&page=2>; rel=\"last\"",
+                    host, host
+                );
+                ([("link", link)], axum::Json(serde_json::json!([entry("a.json")]))).into_response()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_github_dir_follows_link_pagination() {
+        let app = axum::Router::new().route(
+            "/repos/acme/skills/contents/{dir}",
+            axum::routing::get(mock_contents_handler),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let loader = SkillLoader::new("/tmp/skills").with_github_api_url(format!("http://{}/", addr));
+        let client = reqwest::Client::new();
+
+        let entries = loader.list_github_dir(&client, "acme/skills", "skills", "v1.0").await.unwrap();
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["a.json", "b.yaml"]);
+
+        match loader.list_github_dir(&client, "acme/skills", "limited", "main").await {
+            Err(LoaderError::RateLimited { reset_at }) => {
+                assert_eq!(reset_at.as_deref(), Some("2023-11-14T22:13:20+00:00"));
+            }
+            other => panic!("expected rate limit error, got {:?}", other.map(|e| e.len())),
+        }
+    }
+
+    #[test]
+    fn test_github_raw_url_for_tag() {
+        assert_eq!(
+            github_raw_url("acme/skills", "v1.2.0", "commands/review.json"),
+            "https://raw.githubusercontent.com/acme/skills/v1.2.0/commands/review.json"
+        );
+        assert_eq!(
+            github_raw_url("acme/skills", "master", "/review.yaml"),
+            "https://raw.githubusercontent.com/acme/skills/master/review.yaml"
+        );
+    }
+
     #[test]
     fn test_parse_claude_code_skill() {
         let content = r#"
@@ -343,4 +717,66 @@ description = "Run tests"
         assert_eq!(skill.name, "/test");
         assert!(skill.config.slash_command.is_some());
     }
+
+    #[tokio::test]
+    async fn test_load_skills_from_nested_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let loader = SkillLoader::new(dir.path()).with_ignored_dirs(["vendor"]);
+        let skill = loader
+            .parse_claude_code_skill("[slash_commands.lint]\nprompt = \"Lint $ARGUMENTS\"\n", "lint")
+            .unwrap();
+
+        for filename in [
+            "top.json",
+            "git/commit.json",
+            "git/hooks/pre-commit.yaml",
+            ".drafts/unfinished.json",
+            "vendor/third-party.json",
+        ] {
+            loader.save_skill_file(&skill, filename).await.unwrap();
+        }
+
+        let mut tags: Vec<Vec<String>> = loader
+            .load_local_skills()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|skill| skill.metadata.tags)
+            .collect();
+        tags.sort();
+
+        assert_eq!(
+            tags,
+            vec![
+                Vec::<String>::new(),
+                vec!["git".to_string()],
+                vec!["git/hooks".to_string()],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exported_skills_load_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let loader = SkillLoader::new(dir.path());
+        let skill = loader
+            .parse_claude_code_skill(
+                "[slash_commands.review]\nprompt = \"Review $ARGUMENTS\"\ndescription = \"Code review\"\n",
+                "review",
+            )
+            .unwrap();
+
+        for filename in ["review.json", "review.yaml"] {
+            let path = loader.save_skill_file(&skill, filename).await.unwrap();
+            let loaded = loader.load_skill_file(&path).await.unwrap();
+            assert_eq!(loaded.id, skill.id);
+            assert_eq!(loaded.config.slash_command.unwrap().prompt, "Review $ARGUMENTS");
+        }
+
+        let path = loader.save_skill_file(&skill, "review.toml").await.unwrap();
+        let content = std::fs::read_to_string(path).unwrap();
+        let loaded = loader.parse_claude_code_skill(&content, "review").unwrap();
+        assert_eq!(loaded.description, "Code review");
+        assert!(loaded.config.slash_command.unwrap().requires_args);
+    }
 }
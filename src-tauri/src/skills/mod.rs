@@ -13,10 +13,12 @@ pub mod types;
 pub mod registry;
 pub mod loader;
 pub mod executor;
+pub mod condition;
+pub mod tool_stream;
 
 pub use types::{
     Skill, SkillKind, SkillConfig, SkillMetadata, SkillVisibility, SkillContext, SkillResult,
-    SlashCommandConfig, HookConfig, WorkflowConfig, HookTrigger,
+    SlashCommandConfig, HookConfig, WorkflowConfig, WorkflowEvent, HookTrigger, HookDecision, HookOutcome,
 };
 pub use registry::SkillRegistry;
 pub use loader::{SkillLoader, LoaderError};
@@ -63,6 +63,22 @@ impl SkillRegistry {
             [],
         )?;
 
+        // One skill per kind and name in each scope; NULL project paths are the global scope
+        if let Err(e) = conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_skills_scope_name
+             ON skills(kind, name, COALESCE(project_path, ''))",
+            [],
+        ) {
+            // Databases that already hold duplicates keep working without the index
+            warn!("Could not create unique skill name index: {}", e);
+        }
+
+        // Imported skills that run shell commands stay disabled until approved
+        let _ = conn.execute(
+            "ALTER TABLE skills ADD COLUMN needs_approval BOOLEAN DEFAULT 0",
+            [],
+        );
+
         info!("Skills database table initialized");
         Ok(())
     }
@@ -360,4 +376,22 @@ mod tests {
         assert!(registry.has_slash_command("test"));
         assert!(registry.get_slash_command("test").is_some());
     }
+
+    #[test]
+    fn test_skill_names_unique_per_scope() {
+        let conn = Connection::open_in_memory().unwrap();
+        SkillRegistry::init_database(&conn).unwrap();
+
+        let insert = |id: &str, project_path: Option<&str>| {
+            conn.execute(
+                "INSERT INTO skills (id, kind, name, config, project_path) VALUES (?1, 'slash_command', '/test', '{}', ?2)",
+                params![id, project_path],
+            )
+        };
+
+        assert!(insert("a", None).is_ok());
+        assert!(insert("b", None).is_err());
+        assert!(insert("c", Some("/project")).is_ok());
+        assert!(insert("d", Some("/project")).is_err());
+    }
 }
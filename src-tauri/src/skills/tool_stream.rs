@@ -0,0 +1,434 @@
+//! Tool Stream Hooks
+//!
+//! Follows tool calls in Claude's stream-json output so PreTool and PostTool
+//! hooks can run around each of them.
+//!
+//! Blocking PreTool hooks can't wait for the stream, since Claude has already run
+//! the tool by then. For those Claude is started with a PreToolUse hook of its own
+//! that calls back into `opcode --pre-tool-hook`, which answers with the hooks' decision.
+
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::sync::mpsc;
+
+use super::executor::SkillExecutor;
+use super::types::{HookDecision, HookTrigger, SkillContext};
+
+/// A tool call seen in the stream, before or after it ran
+#[derive(Debug, Clone)]
+pub struct ToolUse {
+    /// Claude session the call belongs to, once known
+    pub session_id: Option<String>,
+    /// ID pairing the call with its result
+    pub tool_use_id: String,
+    /// Tool name, e.g. `Bash` or `mcp__github__create_issue`
+    pub tool_name: String,
+    /// Tool arguments
+    pub input: serde_json::Value,
+    /// Set once the tool has run
+    pub result: Option<ToolUseResult>,
+}
+
+/// What a tool call returned
+#[derive(Debug, Clone)]
+pub struct ToolUseResult {
+    /// Result text
+    pub output: String,
+    /// Whether the tool reported an error
+    pub is_error: bool,
+}
+
+impl ToolUse {
+    /// PreTool before the call ran, PostTool after
+    pub fn trigger(&self) -> HookTrigger {
+        match self.result {
+            Some(_) => HookTrigger::PostTool,
+            None => HookTrigger::PreTool,
+        }
+    }
+
+    /// Context for the hooks run around this call
+    ///
+    /// Hooks see the arguments as `OPCODE_TOOL_ARGS`, and PostTool hooks also get
+    /// `OPCODE_TOOL_OUTPUT` and `OPCODE_TOOL_EXIT_STATUS` (0, or 1 if the tool failed).
+    pub fn hook_context(&self, project_path: &str) -> SkillContext {
+        let mut arguments = HashMap::new();
+        arguments.insert("tool_args".to_string(), self.input.clone());
+        if let Some(ref result) = self.result {
+            arguments.insert("tool_output".to_string(), result.output.clone().into());
+            arguments.insert("tool_exit_status".to_string(), i32::from(result.is_error).into());
+        }
+
+        SkillContext {
+            project_path: project_path.to_string(),
+            session_id: self.session_id.clone(),
+            tool_name: Some(self.tool_name.clone()),
+            arguments,
+            env: std::env::vars().collect(),
+            variables: HashMap::new(),
+        }
+    }
+}
+
+/// Pairs tool_use blocks with their tool_result blocks across stream-json messages
+#[derive(Debug, Default)]
+pub struct ToolStreamTracker {
+    session_id: Option<String>,
+    /// Calls still waiting for a result (tool_use_id -> (name, input))
+    pending: HashMap<String, (String, serde_json::Value)>,
+}
+
+impl ToolStreamTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tool calls started or finished by one stream-json message
+    pub fn process_message(&mut self, message: &serde_json::Value) -> Vec<ToolUse> {
+        if message["type"] == "system" && message["subtype"] == "init" {
+            if let Some(session_id) = message["session_id"].as_str() {
+                self.session_id = Some(session_id.to_string());
+            }
+            return Vec::new();
+        }
+
+        let content = match message["message"]["content"].as_array() {
+            Some(content) => content,
+            None => return Vec::new(),
+        };
+
+        let mut calls = Vec::new();
+        for block in content {
+            match block["type"].as_str() {
+                Some("tool_use") => {
+                    let (id, name) = match (block["id"].as_str(), block["name"].as_str()) {
+                        (Some(id), Some(name)) => (id.to_string(), name.to_string()),
+                        _ => continue,
+                    };
+                    let input = block["input"].clone();
+                    self.pending.insert(id.clone(), (name.clone(), input.clone()));
+                    calls.push(ToolUse {
+                        session_id: self.session_id.clone(),
+                        tool_use_id: id,
+                        tool_name: name,
+                        input,
+                        result: None,
+                    });
+                }
+                Some("tool_result") => {
+                    let id = match block["tool_use_id"].as_str() {
+                        Some(id) => id,
+                        None => continue,
+                    };
+                    // Results for calls we never saw start have no tool name to match on
+                    let (name, input) = match self.pending.remove(id) {
+                        Some(call) => call,
+                        None => continue,
+                    };
+                    calls.push(ToolUse {
+                        session_id: self.session_id.clone(),
+                        tool_use_id: id.to_string(),
+                        tool_name: name,
+                        input,
+                        result: Some(ToolUseResult {
+                            output: result_text(&block["content"]),
+                            is_error: block["is_error"].as_bool().unwrap_or(false),
+                        }),
+                    });
+                }
+                _ => {}
+            }
+        }
+        calls
+    }
+}
+
+/// Text of a tool_result's content, which is a string or a list of blocks
+fn result_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Start of the denial reason the bridge gives Claude, followed by `<hook>: <reason>`
+const BLOCKED_BY_HOOK: &str = "Blocked by hook ";
+
+/// Claude `--settings` value sending every tool call through `opcode --pre-tool-hook` first
+pub fn pre_tool_bridge_settings(opcode_exe: &Path) -> String {
+    let command = format!("\"{}\" --pre-tool-hook", opcode_exe.display());
+    serde_json::json!({
+        "hooks": {
+            "PreToolUse": [{
+                "matcher": "*",
+                "hooks": [{ "type": "command", "command": command }],
+            }],
+        },
+    })
+    .to_string()
+}
+
+/// Answer a PreToolUse request from Claude with the PreTool hooks' decision
+///
+/// Returns the JSON to print for Claude, or None to let the call run unchanged.
+pub async fn answer_pre_tool_use(executor: &SkillExecutor, request: &serde_json::Value) -> Option<serde_json::Value> {
+    let call = ToolUse {
+        session_id: request["session_id"].as_str().map(String::from),
+        tool_use_id: request["tool_use_id"].as_str().unwrap_or_default().to_string(),
+        tool_name: request["tool_name"].as_str()?.to_string(),
+        input: request["tool_input"].clone(),
+        result: None,
+    };
+    let project_path = request["cwd"].as_str().unwrap_or_default();
+    let outcome = executor
+        .execute_hooks_for_trigger(HookTrigger::PreTool, call.hook_context(project_path))
+        .await;
+    let hook_name = outcome.decided_by.unwrap_or_else(|| "unknown".to_string());
+
+    let output = match outcome.decision {
+        HookDecision::Allow => return None,
+        HookDecision::Block { reason } => serde_json::json!({
+            "hookEventName": "PreToolUse",
+            "permissionDecision": "deny",
+            "permissionDecisionReason": format!("{}{}: {}", BLOCKED_BY_HOOK, hook_name, reason),
+        }),
+        HookDecision::Modify { tool_args } => {
+            info!("Hook {} rewrote the input of {}", hook_name, call.tool_name);
+            serde_json::json!({
+                "hookEventName": "PreToolUse",
+                "permissionDecision": "allow",
+                "updatedInput": tool_args,
+            })
+        }
+    };
+    Some(serde_json::json!({ "hookSpecificOutput": output }))
+}
+
+/// Hook name and reason, if a finished call was denied by the PreToolUse bridge
+pub fn blocked_by_hook(call: &ToolUse) -> Option<(String, String)> {
+    let result = call.result.as_ref().filter(|result| result.is_error)?;
+    // Claude may wrap the denial reason in text of its own
+    let start = result.output.find(BLOCKED_BY_HOOK)? + BLOCKED_BY_HOOK.len();
+    let (hook_name, reason) = result.output[start..].split_once(": ")?;
+    Some((hook_name.to_string(), reason.trim().to_string()))
+}
+
+/// Run the PreTool and PostTool hooks for each call received, in order, until the channel closes
+///
+/// Claude runs tools itself, so by the time a call shows up in the stream a Block
+/// or Modify decision can no longer be applied; those are logged instead. With
+/// `pre_tool_bridged`, PreTool hooks already ran through the bridge and are skipped.
+pub async fn run_tool_hooks(
+    executor: SkillExecutor,
+    project_path: String,
+    pre_tool_bridged: bool,
+    mut calls: mpsc::UnboundedReceiver<ToolUse>,
+) {
+    while let Some(call) = calls.recv().await {
+        let trigger = call.trigger();
+        if pre_tool_bridged && trigger == HookTrigger::PreTool {
+            continue;
+        }
+        let outcome = executor
+            .execute_hooks_for_trigger(trigger.clone(), call.hook_context(&project_path))
+            .await;
+
+        for result in outcome.results.iter().filter(|result| !result.success) {
+            warn!(
+                "{:?} hook for {} failed: {}",
+                trigger,
+                call.tool_name,
+                result.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        match outcome.decision {
+            HookDecision::Allow => {}
+            HookDecision::Block { reason } => {
+                info!("Hook would block {} but Claude already ran it: {}", call.tool_name, reason);
+            }
+            HookDecision::Modify { .. } => {
+                info!("Hook would modify {} but Claude already ran it", call.tool_name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skills::registry::SkillRegistry;
+    use crate::skills::types::{HookConfig, Skill, SkillConfig, SkillKind, SkillMetadata, SkillVisibility};
+    use std::sync::Arc;
+
+    fn hook(id: &str, trigger: HookTrigger, patterns: &[&str], command: &str) -> Skill {
+        Skill {
+            id: id.to_string(),
+            kind: SkillKind::Hook,
+            name: id.to_string(),
+            description: String::new(),
+            visibility: SkillVisibility::Global,
+            enabled: true,
+            config: SkillConfig {
+                hook: Some(HookConfig {
+                    trigger,
+                    tool_patterns: Some(patterns.iter().map(|p| p.to_string()).collect()),
+                    command: command.to_string(),
+                    timeout_secs: 5,
+                    can_block: false,
+                    env: HashMap::new(),
+                    stdin_json: false,
+                }),
+                ..Default::default()
+            },
+            metadata: SkillMetadata::default(),
+            project_path: None,
+            source: "local".to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_tracker_pairs_calls_with_results() {
+        let mut tracker = ToolStreamTracker::new();
+        tracker.process_message(&serde_json::json!({ "type": "system", "subtype": "init", "session_id": "s1" }));
+
+        let started = tracker.process_message(&serde_json::json!({
+            "type": "assistant",
+            "message": { "content": [
+                { "type": "text", "text": "Listing files" },
+                { "type": "tool_use", "id": "t1", "name": "Bash", "input": { "command": "ls" } },
+            ] },
+        }));
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].trigger(), HookTrigger::PreTool);
+        assert_eq!(started[0].session_id.as_deref(), Some("s1"));
+
+        let finished = tracker.process_message(&serde_json::json!({
+            "type": "user",
+            "message": { "content": [
+                { "type": "tool_result", "tool_use_id": "t1", "content": [{ "type": "text", "text": "a.txt" }] },
+                { "type": "tool_result", "tool_use_id": "unknown", "content": "ignored" },
+            ] },
+        }));
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].tool_name, "Bash");
+        assert_eq!(finished[0].input["command"], "ls");
+        let result = finished[0].result.as_ref().unwrap();
+        assert_eq!(result.output, "a.txt");
+        assert!(!result.is_error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hooks_run_for_streamed_tool_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Arc::new(SkillRegistry::new());
+        registry.register_skill(hook(
+            "pre",
+            HookTrigger::PreTool,
+            &["Bash*"],
+            r#"printf '%s %s\n' "$OPCODE_TOOL_NAME" "$OPCODE_SESSION_ID" >> pre.log"#,
+        ));
+        registry.register_skill(hook(
+            "post",
+            HookTrigger::PostTool,
+            &["mcp__*"],
+            r#"printf '%s %s %s\n' "$OPCODE_TOOL_NAME" "$OPCODE_TOOL_EXIT_STATUS" "$OPCODE_TOOL_OUTPUT" >> post.log"#,
+        ));
+
+        // A fake session: Bash and an MCP tool succeed, Read fails
+        let stream = [
+            r#"{"type":"system","subtype":"init","session_id":"s1"}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#,
+            r#"{"type":"assistant","message":{"content":[
+                {"type":"tool_use","id":"t2","name":"mcp__db__query","input":{}},
+                {"type":"tool_use","id":"t3","name":"Read","input":{}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t2","content":"3 rows"},
+                {"type":"tool_result","tool_use_id":"t3","content":"missing","is_error":true}]}}"#,
+        ];
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut tracker = ToolStreamTracker::new();
+        for line in stream {
+            let message: serde_json::Value = serde_json::from_str(line).unwrap();
+            for call in tracker.process_message(&message) {
+                tx.send(call).unwrap();
+            }
+        }
+        drop(tx);
+
+        let executor = SkillExecutor::new(registry);
+        run_tool_hooks(executor, dir.path().to_string_lossy().to_string(), false, rx).await;
+
+        let pre = std::fs::read_to_string(dir.path().join("pre.log")).unwrap();
+        assert_eq!(pre, "Bash s1\n");
+        let post = std::fs::read_to_string(dir.path().join("post.log")).unwrap();
+        assert_eq!(post, "mcp__db__query 0 3 rows\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bridge_answers_with_hook_decisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = |id: &str, patterns: &[&str], command: &str| {
+            let mut skill = hook(id, HookTrigger::PreTool, patterns, command);
+            if let Some(ref mut hook) = skill.config.hook {
+                hook.can_block = true;
+            }
+            skill
+        };
+        let registry = Arc::new(SkillRegistry::new());
+        registry.register_skill(guard("no-rm", &["Bash"], "echo 'rm is not allowed' >&2; exit 2"));
+        registry.register_skill(guard(
+            "sandbox",
+            &["Write"],
+            r#"echo '{"decision":"modify","input":{"file_path":"/tmp/sandbox/a.txt"}}'"#,
+        ));
+        registry.register_skill(guard("audit", &["Read"], "true"));
+        let executor = SkillExecutor::new(registry);
+        let request = |tool_name: &str| {
+            serde_json::json!({
+                "session_id": "s1",
+                "cwd": dir.path().to_string_lossy(),
+                "hook_event_name": "PreToolUse",
+                "tool_name": tool_name,
+                "tool_input": { "file_path": "a.txt" },
+            })
+        };
+
+        let blocked = answer_pre_tool_use(&executor, &request("Bash")).await.unwrap();
+        assert_eq!(blocked["hookSpecificOutput"]["permissionDecision"], "deny");
+        let reason = blocked["hookSpecificOutput"]["permissionDecisionReason"].as_str().unwrap();
+        assert!(reason.starts_with("Blocked by hook no-rm: "));
+
+        let modified = answer_pre_tool_use(&executor, &request("Write")).await.unwrap();
+        assert_eq!(modified["hookSpecificOutput"]["permissionDecision"], "allow");
+        assert_eq!(modified["hookSpecificOutput"]["updatedInput"]["file_path"], "/tmp/sandbox/a.txt");
+
+        assert!(answer_pre_tool_use(&executor, &request("Read")).await.is_none());
+
+        // The denial comes back to the stream as the tool's error result
+        let denied = ToolUse {
+            session_id: Some("s1".to_string()),
+            tool_use_id: "t1".to_string(),
+            tool_name: "Bash".to_string(),
+            input: serde_json::Value::Null,
+            result: Some(ToolUseResult {
+                output: reason.to_string(),
+                is_error: true,
+            }),
+        };
+        let (hook_name, why) = blocked_by_hook(&denied).unwrap();
+        assert_eq!(hook_name, "no-rm");
+        assert!(why.contains("rm is not allowed"));
+    }
+}
@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Kind of skill
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -84,7 +85,10 @@ pub struct WorkflowStep {
     pub config: serde_json::Value,
     /// IDs of steps this step depends on
     pub depends_on: Vec<String>,
-    /// Optional condition expression
+    /// Dependencies whose condition may skip them without skipping this step
+    #[serde(default)]
+    pub optional_depends_on: Vec<String>,
+    /// Condition expression; the step is skipped when it is false (see `skills::condition`)
     pub condition: Option<String>,
     /// Timeout in seconds
     pub timeout_secs: Option<u64>,
@@ -115,6 +119,328 @@ pub enum BackoffStrategy {
     Linear { initial_ms: u64, increment_ms: u64 },
 }
 
+impl HookConfig {
+    /// Whether the hook applies to a tool, matching `tool_patterns` as globs
+    ///
+    /// Hooks without patterns apply to everything; hooks with patterns never
+    /// apply when the tool is unknown.
+    pub fn matches_tool(&self, tool_name: Option<&str>) -> bool {
+        let patterns = match &self.tool_patterns {
+            Some(patterns) if !patterns.is_empty() => patterns,
+            _ => return true,
+        };
+        let tool_name = match tool_name {
+            Some(name) => name,
+            None => return false,
+        };
+
+        patterns.iter().any(|pattern| match glob::Pattern::new(pattern) {
+            Ok(glob) => glob.matches(tool_name),
+            Err(_) => pattern == tool_name,
+        })
+    }
+}
+
+impl RetryConfig {
+    /// Whether a failure should be retried (any failure if no patterns are set)
+    ///
+    /// Each pattern matches as a substring or, failing that, as a regex.
+    pub fn matches(&self, error: &str) -> bool {
+        self.retry_on.is_empty()
+            || self.retry_on.iter().any(|pattern| {
+                error.contains(pattern.as_str())
+                    || regex::Regex::new(pattern).is_ok_and(|re| re.is_match(error))
+            })
+    }
+}
+
+impl BackoffStrategy {
+    /// Delay before the given retry, counting the first retry as 1
+    pub fn delay(&self, retry: u32) -> Duration {
+        let n = retry.saturating_sub(1);
+        let ms = match *self {
+            Self::Fixed { delay_ms } => delay_ms,
+            Self::Exponential {
+                initial_ms,
+                max_ms,
+                multiplier,
+            } => {
+                // Float-to-int casts saturate, so huge values land on the cap
+                let ms = initial_ms as f64 * multiplier.powi(n.min(i32::MAX as u32) as i32);
+                (ms as u64).min(max_ms)
+            }
+            Self::Linear {
+                initial_ms,
+                increment_ms,
+            } => initial_ms.saturating_add(increment_ms.saturating_mul(n as u64)),
+        };
+        Duration::from_millis(ms)
+    }
+}
+
+impl SlashCommandConfig {
+    /// Expand the prompt for a raw argument string
+    ///
+    /// `$ARGUMENTS` becomes the raw string. With an `args` config each
+    /// argument is parsed and substituted as `${name}`, and positional ones
+    /// also as `$1`, `$2`, ...; optional arguments without a value become
+    /// empty. Placeholders are only read from the template, so values that
+    /// contain `$1` or `${name}` are inserted as typed. Errors carry the usage text.
+    pub fn expand_prompt(&self, raw_args: &str) -> Result<String, ArgsError> {
+        if self.requires_args && raw_args.trim().is_empty() {
+            return Err(self.args_error("Arguments required"));
+        }
+
+        let mut named = HashMap::new();
+        let mut positional = Vec::new();
+        if let Some(ref args) = self.args {
+            let values = args.parse(raw_args).map_err(|e| self.args_error(&e))?;
+            let value = |def: &ArgDef| values.get(&def.name).cloned().unwrap_or_default();
+            for def in args.positional.iter().chain(&args.named) {
+                named.insert(def.name.as_str(), value(def));
+            }
+            positional = args.positional.iter().map(value).collect();
+        }
+        Ok(substitute_placeholders(&self.prompt, raw_args, &named, &positional))
+    }
+
+    /// Whether the command can't run without arguments: it has required
+    /// arguments, or without an `args` config its prompt uses `$ARGUMENTS`
+    pub fn derive_requires_args(&self) -> bool {
+        match self.args {
+            Some(ref args) => args.positional.iter().chain(&args.named).any(|def| def.required),
+            None => self.prompt.contains("$ARGUMENTS"),
+        }
+    }
+
+    /// One-line usage, e.g. `/deploy <env> [target] [--tag <tag>]`
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+        if let Some(ref args) = self.args {
+            for def in &args.positional {
+                let part = if def.required {
+                    format!(" <{}>", def.name)
+                } else {
+                    format!(" [{}]", def.name)
+                };
+                usage.push_str(&part);
+            }
+            for def in &args.named {
+                let part = if def.required {
+                    format!(" --{} <{}>", def.name, def.name)
+                } else {
+                    format!(" [--{} <{}>]", def.name, def.name)
+                };
+                usage.push_str(&part);
+            }
+        }
+        usage
+    }
+
+    fn args_error(&self, problem: &str) -> ArgsError {
+        ArgsError {
+            message: problem.to_string(),
+            usage: self.usage(),
+            help: self.help.clone(),
+        }
+    }
+}
+
+/// Slash command arguments that couldn't be used, with the help to show for them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgsError {
+    /// What was wrong, e.g. `Missing required argument: env`
+    pub message: String,
+    /// Generated usage line
+    pub usage: String,
+    /// The command's extended help, if any
+    pub help: Option<String>,
+}
+
+impl std::fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n\nUsage: {}", self.message, self.usage)?;
+        if let Some(ref help) = self.help {
+            write!(f, "\n\n{}", help)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+/// Replace `$ARGUMENTS`, `${name}` and `$1`, `$2`, ... in a single pass over the template
+///
+/// Unknown names and out-of-range numbers are left alone.
+fn substitute_placeholders(
+    prompt: &str,
+    raw_args: &str,
+    named: &HashMap<&str, String>,
+    positional: &[String],
+) -> String {
+    let mut result = String::with_capacity(prompt.len());
+    let mut rest = prompt;
+    while let Some(pos) = rest.find('$') {
+        result.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if let Some(tail) = after.strip_prefix("ARGUMENTS") {
+            result.push_str(raw_args);
+            rest = tail;
+            continue;
+        }
+
+        let braced = after
+            .strip_prefix('{')
+            .and_then(|inner| inner.find('}').map(|end| &inner[..end]))
+            .and_then(|name| named.get(name).map(|value| (name.len() + 2, value)));
+        if let Some((len, value)) = braced {
+            result.push_str(value);
+            rest = &after[len..];
+            continue;
+        }
+
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let value = after[..digits]
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| positional.get(i));
+        match value {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[pos..pos + 1 + digits]),
+        }
+        rest = &after[digits..];
+    }
+    result.push_str(rest);
+    result
+}
+
+impl ArgsConfig {
+    /// Parse a raw argument string into values keyed by argument name
+    ///
+    /// Words split on whitespace, with single and double quotes and backslash
+    /// escapes as in a shell. Named arguments are `--name value` or
+    /// `--name=value`; a `--name` with no value that follows is set to "true".
+    /// Everything after a bare `--` is positional. Defaults fill in missing
+    /// arguments, and `choices` are enforced on the values given.
+    pub fn parse(&self, raw: &str) -> Result<HashMap<String, String>, String> {
+        let mut values = HashMap::new();
+        let mut positional = Vec::new();
+        let mut words = split_args(raw)?.into_iter().peekable();
+        let mut options_done = false;
+
+        while let Some(word) = words.next() {
+            let option = match word.strip_prefix("--") {
+                Some(option) if !options_done => option.to_string(),
+                _ => {
+                    positional.push(word);
+                    continue;
+                }
+            };
+            if option.is_empty() {
+                options_done = true;
+                continue;
+            }
+
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => {
+                    let value = match words.peek() {
+                        Some(next) if !next.starts_with("--") => words.next().unwrap_or_default(),
+                        _ => "true".to_string(),
+                    };
+                    (option, value)
+                }
+            };
+            if !self.named.iter().any(|def| def.name == name) {
+                return Err(format!("Unknown option: --{}", name));
+            }
+            values.insert(name, value);
+        }
+
+        if positional.len() > self.positional.len() {
+            return Err(format!("Unexpected argument: {}", positional[self.positional.len()]));
+        }
+        for (def, value) in self.positional.iter().zip(positional) {
+            values.insert(def.name.clone(), value);
+        }
+
+        for def in self.positional.iter().chain(&self.named) {
+            match values.get(&def.name) {
+                Some(value) => {
+                    if let Some(ref choices) = def.choices {
+                        if !choices.contains(value) {
+                            return Err(format!(
+                                "Invalid value for {}: {} (expected one of: {})",
+                                def.name,
+                                value,
+                                choices.join(", ")
+                            ));
+                        }
+                    }
+                }
+                None => match (&def.default, def.required) {
+                    (Some(default), _) => {
+                        values.insert(def.name.clone(), default.clone());
+                    }
+                    (None, true) => return Err(format!("Missing required argument: {}", def.name)),
+                    (None, false) => {}
+                },
+            }
+        }
+
+        Ok(values)
+    }
+}
+
+/// Split an argument string into words, honoring quotes and backslash escapes
+fn split_args(raw: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            // Backslashes are literal inside single quotes
+            (Some('\''), c) => current.push(c),
+            (_, '\\') => match chars.next() {
+                Some(escaped) => {
+                    current.push(escaped);
+                    in_word = true;
+                }
+                None => return Err("Trailing backslash in arguments".to_string()),
+            },
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if let Some(q) = quote {
+        return Err(format!("Unterminated {} quote in arguments", q));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
 /// Skill metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillMetadata {
@@ -167,6 +493,40 @@ pub struct SkillConfig {
     pub agent: Option<AgentConfig>,
 }
 
+impl SkillConfig {
+    /// Whether running the skill executes shell commands (a hook or a workflow shell step)
+    pub fn runs_shell_commands(&self) -> bool {
+        self.hook.is_some()
+            || self.workflow.as_ref().is_some_and(|workflow| {
+                workflow
+                    .steps
+                    .iter()
+                    .any(|step| matches!(step.kind, WorkflowStepKind::Shell))
+            })
+    }
+
+    /// Check that the configuration for `kind` is present and well-formed
+    pub fn validate_for(&self, kind: &SkillKind) -> Result<(), String> {
+        let present = match kind {
+            SkillKind::SlashCommand => self.slash_command.is_some(),
+            SkillKind::Hook => self.hook.is_some(),
+            SkillKind::Workflow => self.workflow.is_some(),
+            SkillKind::Template => self.template.is_some(),
+            SkillKind::Agent => self.agent.is_some(),
+        };
+        if !present {
+            return Err(format!("Missing {:?} configuration", kind));
+        }
+        if let Some(ref workflow) = self.workflow {
+            workflow.validate()?;
+        }
+        if let Some(ref template) = self.template {
+            template.validate()?;
+        }
+        Ok(())
+    }
+}
+
 /// Slash command configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlashCommandConfig {
@@ -225,6 +585,43 @@ pub struct HookConfig {
     pub can_block: bool,
     /// Environment variables
     pub env: HashMap<String, String>,
+    /// Also pass the full `SkillContext` as JSON on the hook's stdin
+    #[serde(default)]
+    pub stdin_json: bool,
+}
+
+/// What a blocking hook decided about the tool call that triggered it
+///
+/// Hooks report a decision by printing it as JSON on stdout, e.g.
+/// `{"decision":"block","reason":"..."}` or `{"decision":"modify","input":{...}}`.
+/// Exiting with code 2 blocks too (as does any other non-zero exit).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum HookDecision {
+    /// Let the tool run as requested
+    Allow,
+    /// Abort the tool call
+    Block {
+        #[serde(default)]
+        reason: String,
+    },
+    /// Run the tool with replaced arguments
+    Modify {
+        #[serde(alias = "input")]
+        tool_args: serde_json::Value,
+    },
+}
+
+/// Combined result of the hooks run for a trigger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookOutcome {
+    /// Verdict for the caller to honor (always Allow for non-PreTool triggers)
+    pub decision: HookDecision,
+    /// Name of the hook behind a Block or Modify decision
+    #[serde(default)]
+    pub decided_by: Option<String>,
+    /// Results of the hooks that ran, in order
+    pub results: Vec<SkillResult>,
 }
 
 /// Workflow configuration
@@ -238,10 +635,42 @@ pub struct WorkflowConfig {
     pub outputs: HashMap<String, String>,
     /// Global timeout
     pub timeout_secs: Option<u64>,
-    /// Concurrency limit
+    /// Most steps run at once (4 if unset)
     pub max_parallel: Option<u32>,
 }
 
+impl WorkflowConfig {
+    /// Check that step IDs are unique and every dependency names another step, without cycles
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("Workflow has no steps".to_string());
+        }
+        let mut ids = std::collections::HashSet::new();
+        for step in &self.steps {
+            if step.id.trim().is_empty() {
+                return Err("Workflow step IDs must not be empty".to_string());
+            }
+            if !ids.insert(step.id.as_str()) {
+                return Err(format!("Duplicate workflow step ID: {}", step.id));
+            }
+        }
+        for step in &self.steps {
+            for dep in step.depends_on.iter().chain(&step.optional_depends_on) {
+                if dep == &step.id {
+                    return Err(format!("Step {} depends on itself", step.id));
+                }
+                if !ids.contains(dep.as_str()) {
+                    return Err(format!("Step {} depends on unknown step {}", step.id, dep));
+                }
+            }
+        }
+        if let Some(cycle) = super::executor::dependency_cycle(self) {
+            return Err(format!("Workflow has a dependency cycle: {}", cycle.join(" -> ")));
+        }
+        Ok(())
+    }
+}
+
 /// Input variable definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InputDef {
@@ -260,12 +689,67 @@ pub struct InputDef {
 /// Template configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateConfig {
-    /// Template content with placeholders
+    /// Template content: `{{name}}`, `{{obj.field}}` and `{{#if name}}...{{/if}}` placeholders
     pub content: String,
     /// Variables that can be substituted
     pub variables: Vec<TemplateVariable>,
 }
 
+impl TemplateConfig {
+    /// Check that every variable the content refers to is declared, once
+    pub fn validate(&self) -> Result<(), String> {
+        let mut declared = std::collections::HashSet::new();
+        for var in &self.variables {
+            if !declared.insert(var.name.as_str()) {
+                return Err(format!("Duplicate template variable: {}", var.name));
+            }
+        }
+
+        let undeclared: Vec<String> = template_references(&self.content)
+            .into_iter()
+            .filter(|name| !declared.contains(name.as_str()))
+            .collect();
+        if !undeclared.is_empty() {
+            return Err(format!("Template uses undeclared variables: {}", undeclared.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+/// Root variable names used by `{{name}}`, `{{obj.field}}` and `{{#if name}}` placeholders
+fn template_references(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = match after.find("}}") {
+            Some(end) => end,
+            None => break,
+        };
+        let expression = after[..end].trim_matches(|c: char| c == '{' || c == '}' || c == '~').trim();
+        rest = &after[end + 2..];
+
+        // Block helpers name the variable after the helper: `#if name`, `#each items`
+        let expression = match expression.strip_prefix('#') {
+            Some(block) => block.split_whitespace().nth(1).unwrap_or(""),
+            None => expression,
+        };
+        if expression.is_empty()
+            || expression.starts_with(['/', '!', '@', '>'])
+            || expression == "else"
+            || expression == "this"
+            || expression.starts_with("this.")
+        {
+            continue;
+        }
+        let root = expression.split(['.', ' ']).next().unwrap_or("");
+        if !root.is_empty() && !names.iter().any(|name| name == root) {
+            names.push(root.to_string());
+        }
+    }
+    names
+}
+
 /// Template variable
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateVariable {
@@ -275,6 +759,9 @@ pub struct TemplateVariable {
     pub description: String,
     /// Default value
     pub default: Option<String>,
+    /// Whether the caller must provide a value when there is no default
+    #[serde(default)]
+    pub required: bool,
 }
 
 /// Agent configuration (for skill-based agents)
@@ -327,6 +814,20 @@ pub struct Skill {
     pub updated_at: String,
 }
 
+/// Version of the skill bundle format written by this build
+pub const SKILL_BUNDLE_VERSION: u32 = 1;
+
+/// Skills shared as a single file, together with the skills they depend on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillBundle {
+    /// Bundle format version
+    pub version: u32,
+    /// Export timestamp
+    pub exported_at: String,
+    /// Exported skills, dependencies included
+    pub skills: Vec<Skill>,
+}
+
 impl Default for SkillMetadata {
     fn default() -> Self {
         Self {
@@ -361,6 +862,9 @@ pub struct SkillContext {
     pub project_path: String,
     /// Current session ID
     pub session_id: Option<String>,
+    /// Tool that triggered the execution (for PreTool/PostTool hooks)
+    #[serde(default)]
+    pub tool_name: Option<String>,
     /// Input arguments
     pub arguments: HashMap<String, serde_json::Value>,
     /// Environment variables
@@ -382,6 +886,40 @@ pub struct SkillResult {
     pub duration_ms: u64,
     /// Step results (for workflows)
     pub steps: Option<Vec<StepResult>>,
+    /// Set when a workflow is suspended waiting for user input; pass to `resume_workflow`
+    ///
+    /// This is the workflow's run ID, which stays the same across suspensions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_token: Option<String>,
+}
+
+/// Workflow run event, forwarded to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkflowEvent {
+    /// A run paused at a UserInput step
+    InputRequired {
+        run_id: String,
+        skill_id: String,
+        step_id: String,
+        prompt: Option<String>,
+        /// JSON schema the input should match, from the step's `schema` config
+        schema: Option<serde_json::Value>,
+        /// Seconds until the step times out, if it does
+        timeout_secs: Option<u64>,
+    },
+    /// A resumed run finished
+    Finished { run_id: String, result: SkillResult },
+}
+
+impl WorkflowEvent {
+    /// Tauri event name
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::InputRequired { .. } => "workflow-input-required",
+            Self::Finished { .. } => "workflow-finished",
+        }
+    }
 }
 
 /// Workflow step result
@@ -401,4 +939,284 @@ pub struct StepResult {
     pub duration_ms: u64,
     /// Retry attempts used
     pub retries: u32,
+    /// Whether the step's condition was false, so it never ran
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arg(name: &str, required: bool, default: Option<&str>, choices: Option<&[&str]>) -> ArgDef {
+        ArgDef {
+            name: name.to_string(),
+            description: String::new(),
+            required,
+            default: default.map(String::from),
+            choices: choices.map(|c| c.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    fn deploy_command() -> SlashCommandConfig {
+        SlashCommandConfig {
+            name: "deploy".to_string(),
+            description: "Deploy a service".to_string(),
+            help: Some("Deploys the given service to an environment.".to_string()),
+            prompt: "Deploy ${service} to ${env} with tag ${tag}${dry-run}".to_string(),
+            requires_args: false,
+            args: Some(ArgsConfig {
+                positional: vec![
+                    arg("service", true, None, None),
+                    arg("env", false, Some("staging"), Some(&["staging", "production"])),
+                ],
+                named: vec![arg("tag", false, Some("latest"), None), arg("dry-run", false, None, None)],
+            }),
+            examples: vec![],
+        }
+    }
+
+    fn step(id: &str, depends_on: &[&str]) -> WorkflowStep {
+        WorkflowStep {
+            id: id.to_string(),
+            kind: WorkflowStepKind::Shell,
+            name: id.to_string(),
+            config: serde_json::json!({ "command": "true" }),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            optional_depends_on: vec![],
+            condition: None,
+            timeout_secs: None,
+            retry: None,
+        }
+    }
+
+    #[test]
+    fn test_workflow_validation() {
+        let workflow = |steps| WorkflowConfig {
+            steps,
+            inputs: vec![],
+            outputs: HashMap::new(),
+            timeout_secs: None,
+            max_parallel: None,
+        };
+
+        assert!(workflow(vec![step("a", &[]), step("b", &["a"])]).validate().is_ok());
+        assert_eq!(
+            workflow(vec![step("a", &[]), step("a", &[])]).validate().unwrap_err(),
+            "Duplicate workflow step ID: a"
+        );
+        assert_eq!(
+            workflow(vec![step("a", &["missing"])]).validate().unwrap_err(),
+            "Step a depends on unknown step missing"
+        );
+        assert!(workflow(vec![step("a", &["b"]), step("b", &["a"])])
+            .validate()
+            .unwrap_err()
+            .contains("dependency cycle"));
+
+        let config = SkillConfig {
+            workflow: Some(workflow(vec![step("a", &[])])),
+            ..Default::default()
+        };
+        assert!(config.validate_for(&SkillKind::Workflow).is_ok());
+        assert_eq!(config.validate_for(&SkillKind::Hook).unwrap_err(), "Missing Hook configuration");
+    }
+
+    #[test]
+    fn test_template_validation() {
+        let var = |name: &str| TemplateVariable {
+            name: name.to_string(),
+            description: String::new(),
+            default: None,
+            required: false,
+        };
+        let template = TemplateConfig {
+            content: "Hi {{user.name}}{{#if team}} from {{ team }}{{else}}!{{/if}} {{{raw}}}".to_string(),
+            variables: vec![var("user"), var("team"), var("raw")],
+        };
+        assert!(template.validate().is_ok());
+
+        let missing = TemplateConfig {
+            variables: vec![var("user")],
+            ..template.clone()
+        };
+        assert_eq!(missing.validate().unwrap_err(), "Template uses undeclared variables: team, raw");
+
+        let duplicate = TemplateConfig {
+            variables: vec![var("user"), var("user")],
+            ..template
+        };
+        assert_eq!(duplicate.validate().unwrap_err(), "Duplicate template variable: user");
+    }
+
+    #[test]
+    fn test_split_args_quoting() {
+        assert_eq!(
+            split_args(r#"one "two words" 'it''s' a\ b "say \"hi\"" ''"#).unwrap(),
+            vec!["one", "two words", "its", "a b", "say \"hi\"", ""]
+        );
+        assert_eq!(split_args(r"'C:\path'").unwrap(), vec![r"C:\path"]);
+        assert!(split_args("\"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_parse_slash_command_args() {
+        let command = deploy_command();
+        let args = command.args.as_ref().unwrap();
+
+        let values = args.parse(r#"api production --tag "v1.2 rc""#).unwrap();
+        assert_eq!(values["service"], "api");
+        assert_eq!(values["env"], "production");
+        assert_eq!(values["tag"], "v1.2 rc");
+        assert!(!values.contains_key("dry-run"));
+
+        let values = args.parse("--tag=v2 --dry-run -- --web").unwrap();
+        assert_eq!(values["service"], "--web");
+        assert_eq!(values["env"], "staging");
+        assert_eq!(values["tag"], "v2");
+        assert_eq!(values["dry-run"], "true");
+
+        assert!(args.parse("api qa").unwrap_err().contains("expected one of: staging, production"));
+        assert!(args.parse("api --force").unwrap_err().contains("Unknown option: --force"));
+        assert!(args.parse("api staging extra").unwrap_err().contains("Unexpected argument: extra"));
+
+        assert_eq!(
+            command.expand_prompt("web").unwrap(),
+            "Deploy web to staging with tag latest"
+        );
+    }
+
+    #[test]
+    fn test_missing_required_arg_shows_usage() {
+        let err = deploy_command().expand_prompt("--tag v3").unwrap_err();
+        assert_eq!(err.message, "Missing required argument: service");
+        assert_eq!(err.usage, "/deploy <service> [env] [--tag <tag>] [--dry-run <dry-run>]");
+
+        let err = err.to_string();
+        assert!(err.starts_with("Missing required argument: service"));
+        assert!(err.contains("Usage: /deploy <service> [env] [--tag <tag>] [--dry-run <dry-run>]"));
+        assert!(err.contains("Deploys the given service"));
+    }
+
+    #[test]
+    fn test_positional_args_in_prompt() {
+        let mut command = deploy_command();
+        command.prompt = "Ship $1 to $2 ($ARGUMENTS), budget $100".to_string();
+
+        assert_eq!(
+            command.expand_prompt("'web app'").unwrap(),
+            "Ship web app to staging ('web app'), budget $100"
+        );
+        let err = command.expand_prompt("web dev").unwrap_err();
+        assert!(err.message.contains("expected one of: staging, production"));
+    }
+
+    #[test]
+    fn test_placeholders_in_values_are_not_expanded() {
+        let mut command = deploy_command();
+        command.prompt = "Deploy ${service} as $1 with tag ${tag}: $ARGUMENTS".to_string();
+
+        assert_eq!(
+            command.expand_prompt("'$1 ${tag}' --tag v1").unwrap(),
+            "Deploy $1 ${tag} as $1 ${tag} with tag v1: '$1 ${tag}' --tag v1"
+        );
+    }
+
+    #[test]
+    fn test_requires_args_is_derived_and_enforced() {
+        let mut command = deploy_command();
+        assert!(command.derive_requires_args());
+        command.requires_args = command.derive_requires_args();
+        assert_eq!(command.expand_prompt("  ").unwrap_err().message, "Arguments required");
+
+        let mut review = deploy_command();
+        review.args = None;
+        review.prompt = "Review the code".to_string();
+        assert!(!review.derive_requires_args());
+        review.prompt = "Review $ARGUMENTS".to_string();
+        assert!(review.derive_requires_args());
+    }
+
+    #[test]
+    fn test_fixed_backoff() {
+        let backoff = BackoffStrategy::Fixed { delay_ms: 250 };
+        assert_eq!(backoff.delay(1), Duration::from_millis(250));
+        assert_eq!(backoff.delay(5), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_exponential_backoff_is_capped() {
+        let backoff = BackoffStrategy::Exponential {
+            initial_ms: 100,
+            max_ms: 1000,
+            multiplier: 2.0,
+        };
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(200));
+        assert_eq!(backoff.delay(4), Duration::from_millis(800));
+        assert_eq!(backoff.delay(5), Duration::from_millis(1000));
+        assert_eq!(backoff.delay(200), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_linear_backoff() {
+        let backoff = BackoffStrategy::Linear {
+            initial_ms: 100,
+            increment_ms: 50,
+        };
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(3), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_hook_tool_patterns() {
+        let mut hook = HookConfig {
+            trigger: HookTrigger::PreTool,
+            tool_patterns: None,
+            command: "true".to_string(),
+            timeout_secs: 5,
+            can_block: false,
+            env: HashMap::new(),
+            stdin_json: false,
+        };
+        assert!(hook.matches_tool(Some("Read")));
+        assert!(hook.matches_tool(None));
+
+        hook.tool_patterns = Some(vec!["Bash".to_string(), "Edit*".to_string(), "mcp__?b__*".to_string()]);
+        assert!(hook.matches_tool(Some("Bash")));
+        assert!(hook.matches_tool(Some("Edit")));
+        assert!(hook.matches_tool(Some("EditNotebook")));
+        assert!(hook.matches_tool(Some("mcp__db__query")));
+        assert!(!hook.matches_tool(Some("bash")));
+        assert!(!hook.matches_tool(Some("MultiEdit")));
+        assert!(!hook.matches_tool(None));
+
+        hook.tool_patterns = Some(vec!["Bash*".to_string(), "mcp__*".to_string()]);
+        assert!(hook.matches_tool(Some("Bash")));
+        assert!(hook.matches_tool(Some("BashOutput")));
+        assert!(hook.matches_tool(Some("mcp__github__create_issue")));
+        assert!(!hook.matches_tool(Some("Read")));
+        assert!(!hook.matches_tool(Some("mcp_single_underscore")));
+    }
+
+    #[test]
+    fn test_retry_on_patterns() {
+        let mut retry = RetryConfig {
+            max_attempts: 3,
+            backoff: BackoffStrategy::Fixed { delay_ms: 0 },
+            retry_on: vec![],
+        };
+        assert!(retry.matches("anything"));
+
+        retry.retry_on = vec!["timed out".to_string(), "503".to_string()];
+        assert!(retry.matches("Command timed out"));
+        assert!(retry.matches("HTTP 503 Service Unavailable"));
+        assert!(!retry.matches("Permission denied"));
+
+        // Patterns that aren't substrings are tried as regexes
+        retry.retry_on = vec![r"HTTP 5\d\d".to_string(), "(unclosed".to_string()];
+        assert!(retry.matches("HTTP 502 Bad Gateway"));
+        assert!(!retry.matches("HTTP 404 Not Found"));
+        assert!(retry.matches("error (unclosed paren"));
+    }
 }
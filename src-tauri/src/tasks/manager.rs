@@ -2,13 +2,21 @@
 //!
 //! Concurrent task management with DashMap.
 //! Handles task lifecycle, progress tracking, and cancellation.
+//! When a database is attached, every transition is written through to it.
 
 use dashmap::DashMap;
 use log::{debug, error, info, warn};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 use tokio::sync::{broadcast, oneshot};
 
-use super::types::{Task, TaskInfo, TaskKind, TaskPriority, TaskProgress, TaskResult, TaskStatus};
+use super::types::{Task, TaskFilter, TaskInfo, TaskKind, TaskPriority, TaskProgress, TaskResult, TaskStatus};
 
 /// Task handle for controlling running tasks
 pub struct TaskHandle {
@@ -76,6 +84,16 @@ pub enum TaskEvent {
     Failed(String, String),
 }
 
+/// Default minimum time between Progress events for one task
+const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// When a task's progress was last broadcast
+struct ProgressThrottle {
+    last_sent: Option<Instant>,
+    /// Whether newer progress has been held back since
+    unsent: bool,
+}
+
 /// Task manager for handling concurrent tasks
 pub struct TaskManager {
     /// Active tasks (task_id -> Task)
@@ -88,6 +106,56 @@ pub struct TaskManager {
     max_concurrent: usize,
     /// Maximum history size
     max_history: usize,
+    /// Database the tasks are written through to, if persistence is enabled
+    db: Option<Arc<Mutex<Connection>>>,
+    /// Minimum time between Progress events for one task
+    progress_interval: Duration,
+    /// Progress throttling state (task_id -> ProgressThrottle)
+    progress_throttle: DashMap<String, ProgressThrottle>,
+}
+
+/// Columns read back by `task_from_row`, in order
+const TASK_COLUMNS: &str = "id, kind, name, description, status, priority, progress, result, metadata, cancellable,
+     background, created_at, started_at, completed_at, depends_on";
+
+/// Store a snake_case serde enum as its bare name
+fn enum_to_sql<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+/// Read back an enum stored by `enum_to_sql`
+fn enum_from_sql<T: DeserializeOwned>(name: String) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(name)).ok()
+}
+
+/// Rebuild a task from a row selected with `TASK_COLUMNS`
+fn task_from_row(row: &Row) -> rusqlite::Result<Task> {
+    let progress: String = row.get(6)?;
+    let result: Option<String> = row.get(7)?;
+    let metadata: String = row.get(8)?;
+    let depends_on: Option<String> = row.get(14)?;
+    Ok(Task {
+        id: row.get(0)?,
+        kind: enum_from_sql(row.get(1)?).unwrap_or(TaskKind::Async),
+        name: row.get(2)?,
+        description: row.get(3)?,
+        status: enum_from_sql(row.get(4)?).unwrap_or(TaskStatus::Failed),
+        priority: enum_from_sql(row.get(5)?).unwrap_or_default(),
+        progress: serde_json::from_str(&progress).unwrap_or_default(),
+        result: result.and_then(|result| serde_json::from_str(&result).ok()),
+        metadata: serde_json::from_str(&metadata).unwrap_or_default(),
+        cancellable: row.get(9)?,
+        background: row.get(10)?,
+        created_at: row.get(11)?,
+        started_at: row.get(12)?,
+        completed_at: row.get(13)?,
+        depends_on: depends_on
+            .and_then(|depends_on| serde_json::from_str(&depends_on).ok())
+            .unwrap_or_default(),
+    })
 }
 
 impl TaskManager {
@@ -101,6 +169,9 @@ impl TaskManager {
             event_tx,
             max_concurrent: 10,
             max_history: 100,
+            db: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+            progress_throttle: DashMap::new(),
         }
     }
 
@@ -114,6 +185,135 @@ impl TaskManager {
             event_tx,
             max_concurrent,
             max_history,
+            db: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+            progress_throttle: DashMap::new(),
+        }
+    }
+
+    /// Set the minimum time between Progress events for one task
+    pub fn with_progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Persist tasks to the given database
+    pub fn with_database(mut self, conn: Connection) -> Result<Self, rusqlite::Error> {
+        Self::init_database(&conn)?;
+        self.db = Some(Arc::new(Mutex::new(conn)));
+        Ok(self)
+    }
+
+    /// Initialize the tasks database table
+    pub fn init_database(conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT,
+                status TEXT NOT NULL,
+                priority TEXT NOT NULL,
+                progress TEXT NOT NULL,
+                result TEXT,
+                metadata TEXT NOT NULL,
+                cancellable INTEGER NOT NULL,
+                background INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                started_at TEXT,
+                completed_at TEXT
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tasks_completed_at ON tasks(completed_at)",
+            [],
+        )?;
+
+        // Add columns introduced after the table was first created
+        let _ = conn.execute("ALTER TABLE tasks ADD COLUMN depends_on TEXT", []);
+        Ok(())
+    }
+
+    /// Restore tasks saved by a previous run
+    ///
+    /// Tasks that hadn't finished can't be resumed, so they are marked Failed.
+    /// Only the most recent `max_history` finished tasks are kept in memory;
+    /// older ones stay available through `get_task_history`. Returns the number restored.
+    pub fn load_persisted_tasks(&self) -> Result<usize, rusqlite::Error> {
+        let db = match self.db {
+            Some(ref db) => db.clone(),
+            None => return Ok(0),
+        };
+
+        let tasks = {
+            let conn = db.lock();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM tasks
+                 WHERE status NOT IN ('completed', 'failed', 'cancelled')
+                 OR id IN (SELECT id FROM tasks WHERE status IN ('completed', 'failed', 'cancelled')
+                           ORDER BY completed_at DESC LIMIT ?1)
+                 ORDER BY created_at",
+                TASK_COLUMNS
+            ))?;
+            let rows = stmt.query_map(params![self.max_history as i64], task_from_row)?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut restored = 0;
+        for mut task in tasks {
+            if self.tasks.contains_key(&task.id) {
+                continue;
+            }
+
+            if !task.is_terminal() {
+                warn!("Task {} ({}) was interrupted by a restart; marking it failed", task.name, task.id);
+                task.complete(TaskResult::failure("Interrupted by restart", 0));
+                self.persist(&task);
+            }
+
+            self.tasks.insert(task.id.clone(), task);
+            restored += 1;
+        }
+
+        self.cleanup_history();
+        info!("Restored {} persisted tasks", restored);
+        Ok(restored)
+    }
+
+    /// Save a task's current state, if persistence is enabled
+    fn persist(&self, task: &Task) {
+        let db = match self.db {
+            Some(ref db) => db,
+            None => return,
+        };
+
+        let result = db.lock().execute(
+            &format!(
+                "INSERT OR REPLACE INTO tasks ({})
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                TASK_COLUMNS
+            ),
+            params![
+                task.id,
+                enum_to_sql(&task.kind),
+                task.name,
+                task.description,
+                enum_to_sql(&task.status),
+                enum_to_sql(&task.priority),
+                serde_json::to_string(&task.progress).unwrap_or_default(),
+                task.result.as_ref().and_then(|result| serde_json::to_string(result).ok()),
+                serde_json::to_string(&task.metadata).unwrap_or_default(),
+                task.cancellable,
+                task.background,
+                task.created_at,
+                task.started_at,
+                task.completed_at,
+                serde_json::to_string(&task.depends_on).unwrap_or_default(),
+            ],
+        );
+        if let Err(e) = result {
+            error!("Failed to save task {}: {}", task.id, e);
         }
     }
 
@@ -124,9 +324,32 @@ impl TaskManager {
 
     /// Create and register a new task
     pub fn create_task(&self, kind: TaskKind, name: impl Into<String>) -> Task {
-        let task = Task::new(kind, name);
+        self.register_task(Task::new(kind, name))
+    }
+
+    /// Create a task that starts once every task in `depends_on` has completed
+    ///
+    /// It stays Pending until then, and fails if any dependency fails or is cancelled.
+    pub fn create_dependent_task(
+        &self,
+        kind: TaskKind,
+        name: impl Into<String>,
+        depends_on: Vec<String>,
+    ) -> Result<Task, String> {
+        if let Some(missing) = depends_on.iter().find(|id| !self.tasks.contains_key(id.as_str())) {
+            return Err(format!("Task not found: {}", missing));
+        }
+
+        let task = self.register_task(Task::new(kind, name).with_dependencies(depends_on));
+        self.resolve_dependencies(&task.id);
+        Ok(self.get_task(&task.id).unwrap_or(task))
+    }
+
+    /// Add a task and announce it
+    fn register_task(&self, task: Task) -> Task {
         let info = TaskInfo::from(&task);
 
+        self.persist(&task);
         self.tasks.insert(task.id.clone(), task.clone());
         let _ = self.event_tx.send(TaskEvent::Created(info));
 
@@ -139,10 +362,49 @@ impl TaskManager {
         self.handles.insert(task_id.to_string(), handle);
     }
 
+    /// Create a task and run `f` for it on the Tokio runtime
+    ///
+    /// `f` gets a receiver that resolves once the task is cancelled, and whatever
+    /// it returns completes the task. A task cancelled or aborted in the meantime
+    /// keeps that status. Must be called from within a Tokio runtime.
+    pub fn spawn<F, Fut>(self: &Arc<Self>, kind: TaskKind, name: impl Into<String>, f: F) -> Task
+    where
+        F: FnOnce(oneshot::Receiver<()>) -> Fut + Send + 'static,
+        Fut: Future<Output = TaskResult> + Send + 'static,
+    {
+        let task = self.create_task(kind, name);
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        self.register_handle(&task.id, TaskHandle::new(task.id.clone()).with_cancel(cancel_tx));
+        if let Err(e) = self.start_task(&task.id) {
+            warn!("Failed to start task {}: {}", task.id, e);
+        }
+
+        let manager = Arc::clone(self);
+        let task_id = task.id.clone();
+        let join_handle = tokio::spawn(async move {
+            let result = f(cancel_rx).await;
+            if manager.get_task(&task_id).is_some_and(|task| !task.is_terminal()) {
+                manager.complete_task(&task_id, result.clone());
+            }
+            result
+        });
+        // The task may already be done, in which case its handle is gone too
+        if let Some(mut handle) = self.handles.get_mut(&task.id) {
+            handle.join_handle = Some(join_handle);
+        }
+
+        task
+    }
+
     /// Start a task
     pub fn start_task(&self, task_id: &str) -> Result<(), String> {
+        if let Some(dep) = self.unmet_dependency(task_id) {
+            return Err(format!("Task {} is waiting on dependency {}", task_id, dep));
+        }
+
         if let Some(mut task) = self.tasks.get_mut(task_id) {
             task.start();
+            self.persist(&task);
             let _ = self.event_tx.send(TaskEvent::Started(task_id.to_string()));
             info!("Started task: {} ({})", task.name, task.id);
             Ok(())
@@ -152,18 +414,61 @@ impl TaskManager {
     }
 
     /// Update task progress
+    ///
+    /// The task always holds the latest progress, but Progress events (and database
+    /// writes) for a task are sent at most once per progress interval. Progress held
+    /// back is sent when the task completes or is cancelled.
     pub fn update_progress(&self, task_id: &str, progress: TaskProgress) {
         if let Some(mut task) = self.tasks.get_mut(task_id) {
             task.progress = progress.clone();
+
+            let now = Instant::now();
+            let mut throttle = self
+                .progress_throttle
+                .entry(task_id.to_string())
+                .or_insert(ProgressThrottle {
+                    last_sent: None,
+                    unsent: false,
+                });
+            if throttle.last_sent.is_some_and(|last| now.duration_since(last) < self.progress_interval) {
+                throttle.unsent = true;
+                return;
+            }
+            throttle.last_sent = Some(now);
+            throttle.unsent = false;
+            drop(throttle);
+
+            self.persist(&task);
             let _ = self.event_tx.send(TaskEvent::Progress(task_id.to_string(), progress));
         }
     }
 
+    /// Send progress held back by throttling, and stop tracking the task's progress
+    fn flush_progress(&self, task_id: &str) {
+        let unsent = self
+            .progress_throttle
+            .remove(task_id)
+            .is_some_and(|(_, throttle)| throttle.unsent);
+        if !unsent {
+            return;
+        }
+
+        if let Some(task) = self.tasks.get(task_id) {
+            let _ = self
+                .event_tx
+                .send(TaskEvent::Progress(task_id.to_string(), task.progress.clone()));
+        }
+    }
+
     /// Complete a task
-    pub fn complete_task(&self, task_id: &str, result: TaskResult) {
+    pub fn complete_task(&self, task_id: &str, mut result: TaskResult) {
+        result.cap_logs();
+        self.flush_progress(task_id);
+
         if let Some(mut task) = self.tasks.get_mut(task_id) {
             let success = result.success;
             task.complete(result.clone());
+            self.persist(&task);
 
             if success {
                 info!("Completed task: {} ({})", task.name, task.id);
@@ -180,11 +485,16 @@ impl TaskManager {
             self.handles.remove(task_id);
         }
 
+        self.resolve_dependents(task_id);
+
         // Clean up old completed tasks
         self.cleanup_history();
     }
 
     /// Cancel a task
+    ///
+    /// This only signals the task's cancel receiver; a task body that never
+    /// checks it keeps running. Use `abort_task` to stop it regardless.
     pub fn cancel_task(&self, task_id: &str) -> Result<(), String> {
         // Try to send cancel signal
         if let Some(mut handle) = self.handles.get_mut(task_id) {
@@ -194,12 +504,16 @@ impl TaskManager {
         }
 
         // Update task status
+        if self.tasks.get(task_id).is_some_and(|task| task.cancellable) {
+            self.flush_progress(task_id);
+        }
         if let Some(mut task) = self.tasks.get_mut(task_id) {
             if !task.cancellable {
                 return Err("Task is not cancellable".to_string());
             }
 
             task.cancel();
+            self.persist(&task);
             info!("Cancelled task: {} ({})", task.name, task.id);
             let _ = self.event_tx.send(TaskEvent::Cancelled(task_id.to_string()));
         } else {
@@ -209,10 +523,64 @@ impl TaskManager {
         // Remove handle
         self.handles.remove(task_id);
 
+        self.resolve_dependents(task_id);
         Ok(())
     }
 
+    /// First dependency of a task that hasn't completed yet
+    fn unmet_dependency(&self, task_id: &str) -> Option<String> {
+        let depends_on = self.tasks.get(task_id).map(|t| t.depends_on.clone())?;
+        depends_on
+            .into_iter()
+            .find(|dep| self.tasks.get(dep).map(|t| t.status != TaskStatus::Completed).unwrap_or(true))
+    }
+
+    /// Start a waiting task once all its dependencies completed, or fail it if one didn't
+    fn resolve_dependencies(&self, task_id: &str) {
+        let depends_on = match self.tasks.get(task_id) {
+            Some(task) if task.status == TaskStatus::Pending && !task.depends_on.is_empty() => {
+                task.depends_on.clone()
+            }
+            _ => return,
+        };
+
+        let mut waiting = false;
+        for dep in &depends_on {
+            let status = self.tasks.get(dep).map(|t| t.status.clone());
+            match status {
+                Some(TaskStatus::Completed) => {}
+                Some(TaskStatus::Failed) | Some(TaskStatus::Cancelled) | None => {
+                    self.complete_task(task_id, TaskResult::failure(format!("Dependency failed: {}", dep), 0));
+                    return;
+                }
+                Some(_) => waiting = true,
+            }
+        }
+
+        if !waiting {
+            if let Err(e) = self.start_task(task_id) {
+                warn!("Failed to start dependent task {}: {}", task_id, e);
+            }
+        }
+    }
+
+    /// Re-check the pending tasks waiting on `task_id`
+    fn resolve_dependents(&self, task_id: &str) {
+        let dependents: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == TaskStatus::Pending && t.depends_on.iter().any(|dep| dep == task_id))
+            .map(|t| t.id.clone())
+            .collect();
+
+        for dependent in dependents {
+            self.resolve_dependencies(&dependent);
+        }
+    }
+
     /// Abort a task immediately
+    ///
+    /// Drops the task's future at its next await point, whether or not it watches for cancellation.
     pub fn abort_task(&self, task_id: &str) -> Result<(), String> {
         if let Some(mut handle) = self.handles.get_mut(task_id) {
             handle.abort();
@@ -236,6 +604,15 @@ impl TaskManager {
         self.tasks.iter().map(|t| TaskInfo::from(t.value())).collect()
     }
 
+    /// List tasks matching `filter`
+    pub fn list_tasks_filtered(&self, filter: &TaskFilter) -> Vec<TaskInfo> {
+        self.tasks
+            .iter()
+            .filter(|t| filter.matches(t.value()))
+            .map(|t| TaskInfo::from(t.value()))
+            .collect()
+    }
+
     /// List active tasks
     pub fn list_active_tasks(&self) -> Vec<TaskInfo> {
         self.tasks
@@ -263,6 +640,60 @@ impl TaskManager {
             .collect()
     }
 
+    /// Full result of a finished task, including its error and logs
+    ///
+    /// Falls back to the database for tasks no longer kept in memory. Returns
+    /// `Ok(None)` for unknown tasks and tasks that haven't finished.
+    pub fn get_task_result(&self, task_id: &str) -> Result<Option<TaskResult>, rusqlite::Error> {
+        if let Some(task) = self.tasks.get(task_id) {
+            return Ok(task.result.clone());
+        }
+        let db = match self.db {
+            Some(ref db) => db,
+            None => return Ok(None),
+        };
+
+        let conn = db.lock();
+        let task = conn
+            .query_row(
+                &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+                params![task_id],
+                task_from_row,
+            )
+            .optional()?;
+        Ok(task.and_then(|task| task.result))
+    }
+
+    /// Most recently finished tasks, newest first
+    ///
+    /// Reads from the database when persistence is enabled, so this reaches
+    /// past the `max_history` tasks kept in memory.
+    pub fn get_task_history(&self, limit: usize) -> Result<Vec<TaskInfo>, rusqlite::Error> {
+        let db = match self.db {
+            Some(ref db) => db,
+            None => {
+                let mut finished: Vec<Task> = self
+                    .tasks
+                    .iter()
+                    .filter(|t| t.is_terminal())
+                    .map(|t| t.clone())
+                    .collect();
+                finished.sort_by(|a, b| b.completed_at.cmp(&a.completed_at));
+                return Ok(finished.iter().take(limit).map(TaskInfo::from).collect());
+            }
+        };
+
+        let conn = db.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM tasks WHERE status IN ('completed', 'failed', 'cancelled')
+             ORDER BY completed_at DESC LIMIT ?1",
+            TASK_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![limit as i64], task_from_row)?;
+        let tasks = rows.collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks.iter().map(TaskInfo::from).collect())
+    }
+
     /// Count active tasks
     pub fn active_count(&self) -> usize {
         self.tasks.iter().filter(|t| t.is_active()).count()
@@ -274,11 +705,21 @@ impl TaskManager {
     }
 
     /// Clean up old completed tasks
+    ///
+    /// Only the in-memory cache is trimmed; persisted tasks stay in the database.
+    /// Tasks that an unfinished task depends on are kept.
     pub fn cleanup_history(&self) {
+        let needed: HashSet<String> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.is_terminal())
+            .flat_map(|t| t.depends_on.clone())
+            .collect();
+
         let mut completed: Vec<(String, String)> = self
             .tasks
             .iter()
-            .filter(|t| t.is_terminal())
+            .filter(|t| t.is_terminal() && !needed.contains(&t.id))
             .map(|t| (t.id.clone(), t.completed_at.clone().unwrap_or_default()))
             .collect();
 
@@ -290,6 +731,7 @@ impl TaskManager {
         for (task_id, _) in completed.into_iter().take(excess) {
             self.tasks.remove(&task_id);
             self.handles.remove(&task_id);
+            self.progress_throttle.remove(&task_id);
         }
     }
 
@@ -332,6 +774,7 @@ impl Default for TaskManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tasks::types::MAX_RESULT_LOG_LINES;
 
     #[test]
     fn test_create_task() {
@@ -371,4 +814,298 @@ mod tests {
         let task = manager.get_task(&task_id).unwrap();
         assert_eq!(task.status, TaskStatus::Cancelled);
     }
+
+    #[test]
+    fn test_list_tasks_filtered() {
+        use super::super::types::TaskMetadata;
+
+        let manager = TaskManager::new();
+        let add = |name: &str, metadata: TaskMetadata| {
+            let task = Task::new(TaskKind::Shell, name).with_metadata(metadata);
+            manager.register_task(task).id
+        };
+        let web = add(
+            "web",
+            TaskMetadata {
+                project_path: Some("/work/web".to_string()),
+                session_id: Some("s1".to_string()),
+                tags: vec!["build".to_string()],
+                ..Default::default()
+            },
+        );
+        let api = add(
+            "api",
+            TaskMetadata {
+                project_path: Some("/work/api".to_string()),
+                agent_id: Some(7),
+                tags: vec!["build".to_string(), "deploy".to_string()],
+                ..Default::default()
+            },
+        );
+        let sync = add("sync", TaskMetadata::default());
+        manager.complete_task(&api, TaskResult::success(None, 1));
+
+        let ids = |filter: TaskFilter| {
+            let mut ids: Vec<String> = manager.list_tasks_filtered(&filter).into_iter().map(|t| t.id).collect();
+            ids.sort();
+            ids
+        };
+        let sorted = |mut expected: Vec<&String>| {
+            expected.sort();
+            expected.into_iter().cloned().collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(TaskFilter::default()), sorted(vec![&web, &api, &sync]));
+        let by_project = TaskFilter {
+            project_path: Some("/work/web".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_project), vec![web.clone()]);
+        let by_session = TaskFilter {
+            session_id: Some("s1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_session), vec![web.clone()]);
+        let by_agent = TaskFilter {
+            agent_id: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_agent), vec![api.clone()]);
+        let by_tag = TaskFilter {
+            tag: Some("build".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_tag), sorted(vec![&web, &api]));
+        let active_builds = TaskFilter {
+            tag: Some("build".to_string()),
+            active_only: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(active_builds), vec![web.clone()]);
+
+        let info = manager.get_task_info(&api).unwrap();
+        assert_eq!(info.metadata.tags, vec!["build", "deploy"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_progress_events_are_throttled() {
+        let manager = TaskManager::new().with_progress_interval(Duration::from_millis(50));
+        let mut events = manager.subscribe();
+        let task_id = manager.create_task(TaskKind::FileOperation, "Copy").id;
+        manager.start_task(&task_id).unwrap();
+
+        // The clock only moves when advanced, so each batch lands within one interval
+        for i in 1..=1_000 {
+            manager.update_progress(&task_id, TaskProgress::with_total(i, 10_000, "Copying"));
+        }
+        tokio::time::advance(Duration::from_millis(50)).await;
+        for i in 1_001..=10_000 {
+            manager.update_progress(&task_id, TaskProgress::with_total(i, 10_000, "Copying"));
+        }
+        assert_eq!(manager.get_task(&task_id).unwrap().progress.current, 10_000);
+        manager.complete_task(&task_id, TaskResult::success(None, 0));
+
+        let mut progress = vec![];
+        while let Ok(event) = events.try_recv() {
+            if let TaskEvent::Progress(_, update) = event {
+                progress.push(update.current);
+            }
+        }
+        // One event per interval, plus the final progress flushed on completion
+        assert_eq!(progress, vec![1, 1_001, 10_000]);
+    }
+
+    #[tokio::test]
+    async fn test_abort_task_stops_uncooperative_task() {
+        let manager = TaskManager::new();
+        let task_id = manager.create_task(TaskKind::Async, "Busy").id;
+        manager.start_task(&task_id).unwrap();
+
+        // Never checks its cancel receiver; `dropped` closes once the future is gone
+        let (cancel_tx, _cancel_rx) = oneshot::channel();
+        let (alive_tx, dropped) = oneshot::channel::<()>();
+        let join_handle = tokio::spawn(async move {
+            let _alive = alive_tx;
+            tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+            TaskResult::success(None, 0)
+        });
+        let abort_handle = join_handle.abort_handle();
+        manager.register_handle(
+            &task_id,
+            TaskHandle::new(task_id.clone()).with_cancel(cancel_tx).with_handle(join_handle),
+        );
+
+        manager.abort_task(&task_id).unwrap();
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), dropped)
+            .await
+            .expect("task should have been aborted")
+            .unwrap_err();
+        assert!(abort_handle.is_finished());
+        assert_eq!(manager.get_task(&task_id).unwrap().status, TaskStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_runs_closure_to_completion() {
+        let manager = Arc::new(TaskManager::new());
+        let mut events = manager.subscribe();
+
+        let task = manager.spawn(TaskKind::Async, "Count", |_cancel| async {
+            TaskResult::success(Some(serde_json::json!({ "count": 3 })), 0)
+        });
+
+        let result = tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            loop {
+                if let TaskEvent::Completed(id, result) = events.recv().await.unwrap() {
+                    break (id, result);
+                }
+            }
+        })
+        .await
+        .expect("task should have completed");
+        assert_eq!(result.0, task.id);
+        assert_eq!(result.1.data.unwrap()["count"], 3);
+        assert_eq!(manager.get_task(&task.id).unwrap().status, TaskStatus::Completed);
+
+        // A cancelled task stays cancelled whatever the closure returns
+        let task = manager.spawn(TaskKind::Async, "Wait", |cancel| async {
+            let _ = cancel.await;
+            TaskResult::failure("stopped", 0)
+        });
+        let join_handle = manager.handles.get_mut(&task.id).unwrap().join_handle.take().unwrap();
+        manager.cancel_task(&task.id).unwrap();
+        let result = join_handle.await.unwrap();
+        assert_eq!(result.error.as_deref(), Some("stopped"));
+        assert_eq!(manager.get_task(&task.id).unwrap().status, TaskStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_dependent_tasks_start_in_order() {
+        let manager = TaskManager::new();
+        let mut events = manager.subscribe();
+        let build = manager.create_task(TaskKind::Shell, "Build").id;
+        let test = manager.create_dependent_task(TaskKind::Shell, "Test", vec![build.clone()]).unwrap().id;
+        let deploy = manager.create_dependent_task(TaskKind::Shell, "Deploy", vec![test.clone()]).unwrap().id;
+
+        manager.start_task(&build).unwrap();
+        assert!(manager.start_task(&deploy).is_err());
+        assert_eq!(manager.get_task(&test).unwrap().status, TaskStatus::Pending);
+
+        manager.complete_task(&build, TaskResult::success(None, 10));
+        assert_eq!(manager.get_task(&test).unwrap().status, TaskStatus::Running);
+        assert_eq!(manager.get_task(&deploy).unwrap().status, TaskStatus::Pending);
+
+        manager.complete_task(&test, TaskResult::success(None, 10));
+        assert_eq!(manager.get_task(&deploy).unwrap().status, TaskStatus::Running);
+
+        let mut started = vec![];
+        while let Ok(event) = events.try_recv() {
+            if let TaskEvent::Started(id) = event {
+                started.push(id);
+            }
+        }
+        assert_eq!(started, vec![build, test, deploy]);
+
+        // Already satisfied dependencies start the task right away
+        let done = manager.create_task(TaskKind::Shell, "Done").id;
+        manager.complete_task(&done, TaskResult::success(None, 1));
+        let follow_up = manager.create_dependent_task(TaskKind::Shell, "Follow-up", vec![done]).unwrap();
+        assert_eq!(follow_up.status, TaskStatus::Running);
+
+        assert!(manager.create_dependent_task(TaskKind::Shell, "Orphan", vec!["missing".into()]).is_err());
+    }
+
+    #[test]
+    fn test_dependency_failure_propagates() {
+        let manager = TaskManager::new();
+        let build = manager.create_task(TaskKind::Shell, "Build").id;
+        let test = manager.create_dependent_task(TaskKind::Shell, "Test", vec![build.clone()]).unwrap().id;
+        let deploy = manager.create_dependent_task(TaskKind::Shell, "Deploy", vec![test.clone()]).unwrap().id;
+        let lint = manager.create_task(TaskKind::Shell, "Lint").id;
+        let report = manager.create_dependent_task(TaskKind::Shell, "Report", vec![lint.clone()]).unwrap().id;
+
+        manager.start_task(&build).unwrap();
+        manager.complete_task(&build, TaskResult::failure("compile error", 10));
+
+        for id in [&test, &deploy] {
+            let task = manager.get_task(id).unwrap();
+            assert_eq!(task.status, TaskStatus::Failed);
+            assert!(task.result.unwrap().error.unwrap().starts_with("Dependency failed"));
+        }
+
+        manager.cancel_task(&lint).unwrap();
+        assert_eq!(manager.get_task(&report).unwrap().status, TaskStatus::Failed);
+    }
+
+    #[test]
+    fn test_tasks_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("tasks.db");
+        let open = |max_history| {
+            TaskManager::with_limits(10, max_history)
+                .with_database(Connection::open(&db_path).unwrap())
+                .unwrap()
+        };
+
+        let manager = open(100);
+        let done = manager.create_task(TaskKind::SkillExecution, "Done").id;
+        manager.start_task(&done).unwrap();
+        manager.update_progress(&done, TaskProgress::with_total(3, 3, "Finishing"));
+        manager.complete_task(&done, TaskResult::success(Some(serde_json::json!({"files": 2})), 40));
+
+        let older = manager.create_task(TaskKind::Shell, "Older").id;
+        manager.complete_task(&older, TaskResult::failure("exit 1", 5));
+
+        // Starts straight away, since its dependency already completed
+        let running = manager.create_dependent_task(TaskKind::Sync, "Running", vec![done.clone()]).unwrap().id;
+        manager.update_progress(&running, TaskProgress::with_total(1, 4, "Syncing"));
+        drop(manager);
+
+        // Keeps only one finished task in memory
+        let restarted = open(1);
+        restarted.load_persisted_tasks().unwrap();
+
+        let interrupted = restarted.get_task(&running).unwrap();
+        assert_eq!(interrupted.status, TaskStatus::Failed);
+        assert_eq!(interrupted.kind, TaskKind::Sync);
+        assert_eq!(interrupted.progress.message, "Syncing");
+        assert_eq!(interrupted.depends_on, vec![done.clone()]);
+        let error = interrupted.result.unwrap().error.unwrap();
+        assert_eq!(error, "Interrupted by restart");
+        assert_eq!(restarted.list_tasks().len(), 1);
+
+        let history = restarted.get_task_history(10).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].id, running);
+        assert!(history.iter().any(|t| t.id == done && t.status == "completed" && t.progress.current == 3));
+        assert!(history.iter().any(|t| t.id == older && t.status == "failed"));
+        assert_eq!(restarted.get_task_history(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_failed_task_result_is_retrievable() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = TaskManager::with_limits(10, 0)
+            .with_database(Connection::open(dir.path().join("tasks.db")).unwrap())
+            .unwrap();
+
+        let id = manager.create_task(TaskKind::Shell, "Build").id;
+        manager.start_task(&id).unwrap();
+        let logs: Vec<String> = (0..MAX_RESULT_LOG_LINES + 10).map(|i| format!("line {}", i)).collect();
+        manager.complete_task(&id, TaskResult::failure("cargo build failed\nerror[E0425]", 12).with_logs(logs));
+
+        let info = manager.get_task_history(1).unwrap().remove(0);
+        assert_eq!(info.error.as_deref(), Some("cargo build failed"));
+
+        // No longer in memory, so this comes from the database
+        assert!(manager.get_task(&id).is_none());
+        let result = manager.get_task_result(&id).unwrap().unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("cargo build failed\nerror[E0425]"));
+        let logs = result.logs.unwrap();
+        assert_eq!(logs.len(), MAX_RESULT_LOG_LINES);
+        assert_eq!(logs.last().unwrap(), &format!("line {}", MAX_RESULT_LOG_LINES + 9));
+
+        assert!(manager.get_task_result("missing").unwrap().is_none());
+    }
 }
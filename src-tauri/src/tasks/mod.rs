@@ -7,4 +7,4 @@ pub mod manager;
 pub mod types;
 
 pub use manager::{TaskManager, TaskEvent};
-pub use types::{Task, TaskInfo, TaskStatus, TaskKind, TaskPriority, TaskProgress, TaskResult};
+pub use types::{Task, TaskFilter, TaskInfo, TaskStatus, TaskKind, TaskPriority, TaskProgress, TaskResult};
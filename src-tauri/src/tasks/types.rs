@@ -124,6 +124,23 @@ impl TaskProgress {
     }
 }
 
+/// Most log lines a task result keeps (the latest ones)
+pub const MAX_RESULT_LOG_LINES: usize = 500;
+
+/// Longest log line a task result keeps, in characters
+pub const MAX_RESULT_LOG_LINE_CHARS: usize = 2000;
+
+/// Longest error summary included in `TaskInfo`, in characters
+const MAX_ERROR_SUMMARY_CHARS: usize = 200;
+
+/// Keep at most `max` characters of `text`, marking where it was cut
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
 /// Task result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskResult {
@@ -162,11 +179,33 @@ impl TaskResult {
         }
     }
 
-    /// Add logs
+    /// Add logs, capped like `cap_logs`
     pub fn with_logs(mut self, logs: Vec<String>) -> Self {
         self.logs = Some(logs);
+        self.cap_logs();
         self
     }
+
+    /// Keep only the last `MAX_RESULT_LOG_LINES` lines, each cut to `MAX_RESULT_LOG_LINE_CHARS`
+    pub fn cap_logs(&mut self) {
+        if let Some(ref mut logs) = self.logs {
+            if logs.len() > MAX_RESULT_LOG_LINES {
+                logs.drain(..logs.len() - MAX_RESULT_LOG_LINES);
+            }
+            for line in logs.iter_mut() {
+                if line.len() > MAX_RESULT_LOG_LINE_CHARS {
+                    *line = truncate_chars(line, MAX_RESULT_LOG_LINE_CHARS);
+                }
+            }
+        }
+    }
+
+    /// First line of the error, shortened for task listings
+    pub fn error_summary(&self) -> Option<String> {
+        let error = self.error.as_deref()?;
+        let first_line = error.lines().next().unwrap_or_default();
+        Some(truncate_chars(first_line, MAX_ERROR_SUMMARY_CHARS))
+    }
 }
 
 /// Task metadata
@@ -227,6 +266,9 @@ pub struct Task {
     pub started_at: Option<String>,
     /// Completed timestamp
     pub completed_at: Option<String>,
+    /// Tasks that must complete before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
 }
 
 impl Task {
@@ -247,6 +289,7 @@ impl Task {
             created_at: chrono::Utc::now().to_rfc3339(),
             started_at: None,
             completed_at: None,
+            depends_on: vec![],
         }
     }
 
@@ -274,6 +317,12 @@ impl Task {
         self
     }
 
+    /// Set the tasks this one waits on
+    pub fn with_dependencies(mut self, depends_on: Vec<String>) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
     /// Mark as started
     pub fn start(&mut self) {
         self.status = TaskStatus::Running;
@@ -339,6 +388,10 @@ pub struct TaskInfo {
     pub started_at: Option<String>,
     pub completed_at: Option<String>,
     pub duration_ms: Option<u64>,
+    pub depends_on: Vec<String>,
+    pub metadata: TaskMetadata,
+    /// Short error summary for failed tasks; `get_task_result` has the full result
+    pub error: Option<String>,
 }
 
 impl From<&Task> for TaskInfo {
@@ -357,6 +410,41 @@ impl From<&Task> for TaskInfo {
             started_at: task.started_at.clone(),
             completed_at: task.completed_at.clone(),
             duration_ms: task.duration_ms(),
+            depends_on: task.depends_on.clone(),
+            metadata: task.metadata.clone(),
+            error: match task.status {
+                TaskStatus::Failed => task.result.as_ref().and_then(TaskResult::error_summary),
+                _ => None,
+            },
         }
     }
 }
+
+/// Criteria for listing tasks; unset fields match every task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    pub project_path: Option<String>,
+    pub session_id: Option<String>,
+    pub agent_id: Option<i64>,
+    /// Matches tasks carrying this tag
+    pub tag: Option<String>,
+    /// Only pending and running tasks
+    #[serde(default)]
+    pub active_only: bool,
+    /// Only background tasks
+    #[serde(default)]
+    pub background_only: bool,
+}
+
+impl TaskFilter {
+    /// Whether `task` meets every criterion
+    pub fn matches(&self, task: &Task) -> bool {
+        let metadata = &task.metadata;
+        (self.project_path.is_none() || self.project_path == metadata.project_path)
+            && (self.session_id.is_none() || self.session_id == metadata.session_id)
+            && (self.agent_id.is_none() || self.agent_id == metadata.agent_id)
+            && self.tag.as_ref().is_none_or(|tag| metadata.tags.contains(tag))
+            && (!self.active_only || task.is_active())
+            && (!self.background_only || task.background)
+    }
+}
@@ -0,0 +1,98 @@
+//! Drives `opcode --mcp-serve` over stdio the way an MCP client would.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{ChildStdin, ChildStdout, Command, Stdio};
+
+fn send(stdin: &mut ChildStdin, message: Value) {
+    writeln!(stdin, "{}", message).unwrap();
+    stdin.flush().unwrap();
+}
+
+fn receive(stdout: &mut BufReader<ChildStdout>) -> Value {
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    serde_json::from_str(&line).unwrap_or_else(|e| panic!("Invalid response {:?}: {}", line, e))
+}
+
+#[test]
+fn test_mcp_serve_handshake_and_tool_call() {
+    let home = tempfile::tempdir().unwrap();
+    let project_dir = home.path().join(".claude").join("projects").join("-work-demo");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    std::fs::write(
+        project_dir.join("session-1.jsonl"),
+        "{\"type\":\"user\",\"cwd\":\"/work/demo\",\"message\":{\"role\":\"user\",\"content\":\"hi\"}}\n",
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_opcode"))
+        .arg("--mcp-serve")
+        .env("HOME", home.path())
+        .env("XDG_DATA_HOME", home.path().join("data"))
+        .env("APPDATA", home.path().join("data"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    send(
+        &mut stdin,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" }
+            }
+        }),
+    );
+    let initialized = receive(&mut stdout);
+    assert_eq!(initialized["id"], 1);
+    assert_eq!(initialized["result"]["protocolVersion"], "2025-06-18");
+    assert_eq!(initialized["result"]["serverInfo"]["name"], "opcode");
+
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }));
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }));
+    let listed = receive(&mut stdout);
+    assert_eq!(listed["id"], 2);
+    let tools: Vec<&str> = listed["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        tools,
+        vec!["list_projects", "list_sessions", "create_checkpoint", "list_skills", "execute_slash_command"]
+    );
+
+    send(
+        &mut stdin,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": { "name": "list_projects", "arguments": {} }
+        }),
+    );
+    let called = receive(&mut stdout);
+    assert_eq!(called["id"], 3);
+    assert!(called["result"]["isError"].is_null());
+    let projects = &called["result"]["structuredContent"]["projects"];
+    assert_eq!(projects[0]["id"], "-work-demo");
+    assert_eq!(projects[0]["path"], "/work/demo");
+    assert_eq!(projects[0]["sessions"], json!(["session-1"]));
+
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "id": 4, "method": "sampling/createMessage" }));
+    assert_eq!(receive(&mut stdout)["error"]["code"], -32601);
+
+    // Closing stdin ends the session
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
@@ -52,6 +52,7 @@ interface RemoteMcpServer {
   health_interval: number;
   last_health_check: string | null;
   latency_ms: number | null;
+  instructions: string | null;
   created_at: string;
   updated_at: string;
 }
@@ -65,6 +66,7 @@ interface ServerHealth {
   consecutive_failures: number;
   consecutive_successes: number;
   avg_latency_ms: number | null;
+  instructions?: string;
 }
 
 interface McpTool {
@@ -120,6 +122,7 @@ function AddServerDialog({
   const [healthInterval, setHealthInterval] = useState(60);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [nameError, setNameError] = useState<string | null>(null);
 
   const handleSubmit = async () => {
     if (!name.trim() || !endpoint.trim()) {
@@ -129,6 +132,7 @@ function AddServerDialog({
 
     setLoading(true);
     setError(null);
+    setNameError(null);
 
     try {
       await invoke("add_remote_mcp_server", {
@@ -157,7 +161,12 @@ function AddServerDialog({
       onAdd();
       onOpenChange(false);
     } catch (e) {
-      setError(String(e));
+      const message = String(e);
+      if (message.startsWith("DUPLICATE_NAME:")) {
+        setNameError(message.slice("DUPLICATE_NAME:".length).trim());
+      } else {
+        setError(message);
+      }
     } finally {
       setLoading(false);
     }
@@ -188,6 +197,7 @@ function AddServerDialog({
               onChange={(e) => setName(e.target.value)}
               placeholder="My MCP Server"
             />
+            {nameError && <p className="text-xs text-red-500">{nameError}</p>}
           </div>
 
           <div className="space-y-2">
@@ -355,6 +365,13 @@ function ServerCard({
             </div>
           )}
 
+          {server.instructions && (
+            <div className="text-xs">
+              <span className="text-muted-foreground">Server notes: </span>
+              <p className="mt-1 whitespace-pre-wrap bg-muted px-2 py-1 rounded">{server.instructions}</p>
+            </div>
+          )}
+
           <div className="flex gap-2 pt-2">
             <Button size="sm" variant="outline" onClick={() => onTest(server.id)}>
               Test Connection
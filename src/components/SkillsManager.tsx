@@ -260,6 +260,7 @@ function CreateHookDialog({
   const [command, setCommand] = useState("");
   const [timeout, setTimeout] = useState("30");
   const [canBlock, setCanBlock] = useState(false);
+  const [stdinJson, setStdinJson] = useState(false);
   const [visibility, setVisibility] = useState("global");
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
@@ -282,6 +283,7 @@ function CreateHookDialog({
           command: command.trim(),
           timeout_secs: parseInt(timeout) || 30,
           can_block: canBlock,
+          stdin_json: stdinJson,
           visibility,
         },
       });
@@ -293,6 +295,7 @@ function CreateHookDialog({
       setCommand("");
       setTimeout("30");
       setCanBlock(false);
+      setStdinJson(false);
       setVisibility("global");
 
       onCreated();
@@ -367,6 +370,20 @@ function CreateHookDialog({
               placeholder="The shell command to run..."
               rows={2}
             />
+            <p className="text-xs text-muted-foreground">
+              Available as environment variables: OPCODE_TRIGGER, OPCODE_SKILL_ID, OPCODE_PROJECT_PATH,
+              OPCODE_SESSION_ID, OPCODE_TOOL_NAME, OPCODE_TOOL_ARGS, OPCODE_ERROR_MESSAGE and OPCODE_ARG_&lt;NAME&gt;
+            </p>
+          </div>
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>Context on stdin</Label>
+              <p className="text-xs text-muted-foreground">
+                Pass the full hook context as JSON on stdin
+              </p>
+            </div>
+            <Switch checked={stdinJson} onCheckedChange={setStdinJson} />
           </div>
 
           <div className="grid grid-cols-2 gap-4">
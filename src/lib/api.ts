@@ -382,9 +382,13 @@ export interface MCPProjectConfig {
  * Individual server configuration in .mcp.json
  */
 export interface MCPServerConfig {
-  command: string;
-  args: string[];
-  env: Record<string, string>;
+  /** "stdio" when omitted, or "http"/"sse" for remote servers */
+  type?: string;
+  command?: string;
+  args?: string[];
+  env?: Record<string, string>;
+  url?: string;
+  headers?: Record<string, string>;
 }
 
 /**